@@ -98,6 +98,10 @@ fn run() -> Result<()> {
             "balancer-labs/balancer-subgraph-v2/2b97edd5e65aed06718ce64a69111ccdabccf048/\
              abis/StablePool.json",
         )?
+        .npm(
+            "ERC1271SignatureValidator",
+            "@openzeppelin/contracts@4.5.0/build/contracts/IERC1271.json",
+        )?
         .npm(
             "ERC20",
             "@openzeppelin/contracts@3.3.0/build/contracts/ERC20.json",
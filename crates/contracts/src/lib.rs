@@ -31,6 +31,7 @@ include!(concat!(
 ));
 include!(concat!(env!("OUT_DIR"), "/BaoswapFactory.rs"));
 include!(concat!(env!("OUT_DIR"), "/BaoswapRouter.rs"));
+include!(concat!(env!("OUT_DIR"), "/ERC1271SignatureValidator.rs"));
 include!(concat!(env!("OUT_DIR"), "/ERC20.rs"));
 include!(concat!(env!("OUT_DIR"), "/ERC20Mintable.rs"));
 include!(concat!(env!("OUT_DIR"), "/GPv2AllowListAuthentication.rs"));
@@ -3,14 +3,22 @@ use contracts::{ERC20Mintable, WETH9};
 use ethcontract::{prelude::U256, H160};
 use orderbook::{
     account_balances::Web3BalanceFetcher,
+    api::get_account_stats::AccountStatistics,
+    api::get_native_price::NativePrices,
     api::order_validation::OrderValidator,
     api::post_quote::OrderQuoter,
+    api::rate_limiting::RateLimits,
     cow_subsidy::FixedCowSubsidy,
     database::Postgres,
     event_updater::EventUpdater,
+    expiry_sweeper::ExpirySweeper,
     fee::{FeeSubsidyConfiguration, MinFeeCalculator},
+    gas_model::NoGasModel,
     metrics::NoopMetrics,
+    order_updates::{OrderUpdateEventStore, OrderUpdates},
     orderbook::Orderbook,
+    settlement_timestamps::SettlementTimestampUpdater,
+    signature_validator::Web3SignatureValidator,
     solvable_orders::SolvableOrdersCache,
 };
 use reqwest::Client;
@@ -21,6 +29,7 @@ use shared::{
     maintenance::ServiceMaintenance,
     price_estimation::baseline::BaselinePriceEstimator,
     price_estimation::native::NativePriceEstimator,
+    price_estimation::native_price_cache::CachingNativePriceEstimator,
     price_estimation::sanitized::SanitizedPriceEstimator,
     recent_block_cache::CacheConfig,
     sources::uniswap_v2::{
@@ -65,7 +74,11 @@ pub fn to_wei(base: u32) -> U256 {
 
 #[allow(dead_code)]
 pub fn create_orderbook_api() -> OrderBookApi {
-    OrderBookApi::new(reqwest::Url::from_str(API_HOST).unwrap(), Client::new())
+    OrderBookApi::new(
+        reqwest::Url::from_str(API_HOST).unwrap(),
+        Client::new(),
+        None,
+    )
 }
 
 pub fn create_order_converter(web3: &Web3, weth_address: H160) -> OrderConverter {
@@ -102,9 +115,10 @@ impl OrderbookServices {
     pub async fn new(web3: &Web3, contracts: &Contracts) -> Self {
         let db = Arc::new(Postgres::new("postgresql://").unwrap());
         db.clear().await.unwrap();
+        let order_updates = Arc::new(OrderUpdates::default());
         let event_updater = Arc::new(EventUpdater::new(
             contracts.gp_settlement.clone(),
-            db.as_ref().clone(),
+            OrderUpdateEventStore::new(db.as_ref().clone(), order_updates.clone()),
             None,
         ));
         let pair_provider = uniswap_pair_provider(contracts);
@@ -137,10 +151,33 @@ impl OrderbookServices {
             contracts.weth.address(),
             bad_token_detector.clone(),
         ));
-        let native_price_estimator = Arc::new(NativePriceEstimator::new(
-            price_estimator.clone(),
+        let native_price_estimator = Arc::new(CachingNativePriceEstimator::new(
+            Box::new(NativePriceEstimator::new(
+                price_estimator.clone(),
+                contracts.weth.address(),
+                1_000_000_000_000_000_000_u128.into(),
+            )),
+            Duration::from_secs(10),
+            Arc::new(NoopMetrics),
+        ));
+        let native_prices = Arc::new(NativePrices::new(
+            Arc::new(CachingNativePriceEstimator::new(
+                Box::new(NativePriceEstimator::new(
+                    price_estimator.clone(),
+                    contracts.weth.address(),
+                    1_000_000_000_000_000_000_u128.into(),
+                )),
+                Duration::from_secs(10),
+                Arc::new(NoopMetrics),
+            )),
+            native_price_estimator.clone(),
+        ));
+        let account_statistics = Arc::new(AccountStatistics::new(
+            db.clone(),
+            native_price_estimator.clone(),
             contracts.weth.address(),
-            1_000_000_000_000_000_000_u128.into(),
+            None,
+            current_block_stream.clone(),
         ));
         let fee_calculator = Arc::new(MinFeeCalculator::new(
             price_estimator.clone(),
@@ -154,6 +191,7 @@ impl OrderbookServices {
             native_price_estimator.clone(),
             Arc::new(FixedCowSubsidy(1.0)),
             Default::default(),
+            Arc::new(NoGasModel),
         ));
         let balance_fetcher = Arc::new(Web3BalanceFetcher::new(
             web3.clone(),
@@ -161,26 +199,33 @@ impl OrderbookServices {
             contracts.allowance,
             contracts.gp_settlement.address(),
         ));
+        let signature_validator = Arc::new(Web3SignatureValidator::new(web3.clone()));
         let solvable_orders_cache = SolvableOrdersCache::new(
             Duration::from_secs(120),
             db.clone(),
             Default::default(),
             balance_fetcher.clone(),
             bad_token_detector.clone(),
+            signature_validator.clone(),
             current_block_stream.clone(),
-            native_price_estimator,
+            native_price_estimator.clone(),
             Arc::new(NoopMetrics),
+            db.clone(),
+            db.clone(),
         );
         let order_validator = Arc::new(OrderValidator::new(
             Box::new(web3.clone()),
             contracts.weth.clone(),
-            HashSet::default(),
+            Default::default(),
             HashSet::default(),
             Duration::from_secs(120),
             fee_calculator.clone(),
             bad_token_detector.clone(),
             balance_fetcher,
+            signature_validator.clone(),
+            db.clone(),
         ));
+        let rate_limits = Arc::new(RateLimits::unlimited());
         let orderbook = Arc::new(Orderbook::new(
             contracts.domain_separator,
             contracts.gp_settlement.address(),
@@ -190,19 +235,39 @@ impl OrderbookServices {
             solvable_orders_cache.clone(),
             Duration::from_secs(600),
             order_validator.clone(),
+            signature_validator,
+            order_updates.clone(),
+            db.clone(),
+            rate_limits.clone(),
         ));
         let maintenance = ServiceMaintenance {
-            maintainers: vec![db.clone(), event_updater],
+            maintainers: vec![
+                db.clone(),
+                event_updater,
+                Arc::new(SettlementTimestampUpdater::new(
+                    db.as_ref().clone(),
+                    web3.clone(),
+                )),
+                Arc::new(ExpirySweeper::new(db.as_ref().clone(), order_updates)),
+            ],
         };
         let quoter = Arc::new(OrderQuoter::new(
             fee_calculator,
             price_estimator.clone(),
             order_validator,
+            db.clone(),
         ));
         orderbook::serve_api(
             db.clone(),
             orderbook,
             quoter,
+            rate_limits,
+            native_prices,
+            account_statistics,
+            db.clone(),
+            None,
+            db.clone(),
+            contracts.weth.address(),
             API_HOST[7..].parse().expect("Couldn't parse API address"),
             pending(),
         );
@@ -11,6 +11,10 @@ use std::collections::BTreeMap;
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Auction {
+    /// Uniquely identifies the auction across services and restarts. Increases with every new
+    /// auction.
+    pub id: u64,
+
     /// The block that this auction is valid for.
     /// The block number for the auction. Orders and prices are guaranteed to be
     /// valid on this block.
@@ -47,6 +51,7 @@ mod tests {
             ..Default::default()
         };
         let auction = Auction {
+            id: 7,
             block: 42,
             latest_settlement_block: 40,
             orders: vec![order(1), order(2)],
@@ -59,6 +64,7 @@ mod tests {
         assert_eq!(
             serde_json::to_value(&auction).unwrap(),
             json!({
+                "id": 7,
                 "block": 42,
                 "latestSettlementBlock": 40,
                 "orders": [
@@ -0,0 +1,55 @@
+//! Serialization of Vec<u8> to 0x prefixed hex string
+
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+use std::borrow::Cow;
+
+pub fn serialize<S, T>(bytes: T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: AsRef<[u8]>,
+{
+    let mut v = String::with_capacity(2 + bytes.as_ref().len() * 2);
+    v.push_str("0x");
+    v.push_str(&hex::encode(bytes.as_ref()));
+    serializer.serialize_str(&v)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let prefixed_hex_str = Cow::<str>::deserialize(deserializer)?;
+    let hex_str = prefixed_hex_str
+        .strip_prefix("0x")
+        .ok_or_else(|| D::Error::custom("missing '0x' prefix"))?;
+    hex::decode(hex_str).map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+    struct S {
+        #[serde(with = "super")]
+        b: Vec<u8>,
+    }
+
+    #[test]
+    fn roundtrips() {
+        let value = json!({ "b": "0x0102ff" });
+        let s: S = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(s, S { b: vec![1, 2, 255] });
+        assert_eq!(serde_json::to_value(&s).unwrap(), value);
+
+        let empty: S = serde_json::from_value(json!({ "b": "0x" })).unwrap();
+        assert!(empty.b.is_empty());
+    }
+
+    #[test]
+    fn requires_prefix() {
+        assert!(serde_json::from_value::<S>(json!({ "b": "0102" })).is_err());
+        assert!(serde_json::from_value::<S>(json!({ "b": "0x0" })).is_err());
+    }
+}
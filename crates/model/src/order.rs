@@ -77,7 +77,7 @@ impl Order {
                 full_fee_amount,
                 ..Default::default()
             },
-            creation: *order_creation,
+            creation: order_creation.clone(),
         }
     }
 
@@ -241,6 +241,12 @@ impl OrderBuilder {
         self
     }
 
+    pub fn with_eip1271(mut self, owner: H160, signature: Vec<u8>) -> Self {
+        self.0.metadata.owner = owner;
+        self.0.creation.signature = Signature::Eip1271(signature);
+        self
+    }
+
     pub fn build(self) -> Order {
         self.0
     }
@@ -248,7 +254,7 @@ impl OrderBuilder {
 
 /// An order as provided to the orderbook by the frontend.
 #[serde_as]
-#[derive(Eq, PartialEq, Copy, Clone, Deserialize, Debug, Serialize, Hash)]
+#[derive(Eq, PartialEq, Clone, Deserialize, Debug, Serialize, Hash)]
 #[serde(rename_all = "camelCase")]
 pub struct OrderCreation {
     pub sell_token: H160,
@@ -274,10 +280,17 @@ pub struct OrderCreation {
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct OrderCreationPayload {
     #[serde(flatten)]
     pub order_creation: OrderCreation,
     pub from: Option<H160>,
+    /// The quote the order was created from. Its fee is used instead of computing a new one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_id: Option<i64>,
+    /// An open order of the same owner that gets cancelled when this order is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaces: Option<OrderUid>,
 }
 
 impl Default for OrderCreation {
@@ -382,12 +395,10 @@ impl OrderCreation {
 }
 
 /// An order cancellation as provided to the orderbook by the frontend.
-#[serde_as]
-#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+#[derive(Eq, PartialEq, Clone, Debug, Hash)]
 pub struct OrderCancellation {
     pub order_uid: OrderUid,
-    pub signature: EcdsaSignature,
-    pub signing_scheme: EcdsaSigningScheme,
+    pub signature: Signature,
 }
 
 impl Default for OrderCancellation {
@@ -395,14 +406,14 @@ impl Default for OrderCancellation {
         let mut result = Self {
             order_uid: OrderUid::default(),
             signature: Default::default(),
-            signing_scheme: EcdsaSigningScheme::Eip712,
         };
         result.signature = EcdsaSignature::sign(
-            result.signing_scheme,
+            EcdsaSigningScheme::Eip712,
             &DomainSeparator::default(),
             &result.hash_struct(),
             SecretKeyRef::new(&ONE_KEY),
-        );
+        )
+        .to_signature(EcdsaSigningScheme::Eip712);
         result
     }
 }
@@ -420,9 +431,73 @@ impl OrderCancellation {
         signing::keccak256(&hash_data)
     }
 
+    /// Recovers the signer of ECDSA signed cancellations.
+    ///
+    /// Returns `None` for EIP-1271 signatures which have to be verified on-chain against the order
+    /// owner and for pre-signatures which can't cancel orders.
     pub fn validate(&self, domain_separator: &DomainSeparator) -> Option<H160> {
-        self.signature
-            .validate(self.signing_scheme, domain_separator, &self.hash_struct())
+        match &self.signature {
+            Signature::PreSign(_) => None,
+            signature => signature.validate(domain_separator, &self.hash_struct()),
+        }
+    }
+}
+
+/// A cancellation of multiple orders with a single signature as provided to the orderbook by the
+/// frontend.
+#[derive(Eq, PartialEq, Clone, Debug, Deserialize, Serialize, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderCancellations {
+    pub order_uids: Vec<OrderUid>,
+    #[serde(flatten)]
+    pub signature: Signature,
+}
+
+impl Default for OrderCancellations {
+    fn default() -> Self {
+        let mut result = Self {
+            order_uids: vec![OrderUid::default()],
+            signature: Default::default(),
+        };
+        result.signature = EcdsaSignature::sign(
+            EcdsaSigningScheme::Eip712,
+            &DomainSeparator::default(),
+            &result.hash_struct(),
+            SecretKeyRef::new(&ONE_KEY),
+        )
+        .to_signature(EcdsaSigningScheme::Eip712);
+        result
+    }
+}
+
+// EIP-712
+impl OrderCancellations {
+    // keccak256("OrderCancellations(bytes[] orderUids)")
+    const TYPE_HASH: [u8; 32] =
+        hex!("4c89efb91ae246f78d2fe68b47db2fa1444a121a4f2dc3fda7a5a408c2e3588e");
+
+    pub fn hash_struct(&self) -> [u8; 32] {
+        // Arrays are encoded as the hash of the concatenated encodings of their elements and
+        // dynamic `bytes` elements are encoded as their hash.
+        let mut uid_hashes = Vec::with_capacity(self.order_uids.len() * 32);
+        for uid in &self.order_uids {
+            uid_hashes.extend_from_slice(&signing::keccak256(&uid.0));
+        }
+        let mut hash_data = [0u8; 64];
+        hash_data[0..32].copy_from_slice(&Self::TYPE_HASH);
+        hash_data[32..64].copy_from_slice(&signing::keccak256(&uid_hashes));
+        signing::keccak256(&hash_data)
+    }
+
+    /// Recovers the signer of ECDSA signed cancellations.
+    ///
+    /// Returns `None` for EIP-1271 signatures which have to be verified on-chain against the order
+    /// owners and for pre-signatures which can't cancel orders.
+    pub fn validate(&self, domain_separator: &DomainSeparator) -> Option<H160> {
+        match &self.signature {
+            Signature::PreSign(_) => None,
+            signature => signature.validate(domain_separator, &self.hash_struct()),
+        }
     }
 }
 
@@ -452,6 +527,9 @@ pub struct OrderMetadata {
     pub settlement_contract: H160,
     #[serde(default, with = "u256_decimal")]
     pub full_fee_amount: U256,
+    /// The quote the order was created from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_id: Option<i64>,
 }
 
 impl Default for OrderMetadata {
@@ -469,6 +547,7 @@ impl Default for OrderMetadata {
             status: OrderStatus::Open,
             settlement_contract: H160::default(),
             full_fee_amount: U256::default(),
+            quote_id: None,
         }
     }
 }
@@ -484,6 +563,15 @@ impl OrderUid {
         uid.0[0..4].copy_from_slice(&i.to_le_bytes());
         uid
     }
+
+    /// Splits the uid into the order digest, the owner and the valid to timestamp.
+    pub fn parts(&self) -> (H256, H160, u32) {
+        (
+            H256::from_slice(&self.0[0..32]),
+            H160::from_slice(&self.0[32..52]),
+            u32::from_be_bytes(self.0[52..56].try_into().unwrap()),
+        )
+    }
 }
 
 impl FromStr for OrderUid {
@@ -704,6 +792,7 @@ mod tests {
                 status: OrderStatus::Open,
                 settlement_contract: H160::from_low_u64_be(2),
                 full_fee_amount: U256::MAX,
+                quote_id: None,
             },
             creation: OrderCreation {
                 sell_token: H160::from_low_u64_be(10),
@@ -847,19 +936,93 @@ mod tests {
         ] {
             let cancellation = OrderCancellation {
                 order_uid: OrderUid(hex!("2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a")),
-                signature: EcdsaSignature::from_bytes(signature),
-                signing_scheme: *signing_scheme,
+                signature: EcdsaSignature::from_bytes(signature).to_signature(*signing_scheme),
             };
             let owner = cancellation.validate(&domain_separator).unwrap();
             assert_eq!(owner, expected_owner);
         }
     }
 
+    #[test]
+    fn order_cancellation_only_recovers_ecdsa_signers() {
+        let domain_separator = DomainSeparator::default();
+        for signature in [
+            Signature::Eip1271(vec![1, 2, 3]),
+            Signature::PreSign(H160([1; 20])),
+        ] {
+            let cancellations = OrderCancellations {
+                order_uids: vec![Default::default()],
+                signature: signature.clone(),
+            };
+            assert_eq!(cancellations.validate(&domain_separator), None);
+            let cancellation = OrderCancellation {
+                order_uid: Default::default(),
+                signature,
+            };
+            assert_eq!(cancellation.validate(&domain_separator), None);
+        }
+    }
+
+    #[test]
+    fn order_cancellations_deserialization() {
+        let value = json!({
+            "orderUids": [OrderUid([1; 56])],
+            "signature": "0x010203",
+            "signingScheme": "eip1271",
+        });
+        let cancellations = OrderCancellations {
+            order_uids: vec![OrderUid([1; 56])],
+            signature: Signature::Eip1271(vec![1, 2, 3]),
+        };
+        assert_eq!(
+            serde_json::from_value::<OrderCancellations>(value.clone()).unwrap(),
+            cancellations
+        );
+        assert_eq!(serde_json::to_value(&cancellations).unwrap(), value);
+    }
+
+    #[test]
+    fn order_cancellations_type_hash() {
+        assert_eq!(
+            OrderCancellations::TYPE_HASH,
+            signing::keccak256(b"OrderCancellations(bytes[] orderUids)")
+        );
+    }
+
+    #[test]
+    fn order_cancellations_signature_covers_all_uids() {
+        let domain_separator = DomainSeparator::default();
+        let owner = h160_from_public_key(PublicKey::from_secret_key(&Secp256k1::new(), &ONE_KEY));
+
+        let cancellations = OrderCancellations::default();
+        assert_eq!(cancellations.validate(&domain_separator), Some(owner));
+
+        let cancellations = OrderCancellations {
+            order_uids: vec![OrderUid::default(), OrderUid([1; 56])],
+            ..cancellations
+        };
+        assert_ne!(cancellations.validate(&domain_separator), Some(owner));
+    }
+
     #[test]
     fn domain_separator_does_not_panic_in_debug() {
         println!("{:?}", DomainSeparator::default());
     }
 
+    #[test]
+    fn uid_parts() {
+        let order = OrderCreation {
+            valid_to: 0x01020304,
+            ..Default::default()
+        };
+        let owner = H160([0x42; 20]);
+        let uid = order.uid(&DomainSeparator::default(), &owner);
+        let (digest, uid_owner, valid_to) = uid.parts();
+        assert_eq!(digest.as_bytes(), &uid.0[0..32]);
+        assert_eq!(uid_owner, owner);
+        assert_eq!(valid_to, 0x01020304);
+    }
+
     #[test]
     fn uid_is_displayed_as_hex() {
         let mut uid = OrderUid([0u8; 56]);
@@ -13,14 +13,18 @@ use web3::{
 pub enum SigningScheme {
     Eip712,
     EthSign,
+    Eip1271,
     PreSign,
 }
-#[derive(Eq, PartialEq, Clone, Copy, Debug, Deserialize, Serialize, Hash)]
+#[derive(Eq, PartialEq, Clone, Debug, Deserialize, Serialize, Hash)]
 #[serde(rename_all = "lowercase")]
 #[serde(tag = "signingScheme", content = "signature")]
 pub enum Signature {
     Eip712(EcdsaSignature),
     EthSign(EcdsaSignature),
+    /// Smart contract signature that gets verified by calling `isValidSignature` on the order
+    /// owner. Unlike the other schemes the owner can not be recovered from the signature itself.
+    Eip1271(#[serde(with = "crate::bytes_hex")] Vec<u8>),
     PreSign(H160),
 }
 
@@ -35,12 +39,17 @@ impl Signature {
         match scheme {
             SigningScheme::Eip712 => Signature::Eip712(Default::default()),
             SigningScheme::EthSign => Signature::EthSign(Default::default()),
+            SigningScheme::Eip1271 => Signature::Eip1271(Default::default()),
             SigningScheme::PreSign => Signature::PreSign(Default::default()),
         }
     }
 }
 
 impl Signature {
+    /// Recovers the owner of the signature.
+    ///
+    /// Returns `None` for invalid ECDSA signatures as well as for EIP-1271 signatures which can only
+    /// be verified on-chain against a known owner.
     pub fn validate(
         &self,
         domain_separator: &DomainSeparator,
@@ -54,6 +63,7 @@ impl Signature {
                 domain_separator,
                 struct_hash,
             ),
+            Signature::Eip1271(_) => None,
             Signature::PreSign(account) => Some(*account),
         }
     }
@@ -75,6 +85,7 @@ impl Signature {
                         .expect("scheme is an ecdsa scheme"),
                 )
             }
+            SigningScheme::Eip1271 => Signature::Eip1271(bytes.to_vec()),
             SigningScheme::PreSign => Signature::PreSign(H160(
                 bytes
                     .try_into()
@@ -87,14 +98,26 @@ impl Signature {
     pub fn to_bytes(&self) -> Vec<u8> {
         match self {
             Signature::Eip712(sig) | Signature::EthSign(sig) => sig.to_bytes().to_vec(),
+            Signature::Eip1271(signature) => signature.clone(),
             Signature::PreSign(account) => account.0.to_vec(),
         }
     }
 
+    /// Encodes the signature the way the settlement contract expects it in a trade. EIP-1271
+    /// signatures are prefixed with the owner as the contract has no other way of knowing which
+    /// account to verify the signature against.
+    pub fn encode_for_settlement(&self, owner: H160) -> Vec<u8> {
+        match self {
+            Signature::Eip1271(signature) => [owner.as_bytes(), signature.as_slice()].concat(),
+            _ => self.to_bytes(),
+        }
+    }
+
     pub fn scheme(&self) -> SigningScheme {
         match self {
             Signature::Eip712(_) => SigningScheme::Eip712,
             Signature::EthSign(_) => SigningScheme::EthSign,
+            Signature::Eip1271(_) => SigningScheme::Eip1271,
             Signature::PreSign(_) => SigningScheme::PreSign,
         }
     }
@@ -125,7 +148,7 @@ impl SigningScheme {
         match self {
             Self::Eip712 => Some(EcdsaSigningScheme::Eip712),
             Self::EthSign => Some(EcdsaSigningScheme::EthSign),
-            Self::PreSign => None,
+            Self::Eip1271 | Self::PreSign => None,
         }
     }
 }
@@ -294,6 +317,28 @@ mod tests {
         assert!(SigningScheme::PreSign.try_to_ecdsa_scheme().is_none());
     }
 
+    #[test]
+    fn eip1271_does_not_recover_owner() {
+        assert_eq!(
+            Signature::Eip1271(vec![0x42; 65]).validate(&Default::default(), &Default::default()),
+            None,
+        );
+        assert!(SigningScheme::Eip1271.try_to_ecdsa_scheme().is_none());
+    }
+
+    #[test]
+    fn eip1271_settlement_encoding_is_prefixed_with_owner() {
+        let owner = H160([0x01; 20]);
+        assert_eq!(
+            Signature::Eip1271(vec![0x02, 0x03]).encode_for_settlement(owner),
+            [&[0x01; 20][..], &[0x02, 0x03][..]].concat(),
+        );
+        assert_eq!(
+            Signature::PreSign(owner).encode_for_settlement(owner),
+            owner.0.to_vec(),
+        );
+    }
+
     #[test]
     fn signature_from_bytes() {
         assert_eq!(
@@ -327,6 +372,10 @@ mod tests {
             Signature::from_bytes(SigningScheme::PreSign, &[0u8; 20]).unwrap(),
             Signature::default_with(SigningScheme::PreSign)
         );
+        assert_eq!(
+            Signature::from_bytes(SigningScheme::Eip1271, &[1u8; 42]).unwrap(),
+            Signature::Eip1271(vec![1u8; 42])
+        );
     }
 
     #[test]
@@ -358,7 +407,8 @@ mod tests {
             let scheme = SigningScheme::from(ecdsa_scheme);
             assert!(scheme.is_ecdsa_scheme())
         }
-        assert!(!SigningScheme::PreSign.is_ecdsa_scheme())
+        assert!(!SigningScheme::PreSign.is_ecdsa_scheme());
+        assert!(!SigningScheme::Eip1271.is_ecdsa_scheme());
     }
 
     #[test]
@@ -374,6 +424,17 @@ mod tests {
         let serialized = serde_json::to_value(expected).unwrap();
         assert_eq!(value, serialized);
 
+        let value = json!(
+        {
+            "signature": "0x0102",
+            "signingScheme": "eip1271"
+        });
+        let expected = Signature::Eip1271(vec![1, 2]);
+        let deserialized: Signature = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(deserialized, expected);
+        let serialized = serde_json::to_value(expected).unwrap();
+        assert_eq!(value, serialized);
+
         assert_eq!(
             serde_json::from_value::<Signature>(json!(
             {
@@ -0,0 +1,129 @@
+//! Module defining the result of the solver competition of a single auction.
+
+use crate::{order::OrderUid, u256_decimal::DecimalU256};
+use primitive_types::{H160, H256, U256};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use std::collections::BTreeMap;
+
+/// All settlements the solvers proposed for an auction.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolverCompetition {
+    pub auction_id: u64,
+    /// The gas price in wei used to compute the objective values.
+    pub gas_price: f64,
+    /// The block the liquidity was fetched at.
+    pub liquidity_collected_block: u64,
+    /// The hash of the transaction of the winning settlement if it was submitted.
+    pub transaction_hash: Option<H256>,
+    /// The settlements ordered by objective value with the winner last. Settlements that failed
+    /// simulation come first.
+    pub solutions: Vec<SolverSettlement>,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolverSettlement {
+    pub solver: String,
+    /// Only set if the settlement passed simulation.
+    pub objective: Option<Objective>,
+    /// Why the simulation of the settlement failed.
+    pub simulation_error: Option<String>,
+    #[serde_as(as = "BTreeMap<_, DecimalU256>")]
+    pub clearing_prices: BTreeMap<H160, U256>,
+    pub orders: Vec<OrderUid>,
+}
+
+/// The components of the objective value. All values except `gas` are in wei.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Objective {
+    pub total: f64,
+    pub surplus: f64,
+    pub fees: f64,
+    pub cost: f64,
+    pub gas: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maplit::btreemap;
+    use serde_json::json;
+
+    #[test]
+    fn roundtrips_solver_competition() {
+        let competition = SolverCompetition {
+            auction_id: 13,
+            gas_price: 1.5,
+            liquidity_collected_block: 14,
+            transaction_hash: Some(H256([0x11; 32])),
+            solutions: vec![
+                SolverSettlement {
+                    solver: "1inch".to_string(),
+                    objective: None,
+                    simulation_error: Some("reverted".to_string()),
+                    clearing_prices: Default::default(),
+                    orders: vec![OrderUid([0x22; 56])],
+                },
+                SolverSettlement {
+                    solver: "naive".to_string(),
+                    objective: Some(Objective {
+                        total: 3.,
+                        surplus: 4.,
+                        fees: 5.,
+                        cost: 6.,
+                        gas: 7,
+                    }),
+                    simulation_error: None,
+                    clearing_prices: btreemap! {
+                        H160([0x33; 20]) => U256::from(8),
+                    },
+                    orders: vec![OrderUid([0x22; 56])],
+                },
+            ],
+        };
+
+        let value = json!({
+            "auctionId": 13,
+            "gasPrice": 1.5,
+            "liquidityCollectedBlock": 14,
+            "transactionHash": "0x1111111111111111111111111111111111111111111111111111111111111111",
+            "solutions": [
+                {
+                    "solver": "1inch",
+                    "objective": null,
+                    "simulationError": "reverted",
+                    "clearingPrices": {},
+                    "orders": [
+                        "0x22222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222",
+                    ],
+                },
+                {
+                    "solver": "naive",
+                    "objective": {
+                        "total": 3.,
+                        "surplus": 4.,
+                        "fees": 5.,
+                        "cost": 6.,
+                        "gas": 7,
+                    },
+                    "simulationError": null,
+                    "clearingPrices": {
+                        "0x3333333333333333333333333333333333333333": "8",
+                    },
+                    "orders": [
+                        "0x22222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222222",
+                    ],
+                },
+            ],
+        });
+        assert_eq!(serde_json::to_value(&competition).unwrap(), value);
+        assert_eq!(
+            serde_json::from_value::<SolverCompetition>(value).unwrap(),
+            competition
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use anyhow::{Context, Result};
+use primitive_types::H160;
+use serde::Deserialize;
+use shared::{bad_token::list_based::ListBasedDetector, maintenance::Maintaining};
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+};
+
+/// Users whose orders are rejected and left out of the auction.
+#[derive(Debug, Default)]
+pub struct BannedUsers(RwLock<Arc<HashSet<H160>>>);
+
+impl BannedUsers {
+    pub fn new(users: HashSet<H160>) -> Self {
+        Self(RwLock::new(Arc::new(users)))
+    }
+
+    pub fn contains(&self, user: &H160) -> bool {
+        self.0.read().unwrap().contains(user)
+    }
+
+    /// The current users. Later changes don't affect the returned set.
+    pub fn get(&self) -> Arc<HashSet<H160>> {
+        self.0.read().unwrap().clone()
+    }
+
+    pub fn set(&self, users: HashSet<H160>) {
+        *self.0.write().unwrap() = Arc::new(users);
+    }
+}
+
+/// The user and token lists that can be changed at runtime.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct AccessLists {
+    pub banned_users: BTreeSet<H160>,
+    pub unsupported_tokens: BTreeSet<H160>,
+    pub allowed_tokens: BTreeSet<H160>,
+}
+
+impl AccessLists {
+    fn union(&self, other: &Self) -> Self {
+        Self {
+            banned_users: self
+                .banned_users
+                .union(&other.banned_users)
+                .copied()
+                .collect(),
+            unsupported_tokens: self
+                .unsupported_tokens
+                .union(&other.unsupported_tokens)
+                .copied()
+                .collect(),
+            allowed_tokens: self
+                .allowed_tokens
+                .union(&other.allowed_tokens)
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+/// Periodically reads access lists from a JSON file and applies them to the banned users and the
+/// token lists.
+///
+/// The lists in the file extend the ones the service was started with so removing an entry from
+/// the file never removes an entry that was configured through the command line. Every change is
+/// logged.
+pub struct AccessListsReloader {
+    path: PathBuf,
+    static_lists: AccessLists,
+    banned_users: Arc<BannedUsers>,
+    token_lists: Arc<ListBasedDetector>,
+    current: Mutex<AccessLists>,
+}
+
+impl AccessListsReloader {
+    /// Expects `banned_users` and `token_lists` to currently contain `static_lists`.
+    pub fn new(
+        path: PathBuf,
+        static_lists: AccessLists,
+        banned_users: Arc<BannedUsers>,
+        token_lists: Arc<ListBasedDetector>,
+    ) -> Self {
+        Self {
+            path,
+            current: Mutex::new(static_lists.clone()),
+            static_lists,
+            banned_users,
+            token_lists,
+        }
+    }
+
+    /// Reads the file and applies its lists if they changed since the last reload.
+    pub fn reload(&self) -> Result<()> {
+        let contents = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("failed to read access lists from {:?}", self.path))?;
+        let lists: AccessLists = serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse access lists from {:?}", self.path))?;
+        self.apply(&lists)
+    }
+
+    fn apply(&self, file_lists: &AccessLists) -> Result<()> {
+        let lists = self.static_lists.union(file_lists);
+        let mut current = self.current.lock().unwrap();
+        if *current == lists {
+            return Ok(());
+        }
+        self.token_lists
+            .set_lists(
+                lists.allowed_tokens.iter().copied().collect(),
+                lists.unsupported_tokens.iter().copied().collect(),
+            )
+            .context("invalid token lists")?;
+        self.banned_users
+            .set(lists.banned_users.iter().copied().collect());
+
+        log_changes("banned_users", &current.banned_users, &lists.banned_users);
+        log_changes(
+            "unsupported_tokens",
+            &current.unsupported_tokens,
+            &lists.unsupported_tokens,
+        );
+        log_changes(
+            "allowed_tokens",
+            &current.allowed_tokens,
+            &lists.allowed_tokens,
+        );
+        *current = lists;
+        Ok(())
+    }
+}
+
+fn log_changes(list: &str, previous: &BTreeSet<H160>, current: &BTreeSet<H160>) {
+    let added = current.difference(previous).collect::<Vec<_>>();
+    let removed = previous.difference(current).collect::<Vec<_>>();
+    if !added.is_empty() || !removed.is_empty() {
+        tracing::info!(%list, ?added, ?removed, "access list changed");
+    }
+}
+
+#[async_trait::async_trait]
+impl Maintaining for AccessListsReloader {
+    async fn run_maintenance(&self) -> Result<()> {
+        self.reload()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+    use maplit::{btreeset, hashset};
+    use shared::bad_token::BadTokenDetecting;
+
+    #[test]
+    fn deserialize_access_lists() {
+        let lists: AccessLists = serde_json::from_value(serde_json::json!({
+            "bannedUsers": ["0x0000000000000000000000000000000000000001"],
+            "allowedTokens": ["0x0000000000000000000000000000000000000002"],
+        }))
+        .unwrap();
+        assert_eq!(
+            lists,
+            AccessLists {
+                banned_users: btreeset![H160::from_low_u64_be(1)],
+                unsupported_tokens: btreeset![],
+                allowed_tokens: btreeset![H160::from_low_u64_be(2)],
+            }
+        );
+    }
+
+    #[test]
+    fn applies_file_lists_on_top_of_static_lists() {
+        let static_user = H160::from_low_u64_be(1);
+        let file_user = H160::from_low_u64_be(2);
+        let token = H160::from_low_u64_be(3);
+        let banned_users = Arc::new(BannedUsers::new(hashset![static_user]));
+        let token_lists = Arc::new(ListBasedDetector::deny_list(Vec::new()));
+        let reloader = AccessListsReloader::new(
+            Default::default(),
+            AccessLists {
+                banned_users: btreeset![static_user],
+                ..Default::default()
+            },
+            banned_users.clone(),
+            token_lists.clone(),
+        );
+        let token_is_good = || {
+            token_lists
+                .detect(token)
+                .now_or_never()
+                .unwrap()
+                .unwrap()
+                .is_good()
+        };
+
+        reloader
+            .apply(&AccessLists {
+                banned_users: btreeset![file_user],
+                unsupported_tokens: btreeset![token],
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(*banned_users.get(), hashset![static_user, file_user]);
+        assert!(!token_is_good());
+
+        // Entries removed from the file are removed again but static ones stay.
+        reloader.apply(&AccessLists::default()).unwrap();
+        assert_eq!(*banned_users.get(), hashset![static_user]);
+        assert!(token_is_good());
+
+        // Conflicting token lists are rejected without applying anything.
+        assert!(reloader
+            .apply(&AccessLists {
+                banned_users: btreeset![file_user],
+                unsupported_tokens: btreeset![token],
+                allowed_tokens: btreeset![token],
+            })
+            .is_err());
+        assert_eq!(*banned_users.get(), hashset![static_user]);
+        assert!(token_is_good());
+    }
+}
@@ -1,23 +1,43 @@
 mod cancel_order;
+mod cancel_orders;
 mod create_order;
+mod create_orders;
+pub mod get_account_stats;
 mod get_auction;
+mod get_candles;
+mod get_fee_accuracy;
 mod get_fee_and_quote;
 mod get_fee_info;
 mod get_markets;
+pub mod get_native_price;
 mod get_order_by_uid;
+mod get_order_events;
 mod get_orders;
 mod get_orders_by_tx;
 mod get_solvable_orders;
 mod get_solvable_orders_v2;
+mod get_solver_competition;
 mod get_trades;
 mod get_user_orders;
 pub mod order_validation;
 pub mod post_quote;
+mod post_solver_competition;
+pub mod rate_limiting;
+mod stream_order_updates;
 
 use crate::{
-    api::post_quote::OrderQuoter, database::trades::TradeRetrieving, orderbook::Orderbook,
+    api::{
+        get_account_stats::AccountStatistics, get_native_price::NativePrices,
+        post_quote::OrderQuoter, rate_limiting::RateLimits,
+    },
+    database::{
+        fee_reconciliations::FeeReconciliationRetrieving,
+        solver_competition::SolverCompetitionStoring, trades::TradeRetrieving,
+    },
+    orderbook::Orderbook,
 };
 use anyhow::{Error as anyhowError, Result};
+use primitive_types::H160;
 use serde::{de::DeserializeOwned, Serialize};
 use shared::{metrics::get_metric_storage_registry, price_estimation::PriceEstimationError};
 use std::fmt::Debug;
@@ -34,6 +54,13 @@ pub fn handle_all_routes(
     database: Arc<dyn TradeRetrieving>,
     orderbook: Arc<Orderbook>,
     quoter: Arc<OrderQuoter>,
+    rate_limits: Arc<RateLimits>,
+    native_prices: Arc<NativePrices>,
+    account_statistics: Arc<AccountStatistics>,
+    solver_competition: Arc<dyn SolverCompetitionStoring>,
+    solver_competition_auth: Option<String>,
+    fee_reconciliations: Arc<dyn FeeReconciliationRetrieving>,
+    native_token: H160,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
     // Routes for api v1.
 
@@ -41,9 +68,12 @@ pub fn handle_all_routes(
     // This string will be used later to report metrics.
     // It is not used to form the actual server response.
 
-    let create_order = create_order::create_order(orderbook.clone())
+    let create_order = create_order::create_order(orderbook.clone(), rate_limits.clone())
         .map(|result| (result, "v1/create_order"))
         .boxed();
+    let create_orders = create_orders::create_orders(orderbook.clone(), rate_limits.clone())
+        .map(|result| (result, "v1/create_orders"))
+        .boxed();
     let get_orders = get_orders::get_orders(orderbook.clone())
         .map(|result| (result, "v1/get_orders"))
         .boxed();
@@ -53,52 +83,93 @@ pub fn handle_all_routes(
     let get_order = get_order_by_uid::get_order_by_uid(orderbook.clone())
         .map(|result| (result, "v1/get_order"))
         .boxed();
+    let get_order_events = get_order_events::get_order_events(orderbook.clone())
+        .map(|result| (result, "v1/get_order_events"))
+        .boxed();
     let get_solvable_orders = get_solvable_orders::get_solvable_orders(orderbook.clone())
         .map(|result| (result, "v1/get_solvable_orders"))
         .boxed();
-    let get_trades = get_trades::get_trades(database)
+    let get_trades = get_trades::get_trades(database.clone())
         .map(|result| (result, "v1/get_trades"))
         .boxed();
     let cancel_order = cancel_order::cancel_order(orderbook.clone())
         .map(|result| (result, "v1/cancel_order"))
         .boxed();
-    let get_amount_estimate = get_markets::get_amount_estimate(quoter.price_estimator.clone())
-        .map(|result| (result, "v1/get_amount_estimate"))
-        .boxed();
-    let get_fee_and_quote_sell = get_fee_and_quote::get_fee_and_quote_sell(quoter.clone())
-        .map(|result| (result, "v1/get_fee_and_quote_sell"))
-        .boxed();
-    let get_fee_and_quote_buy = get_fee_and_quote::get_fee_and_quote_buy(quoter.clone())
-        .map(|result| (result, "v1/get_fee_and_quote_buy"))
+    let cancel_orders = cancel_orders::cancel_orders(orderbook.clone())
+        .map(|result| (result, "v1/cancel_orders"))
         .boxed();
+    let get_amount_estimate =
+        get_markets::get_amount_estimate(quoter.price_estimator.clone(), rate_limits.clone())
+            .map(|result| (result, "v1/get_amount_estimate"))
+            .boxed();
+    let get_fee_and_quote_sell =
+        get_fee_and_quote::get_fee_and_quote_sell(quoter.clone(), rate_limits.clone())
+            .map(|result| (result, "v1/get_fee_and_quote_sell"))
+            .boxed();
+    let get_fee_and_quote_buy =
+        get_fee_and_quote::get_fee_and_quote_buy(quoter.clone(), rate_limits.clone())
+            .map(|result| (result, "v1/get_fee_and_quote_buy"))
+            .boxed();
     let get_user_orders = get_user_orders::get_user_orders(orderbook.clone())
         .map(|result| (result, "v1/get_user_orders"))
         .boxed();
     let get_orders_by_tx = get_orders_by_tx::get_orders_by_tx(orderbook.clone())
         .map(|result| (result, "v1/get_orders_by_tx"))
         .boxed();
-    let post_quote = post_quote::post_quote(quoter)
+    let post_quote = post_quote::post_quote(quoter, rate_limits.clone())
         .map(|result| (result, "v1/post_quote"))
         .boxed();
     let get_auction = get_auction::get_auction(orderbook.clone())
         .map(|result| (result, "v1/auction"))
         .boxed();
+    let get_native_price = get_native_price::get_native_price(native_prices, rate_limits.clone())
+        .map(|result| (result, "v1/get_native_price"))
+        .boxed();
+    let get_candles = get_candles::get_candles(database, native_token)
+        .map(|result| (result, "v1/get_candles"))
+        .boxed();
+    let get_account_stats = get_account_stats::get_account_stats(account_statistics, rate_limits)
+        .map(|result| (result, "v1/get_account_stats"))
+        .boxed();
+    let get_solver_competition =
+        get_solver_competition::get_solver_competition(solver_competition.clone())
+            .map(|result| (result, "v1/get_solver_competition"))
+            .boxed();
+    let get_solver_competition_by_tx_hash =
+        get_solver_competition::get_solver_competition_by_tx_hash(solver_competition.clone())
+            .map(|result| (result, "v1/get_solver_competition_by_tx_hash"))
+            .boxed();
+    let post_solver_competition = post_solver_competition::post_solver_competition(
+        solver_competition,
+        solver_competition_auth,
+    )
+    .map(|result| (result, "v1/post_solver_competition"))
+    .boxed();
+    let get_fee_accuracy = get_fee_accuracy::get_fee_accuracy(fee_reconciliations)
+        .map(|result| (result, "v1/get_fee_accuracy"))
+        .boxed();
 
     let routes_v1 = warp::path!("api" / "v1" / ..)
         .and(
             create_order
+                .or(create_orders)
+                .unify()
                 .or(get_orders)
                 .unify()
                 .or(fee_info)
                 .unify()
                 .or(get_order)
                 .unify()
+                .or(get_order_events)
+                .unify()
                 .or(get_solvable_orders)
                 .unify()
                 .or(get_trades)
                 .unify()
                 .or(cancel_order)
                 .unify()
+                .or(cancel_orders)
+                .unify()
                 .or(get_amount_estimate)
                 .unify()
                 .or(get_fee_and_quote_sell)
@@ -112,11 +183,34 @@ pub fn handle_all_routes(
                 .or(post_quote)
                 .unify()
                 .or(get_auction)
+                .unify()
+                .or(get_native_price)
+                .unify()
+                .or(get_candles)
+                .unify()
+                .or(get_account_stats)
+                .unify()
+                .or(get_solver_competition)
+                .unify()
+                .or(get_solver_competition_by_tx_hash)
+                .unify()
+                .or(post_solver_competition)
+                .unify()
+                .or(get_fee_accuracy)
                 .unify(),
         )
         .untuple_one()
         .boxed();
 
+    // Streaming routes. These reply with a long lived event stream instead of an `ApiReply` so they
+    // can't be unified with the other routes and don't report request metrics.
+
+    let stream_order_updates = warp::path!("api" / "v1" / ..)
+        .and(stream_order_updates::stream_order_updates(
+            orderbook.clone(),
+        ))
+        .boxed();
+
     // Routes for api v2.
 
     let get_solvable_orders_v2 = get_solvable_orders_v2::get_solvable_orders(orderbook)
@@ -190,7 +284,9 @@ pub fn handle_all_routes(
         tracing::info_span!("request", id = request_id.fetch_add(1, Ordering::SeqCst))
     });
 
-    routes_with_metrics
+    stream_order_updates
+        .or(routes_with_metrics)
+        .unify()
         .recover(handle_rejection)
         .with(cors)
         .with(warp::log::log("orderbook::api::request_summary"))
@@ -286,6 +382,15 @@ pub trait IntoWarpReply {
     fn into_warp_reply(self) -> ApiReply;
 }
 
+/// The JSON body of the reply an error would produce. Used to report errors of individual items
+/// in batch responses.
+async fn error_body(err: impl IntoWarpReply) -> serde_json::Value {
+    let body = warp::hyper::body::to_bytes(err.into_warp_reply().into_response().into_body())
+        .await
+        .unwrap_or_default();
+    serde_json::from_slice(&body).unwrap_or_default()
+}
+
 impl IntoWarpReply for anyhowError {
     fn into_warp_reply(self) -> ApiReply {
         with_status(internal_error(self), StatusCode::INTERNAL_SERVER_ERROR)
@@ -332,9 +437,15 @@ async fn response_body(response: warp::hyper::Response<warp::hyper::Body>) -> Ve
 const MAX_JSON_BODY_PAYLOAD: u64 = 1024 * 16;
 
 fn extract_payload<T: DeserializeOwned + Send>(
+) -> impl Filter<Extract = (T,), Error = Rejection> + Clone {
+    extract_payload_with_max_size(MAX_JSON_BODY_PAYLOAD)
+}
+
+fn extract_payload_with_max_size<T: DeserializeOwned + Send>(
+    max_size: u64,
 ) -> impl Filter<Extract = (T,), Error = Rejection> + Clone {
     // (rejecting huge payloads)...
-    warp::body::content_length_limit(MAX_JSON_BODY_PAYLOAD).and(warp::body::json())
+    warp::body::content_length_limit(max_size).and(warp::body::json())
 }
 
 #[cfg(test)]
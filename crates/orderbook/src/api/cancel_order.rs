@@ -1,10 +1,9 @@
 use crate::api::{convert_json_response, extract_payload, IntoWarpReply};
 use crate::orderbook::{OrderCancellationError, Orderbook};
 use anyhow::Result;
-use model::signature::EcdsaSignature;
 use model::{
     order::{OrderCancellation, OrderUid},
-    signature::EcdsaSigningScheme,
+    signature::Signature,
 };
 use serde::{Deserialize, Serialize};
 use std::{convert::Infallible, sync::Arc};
@@ -14,8 +13,8 @@ use warp::{hyper::StatusCode, Filter, Rejection};
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct CancellationPayload {
-    signature: EcdsaSignature,
-    signing_scheme: EcdsaSigningScheme,
+    #[serde(flatten)]
+    signature: Signature,
 }
 
 pub fn cancel_order_request(
@@ -26,7 +25,6 @@ pub fn cancel_order_request(
         .map(|uid, payload: CancellationPayload| OrderCancellation {
             order_uid: uid,
             signature: payload.signature,
-            signing_scheme: payload.signing_scheme,
         })
 }
 
@@ -93,6 +91,7 @@ mod tests {
     use super::*;
     use ethcontract::H256;
     use hex_literal::hex;
+    use model::signature::EcdsaSignature;
     use serde_json::json;
     use warp::{test::request, Reply};
 
@@ -108,7 +107,7 @@ mod tests {
             }))
             .unwrap(),
             CancellationPayload {
-                signature: EcdsaSignature {
+                signature: Signature::Eip712(EcdsaSignature {
                     r: H256(hex!(
                         "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"
                     )),
@@ -116,8 +115,17 @@ mod tests {
                         "202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f"
                     )),
                     v: 27,
-                },
-                signing_scheme: EcdsaSigningScheme::Eip712,
+                }),
+            },
+        );
+        assert_eq!(
+            CancellationPayload::deserialize(json!({
+                "signature": "0x0102",
+                "signingScheme": "eip1271"
+            }))
+            .unwrap(),
+            CancellationPayload {
+                signature: Signature::Eip1271(vec![1, 2]),
             },
         );
     }
@@ -132,8 +140,7 @@ mod tests {
             .method("DELETE")
             .header("content-type", "application/json")
             .json(&CancellationPayload {
-                signature: cancellation.signature,
-                signing_scheme: cancellation.signing_scheme,
+                signature: cancellation.signature.clone(),
             });
         let result = request.filter(&filter).await.unwrap();
         assert_eq!(result, cancellation);
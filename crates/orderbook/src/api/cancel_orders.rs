@@ -0,0 +1,124 @@
+use crate::{
+    api::{error_body, extract_payload, IntoWarpReply},
+    orderbook::{OrderCancellationError, Orderbook},
+};
+use anyhow::Result;
+use model::order::{OrderCancellations, OrderUid};
+use serde::Serialize;
+use std::{convert::Infallible, sync::Arc};
+use warp::{hyper::StatusCode, reply::with_status, Filter, Rejection};
+
+/// The maximum number of orders that can be cancelled with a single signature.
+const MAX_ORDERS_PER_CANCELLATION: usize = 100;
+
+#[derive(Debug, Serialize)]
+struct CancellationResult {
+    uid: OrderUid,
+    /// The same error that cancelling the order on its own would have returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<serde_json::Value>,
+}
+
+fn cancel_orders_request() -> impl Filter<Extract = (OrderCancellations,), Error = Rejection> + Clone
+{
+    warp::path!("orders")
+        .and(warp::delete())
+        .and(extract_payload())
+}
+
+async fn cancel_orders_response(
+    result: Result<Vec<(OrderUid, Result<(), OrderCancellationError>)>, OrderCancellationError>,
+) -> super::ApiReply {
+    match result {
+        Ok(results) => {
+            let mut response = Vec::with_capacity(results.len());
+            for (uid, result) in results {
+                let error = match result {
+                    Ok(()) => None,
+                    Err(err) => Some(error_body(err).await),
+                };
+                response.push(CancellationResult { uid, error });
+            }
+            with_status(warp::reply::json(&response), StatusCode::OK)
+        }
+        Err(err) => err.into_warp_reply(),
+    }
+}
+
+pub fn cancel_orders(
+    orderbook: Arc<Orderbook>,
+) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
+    cancel_orders_request().and_then(move |cancellations: OrderCancellations| {
+        let orderbook = orderbook.clone();
+        async move {
+            if cancellations.order_uids.len() > MAX_ORDERS_PER_CANCELLATION {
+                return Result::<_, Infallible>::Ok(with_status(
+                    super::error(
+                        "TooManyOrders",
+                        format!(
+                            "at most {} orders per cancellation",
+                            MAX_ORDERS_PER_CANCELLATION
+                        ),
+                    ),
+                    StatusCode::BAD_REQUEST,
+                ));
+            }
+            let result = orderbook.cancel_orders(cancellations).await;
+            Result::<_, Infallible>::Ok(cancel_orders_response(result).await)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::response_body;
+    use serde_json::json;
+    use warp::{test::request, Reply};
+
+    #[tokio::test]
+    async fn cancel_orders_request_ok() {
+        let filter = cancel_orders_request();
+        let cancellations = OrderCancellations::default();
+
+        let request = request()
+            .path("/orders")
+            .method("DELETE")
+            .header("content-type", "application/json")
+            .json(&cancellations);
+        let result = request.filter(&filter).await.unwrap();
+        assert_eq!(result, cancellations);
+    }
+
+    #[tokio::test]
+    async fn cancel_orders_response_reports_each_order() {
+        let response = cancel_orders_response(Ok(vec![
+            (OrderUid([1; 56]), Ok(())),
+            (OrderUid([2; 56]), Err(OrderCancellationError::WrongOwner)),
+        ]))
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response_body(response).await;
+        let body: serde_json::Value = serde_json::from_slice(body.as_slice()).unwrap();
+        let expected = json!([
+            { "uid": OrderUid([1; 56]) },
+            {
+                "uid": OrderUid([2; 56]),
+                "error": {
+                    "errorType": "WrongOwner",
+                    "description": "Signature recovery's owner doesn't match order's",
+                },
+            },
+        ]);
+        assert_eq!(body, expected);
+    }
+
+    #[tokio::test]
+    async fn cancel_orders_response_invalid_signature() {
+        let response = cancel_orders_response(Err(OrderCancellationError::InvalidSignature))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,10 +1,10 @@
 use crate::{
-    api::{extract_payload, IntoWarpReply},
-    orderbook::{AddOrderError, Orderbook},
+    api::{extract_payload, rate_limiting::RateLimits, IntoWarpReply},
+    orderbook::{AddOrderError, OrderCancellationError, Orderbook},
 };
 use anyhow::Result;
 use model::order::{OrderCreationPayload, OrderUid};
-use std::{convert::Infallible, sync::Arc};
+use std::{convert::Infallible, net::IpAddr, sync::Arc};
 use warp::reply::with_status;
 use warp::{hyper::StatusCode, Filter, Rejection};
 
@@ -23,6 +23,28 @@ impl IntoWarpReply for AddOrderError {
                 super::error("UnsupportedSignature", "signing scheme is not supported"),
                 StatusCode::BAD_REQUEST,
             ),
+            Self::InvalidReplacement(OrderCancellationError::Other(err)) => with_status(
+                super::internal_error(err.context("create_order")),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+            Self::InvalidReplacement(err) => with_status(
+                super::error("InvalidReplacement", err.to_string()),
+                StatusCode::BAD_REQUEST,
+            ),
+            Self::UnsupportedReplacement => with_status(
+                super::error(
+                    "UnsupportedReplacement",
+                    "orders in a batch can not replace other orders",
+                ),
+                StatusCode::BAD_REQUEST,
+            ),
+            Self::RateLimited => with_status(
+                super::error(
+                    "TooManyRequests",
+                    "rate limit per owner exceeded, try again later",
+                ),
+                StatusCode::TOO_MANY_REQUESTS,
+            ),
             Self::DuplicatedOrder => with_status(
                 super::error("DuplicatedOrder", "order already exists"),
                 StatusCode::BAD_REQUEST,
@@ -44,14 +66,24 @@ pub fn create_order_response(result: Result<OrderUid, AddOrderError>) -> super::
 
 pub fn create_order(
     orderbook: Arc<Orderbook>,
+    rate_limits: Arc<RateLimits>,
 ) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
-    create_order_request().and_then(move |order_payload: OrderCreationPayload| {
-        let orderbook = orderbook.clone();
-        async move {
-            let result = orderbook.add_order(order_payload).await;
-            Result::<_, Infallible>::Ok(create_order_response(result))
-        }
-    })
+    create_order_request()
+        .and(rate_limits.client_ip())
+        .and_then(
+            move |order_payload: OrderCreationPayload, ip: Option<IpAddr>| {
+                let orderbook = orderbook.clone();
+                let rate_limits = rate_limits.clone();
+                async move {
+                    // The owner is limited once validation verified it.
+                    if let Err(reply) = rate_limits.check("create_order", ip, None) {
+                        return Result::<_, Infallible>::Ok(reply);
+                    }
+                    let result = orderbook.add_order(order_payload).await;
+                    Result::<_, Infallible>::Ok(create_order_response(result))
+                }
+            },
+        )
 }
 
 #[cfg(test)]
@@ -98,4 +130,20 @@ mod tests {
             json!({"errorType": "DuplicatedOrder", "description": "order already exists"});
         assert_eq!(body, expected_error);
     }
+
+    #[tokio::test]
+    async fn create_order_response_invalid_replacement() {
+        let response = create_order_response(Err(AddOrderError::InvalidReplacement(
+            OrderCancellationError::WrongOwner,
+        )))
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = response_body(response).await;
+        let body: serde_json::Value = serde_json::from_slice(body.as_slice()).unwrap();
+        let expected_error = json!({
+            "errorType": "InvalidReplacement",
+            "description": "signer does not match order owner"
+        });
+        assert_eq!(body, expected_error);
+    }
 }
@@ -0,0 +1,134 @@
+use crate::{
+    api::{error_body, extract_payload_with_max_size, rate_limiting::RateLimits, IntoWarpReply},
+    orderbook::{AddOrderError, Orderbook},
+};
+use anyhow::Result;
+use model::order::{OrderCreationPayload, OrderUid};
+use serde::Serialize;
+use std::{convert::Infallible, net::IpAddr, sync::Arc};
+use warp::{hyper::StatusCode, reply::with_status, Filter, Rejection};
+
+/// The maximum number of orders that can be submitted in a single batch.
+const MAX_ORDERS_PER_BATCH: usize = 100;
+
+/// Batches contain many orders so they get a larger payload limit than the other routes.
+const MAX_BATCH_PAYLOAD: u64 = 1024 * 512;
+
+#[derive(Debug, Serialize)]
+struct BatchResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uid: Option<OrderUid>,
+    /// The same error that creating the order on its own would have returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<serde_json::Value>,
+}
+
+impl BatchResult {
+    async fn new(result: Result<OrderUid, AddOrderError>) -> Self {
+        match result {
+            Ok(uid) => Self {
+                uid: Some(uid),
+                error: None,
+            },
+            Err(err) => Self {
+                uid: None,
+                error: Some(error_body(err).await),
+            },
+        }
+    }
+}
+
+fn create_orders_request(
+) -> impl Filter<Extract = (Vec<OrderCreationPayload>,), Error = Rejection> + Clone {
+    warp::path!("orders" / "batch")
+        .and(warp::post())
+        .and(extract_payload_with_max_size(MAX_BATCH_PAYLOAD))
+}
+
+async fn create_orders_response(
+    result: Result<Vec<Result<OrderUid, AddOrderError>>, AddOrderError>,
+) -> super::ApiReply {
+    match result {
+        Ok(results) => {
+            let mut response = Vec::with_capacity(results.len());
+            for result in results {
+                response.push(BatchResult::new(result).await);
+            }
+            with_status(warp::reply::json(&response), StatusCode::OK)
+        }
+        Err(err) => err.into_warp_reply(),
+    }
+}
+
+pub fn create_orders(
+    orderbook: Arc<Orderbook>,
+    rate_limits: Arc<RateLimits>,
+) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
+    create_orders_request()
+        .and(rate_limits.client_ip())
+        .and_then(
+            move |payloads: Vec<OrderCreationPayload>, ip: Option<IpAddr>| {
+                let orderbook = orderbook.clone();
+                let rate_limits = rate_limits.clone();
+                async move {
+                    if payloads.len() > MAX_ORDERS_PER_BATCH {
+                        return Result::<_, Infallible>::Ok(with_status(
+                            super::error(
+                                "TooManyOrders",
+                                format!("at most {} orders per batch", MAX_ORDERS_PER_BATCH),
+                            ),
+                            StatusCode::BAD_REQUEST,
+                        ));
+                    }
+                    // Every order counts like a request to create a single order. The owners are
+                    // limited per order once validation verified them.
+                    let requests = payloads.len() as u32;
+                    if let Err(reply) =
+                        rate_limits.check_requests("create_orders", ip, None, requests)
+                    {
+                        return Result::<_, Infallible>::Ok(reply);
+                    }
+                    let result = orderbook.add_orders(payloads).await;
+                    Result::<_, Infallible>::Ok(create_orders_response(result).await)
+                }
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::response_body;
+    use serde_json::json;
+    use warp::{test::request, Reply};
+
+    #[tokio::test]
+    async fn create_orders_request_ok() {
+        let filter = create_orders_request();
+        let payloads = vec![OrderCreationPayload::default(); 2];
+        let request = request()
+            .path("/orders/batch")
+            .method("POST")
+            .header("content-type", "application/json")
+            .json(&payloads);
+        let result = request.filter(&filter).await.unwrap();
+        assert_eq!(result, payloads);
+    }
+
+    #[tokio::test]
+    async fn create_orders_response_reports_each_order() {
+        let uid = OrderUid([1u8; 56]);
+        let response =
+            create_orders_response(Ok(vec![Ok(uid), Err(AddOrderError::DuplicatedOrder)]))
+                .await
+                .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response_body(response).await;
+        let body: serde_json::Value = serde_json::from_slice(body.as_slice()).unwrap();
+        let expected = json!([
+            { "uid": uid },
+            { "error": { "errorType": "DuplicatedOrder", "description": "order already exists" } },
+        ]);
+        assert_eq!(body, expected);
+    }
+}
@@ -0,0 +1,388 @@
+use crate::{
+    api::{convert_json_response, rate_limiting::RateLimits},
+    database::trades::{OrderExecution, TradeRetrieving},
+};
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use model::order::{OrderKind, BUY_ETH_ADDRESS};
+use num::{BigRational, Zero as _};
+use primitive_types::{H160, U256};
+use serde::Serialize;
+use shared::{
+    conversions::{big_rational_to_float, U256Ext as _},
+    current_block::{block_number, CurrentBlockStream},
+    price_estimation::native::NativePriceEstimating,
+    surplus::{buy_order_surplus, sell_order_surplus},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+};
+use warp::{Filter, Rejection};
+
+/// Surplus and fees are in native token atoms. The USD values are only set if a USD reference
+/// token is configured and has a price.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountStats {
+    #[serde(with = "model::u256_decimal")]
+    total_surplus: U256,
+    #[serde(with = "model::u256_decimal")]
+    total_fees: U256,
+    total_surplus_usd: Option<f64>,
+    total_fees_usd: Option<f64>,
+    /// Traded orders that are left out of the totals because one of their tokens has no native
+    /// price.
+    unpriced_orders: u64,
+}
+
+/// A token whose price is used to convert native token amounts to USD.
+#[derive(Clone, Copy, Debug)]
+pub struct UsdReference {
+    pub token: H160,
+    pub decimals: u8,
+}
+
+/// Computes the surplus and fees of all trades of an account. Results are cached until the next
+/// block.
+pub struct AccountStatistics {
+    database: Arc<dyn TradeRetrieving>,
+    /// Should be a short lived cache that is shared between owners but not the one for auctions
+    /// because that cache keeps updating every price it was asked for and accounts can have traded
+    /// any token.
+    native_price_estimator: Arc<dyn NativePriceEstimating>,
+    native_token: H160,
+    usd_reference: Option<UsdReference>,
+    current_block: CurrentBlockStream,
+    cache: Mutex<Cache>,
+}
+
+#[derive(Default)]
+struct Cache {
+    block: u64,
+    stats: HashMap<H160, AccountStats>,
+}
+
+impl AccountStatistics {
+    pub fn new(
+        database: Arc<dyn TradeRetrieving>,
+        native_price_estimator: Arc<dyn NativePriceEstimating>,
+        native_token: H160,
+        usd_reference: Option<UsdReference>,
+        current_block: CurrentBlockStream,
+    ) -> Self {
+        Self {
+            database,
+            native_price_estimator,
+            native_token,
+            usd_reference,
+            current_block,
+            cache: Default::default(),
+        }
+    }
+
+    async fn get(&self, owner: &H160) -> Result<AccountStats> {
+        let block = block_number(&self.current_block.borrow())?;
+        {
+            let cache = self.cache.lock().unwrap();
+            if cache.block == block {
+                if let Some(stats) = cache.stats.get(owner) {
+                    return Ok(stats.clone());
+                }
+            }
+        }
+
+        let stats = self.compute(owner).await?;
+        let mut cache = self.cache.lock().unwrap();
+        if cache.block < block {
+            cache.block = block;
+            cache.stats.clear();
+        }
+        if cache.block == block {
+            cache.stats.insert(*owner, stats.clone());
+        }
+        Ok(stats)
+    }
+
+    async fn compute(&self, owner: &H160) -> Result<AccountStats> {
+        let executions = self
+            .database
+            .order_executions(owner)
+            .await
+            .context("order_executions")?;
+        let normalize = |token: H160| {
+            if token == BUY_ETH_ADDRESS {
+                self.native_token
+            } else {
+                token
+            }
+        };
+
+        let tokens = executions
+            .iter()
+            .flat_map(|execution| [execution.sell_token, normalize(execution.buy_token)])
+            .chain(self.usd_reference.map(|usd| usd.token))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let prices = self
+            .native_price_estimator
+            .estimate_native_prices(&tokens)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .filter_map(|(index, result)| Some((tokens[index], result.ok()?)))
+            .collect::<HashMap<_, _>>();
+
+        let mut total_surplus = 0.;
+        let mut total_fees = 0.;
+        let mut unpriced_orders = 0;
+        for execution in &executions {
+            let sell_token_price = prices.get(&execution.sell_token);
+            let buy_token_price = prices.get(&normalize(execution.buy_token));
+            let (sell_token_price, buy_token_price) = match (sell_token_price, buy_token_price) {
+                (Some(sell), Some(buy)) => (*sell, *buy),
+                _ => {
+                    unpriced_orders += 1;
+                    continue;
+                }
+            };
+            total_surplus +=
+                order_surplus(execution, sell_token_price, buy_token_price).unwrap_or_default();
+            total_fees += execution.executed_fee_amount.to_f64_lossy() * sell_token_price;
+        }
+
+        let to_usd = |amount: f64| {
+            let usd = self.usd_reference?;
+            let price = prices.get(&usd.token).filter(|price| **price > 0.)?;
+            Some(amount / price / 10f64.powi(usd.decimals as i32))
+        };
+        Ok(AccountStats {
+            total_surplus: U256::from_f64_lossy(total_surplus),
+            total_fees: U256::from_f64_lossy(total_fees),
+            total_surplus_usd: to_usd(total_surplus),
+            total_fees_usd: to_usd(total_fees),
+            unpriced_orders,
+        })
+    }
+}
+
+/// The surplus of the order in native token atoms.
+///
+/// Uses the surplus definitions of the solver with the uniform clearing prices implied by the
+/// executed amounts. The surplus is denominated in the buy token for sell orders and in the sell
+/// token for buy orders so the clearing price of that token is its native price.
+fn order_surplus(
+    execution: &OrderExecution,
+    sell_token_price: f64,
+    buy_token_price: f64,
+) -> Option<f64> {
+    let sell_amount_limit = execution.sell_amount.to_big_rational();
+    let buy_amount_limit = execution.buy_amount.to_big_rational();
+    let executed_sell_amount = execution.executed_sell_amount.to_big_rational();
+    let executed_buy_amount = execution.executed_buy_amount.to_big_rational();
+    let surplus = match execution.kind {
+        OrderKind::Sell => {
+            if executed_sell_amount.is_zero() {
+                return None;
+            }
+            let buy_token_price = BigRational::from_float(buy_token_price)?;
+            let sell_token_price = &buy_token_price * &executed_buy_amount / &executed_sell_amount;
+            sell_order_surplus(
+                &sell_token_price,
+                &buy_token_price,
+                &sell_amount_limit,
+                &buy_amount_limit,
+                &executed_sell_amount,
+            )?
+        }
+        OrderKind::Buy => {
+            if executed_buy_amount.is_zero() {
+                return None;
+            }
+            let sell_token_price = BigRational::from_float(sell_token_price)?;
+            let buy_token_price = &sell_token_price * &executed_sell_amount / &executed_buy_amount;
+            buy_order_surplus(
+                &sell_token_price,
+                &buy_token_price,
+                &sell_amount_limit,
+                &buy_amount_limit,
+                &executed_buy_amount,
+            )?
+        }
+    };
+    big_rational_to_float(&surplus)
+}
+
+fn get_account_stats_request() -> impl Filter<Extract = (H160,), Error = Rejection> + Clone {
+    warp::path!("account" / H160 / "stats").and(warp::get())
+}
+
+pub fn get_account_stats(
+    statistics: Arc<AccountStatistics>,
+    rate_limits: Arc<RateLimits>,
+) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
+    get_account_stats_request()
+        .and(rate_limits.client_ip())
+        .and_then(move |owner: H160, ip: Option<IpAddr>| {
+            let statistics = statistics.clone();
+            let rate_limits = rate_limits.clone();
+            async move {
+                // Accounts can have traded many tokens that all need prices.
+                if let Err(reply) = rate_limits.check("account_stats", ip, None) {
+                    return Result::<_, Infallible>::Ok(reply);
+                }
+                let result = statistics.get(&owner).await;
+                Result::<_, Infallible>::Ok(convert_json_response(result))
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::trades::MockTradeRetrieving;
+    use maplit::hashmap;
+    use shared::{
+        current_block::Block,
+        price_estimation::{native::MockNativePriceEstimating, PriceEstimationError},
+    };
+    use warp::test::request;
+
+    #[tokio::test]
+    async fn get_account_stats_request_ok() {
+        let owner = H160::from_low_u64_be(1);
+        let path = format!("/account/{:?}/stats", owner);
+        let result = request()
+            .path(&path)
+            .filter(&get_account_stats_request())
+            .await
+            .unwrap();
+        assert_eq!(result, owner);
+    }
+
+    #[test]
+    fn order_surplus_uses_executed_prices() {
+        let execution = OrderExecution {
+            sell_token: H160::from_low_u64_be(1),
+            buy_token: H160::from_low_u64_be(2),
+            kind: OrderKind::Sell,
+            sell_amount: 100.into(),
+            buy_amount: 50.into(),
+            executed_sell_amount: 60.into(),
+            executed_buy_amount: 40.into(),
+            executed_fee_amount: 0.into(),
+        };
+        // The limit price only asks for 30 buy tokens so 10 buy tokens are surplus.
+        assert_eq!(order_surplus(&execution, 1., 2.), Some(20.));
+
+        let execution = OrderExecution {
+            kind: OrderKind::Buy,
+            ..execution
+        };
+        // The limit price allows selling 80 sell tokens so 20 sell tokens are surplus.
+        assert_eq!(order_surplus(&execution, 0.5, 2.), Some(10.));
+
+        let execution = OrderExecution {
+            executed_sell_amount: 0.into(),
+            executed_buy_amount: 0.into(),
+            ..execution
+        };
+        assert_eq!(order_surplus(&execution, 0.5, 2.), None);
+    }
+
+    #[tokio::test]
+    async fn computes_stats_once_per_block() {
+        let native_token = H160::from_low_u64_be(1);
+        let token = H160::from_low_u64_be(2);
+        let unpriced_token = H160::from_low_u64_be(3);
+        let usd = H160::from_low_u64_be(4);
+
+        let mut database = MockTradeRetrieving::new();
+        database
+            .expect_order_executions()
+            .times(2)
+            .returning(move |_| {
+                Ok(vec![
+                    OrderExecution {
+                        sell_token: token,
+                        buy_token: BUY_ETH_ADDRESS,
+                        kind: OrderKind::Sell,
+                        sell_amount: 100.into(),
+                        buy_amount: 50.into(),
+                        executed_sell_amount: 100.into(),
+                        executed_buy_amount: 60.into(),
+                        executed_fee_amount: 10.into(),
+                    },
+                    OrderExecution {
+                        sell_token: unpriced_token,
+                        buy_token: token,
+                        kind: OrderKind::Sell,
+                        sell_amount: 1.into(),
+                        buy_amount: 1.into(),
+                        executed_sell_amount: 1.into(),
+                        executed_buy_amount: 1.into(),
+                        executed_fee_amount: 1.into(),
+                    },
+                ])
+            });
+        let prices = hashmap! {native_token => 1., token => 0.25, usd => 0.5};
+        let mut native_price_estimator = MockNativePriceEstimating::new();
+        native_price_estimator
+            .expect_estimate_native_prices()
+            .times(2)
+            .returning(move |tokens| {
+                let results = tokens
+                    .iter()
+                    .map(|token| {
+                        prices
+                            .get(token)
+                            .copied()
+                            .ok_or(PriceEstimationError::NoLiquidity)
+                    })
+                    .enumerate()
+                    .collect::<Vec<_>>();
+                futures::stream::iter(results).boxed()
+            });
+        let (sender, receiver) = tokio::sync::watch::channel(Block {
+            number: Some(1.into()),
+            ..Default::default()
+        });
+        let statistics = AccountStatistics::new(
+            Arc::new(database),
+            Arc::new(native_price_estimator),
+            native_token,
+            Some(UsdReference {
+                token: usd,
+                decimals: 1,
+            }),
+            receiver,
+        );
+
+        let owner = H160::from_low_u64_be(5);
+        let stats = statistics.get(&owner).await.unwrap();
+        // 10 ETH atoms above the limit price and 2.5 ETH atoms of fees (truncated to 2) with one USD
+        // atom being worth 0.5 ETH atoms and one USD being 10 atoms.
+        assert_eq!(
+            stats,
+            AccountStats {
+                total_surplus: 10.into(),
+                total_fees: 2.into(),
+                total_surplus_usd: Some(2.),
+                total_fees_usd: Some(0.5),
+                unpriced_orders: 1,
+            }
+        );
+        assert_eq!(statistics.get(&owner).await.unwrap(), stats);
+
+        sender
+            .send(Block {
+                number: Some(2.into()),
+                ..Default::default()
+            })
+            .unwrap();
+        statistics.get(&owner).await.unwrap();
+    }
+}
@@ -0,0 +1,371 @@
+use super::get_markets::Market;
+use crate::{
+    api::convert_json_response,
+    database::trades::{MarketTrade, MarketTradeFilter, TradeRetrieving},
+};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use model::order::BUY_ETH_ADDRESS;
+use primitive_types::{H160, U256};
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+use std::{convert::Infallible, str::FromStr, sync::Arc};
+use warp::{hyper::StatusCode, Filter, Rejection};
+
+const MAX_CANDLES: i64 = 1000;
+const MAX_INTERVAL_DAYS: i64 = 365;
+
+/// The length of a candle. Parsed from a number followed by one of the units `m`, `h` or `d` like
+/// "15m" or "4h".
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct CandleInterval(Duration);
+
+impl FromStr for CandleInterval {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let split = s
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(|| anyhow!("interval \"{}\" has no unit", s))?;
+        let (count, unit) = s.split_at(split);
+        let count: i64 = count
+            .parse()
+            .with_context(|| format!("can not parse interval \"{}\"", s))?;
+        let unit = match unit {
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            _ => return Err(anyhow!("unknown interval unit \"{}\"", unit)),
+        };
+        let interval = count
+            .checked_mul(unit)
+            .filter(|seconds| (1..=MAX_INTERVAL_DAYS * 24 * 60 * 60).contains(seconds))
+            .map(Duration::seconds)
+            .ok_or_else(|| {
+                anyhow!(
+                    "interval must be positive and at most {} days",
+                    MAX_INTERVAL_DAYS
+                )
+            })?;
+        Ok(Self(interval))
+    }
+}
+
+#[serde_as]
+#[derive(Deserialize)]
+struct Query {
+    #[serde_as(as = "DisplayFromStr")]
+    interval: CandleInterval,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct CandlesQuery {
+    market: Market,
+    interval: CandleInterval,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum CandlesQueryError {
+    InvalidRange(String),
+}
+
+impl Query {
+    /// Defaults to the most recent candles ending at `now`.
+    fn validate(
+        self,
+        market: Market,
+        now: DateTime<Utc>,
+    ) -> Result<CandlesQuery, CandlesQueryError> {
+        let interval = self.interval.0;
+        let to = self.to.unwrap_or(now);
+        let from = self
+            .from
+            .unwrap_or(to - Duration::seconds(interval.num_seconds() * MAX_CANDLES));
+        if from >= to {
+            return Err(CandlesQueryError::InvalidRange(
+                "from must be before to.".to_owned(),
+            ));
+        }
+        if (to - from).num_seconds() > interval.num_seconds() * MAX_CANDLES {
+            return Err(CandlesQueryError::InvalidRange(format!(
+                "The range can contain at most {} candles.",
+                MAX_CANDLES
+            )));
+        }
+        Ok(CandlesQuery {
+            market,
+            interval: self.interval,
+            from,
+            to,
+        })
+    }
+}
+
+/// Prices are the amount of quote token atoms per base token atom. Volumes are in atoms.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Candle {
+    start: DateTime<Utc>,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    #[serde(with = "model::u256_decimal")]
+    base_volume: U256,
+    #[serde(with = "model::u256_decimal")]
+    quote_volume: U256,
+    trades: u64,
+}
+
+/// ETH is traded through orders that buy `BUY_ETH_ADDRESS` so it counts as the native token.
+fn normalize_token(token: H160, native_token: H160) -> H160 {
+    if token == BUY_ETH_ADDRESS {
+        native_token
+    } else {
+        token
+    }
+}
+
+/// Aggregates the trades of the market into candles. Expects the trades to be ordered by time.
+/// Intervals without trades have no candle.
+fn aggregate_candles(
+    trades: &[MarketTrade],
+    market: &Market,
+    native_token: H160,
+    interval: Duration,
+) -> Vec<Candle> {
+    let interval = interval.num_seconds();
+    let mut candles: Vec<Candle> = Vec::new();
+    for trade in trades {
+        let sell_token = normalize_token(trade.sell_token, native_token);
+        let buy_token = normalize_token(trade.buy_token, native_token);
+        let (base_amount, quote_amount) =
+            if (sell_token, buy_token) == (market.base_token, market.quote_token) {
+                (trade.sell_amount, trade.buy_amount)
+            } else if (sell_token, buy_token) == (market.quote_token, market.base_token) {
+                (trade.buy_amount, trade.sell_amount)
+            } else {
+                continue;
+            };
+        if base_amount.is_zero() {
+            continue;
+        }
+        let price = quote_amount.to_f64_lossy() / base_amount.to_f64_lossy();
+
+        let timestamp = trade.timestamp.timestamp();
+        let start = DateTime::from_utc(
+            NaiveDateTime::from_timestamp(timestamp - timestamp.rem_euclid(interval), 0),
+            Utc,
+        );
+        match candles.last_mut() {
+            Some(candle) if candle.start == start => {
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+                candle.close = price;
+                candle.base_volume = candle.base_volume.saturating_add(base_amount);
+                candle.quote_volume = candle.quote_volume.saturating_add(quote_amount);
+                candle.trades += 1;
+            }
+            _ => candles.push(Candle {
+                start,
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                base_volume: base_amount,
+                quote_volume: quote_amount,
+                trades: 1,
+            }),
+        }
+    }
+    candles
+}
+
+async fn candles(
+    database: &dyn TradeRetrieving,
+    native_token: H160,
+    query: &CandlesQuery,
+) -> Result<Vec<Candle>> {
+    let market = Market {
+        base_token: normalize_token(query.market.base_token, native_token),
+        quote_token: normalize_token(query.market.quote_token, native_token),
+    };
+    let trades = database
+        .market_trades(&MarketTradeFilter {
+            token_a: market.base_token,
+            token_b: market.quote_token,
+            native_token,
+            from: query.from,
+            to: query.to,
+        })
+        .await
+        .context("get_candles")?;
+    Ok(aggregate_candles(
+        &trades,
+        &market,
+        native_token,
+        query.interval.0,
+    ))
+}
+
+fn get_candles_request(
+) -> impl Filter<Extract = (Result<CandlesQuery, CandlesQueryError>,), Error = Rejection> + Clone {
+    warp::path!("markets" / Market / "candles")
+        .and(warp::get())
+        .and(warp::query::<Query>())
+        .map(|market, query: Query| query.validate(market, Utc::now()))
+}
+
+pub fn get_candles(
+    database: Arc<dyn TradeRetrieving>,
+    native_token: H160,
+) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
+    get_candles_request().and_then(move |query| {
+        let database = database.clone();
+        async move {
+            match query {
+                Ok(query) => {
+                    let result = candles(database.as_ref(), native_token, &query).await;
+                    Result::<_, Infallible>::Ok(convert_json_response(result))
+                }
+                Err(CandlesQueryError::InvalidRange(msg)) => {
+                    let err = super::error("InvalidCandleRange", msg);
+                    Ok(warp::reply::with_status(err, StatusCode::BAD_REQUEST))
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warp::test::request;
+
+    fn timestamp(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_utc(NaiveDateTime::from_timestamp(seconds, 0), Utc)
+    }
+
+    #[test]
+    fn parse_candle_interval() {
+        assert_eq!(
+            "15m".parse::<CandleInterval>().unwrap(),
+            CandleInterval(Duration::minutes(15))
+        );
+        assert_eq!(
+            "4h".parse::<CandleInterval>().unwrap(),
+            CandleInterval(Duration::hours(4))
+        );
+        assert_eq!(
+            "1d".parse::<CandleInterval>().unwrap(),
+            CandleInterval(Duration::days(1))
+        );
+        assert!("15".parse::<CandleInterval>().is_err());
+        assert!("m".parse::<CandleInterval>().is_err());
+        assert!("0h".parse::<CandleInterval>().is_err());
+        assert!("1s".parse::<CandleInterval>().is_err());
+        assert!("-1h".parse::<CandleInterval>().is_err());
+        assert!("366d".parse::<CandleInterval>().is_err());
+    }
+
+    #[tokio::test]
+    async fn get_candles_request_ok() {
+        let path = "/markets/0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2-0x6b175474e89094c44da98b954eedeac495271d0f/candles?interval=1h&from=2021-01-01T00:00:00Z&to=2021-01-02T00:00:00Z";
+        let result = request()
+            .path(path)
+            .filter(&get_candles_request())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            result,
+            CandlesQuery {
+                market: Market {
+                    base_token: testlib::tokens::WETH,
+                    quote_token: testlib::tokens::DAI,
+                },
+                interval: CandleInterval(Duration::hours(1)),
+                from: timestamp(1_609_459_200),
+                to: timestamp(1_609_545_600),
+            }
+        );
+    }
+
+    #[test]
+    fn validates_candle_range() {
+        let now = timestamp(1_600_000_000);
+        let query = |from: Option<i64>, to: Option<i64>| Query {
+            interval: CandleInterval(Duration::minutes(1)),
+            from: from.map(timestamp),
+            to: to.map(timestamp),
+        };
+
+        let result = query(None, None).validate(Default::default(), now).unwrap();
+        assert_eq!(result.to, now);
+        assert_eq!(result.from, now - Duration::minutes(MAX_CANDLES));
+
+        let result = query(Some(0), Some(60 * MAX_CANDLES)).validate(Default::default(), now);
+        assert!(result.is_ok());
+        let result = query(Some(0), Some(60 * MAX_CANDLES + 1)).validate(Default::default(), now);
+        assert!(result.is_err());
+        let result = query(Some(10), Some(10)).validate(Default::default(), now);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn aggregates_trades_into_candles() {
+        let native_token = H160::from_low_u64_be(1);
+        let token = H160::from_low_u64_be(2);
+        let market = Market {
+            base_token: native_token,
+            quote_token: token,
+        };
+        let trade =
+            |time: i64, sell_token: H160, buy_token: H160, sell: u64, buy: u64| MarketTrade {
+                timestamp: timestamp(time),
+                sell_token,
+                buy_token,
+                sell_amount: sell.into(),
+                buy_amount: buy.into(),
+            };
+        let trades = [
+            trade(60, native_token, token, 10, 20),
+            // Buying ETH counts as buying the native token.
+            trade(90, token, BUY_ETH_ADDRESS, 40, 10),
+            trade(100, token, native_token, 30, 10),
+            // Trades of other markets are ignored.
+            trade(110, token, H160::from_low_u64_be(3), 1, 1000),
+            trade(200, native_token, token, 1, 5),
+        ];
+
+        assert_eq!(
+            aggregate_candles(&trades, &market, native_token, Duration::minutes(1)),
+            vec![
+                Candle {
+                    start: timestamp(60),
+                    open: 2.,
+                    high: 4.,
+                    low: 2.,
+                    close: 3.,
+                    base_volume: 30.into(),
+                    quote_volume: 90.into(),
+                    trades: 3,
+                },
+                Candle {
+                    start: timestamp(180),
+                    open: 5.,
+                    high: 5.,
+                    low: 5.,
+                    close: 5.,
+                    base_volume: 1.into(),
+                    quote_volume: 5.into(),
+                    trades: 1,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{
+    api::convert_json_response, database::fee_reconciliations::FeeReconciliationRetrieving,
+};
+use anyhow::Result;
+use std::{convert::Infallible, sync::Arc};
+use warp::{Filter, Rejection};
+
+fn get_fee_accuracy_request() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::path!("fee_accuracy").and(warp::get())
+}
+
+pub fn get_fee_accuracy(
+    database: Arc<dyn FeeReconciliationRetrieving>,
+) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
+    get_fee_accuracy_request().and_then(move || {
+        let database = database.clone();
+        async move {
+            let result = database.fee_accuracy().await;
+            Result::<_, Infallible>::Ok(convert_json_response(result))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::response_body,
+        database::fee_reconciliations::{FeeAccuracy, MockFeeReconciliationRetrieving},
+    };
+    use warp::{hyper::StatusCode, test::request, Reply};
+
+    #[tokio::test]
+    async fn get_fee_accuracy_response() {
+        let mut database = MockFeeReconciliationRetrieving::new();
+        database.expect_fee_accuracy().returning(|| {
+            Ok(vec![FeeAccuracy {
+                count: 2,
+                mean_error: 0.5,
+                ..Default::default()
+            }])
+        });
+        let filter = get_fee_accuracy(Arc::new(database));
+
+        let response = request()
+            .path("/fee_accuracy")
+            .method("GET")
+            .filter(&filter)
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value =
+            serde_json::from_slice(&response_body(response).await).unwrap();
+        assert_eq!(body[0]["count"], 2);
+        assert_eq!(body[0]["meanError"], 0.5);
+        assert_eq!(body[0]["sellToken"], serde_json::Value::Null);
+        assert_eq!(body[0]["errorPercentiles"]["p50"], 0.);
+    }
+}
@@ -1,13 +1,14 @@
 use crate::api::{
     convert_json_response,
     post_quote::{OrderQuoteRequest, OrderQuoteResponse, OrderQuoteSide, OrderQuoter, SellAmount},
+    rate_limiting::RateLimits,
 };
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use ethcontract::{H160, U256};
 use model::u256_decimal;
 use serde::{Deserialize, Serialize};
-use std::{convert::Infallible, sync::Arc};
+use std::{convert::Infallible, net::IpAddr, sync::Arc};
 use warp::{Filter, Rejection};
 
 #[derive(Debug, Serialize)]
@@ -16,6 +17,7 @@ struct Fee {
     #[serde(with = "u256_decimal")]
     amount: U256,
     expiration_date: DateTime<Utc>,
+    applied_fee_rules: Vec<String>,
 }
 
 #[derive(Deserialize)]
@@ -56,6 +58,7 @@ impl From<OrderQuoteResponse> for SellResponse {
             fee: Fee {
                 amount: response.quote.fee_amount,
                 expiration_date: response.expiration,
+                applied_fee_rules: response.applied_fee_rules,
             },
             buy_amount_after_fee: response.quote.buy_amount,
         }
@@ -97,6 +100,7 @@ impl From<OrderQuoteResponse> for BuyResponse {
             fee: Fee {
                 amount: response.quote.fee_amount,
                 expiration_date: response.expiration,
+                applied_fee_rules: response.applied_fee_rules,
             },
             sell_amount_before_fee: response.quote.sell_amount,
         }
@@ -117,34 +121,48 @@ fn buy_request() -> impl Filter<Extract = (BuyQuery,), Error = Rejection> + Clon
 
 pub fn get_fee_and_quote_sell(
     quoter: Arc<OrderQuoter>,
+    rate_limits: Arc<RateLimits>,
 ) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
-    sell_request().and_then(move |query: SellQuery| {
-        let quoter = quoter.clone();
-        async move {
-            Result::<_, Infallible>::Ok(convert_json_response(
-                quoter
-                    .calculate_quote(&query.into())
-                    .await
-                    .map(SellResponse::from),
-            ))
-        }
-    })
+    sell_request().and(rate_limits.client_ip()).and_then(
+        move |query: SellQuery, ip: Option<IpAddr>| {
+            let quoter = quoter.clone();
+            let rate_limits = rate_limits.clone();
+            async move {
+                if let Err(reply) = rate_limits.check("fee_and_quote", ip, None) {
+                    return Result::<_, Infallible>::Ok(reply);
+                }
+                Result::<_, Infallible>::Ok(convert_json_response(
+                    quoter
+                        .calculate_quote(&query.into())
+                        .await
+                        .map(SellResponse::from),
+                ))
+            }
+        },
+    )
 }
 
 pub fn get_fee_and_quote_buy(
     quoter: Arc<OrderQuoter>,
+    rate_limits: Arc<RateLimits>,
 ) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
-    buy_request().and_then(move |query: BuyQuery| {
-        let quoter = quoter.clone();
-        async move {
-            Result::<_, Infallible>::Ok(convert_json_response(
-                quoter
-                    .calculate_quote(&query.into())
-                    .await
-                    .map(BuyResponse::from),
-            ))
-        }
-    })
+    buy_request().and(rate_limits.client_ip()).and_then(
+        move |query: BuyQuery, ip: Option<IpAddr>| {
+            let quoter = quoter.clone();
+            let rate_limits = rate_limits.clone();
+            async move {
+                if let Err(reply) = rate_limits.check("fee_and_quote", ip, None) {
+                    return Result::<_, Infallible>::Ok(reply);
+                }
+                Result::<_, Infallible>::Ok(convert_json_response(
+                    quoter
+                        .calculate_quote(&query.into())
+                        .await
+                        .map(BuyResponse::from),
+                ))
+            }
+        },
+    )
 }
 
 #[cfg(test)]
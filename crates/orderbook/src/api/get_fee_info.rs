@@ -20,6 +20,8 @@ struct FeeInfo {
     pub expiration_date: DateTime<Utc>,
     #[serde(with = "u256_decimal")]
     pub amount: U256,
+    /// The names of the fee rules that were applied to the fee.
+    pub applied_fee_rules: Vec<String>,
 }
 
 #[derive(Deserialize)]
@@ -63,9 +65,10 @@ pub fn get_fee_info(
                 )
                 .await;
             Result::<_, Infallible>::Ok(convert_json_response(result.map(
-                |(amount, expiration_date)| FeeInfo {
+                |(amount, expiration_date, applied_fee_rules)| FeeInfo {
                     expiration_date,
                     amount,
+                    applied_fee_rules,
                 },
             )))
         }
@@ -105,12 +108,14 @@ mod tests {
         let result = Ok(FeeInfo {
             expiration_date: Utc::now() + FixedOffset::east(10),
             amount: U256::zero(),
+            applied_fee_rules: vec!["partner".to_string()],
         });
         let response = convert_json_response::<_, PriceEstimationError>(result).into_response();
         assert_eq!(response.status(), StatusCode::OK);
         let body = response_body(response).await;
         let body: FeeInfo = serde_json::from_slice(body.as_slice()).unwrap();
         assert_eq!(body.amount, U256::zero());
+        assert_eq!(body.applied_fee_rules, ["partner"]);
         assert!(body.expiration_date.gt(&chrono::offset::Utc::now()))
     }
 }
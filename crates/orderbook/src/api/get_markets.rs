@@ -1,10 +1,10 @@
-use crate::api::convert_json_response;
+use crate::api::{convert_json_response, rate_limiting::RateLimits};
 use anyhow::{anyhow, Result};
 use ethcontract::{H160, U256};
 use model::order::OrderKind;
 use serde::{Deserialize, Serialize};
 use shared::price_estimation::{self, single_estimate, PriceEstimating};
-use std::{convert::Infallible, str::FromStr, sync::Arc};
+use std::{convert::Infallible, net::IpAddr, str::FromStr, sync::Arc};
 use warp::{Filter, Rejection};
 
 #[derive(Clone, Debug, PartialEq)]
@@ -31,9 +31,9 @@ impl FromStr for TokenAmount {
 }
 
 #[derive(Clone, Debug, PartialEq, Default)]
-struct Market {
-    base_token: H160,
-    quote_token: H160,
+pub struct Market {
+    pub base_token: H160,
+    pub quote_token: H160,
 }
 
 impl FromStr for Market {
@@ -67,35 +67,42 @@ fn get_amount_estimate_request(
 
 pub fn get_amount_estimate(
     price_estimator: Arc<dyn PriceEstimating>,
+    rate_limits: Arc<RateLimits>,
 ) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
-    get_amount_estimate_request().and_then(move |query: AmountEstimateQuery| {
-        let price_estimator = price_estimator.clone();
-        async move {
-            let market = &query.market;
-            let (buy_token, sell_token) = match query.kind {
-                // Buy in WETH/DAI means buying ETH (selling DAI)
-                OrderKind::Buy => (market.base_token, market.quote_token),
-                // Sell in WETH/DAI means selling ETH (buying DAI)
-                OrderKind::Sell => (market.quote_token, market.base_token),
-            };
-            let result = single_estimate(
-                price_estimator.as_ref(),
-                &price_estimation::Query {
-                    sell_token,
-                    buy_token,
-                    in_amount: query.amount,
-                    kind: query.kind,
-                },
-            )
-            .await;
-            Result::<_, Infallible>::Ok(convert_json_response(result.map(|estimate| {
-                AmountEstimateResult {
-                    amount: estimate.out_amount,
-                    token: query.market.quote_token,
+    get_amount_estimate_request()
+        .and(rate_limits.client_ip())
+        .and_then(move |query: AmountEstimateQuery, ip: Option<IpAddr>| {
+            let price_estimator = price_estimator.clone();
+            let rate_limits = rate_limits.clone();
+            async move {
+                if let Err(reply) = rate_limits.check("markets", ip, None) {
+                    return Result::<_, Infallible>::Ok(reply);
                 }
-            })))
-        }
-    })
+                let market = &query.market;
+                let (buy_token, sell_token) = match query.kind {
+                    // Buy in WETH/DAI means buying ETH (selling DAI)
+                    OrderKind::Buy => (market.base_token, market.quote_token),
+                    // Sell in WETH/DAI means selling ETH (buying DAI)
+                    OrderKind::Sell => (market.quote_token, market.base_token),
+                };
+                let result = single_estimate(
+                    price_estimator.as_ref(),
+                    &price_estimation::Query {
+                        sell_token,
+                        buy_token,
+                        in_amount: query.amount,
+                        kind: query.kind,
+                    },
+                )
+                .await;
+                Result::<_, Infallible>::Ok(convert_json_response(result.map(|estimate| {
+                    AmountEstimateResult {
+                        amount: estimate.out_amount,
+                        token: query.market.quote_token,
+                    }
+                })))
+            }
+        })
 }
 
 #[cfg(test)]
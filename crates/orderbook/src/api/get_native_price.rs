@@ -0,0 +1,177 @@
+use crate::api::{convert_json_response, rate_limiting::RateLimits};
+use anyhow::Result;
+use primitive_types::H160;
+use serde::Serialize;
+use shared::price_estimation::{
+    native::native_single_estimate,
+    native_price_cache::{CachedNativePrice, CachingNativePriceEstimator},
+    PriceEstimationError,
+};
+use std::{convert::Infallible, net::IpAddr, sync::Arc};
+use warp::{Filter, Rejection};
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NativePriceResponse {
+    /// The amount of native token atoms needed to buy one atom of the token.
+    price: f64,
+    /// Seconds since the price was estimated.
+    age: u64,
+    /// The price estimator that produced the price. Missing for prices that don't need to be
+    /// estimated like the one of the native token itself.
+    estimator: Option<String>,
+}
+
+impl From<CachedNativePrice> for NativePriceResponse {
+    fn from(cached: CachedNativePrice) -> Self {
+        Self {
+            price: cached.price,
+            age: cached.age.as_secs(),
+            estimator: cached.estimator,
+        }
+    }
+}
+
+/// Serves the native prices that the orderbook keeps cached for building auctions.
+pub struct NativePrices {
+    cache: Arc<CachingNativePriceEstimator>,
+    /// Estimates and briefly caches the prices that aren't in the auction cache. The estimates
+    /// are not added to the auction cache because that cache keeps every price it knows up to date
+    /// and requests can be for any token.
+    api_cache: Arc<CachingNativePriceEstimator>,
+}
+
+impl NativePrices {
+    pub fn new(
+        cache: Arc<CachingNativePriceEstimator>,
+        api_cache: Arc<CachingNativePriceEstimator>,
+    ) -> Self {
+        Self { cache, api_cache }
+    }
+
+    /// Returns the price if either cache has it.
+    fn cached(&self, token: &H160) -> Option<NativePriceResponse> {
+        self.cache
+            .get_cached_price(token)
+            .or_else(|| self.api_cache.get_cached_price(token))
+            .map(From::from)
+    }
+
+    /// Estimates the price and adds it to the API cache.
+    async fn estimate(&self, token: &H160) -> Result<NativePriceResponse, PriceEstimationError> {
+        let price = native_single_estimate(self.api_cache.as_ref(), token).await?;
+        Ok(self
+            .api_cache
+            .get_cached_price(token)
+            .map(From::from)
+            .unwrap_or(NativePriceResponse {
+                price,
+                age: 0,
+                estimator: None,
+            }))
+    }
+}
+
+fn get_native_price_request() -> impl Filter<Extract = (H160,), Error = Rejection> + Clone {
+    warp::path!("token" / H160 / "native_price").and(warp::get())
+}
+
+pub fn get_native_price(
+    native_prices: Arc<NativePrices>,
+    rate_limits: Arc<RateLimits>,
+) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
+    get_native_price_request()
+        .and(rate_limits.client_ip())
+        .and_then(move |token: H160, ip: Option<IpAddr>| {
+            let native_prices = native_prices.clone();
+            let rate_limits = rate_limits.clone();
+            async move {
+                let result = match native_prices.cached(&token) {
+                    Some(price) => Ok(price),
+                    // Only prices that need to be estimated count against the limit.
+                    None => {
+                        if let Err(reply) = rate_limits.check("native_price", ip, None) {
+                            return Result::<_, Infallible>::Ok(reply);
+                        }
+                        native_prices.estimate(&token).await
+                    }
+                };
+                Result::<_, Infallible>::Ok(convert_json_response(result))
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::NoopMetrics;
+    use futures::StreamExt;
+    use shared::price_estimation::native::MockNativePriceEstimating;
+    use std::time::Duration;
+    use warp::test::request;
+
+    #[tokio::test]
+    async fn get_native_price_request_ok() {
+        let token = H160::from_low_u64_be(1);
+        let filter = get_native_price_request();
+        let path = format!("/token/{:?}/native_price", token);
+        let result = request().path(&path).filter(&filter).await.unwrap();
+        assert_eq!(result, token);
+    }
+
+    #[tokio::test]
+    async fn caches_missing_prices_outside_of_auction_cache() {
+        let mut cached = MockNativePriceEstimating::new();
+        cached
+            .expect_estimate_native_prices_with_estimator()
+            .times(1)
+            .returning(|_| {
+                futures::stream::iter([(0, Ok(0.25), Some("cached".to_string()))]).boxed()
+            });
+        let mut uncached = MockNativePriceEstimating::new();
+        uncached
+            .expect_estimate_native_prices_with_estimator()
+            .times(1)
+            .returning(|_| {
+                futures::stream::iter([(0, Ok(0.5), Some("uncached".to_string()))]).boxed()
+            });
+        let cache = Arc::new(CachingNativePriceEstimator::new(
+            Box::new(cached),
+            Duration::from_secs(60),
+            Arc::new(NoopMetrics),
+        ));
+        let api_cache = Arc::new(CachingNativePriceEstimator::new(
+            Box::new(uncached),
+            Duration::from_secs(60),
+            Arc::new(NoopMetrics),
+        ));
+        let native_prices = NativePrices::new(cache.clone(), api_cache);
+
+        let cached_token = H160::from_low_u64_be(1);
+        native_single_estimate(cache.as_ref(), &cached_token)
+            .await
+            .unwrap();
+        let expected = NativePriceResponse {
+            price: 0.25,
+            age: 0,
+            estimator: Some("cached".to_string()),
+        };
+        assert_eq!(native_prices.cached(&cached_token).unwrap(), expected);
+
+        // The missing price is estimated once and then served from the API cache without adding it
+        // to the auction cache.
+        let missing_token = H160::from_low_u64_be(2);
+        assert_eq!(native_prices.cached(&missing_token), None);
+        let expected = NativePriceResponse {
+            price: 0.5,
+            age: 0,
+            estimator: Some("uncached".to_string()),
+        };
+        assert_eq!(
+            native_prices.estimate(&missing_token).await.unwrap(),
+            expected
+        );
+        assert_eq!(native_prices.cached(&missing_token).unwrap(), expected);
+        assert_eq!(cache.get_cached_price(&missing_token), None);
+    }
+}
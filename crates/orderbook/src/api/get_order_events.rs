@@ -0,0 +1,41 @@
+use crate::{api::convert_json_response, orderbook::Orderbook};
+use anyhow::Result;
+use model::order::OrderUid;
+use std::{convert::Infallible, sync::Arc};
+use warp::{Filter, Rejection};
+
+fn get_order_events_request() -> impl Filter<Extract = (OrderUid,), Error = Rejection> + Clone {
+    warp::path!("orders" / OrderUid / "events").and(warp::get())
+}
+
+pub fn get_order_events(
+    orderbook: Arc<Orderbook>,
+) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
+    get_order_events_request().and_then(move |uid| {
+        let orderbook = orderbook.clone();
+        async move {
+            let result = orderbook.get_order_events(&uid).await;
+            Result::<_, Infallible>::Ok(convert_json_response(result))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warp::test::request;
+
+    #[tokio::test]
+    async fn get_order_events_request_ok() {
+        let uid = OrderUid::from_integer(1);
+        let filter = get_order_events_request();
+        let path = format!("/orders/{}/events", uid);
+        let result = request().path(&path).filter(&filter).await.unwrap();
+        assert_eq!(result, uid);
+        assert!(request()
+            .path(&format!("/orders/{}", uid))
+            .filter(&filter)
+            .await
+            .is_err());
+    }
+}
@@ -3,6 +3,7 @@ use crate::{
     {database::orders::OrderFilter, orderbook::Orderbook},
 };
 use anyhow::{Context, Result};
+use model::order::OrderUid;
 use primitive_types::H160;
 use serde::Deserialize;
 use shared::time::now_in_epoch_seconds;
@@ -28,13 +29,20 @@ struct Query {
     include_unsupported_tokens: bool,
     #[serde(default)]
     include_presignature_pending: bool,
+    after: Option<OrderUid>,
+    limit: Option<u64>,
 }
 
+const MAX_LIMIT: u64 = 1000;
+
 impl Query {
     fn order_filter(&self) -> Result<OrderFilter, &'static str> {
         if self.owner.is_none() && self.sell_token.is_none() && self.buy_token.is_none() {
             return Err("need to set at least one of owner, sell_token, buy_token");
         }
+        if matches!(self.limit, Some(limit) if !(1..=MAX_LIMIT).contains(&limit)) {
+            return Err("limit must be between 1 and 1000");
+        }
         Ok(OrderFilter {
             min_valid_to: self.min_valid_to,
             owner: self.owner,
@@ -46,6 +54,8 @@ impl Query {
             exclude_unsupported_tokens: !self.include_unsupported_tokens,
             exclude_presignature_pending: !self.include_presignature_pending,
             uid: None,
+            after: self.after,
+            limit: self.limit,
         })
     }
 }
@@ -105,8 +115,13 @@ mod tests {
                  &includeFullyExecuted=true\
                  &includeInvalidated=true\
                  &includeInsufficientBalance=true\
-                 &includePresignaturePending=true",
-            owner, sell, buy
+                 &includePresignaturePending=true\
+                 &after={}\
+                 &limit=5",
+            owner,
+            sell,
+            buy,
+            OrderUid([1; 56])
         );
         let request = request().path(path.as_str());
         let result = order_filter(request).await.unwrap().unwrap();
@@ -118,6 +133,8 @@ mod tests {
         assert!(!result.exclude_invalidated);
         assert!(!result.exclude_insufficient_balance);
         assert!(!result.exclude_presignature_pending);
+        assert_eq!(result.after, Some(OrderUid([1; 56])));
+        assert_eq!(result.limit, Some(5));
     }
 
     #[test]
@@ -130,4 +147,17 @@ mod tests {
         };
         assert!(query.order_filter().is_err());
     }
+
+    #[test]
+    fn cannot_create_filter_with_limit_out_of_bounds() {
+        let query = |limit| Query {
+            owner: Some(H160::from_low_u64_be(1)),
+            limit: Some(limit),
+            ..Default::default()
+        };
+        assert!(query(0).order_filter().is_err());
+        assert!(query(1).order_filter().is_ok());
+        assert!(query(MAX_LIMIT).order_filter().is_ok());
+        assert!(query(MAX_LIMIT + 1).order_filter().is_err());
+    }
 }
@@ -0,0 +1,112 @@
+use crate::{api::IntoWarpReply, database::solver_competition::SolverCompetitionStoring};
+use anyhow::Result;
+use model::solver_competition::SolverCompetition;
+use primitive_types::H256;
+use std::{convert::Infallible, sync::Arc};
+use warp::{hyper::StatusCode, reply, Filter, Rejection};
+
+fn get_solver_competition_request() -> impl Filter<Extract = (u64,), Error = Rejection> + Clone {
+    warp::path!("solver_competition" / u64).and(warp::get())
+}
+
+fn get_solver_competition_by_tx_hash_request(
+) -> impl Filter<Extract = (H256,), Error = Rejection> + Clone {
+    warp::path!("solver_competition" / "by_tx_hash" / H256).and(warp::get())
+}
+
+fn get_solver_competition_response(result: Result<Option<SolverCompetition>>) -> super::ApiReply {
+    match result {
+        Ok(Some(competition)) => reply::with_status(reply::json(&competition), StatusCode::OK),
+        Ok(None) => reply::with_status(
+            super::error("NotFound", "Solver competition was not found"),
+            StatusCode::NOT_FOUND,
+        ),
+        Err(err) => err.into_warp_reply(),
+    }
+}
+
+pub fn get_solver_competition(
+    database: Arc<dyn SolverCompetitionStoring>,
+) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
+    get_solver_competition_request().and_then(move |auction_id| {
+        let database = database.clone();
+        async move {
+            let result = database.load_solver_competition(auction_id).await;
+            Result::<_, Infallible>::Ok(get_solver_competition_response(result))
+        }
+    })
+}
+
+pub fn get_solver_competition_by_tx_hash(
+    database: Arc<dyn SolverCompetitionStoring>,
+) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
+    get_solver_competition_by_tx_hash_request().and_then(move |tx_hash: H256| {
+        let database = database.clone();
+        async move {
+            let result = database.load_solver_competition_by_tx_hash(&tx_hash).await;
+            Result::<_, Infallible>::Ok(get_solver_competition_response(result))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api::response_body, database::solver_competition::MockSolverCompetitionStoring};
+    use warp::{test::request, Reply};
+
+    #[tokio::test]
+    async fn get_solver_competition_request_ok() {
+        let result = request()
+            .path("/solver_competition/1337")
+            .method("GET")
+            .filter(&get_solver_competition_request())
+            .await
+            .unwrap();
+        assert_eq!(result, 1337);
+
+        let tx_hash = H256::from_low_u64_be(1);
+        let result = request()
+            .path(&format!("/solver_competition/by_tx_hash/{:?}", tx_hash))
+            .method("GET")
+            .filter(&get_solver_competition_by_tx_hash_request())
+            .await
+            .unwrap();
+        assert_eq!(result, tx_hash);
+    }
+
+    #[tokio::test]
+    async fn get_solver_competition_responses() {
+        let mut database = MockSolverCompetitionStoring::new();
+        database
+            .expect_load_solver_competition()
+            .returning(|auction_id| {
+                Ok((auction_id == 1).then(|| SolverCompetition {
+                    auction_id,
+                    ..Default::default()
+                }))
+            });
+        let filter = get_solver_competition(Arc::new(database));
+
+        let response = request()
+            .path("/solver_competition/1")
+            .method("GET")
+            .filter(&filter)
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response_body(response).await;
+        let competition: SolverCompetition = serde_json::from_slice(&body).unwrap();
+        assert_eq!(competition.auction_id, 1);
+
+        let response = request()
+            .path("/solver_competition/2")
+            .method("GET")
+            .filter(&filter)
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
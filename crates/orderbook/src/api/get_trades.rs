@@ -1,21 +1,29 @@
 use crate::{
     api::convert_json_response,
-    database::trades::{TradeFilter, TradeRetrieving},
+    database::trades::{TradeCursor, TradeFilter, TradeRetrieving},
 };
 use anyhow::{Context, Result};
 use model::order::OrderUid;
 use primitive_types::H160;
 use serde::Deserialize;
+use serde_with::{serde_as, DisplayFromStr};
 use std::{convert::Infallible, sync::Arc};
 use warp::{hyper::StatusCode, Filter, Rejection};
 
+#[serde_as]
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Query {
     pub order_uid: Option<OrderUid>,
     pub owner: Option<H160>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub after: Option<TradeCursor>,
+    pub limit: Option<u64>,
 }
 
+const MAX_LIMIT: u64 = 1000;
+
 #[derive(Debug, Eq, PartialEq)]
 enum TradeFilterError {
     InvalidFilter(String),
@@ -26,10 +34,18 @@ impl Query {
         TradeFilter {
             order_uid: self.order_uid,
             owner: self.owner,
+            after: self.after,
+            limit: self.limit,
         }
     }
 
     fn validate(&self) -> Result<TradeFilter, TradeFilterError> {
+        if matches!(self.limit, Some(limit) if !(1..=MAX_LIMIT).contains(&limit)) {
+            return Err(TradeFilterError::InvalidFilter(format!(
+                "Limit must be between 1 and {}.",
+                MAX_LIMIT
+            )));
+        }
         match (self.order_uid.as_ref(), self.owner.as_ref()) {
             (Some(_), None) | (None, Some(_)) => Ok(self.trade_filter()),
             _ => Err(TradeFilterError::InvalidFilter(
@@ -98,6 +114,20 @@ mod tests {
             .unwrap();
         assert_eq!(result.owner, None);
         assert_eq!(result.order_uid, Some(uid));
+
+        let path = format!("/trades?owner=0x{:x}&after=12-3&limit=5", owner);
+        let result = trade_filter(request().path(path.as_str()))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            result.after,
+            Some(TradeCursor {
+                block_number: 12,
+                log_index: 3,
+            })
+        );
+        assert_eq!(result.limit, Some(5));
     }
 
     #[tokio::test]
@@ -117,5 +147,9 @@ mod tests {
         let path = "/trades";
         let result = trade_filter(request().path(path)).await.unwrap();
         assert!(result.is_err());
+
+        let path = format!("/trades?owner=0x{:x}&limit=0", owner);
+        let result = trade_filter(request().path(path.as_str())).await.unwrap();
+        assert!(result.is_err());
     }
 }
@@ -1,5 +1,6 @@
 use crate::{api::convert_json_response, orderbook::Orderbook};
 use anyhow::Result;
+use model::order::OrderUid;
 use primitive_types::H160;
 use serde::Deserialize;
 use std::{convert::Infallible, sync::Arc};
@@ -7,6 +8,7 @@ use warp::{hyper::StatusCode, reply::with_status, Filter, Rejection};
 
 #[derive(Clone, Copy, Debug, Deserialize)]
 struct Query {
+    after: Option<OrderUid>,
     offset: Option<u64>,
     limit: Option<u64>,
 }
@@ -38,7 +40,9 @@ pub fn get_user_orders(
                     StatusCode::BAD_REQUEST,
                 ));
             }
-            let result = orderbook.get_user_orders(&owner, offset, limit).await;
+            let result = orderbook
+                .get_user_orders(&owner, query.after, offset, limit)
+                .await;
             Result::<_, Infallible>::Ok(convert_json_response(result))
         }
     })
@@ -59,6 +63,7 @@ mod tests {
             .await
             .unwrap();
         assert_eq!(result.0, addr!("0000000000000000000000000000000000000001"));
+        assert_eq!(result.1.after, None);
         assert_eq!(result.1.offset, None);
         assert_eq!(result.1.limit, None);
 
@@ -71,5 +76,19 @@ mod tests {
             .unwrap();
         assert_eq!(result.1.offset, Some(1));
         assert_eq!(result.1.limit, Some(2));
+
+        let uid = OrderUid([1; 56]);
+        let path = format!(
+            "/account/0x0000000000000000000000000000000000000001/orders?after={}&limit=2",
+            uid
+        );
+        let result = warp::test::request()
+            .path(&path)
+            .method("GET")
+            .filter(&request())
+            .await
+            .unwrap();
+        assert_eq!(result.1.after, Some(uid));
+        assert_eq!(result.1.offset, None);
     }
 }
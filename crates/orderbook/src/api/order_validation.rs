@@ -1,21 +1,31 @@
 use crate::{
-    account_balances::{BalanceFetching, TransferSimulationError},
+    access_lists::BannedUsers,
+    account_balances::{BalanceFetching, Query, TransferSimulationError},
     api::IntoWarpReply,
-    fee::{FeeData, FeeParameters, GetUnsubsidizedMinFeeError, MinFeeCalculating},
+    database::quotes::{QuoteData, QuoteId, QuoteStoring},
+    fee::{FeeData, FeeParameters, GetUnsubsidizedMinFeeError, Measurement, MinFeeCalculating},
+    signature_validator::{SignatureValidating, SignatureValidationError},
 };
+use anyhow::Result;
+use chrono::Utc;
 use contracts::WETH9;
 use ethcontract::{H160, U256};
 use model::{
+    app_id::AppId,
     order::{
         BuyTokenDestination, Order, OrderCreation, OrderKind, SellTokenSource, BUY_ETH_ADDRESS,
     },
-    signature::SigningScheme,
+    signature::{hashed_eip712_message, Signature, SigningScheme},
     DomainSeparator,
 };
 use shared::{
     bad_token::BadTokenDetecting, price_estimation::PriceEstimationError, web3_traits::CodeFetching,
 };
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use warp::{http::StatusCode, reply::with_status};
 
 #[cfg_attr(test, mockall::automock)]
@@ -40,7 +50,8 @@ pub trait OrderValidating: Send + Sync {
     /// (i.e. once all the required fields on an Order are provided). Specifically, verifying that
     ///     - buy & sell amounts are non-zero,
     ///     - order's owner matches the from field (if specified),
-    ///     - fee is sufficient,
+    ///     - the owner contract accepts the signature for EIP-1271 orders,
+    ///     - fee is sufficient (or the referenced quote applies to the order),
     ///     - buy & sell tokens passed "bad token" detection,
     ///     - user has sufficient (transferable) funds to execute the order.
     ///
@@ -50,9 +61,20 @@ pub trait OrderValidating: Send + Sync {
         &self,
         order_creation: OrderCreation,
         sender: Option<H160>,
+        quote_id: Option<QuoteId>,
         domain_separator: &DomainSeparator,
         settlement_contract: H160,
     ) -> Result<(Order, FeeParameters), ValidationError>;
+
+    /// Full validation of multiple orders at once. Balance and fee lookups are shared between
+    /// the orders so that, for example, the transferability of a token only has to be checked
+    /// once for several orders of the same owner. Returns the results in the same order.
+    async fn validate_and_construct_orders(
+        &self,
+        orders: Vec<(OrderCreation, Option<H160>, Option<QuoteId>)>,
+        domain_separator: &DomainSeparator,
+        settlement_contract: H160,
+    ) -> Vec<Result<(Order, FeeParameters), ValidationError>>;
 }
 
 #[derive(Debug)]
@@ -133,12 +155,18 @@ pub enum ValidationError {
     InsufficientBalance,
     InsufficientAllowance,
     InvalidSignature,
+    // EIP-1271 orders need to specify the from field because the owner can't be recovered.
+    MissingFrom,
     // If fee and sell amount overflow u256
     SellAmountOverflow,
     TransferSimulationFailed,
     UnsupportedToken(H160),
     WrongOwner(H160),
     ZeroAmount,
+    // The referenced quote does not exist or has expired.
+    QuoteNotFound,
+    // The referenced quote was for a different owner, tokens or smaller amounts.
+    QuoteMismatch,
     Other(anyhow::Error),
 }
 
@@ -178,6 +206,13 @@ impl IntoWarpReply for ValidationError {
                 super::error("InvalidSignature", "invalid signature"),
                 StatusCode::BAD_REQUEST,
             ),
+            Self::MissingFrom => with_status(
+                super::error(
+                    "MissingFrom",
+                    "From address must be specified for EIP-1271 signatures",
+                ),
+                StatusCode::BAD_REQUEST,
+            ),
             Self::InsufficientFee => with_status(
                 super::error("InsufficientFee", "Order does not include sufficient fee"),
                 StatusCode::BAD_REQUEST,
@@ -200,6 +235,14 @@ impl IntoWarpReply for ValidationError {
                 super::error("ZeroAmount", "Buy or sell amount is zero."),
                 StatusCode::BAD_REQUEST,
             ),
+            Self::QuoteNotFound => with_status(
+                super::error("QuoteNotFound", "Quote does not exist or has expired"),
+                StatusCode::BAD_REQUEST,
+            ),
+            Self::QuoteMismatch => with_status(
+                super::error("QuoteMismatch", "Order does not match the referenced quote"),
+                StatusCode::BAD_REQUEST,
+            ),
             Self::Other(err) => with_status(
                 super::internal_error(err.context("order_validation")),
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -213,13 +256,15 @@ pub struct OrderValidator {
     /// when only part of the order data is available
     code_fetcher: Box<dyn CodeFetching>,
     native_token: WETH9,
-    banned_users: HashSet<H160>,
+    banned_users: Arc<BannedUsers>,
     liquidity_order_owners: HashSet<H160>,
     min_order_validity_period: Duration,
     /// For Full-Validation: performed time of order placement
     fee_validator: Arc<dyn MinFeeCalculating>,
     bad_token_detector: Arc<dyn BadTokenDetecting>,
     balance_fetcher: Arc<dyn BalanceFetching>,
+    signature_validator: Arc<dyn SignatureValidating>,
+    quotes: Arc<dyn QuoteStoring>,
 }
 
 #[derive(Default, Debug, PartialEq)]
@@ -263,12 +308,14 @@ impl OrderValidator {
     pub fn new(
         code_fetcher: Box<dyn CodeFetching>,
         native_token: WETH9,
-        banned_users: HashSet<H160>,
+        banned_users: Arc<BannedUsers>,
         liquidity_order_owners: HashSet<H160>,
         min_order_validity_period: Duration,
         fee_validator: Arc<dyn MinFeeCalculating>,
         bad_token_detector: Arc<dyn BadTokenDetecting>,
         balance_fetcher: Arc<dyn BalanceFetching>,
+        signature_validator: Arc<dyn SignatureValidating>,
+        quotes: Arc<dyn QuoteStoring>,
     ) -> Self {
         Self {
             code_fetcher,
@@ -279,6 +326,8 @@ impl OrderValidator {
             fee_validator,
             bad_token_detector,
             balance_fetcher,
+            signature_validator,
+            quotes,
         }
     }
 }
@@ -333,13 +382,81 @@ impl OrderValidating for OrderValidator {
         &self,
         order_creation: OrderCreation,
         sender: Option<H160>,
+        quote_id: Option<QuoteId>,
         domain_separator: &DomainSeparator,
         settlement_contract: H160,
     ) -> Result<(Order, FeeParameters), ValidationError> {
-        let owner = order_creation
-            .signature
-            .validate(domain_separator, &order_creation.hash_struct())
-            .ok_or(ValidationError::InvalidSignature)?;
+        self.validate_and_construct_order_with(
+            order_creation,
+            sender,
+            quote_id,
+            domain_separator,
+            settlement_contract,
+            self.fee_validator.as_ref(),
+            self.balance_fetcher.as_ref(),
+        )
+        .await
+    }
+
+    async fn validate_and_construct_orders(
+        &self,
+        orders: Vec<(OrderCreation, Option<H160>, Option<QuoteId>)>,
+        domain_separator: &DomainSeparator,
+        settlement_contract: H160,
+    ) -> Vec<Result<(Order, FeeParameters), ValidationError>> {
+        let fee_validator = SharedFeeLookups::new(self.fee_validator.clone());
+        let balance_fetcher = SharedBalanceLookups::new(self.balance_fetcher.clone());
+        // The orders are validated one after the other so that later orders can reuse the
+        // lookups of earlier ones.
+        let mut results = Vec::with_capacity(orders.len());
+        for (order_creation, sender, quote_id) in orders {
+            results.push(
+                self.validate_and_construct_order_with(
+                    order_creation,
+                    sender,
+                    quote_id,
+                    domain_separator,
+                    settlement_contract,
+                    &fee_validator,
+                    &balance_fetcher,
+                )
+                .await,
+            );
+        }
+        results
+    }
+}
+
+impl OrderValidator {
+    async fn validate_and_construct_order_with(
+        &self,
+        order_creation: OrderCreation,
+        sender: Option<H160>,
+        quote_id: Option<QuoteId>,
+        domain_separator: &DomainSeparator,
+        settlement_contract: H160,
+        fee_validator: &dyn MinFeeCalculating,
+        balance_fetcher: &dyn BalanceFetching,
+    ) -> Result<(Order, FeeParameters), ValidationError> {
+        let owner = match &order_creation.signature {
+            Signature::Eip1271(signature) => {
+                // The owner of a smart contract signature can't be recovered so we rely on the from
+                // field and let the owner contract decide whether it accepts the signature.
+                let owner = sender.ok_or(ValidationError::MissingFrom)?;
+                let hash = hashed_eip712_message(domain_separator, &order_creation.hash_struct());
+                self.signature_validator
+                    .validate_signature(owner, hash, signature)
+                    .await
+                    .map_err(|err| match err {
+                        SignatureValidationError::Invalid => ValidationError::InvalidSignature,
+                        SignatureValidationError::Other(err) => ValidationError::Other(err),
+                    })?;
+                owner
+            }
+            signature => signature
+                .validate(domain_separator, &order_creation.hash_struct())
+                .ok_or(ValidationError::InvalidSignature)?,
+        };
 
         if order_creation.buy_amount.is_zero() || order_creation.sell_amount.is_zero() {
             return Err(ValidationError::ZeroAmount);
@@ -363,37 +480,39 @@ impl OrderValidating for OrderValidator {
             .await
             .map_err(ValidationError::Partial)?;
 
-        let unsubsidized_fee = self
-            .fee_validator
-            .get_unsubsidized_min_fee(
-                FeeData {
-                    sell_token: order_creation.sell_token,
-                    buy_token: order_creation.buy_token,
-                    amount: match order_creation.kind {
-                        OrderKind::Buy => order_creation.buy_amount,
-                        OrderKind::Sell => order_creation.sell_amount,
+        let unsubsidized_fee = match quote_id {
+            Some(quote_id) => self.quoted_fee(quote_id, owner, &order_creation).await?,
+            None => fee_validator
+                .get_unsubsidized_min_fee(
+                    FeeData {
+                        sell_token: order_creation.sell_token,
+                        buy_token: order_creation.buy_token,
+                        amount: match order_creation.kind {
+                            OrderKind::Buy => order_creation.buy_amount,
+                            OrderKind::Sell => order_creation.sell_amount,
+                        },
+                        kind: order_creation.kind,
                     },
-                    kind: order_creation.kind,
-                },
-                order_creation.app_data,
-                order_creation.fee_amount,
-                owner,
-            )
-            .await
-            .map_err(|err| match err {
-                GetUnsubsidizedMinFeeError::Other(err) => ValidationError::Other(err),
-                GetUnsubsidizedMinFeeError::PriceEstimationError(PriceEstimationError::Other(
-                    err,
-                )) => ValidationError::Other(err),
-                GetUnsubsidizedMinFeeError::InsufficientFee => ValidationError::InsufficientFee,
-                // Some of the possible errors here have been already checked in this function or
-                // should have been checked when the order was pre-validated. There is no good way
-                // and not much need to bubble them up and we don't want to error log for them so
-                // treat them as insufficient fee.
-                GetUnsubsidizedMinFeeError::PriceEstimationError(_) => {
-                    ValidationError::InsufficientFee
-                }
-            })?;
+                    order_creation.app_data,
+                    order_creation.fee_amount,
+                    owner,
+                )
+                .await
+                .map_err(|err| match err {
+                    GetUnsubsidizedMinFeeError::Other(err) => ValidationError::Other(err),
+                    GetUnsubsidizedMinFeeError::PriceEstimationError(
+                        PriceEstimationError::Other(err),
+                    ) => ValidationError::Other(err),
+                    GetUnsubsidizedMinFeeError::InsufficientFee => ValidationError::InsufficientFee,
+                    // Some of the possible errors here have been already checked in this function
+                    // or should have been checked when the order was pre-validated. There is no
+                    // good way and not much need to bubble them up and we don't want to error log
+                    // for them so treat them as insufficient fee.
+                    GetUnsubsidizedMinFeeError::PriceEstimationError(_) => {
+                        ValidationError::InsufficientFee
+                    }
+                })?,
+        };
 
         let min_balance = match minimum_balance(&order_creation) {
             Some(amount) => amount,
@@ -402,8 +521,7 @@ impl OrderValidating for OrderValidator {
 
         // Fast path to check if transfer is possible with a single node query.
         // If not, run extra queries for additional information.
-        match self
-            .balance_fetcher
+        match balance_fetcher
             .can_transfer(
                 order_creation.sell_token,
                 owner,
@@ -442,15 +560,157 @@ impl OrderValidating for OrderValidator {
             },
         }
 
-        let order = Order::from_order_creation(
+        let mut order = Order::from_order_creation(
             &order_creation,
             domain_separator,
             settlement_contract,
             unsubsidized_fee.amount_in_sell_token(),
             owner,
         );
+        order.metadata.quote_id = quote_id;
         Ok((order, unsubsidized_fee))
     }
+
+    /// Returns the fee parameters of the referenced quote instead of looking up a fee measurement
+    /// after checking that the quote applies to the order.
+    async fn quoted_fee(
+        &self,
+        quote_id: QuoteId,
+        owner: H160,
+        order: &OrderCreation,
+    ) -> Result<FeeParameters, ValidationError> {
+        let quote = self
+            .quotes
+            .find_quote(quote_id, Utc::now())
+            .await
+            .map_err(ValidationError::Other)?
+            .ok_or(ValidationError::QuoteNotFound)?;
+        if !quote_applies_to_order(&quote, owner, order) {
+            return Err(ValidationError::QuoteMismatch);
+        }
+        if order.fee_amount < quote.fee_amount {
+            return Err(ValidationError::InsufficientFee);
+        }
+        Ok(quote.fee_parameters)
+    }
+}
+
+/// Orders can have worse limit prices than their quote (to account for slippage) but can't trade
+/// larger amounts because the quoted fee might not cover them.
+fn quote_applies_to_order(quote: &QuoteData, owner: H160, order: &OrderCreation) -> bool {
+    quote.owner == owner
+        && quote.sell_token == order.sell_token
+        && quote.buy_token == order.buy_token
+        && quote.app_data == order.app_data
+        && quote.kind == order.kind
+        && match order.kind {
+            OrderKind::Sell => order.sell_amount <= quote.sell_amount,
+            OrderKind::Buy => order.buy_amount <= quote.buy_amount,
+        }
+}
+
+/// Remembers the largest amount that was found to be transferable for every owner, token and
+/// source so that further checks for smaller amounts don't need to query the node again.
+struct SharedBalanceLookups {
+    inner: Arc<dyn BalanceFetching>,
+    transferable: Mutex<HashMap<Query, U256>>,
+}
+
+impl SharedBalanceLookups {
+    fn new(inner: Arc<dyn BalanceFetching>) -> Self {
+        Self {
+            inner,
+            transferable: Default::default(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl BalanceFetching for SharedBalanceLookups {
+    async fn get_balances(&self, queries: &[Query]) -> Vec<Result<U256>> {
+        self.inner.get_balances(queries).await
+    }
+
+    async fn can_transfer(
+        &self,
+        token: H160,
+        from: H160,
+        amount: U256,
+        source: SellTokenSource,
+    ) -> Result<(), TransferSimulationError> {
+        let query = Query {
+            owner: from,
+            token,
+            source,
+        };
+        if matches!(
+            self.transferable.lock().unwrap().get(&query),
+            Some(transferable) if *transferable >= amount
+        ) {
+            return Ok(());
+        }
+        self.inner.can_transfer(token, from, amount, source).await?;
+        let mut transferable = self.transferable.lock().unwrap();
+        let entry = transferable.entry(query).or_default();
+        *entry = (*entry).max(amount);
+        Ok(())
+    }
+}
+
+/// Remembers the unsubsidized fees that were accepted so that orders with the same fee data and
+/// at least the same fee don't need to look up fee measurements again.
+struct SharedFeeLookups {
+    inner: Arc<dyn MinFeeCalculating>,
+    accepted: Mutex<HashMap<(FeeData, AppId, H160), (U256, FeeParameters)>>,
+}
+
+impl SharedFeeLookups {
+    fn new(inner: Arc<dyn MinFeeCalculating>) -> Self {
+        Self {
+            inner,
+            accepted: Default::default(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MinFeeCalculating for SharedFeeLookups {
+    async fn compute_subsidized_min_fee(
+        &self,
+        fee_data: FeeData,
+        app_data: AppId,
+        user: H160,
+    ) -> Result<Measurement, PriceEstimationError> {
+        self.inner
+            .compute_subsidized_min_fee(fee_data, app_data, user)
+            .await
+    }
+
+    async fn get_unsubsidized_min_fee(
+        &self,
+        fee_data: FeeData,
+        app_data: AppId,
+        subsidized_fee: U256,
+        user: H160,
+    ) -> Result<FeeParameters, GetUnsubsidizedMinFeeError> {
+        let key = (fee_data, app_data, user);
+        // A fee is accepted when it is at least the subsidized fee of the measurement so any larger
+        // fee is accepted too.
+        if let Some((accepted_fee, parameters)) = self.accepted.lock().unwrap().get(&key) {
+            if subsidized_fee >= *accepted_fee {
+                return Ok(*parameters);
+            }
+        }
+        let parameters = self
+            .inner
+            .get_unsubsidized_min_fee(fee_data, app_data, subsidized_fee, user)
+            .await?;
+        self.accepted
+            .lock()
+            .unwrap()
+            .insert(key, (subsidized_fee, parameters));
+        Ok(parameters)
+    }
 }
 
 /// Returns true if the orders have same buy and sell tokens.
@@ -478,7 +738,9 @@ mod tests {
     use super::*;
     use crate::{
         account_balances::MockBalanceFetching,
+        database::quotes::MockQuoteStoring,
         fee::{GetUnsubsidizedMinFeeError, MockMinFeeCalculating},
+        signature_validator::MockSignatureValidating,
     };
     use anyhow::anyhow;
     use ethcontract::web3::signing::SecretKeyRef;
@@ -552,7 +814,7 @@ mod tests {
         let mut code_fetcher = Box::new(MockCodeFetching::new());
         let native_token = dummy_contract!(WETH9, [0xef; 20]);
         let min_order_validity_period = Duration::from_secs(1);
-        let banned_users = hashset![H160::from_low_u64_be(1)];
+        let banned_users = Arc::new(BannedUsers::new(hashset![H160::from_low_u64_be(1)]));
         let legit_valid_to =
             shared::time::now_in_epoch_seconds() + min_order_validity_period.as_secs() as u32 + 2;
         code_fetcher
@@ -568,6 +830,8 @@ mod tests {
             Arc::new(MockMinFeeCalculating::new()),
             Arc::new(MockBadTokenDetecting::new()),
             Arc::new(MockBalanceFetching::new()),
+            Arc::new(MockSignatureValidating::new()),
+            Arc::new(MockQuoteStoring::new()),
         );
         assert!(matches!(
             validator
@@ -659,12 +923,14 @@ mod tests {
         let validator = OrderValidator::new(
             code_fetcher,
             dummy_contract!(WETH9, [0xef; 20]),
-            hashset!(),
+            Default::default(),
             hashset!(),
             Duration::from_secs(1),
             Arc::new(MockMinFeeCalculating::new()),
             Arc::new(MockBadTokenDetecting::new()),
             Arc::new(MockBalanceFetching::new()),
+            Arc::new(MockSignatureValidating::new()),
+            Arc::new(MockQuoteStoring::new()),
         );
 
         assert!(matches!(
@@ -686,12 +952,14 @@ mod tests {
         let validator = OrderValidator::new(
             Box::new(MockCodeFetching::new()),
             dummy_contract!(WETH9, [0xef; 20]),
-            hashset!(),
+            Default::default(),
             hashset!(liquidity_order_owner),
             min_order_validity_period,
             Arc::new(MockMinFeeCalculating::new()),
             Arc::new(MockBadTokenDetecting::new()),
             Arc::new(MockBalanceFetching::new()),
+            Arc::new(MockSignatureValidating::new()),
+            Arc::new(MockQuoteStoring::new()),
         );
         let order = || PreOrderData {
             valid_to: shared::time::now_in_epoch_seconds()
@@ -730,12 +998,14 @@ mod tests {
         let validator = OrderValidator::new(
             Box::new(MockCodeFetching::new()),
             dummy_contract!(WETH9, [0xef; 20]),
-            hashset!(),
+            Default::default(),
             hashset!(),
             Duration::from_secs(1),
             Arc::new(fee_calculator),
             Arc::new(bad_token_detector),
             Arc::new(balance_fetcher),
+            Arc::new(MockSignatureValidating::new()),
+            Arc::new(MockQuoteStoring::new()),
         );
         let order = OrderCreation {
             valid_to: shared::time::now_in_epoch_seconds() + 2,
@@ -746,7 +1016,13 @@ mod tests {
             ..Default::default()
         };
         let (order, _) = validator
-            .validate_and_construct_order(order, None, &Default::default(), Default::default())
+            .validate_and_construct_order(
+                order,
+                None,
+                None,
+                &Default::default(),
+                Default::default(),
+            )
             .await
             .unwrap();
         assert_eq!(order.metadata.full_fee_amount, order.creation.fee_amount);
@@ -769,12 +1045,14 @@ mod tests {
         let validator = OrderValidator::new(
             Box::new(MockCodeFetching::new()),
             dummy_contract!(WETH9, [0xef; 20]),
-            hashset!(),
+            Default::default(),
             hashset!(),
             Duration::from_secs(1),
             Arc::new(fee_calculator),
             Arc::new(bad_token_detector),
             Arc::new(balance_fetcher),
+            Arc::new(MockSignatureValidating::new()),
+            Arc::new(MockQuoteStoring::new()),
         );
         let order = OrderCreation {
             valid_to: shared::time::now_in_epoch_seconds() + 2,
@@ -785,7 +1063,13 @@ mod tests {
             ..Default::default()
         };
         let result = validator
-            .validate_and_construct_order(order, None, &Default::default(), Default::default())
+            .validate_and_construct_order(
+                order,
+                None,
+                None,
+                &Default::default(),
+                Default::default(),
+            )
             .await;
         dbg!(&result);
         assert!(matches!(result, Err(ValidationError::ZeroAmount)));
@@ -808,12 +1092,14 @@ mod tests {
         let validator = OrderValidator::new(
             Box::new(MockCodeFetching::new()),
             dummy_contract!(WETH9, [0xef; 20]),
-            hashset!(),
+            Default::default(),
             hashset!(),
             Duration::from_secs(1),
             Arc::new(fee_calculator),
             Arc::new(bad_token_detector),
             Arc::new(balance_fetcher),
+            Arc::new(MockSignatureValidating::new()),
+            Arc::new(MockQuoteStoring::new()),
         );
         let order = OrderCreation {
             valid_to: shared::time::now_in_epoch_seconds() + 2,
@@ -827,6 +1113,7 @@ mod tests {
             .validate_and_construct_order(
                 order,
                 Some(Default::default()),
+                None,
                 &Default::default(),
                 Default::default(),
             )
@@ -851,12 +1138,14 @@ mod tests {
         let validator = OrderValidator::new(
             Box::new(MockCodeFetching::new()),
             dummy_contract!(WETH9, [0xef; 20]),
-            hashset!(),
+            Default::default(),
             hashset!(),
             Duration::from_secs(1),
             Arc::new(fee_calculator),
             Arc::new(bad_token_detector),
             Arc::new(balance_fetcher),
+            Arc::new(MockSignatureValidating::new()),
+            Arc::new(MockQuoteStoring::new()),
         );
         let order = OrderCreation {
             valid_to: shared::time::now_in_epoch_seconds() + 2,
@@ -867,7 +1156,13 @@ mod tests {
             ..Default::default()
         };
         let result = validator
-            .validate_and_construct_order(order, None, &Default::default(), Default::default())
+            .validate_and_construct_order(
+                order,
+                None,
+                None,
+                &Default::default(),
+                Default::default(),
+            )
             .await;
         dbg!(&result);
         assert!(matches!(result, Err(ValidationError::InsufficientFee)));
@@ -892,12 +1187,14 @@ mod tests {
         let validator = OrderValidator::new(
             Box::new(MockCodeFetching::new()),
             dummy_contract!(WETH9, [0xef; 20]),
-            hashset!(),
+            Default::default(),
             hashset!(),
             Duration::from_secs(1),
             Arc::new(fee_calculator),
             Arc::new(bad_token_detector),
             Arc::new(balance_fetcher),
+            Arc::new(MockSignatureValidating::new()),
+            Arc::new(MockQuoteStoring::new()),
         );
         let order = OrderCreation {
             valid_to: shared::time::now_in_epoch_seconds() + 2,
@@ -908,7 +1205,13 @@ mod tests {
             ..Default::default()
         };
         let result = validator
-            .validate_and_construct_order(order, None, &Default::default(), Default::default())
+            .validate_and_construct_order(
+                order,
+                None,
+                None,
+                &Default::default(),
+                Default::default(),
+            )
             .await;
         dbg!(&result);
         assert!(matches!(result, Err(ValidationError::UnsupportedToken(_))));
@@ -931,12 +1234,14 @@ mod tests {
         let validator = OrderValidator::new(
             Box::new(MockCodeFetching::new()),
             dummy_contract!(WETH9, [0xef; 20]),
-            hashset!(),
+            Default::default(),
             hashset!(),
             Duration::from_secs(1),
             Arc::new(fee_calculator),
             Arc::new(bad_token_detector),
             Arc::new(balance_fetcher),
+            Arc::new(MockSignatureValidating::new()),
+            Arc::new(MockQuoteStoring::new()),
         );
         let order = OrderCreation {
             valid_to: shared::time::now_in_epoch_seconds() + 2,
@@ -948,7 +1253,13 @@ mod tests {
             ..Default::default()
         };
         let result = validator
-            .validate_and_construct_order(order, None, &Default::default(), Default::default())
+            .validate_and_construct_order(
+                order,
+                None,
+                None,
+                &Default::default(),
+                Default::default(),
+            )
             .await;
         dbg!(&result);
         assert!(matches!(result, Err(ValidationError::SellAmountOverflow)));
@@ -971,12 +1282,14 @@ mod tests {
         let validator = OrderValidator::new(
             Box::new(MockCodeFetching::new()),
             dummy_contract!(WETH9, [0xef; 20]),
-            hashset!(),
+            Default::default(),
             hashset!(),
             Duration::from_secs(1),
             Arc::new(fee_calculator),
             Arc::new(bad_token_detector),
             Arc::new(balance_fetcher),
+            Arc::new(MockSignatureValidating::new()),
+            Arc::new(MockQuoteStoring::new()),
         );
         let order = OrderCreation {
             valid_to: shared::time::now_in_epoch_seconds() + 2,
@@ -987,12 +1300,107 @@ mod tests {
             ..Default::default()
         };
         let result = validator
-            .validate_and_construct_order(order, None, &Default::default(), Default::default())
+            .validate_and_construct_order(
+                order,
+                None,
+                None,
+                &Default::default(),
+                Default::default(),
+            )
             .await;
         dbg!(&result);
         assert!(matches!(result, Err(ValidationError::InsufficientBalance)));
     }
 
+    #[tokio::test]
+    async fn post_validate_eip1271_signature() {
+        let owner = H160([0x42; 20]);
+        let order = OrderCreation {
+            valid_to: shared::time::now_in_epoch_seconds() + 2,
+            sell_token: H160::from_low_u64_be(1),
+            buy_token: H160::from_low_u64_be(2),
+            buy_amount: U256::from(1),
+            sell_amount: U256::from(1),
+            signature: Signature::Eip1271(vec![1, 2, 3]),
+            ..Default::default()
+        };
+        let expected_hash = hashed_eip712_message(&Default::default(), &order.hash_struct());
+
+        let validator = |valid: bool| {
+            let mut fee_calculator = MockMinFeeCalculating::new();
+            let mut bad_token_detector = MockBadTokenDetecting::new();
+            let mut balance_fetcher = MockBalanceFetching::new();
+            let mut signature_validator = MockSignatureValidating::new();
+            fee_calculator
+                .expect_get_unsubsidized_min_fee()
+                .returning(|_, _, _, _| Ok(Default::default()));
+            bad_token_detector
+                .expect_detect()
+                .returning(|_| Ok(TokenQuality::Good));
+            balance_fetcher
+                .expect_can_transfer()
+                .returning(|_, _, _, _| Ok(()));
+            signature_validator
+                .expect_validate_signature()
+                .withf(move |owner_, hash, signature| {
+                    *owner_ == owner && *hash == expected_hash && signature == [1, 2, 3]
+                })
+                .returning(move |_, _, _| {
+                    if valid {
+                        Ok(())
+                    } else {
+                        Err(SignatureValidationError::Invalid)
+                    }
+                });
+            OrderValidator::new(
+                Box::new(MockCodeFetching::new()),
+                dummy_contract!(WETH9, [0xef; 20]),
+                Default::default(),
+                hashset!(),
+                Duration::from_secs(1),
+                Arc::new(fee_calculator),
+                Arc::new(bad_token_detector),
+                Arc::new(balance_fetcher),
+                Arc::new(signature_validator),
+                Arc::new(MockQuoteStoring::new()),
+            )
+        };
+
+        let result = validator(true)
+            .validate_and_construct_order(
+                order.clone(),
+                None,
+                None,
+                &Default::default(),
+                Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ValidationError::MissingFrom)));
+
+        let result = validator(false)
+            .validate_and_construct_order(
+                order.clone(),
+                Some(owner),
+                None,
+                &Default::default(),
+                Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ValidationError::InvalidSignature)));
+
+        let (order, _) = validator(true)
+            .validate_and_construct_order(
+                order,
+                Some(owner),
+                None,
+                &Default::default(),
+                Default::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(order.metadata.owner, owner);
+    }
+
     #[tokio::test]
     async fn allows_insufficient_allowance_and_balance_for_presign_orders() {
         macro_rules! assert_allows_failed_transfer {
@@ -1012,12 +1420,14 @@ mod tests {
                 let validator = OrderValidator::new(
                     Box::new(MockCodeFetching::new()),
                     dummy_contract!(WETH9, [0xef; 20]),
-                    hashset!(),
+                    Default::default(),
                     hashset!(),
                     Duration::from_secs(1),
                     Arc::new(fee_calculator),
                     Arc::new(bad_token_detector),
                     Arc::new(balance_fetcher),
+                    Arc::new(MockSignatureValidating::new()),
+                    Arc::new(MockQuoteStoring::new()),
                 );
 
                 let order = OrderBuilder::default()
@@ -1041,6 +1451,7 @@ mod tests {
                                     .build()
                                     .creation,
                                 None,
+                                None,
                                 &Default::default(),
                                 Default::default()
                             )
@@ -1054,6 +1465,7 @@ mod tests {
                         .validate_and_construct_order(
                             order.with_presign(Default::default()).build().creation,
                             None,
+                            None,
                             &Default::default(),
                             Default::default()
                         )
@@ -1066,4 +1478,178 @@ mod tests {
         assert_allows_failed_transfer!(InsufficientAllowance);
         assert_allows_failed_transfer!(InsufficientBalance);
     }
+
+    #[tokio::test]
+    async fn batch_validation_shares_lookups() {
+        let mut fee_calculator = MockMinFeeCalculating::new();
+        let mut bad_token_detector = MockBadTokenDetecting::new();
+        let mut balance_fetcher = MockBalanceFetching::new();
+        fee_calculator
+            .expect_get_unsubsidized_min_fee()
+            .times(1)
+            .returning(|_, _, _, _| Ok(Default::default()));
+        bad_token_detector
+            .expect_detect()
+            .returning(|_| Ok(TokenQuality::Good));
+        balance_fetcher
+            .expect_can_transfer()
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+        let validator = OrderValidator::new(
+            Box::new(MockCodeFetching::new()),
+            dummy_contract!(WETH9, [0xef; 20]),
+            Default::default(),
+            hashset!(),
+            Duration::from_secs(1),
+            Arc::new(fee_calculator),
+            Arc::new(bad_token_detector),
+            Arc::new(balance_fetcher),
+            Arc::new(MockSignatureValidating::new()),
+            Arc::new(MockQuoteStoring::new()),
+        );
+
+        let order = |sell_amount: u64, buy_amount: u64| {
+            OrderBuilder::default()
+                .with_valid_to(u32::MAX)
+                .with_sell_token(H160::from_low_u64_be(1))
+                .with_sell_amount(sell_amount.into())
+                .with_buy_token(H160::from_low_u64_be(2))
+                .with_buy_amount(buy_amount.into())
+                .sign_with(
+                    EcdsaSigningScheme::Eip712,
+                    &Default::default(),
+                    SecretKeyRef::new(&ONE_KEY),
+                )
+                .build()
+                .creation
+        };
+        // Both orders have the same owner, sell token, sell amount and fee so the second order can
+        // reuse the fee and balance lookups of the first one.
+        let results = validator
+            .validate_and_construct_orders(
+                vec![(order(1, 1), None, None), (order(1, 2), None, None)],
+                &Default::default(),
+                Default::default(),
+            )
+            .await;
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.is_ok()));
+    }
+
+    #[tokio::test]
+    async fn post_validate_uses_quoted_fee() {
+        let order = OrderBuilder::default()
+            .with_valid_to(u32::MAX)
+            .with_sell_token(H160::from_low_u64_be(1))
+            .with_sell_amount(10.into())
+            .with_buy_token(H160::from_low_u64_be(2))
+            .with_buy_amount(1.into())
+            .with_fee_amount(2.into())
+            .with_kind(OrderKind::Sell)
+            .sign_with(
+                EcdsaSigningScheme::Eip712,
+                &Default::default(),
+                SecretKeyRef::new(&ONE_KEY),
+            )
+            .build();
+        let owner = order.metadata.owner;
+        let order = order.creation;
+        let quote = QuoteData {
+            owner,
+            sell_token: order.sell_token,
+            buy_token: order.buy_token,
+            sell_amount: 10.into(),
+            buy_amount: 2.into(),
+            fee_amount: 2.into(),
+            app_data: order.app_data,
+            kind: OrderKind::Sell,
+            fee_parameters: FeeParameters {
+                gas_amount: 1.,
+                gas_price: 2.,
+                sell_token_price: 1.,
+            },
+            expiration: Utc::now(),
+        };
+
+        let validator = |quote: Option<QuoteData>| {
+            let mut bad_token_detector = MockBadTokenDetecting::new();
+            let mut balance_fetcher = MockBalanceFetching::new();
+            let mut quotes = MockQuoteStoring::new();
+            bad_token_detector
+                .expect_detect()
+                .returning(|_| Ok(TokenQuality::Good));
+            balance_fetcher
+                .expect_can_transfer()
+                .returning(|_, _, _, _| Ok(()));
+            quotes
+                .expect_find_quote()
+                .withf(|id, _| *id == 42)
+                .returning(move |_, _| Ok(quote.clone()));
+            // No fee measurement is looked up for orders with a quote.
+            OrderValidator::new(
+                Box::new(MockCodeFetching::new()),
+                dummy_contract!(WETH9, [0xef; 20]),
+                Default::default(),
+                hashset!(),
+                Duration::from_secs(1),
+                Arc::new(MockMinFeeCalculating::new()),
+                Arc::new(bad_token_detector),
+                Arc::new(balance_fetcher),
+                Arc::new(MockSignatureValidating::new()),
+                Arc::new(quotes),
+            )
+        };
+
+        let (validated, fee) = validator(Some(quote.clone()))
+            .validate_and_construct_order(
+                order.clone(),
+                None,
+                Some(42),
+                &Default::default(),
+                Default::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(validated.metadata.quote_id, Some(42));
+        assert_eq!(fee, quote.fee_parameters);
+
+        let result = validator(None)
+            .validate_and_construct_order(
+                order.clone(),
+                None,
+                Some(42),
+                &Default::default(),
+                Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ValidationError::QuoteNotFound)));
+
+        let result = validator(Some(QuoteData {
+            sell_amount: 9.into(),
+            ..quote.clone()
+        }))
+        .validate_and_construct_order(
+            order.clone(),
+            None,
+            Some(42),
+            &Default::default(),
+            Default::default(),
+        )
+        .await;
+        assert!(matches!(result, Err(ValidationError::QuoteMismatch)));
+
+        let result = validator(Some(QuoteData {
+            fee_amount: 3.into(),
+            ..quote
+        }))
+        .validate_and_construct_order(
+            order,
+            None,
+            Some(42),
+            &Default::default(),
+            Default::default(),
+        )
+        .await;
+        assert!(matches!(result, Err(ValidationError::InsufficientFee)));
+    }
 }
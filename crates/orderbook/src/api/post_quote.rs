@@ -2,22 +2,33 @@ use crate::{
     api::{
         self, convert_json_response,
         order_validation::{OrderValidating, PreOrderData, ValidationError},
+        rate_limiting::RateLimits,
         IntoWarpReply,
     },
+    database::quotes::{QuoteData, QuoteId, QuoteStoring},
     fee::{FeeData, MinFeeCalculating, PriceQuality},
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use ethcontract::{H160, U256};
-use futures::try_join;
+use futures::{stream::BoxStream, try_join, StreamExt};
 use model::{
     app_id::AppId,
     order::{BuyTokenDestination, OrderKind, SellTokenSource},
-    u256_decimal,
+    u256_decimal::{self, DecimalU256},
 };
 use serde::{Deserialize, Serialize};
-use shared::price_estimation::{self, single_estimate, PriceEstimating, PriceEstimationError};
-use std::{convert::Infallible, sync::Arc};
+use serde_with::serde_as;
+use shared::price_estimation::{
+    self,
+    competition::{CompetitionBreakdown, CompetitionPriceEstimator},
+    single_estimate, PriceEstimateResult, PriceEstimating, PriceEstimationError,
+};
+use std::{
+    convert::Infallible,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+};
 use warp::{hyper::StatusCode, Filter, Rejection};
 
 /// The order parameters to quote a price and fee for.
@@ -120,6 +131,56 @@ pub struct OrderQuoteResponse {
     pub quote: OrderQuote,
     pub from: H160,
     pub expiration: DateTime<Utc>,
+    /// Orders created from this quote can reference it by this id until it expires.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<QuoteId>,
+    /// The result of every price estimator. Only set for verbose quotes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimates: Option<Vec<EstimatorQuote>>,
+    /// The names of the fee rules that were applied to the fee.
+    pub applied_fee_rules: Vec<String>,
+}
+
+/// The price estimate of a single estimator for the quoted order.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimatorQuote {
+    pub estimator: String,
+    /// The estimated buy amount of sell orders or sell amount of buy orders for the amount of the
+    /// request. For sell orders with a sell amount before fees the quoted buy amount is this
+    /// amount scaled down to the sell amount after fees.
+    #[serde_as(as = "Option<DecimalU256>")]
+    pub out_amount: Option<U256>,
+    pub gas: Option<u64>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+    /// Whether the quote uses this estimate.
+    pub winner: bool,
+}
+
+impl EstimatorQuote {
+    fn from_breakdown(breakdown: &CompetitionBreakdown) -> Vec<Self> {
+        breakdown
+            .results
+            .iter()
+            .enumerate()
+            .map(|(i, result)| {
+                let (out_amount, gas, error) = match &result.result {
+                    Ok(estimate) => (Some(estimate.out_amount), Some(estimate.gas), None),
+                    Err(err) => (None, None, Some(err.to_string())),
+                };
+                Self {
+                    estimator: result.estimator.clone(),
+                    out_amount,
+                    gas,
+                    latency_ms: result.elapsed.as_millis() as u64,
+                    error,
+                    winner: i == breakdown.winner,
+                }
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug)]
@@ -174,6 +235,7 @@ struct FeeParameters {
     fee_amount: U256,
     expiration: DateTime<Utc>,
     kind: OrderKind,
+    applied_fee_rules: Vec<String>,
 }
 
 #[derive(Clone)]
@@ -183,6 +245,8 @@ pub struct OrderQuoter {
     pub order_validator: Arc<dyn OrderValidating>,
     pub fast_fee_calculator: Arc<dyn MinFeeCalculating>,
     pub fast_price_estimator: Arc<dyn PriceEstimating>,
+    pub quotes: Arc<dyn QuoteStoring>,
+    pub estimator_breakdown: Option<Arc<CompetitionPriceEstimator>>,
 }
 
 impl OrderQuoter {
@@ -190,6 +254,7 @@ impl OrderQuoter {
         fee_calculator: Arc<dyn MinFeeCalculating>,
         price_estimator: Arc<dyn PriceEstimating>,
         order_validator: Arc<dyn OrderValidating>,
+        quotes: Arc<dyn QuoteStoring>,
     ) -> Self {
         Self {
             fast_fee_calculator: fee_calculator.clone(),
@@ -197,6 +262,8 @@ impl OrderQuoter {
             fee_calculator,
             price_estimator,
             order_validator,
+            quotes,
+            estimator_breakdown: None,
         }
     }
 
@@ -210,9 +277,50 @@ impl OrderQuoter {
         self
     }
 
+    /// Enables verbose quotes which report the result of every estimator of the competition.
+    pub fn with_estimator_breakdown(mut self, estimator: Arc<CompetitionPriceEstimator>) -> Self {
+        self.estimator_breakdown = Some(estimator);
+        self
+    }
+
     pub async fn calculate_quote(
         &self,
         quote_request: &OrderQuoteRequest,
+    ) -> Result<OrderQuoteResponse, OrderQuoteError> {
+        let price_estimator = match quote_request.price_quality {
+            PriceQuality::Fast => &self.fast_price_estimator,
+            PriceQuality::Optimal => &self.price_estimator,
+        };
+        self.calculate_quote_with(quote_request, price_estimator.as_ref())
+            .await
+    }
+
+    /// Like `calculate_quote` but also includes the results of all price estimators if estimator
+    /// breakdowns are enabled. The quote uses the winning estimate of the same competition.
+    ///
+    /// The breakdown always waits for all estimators regardless of the requested price quality.
+    pub async fn calculate_verbose_quote(
+        &self,
+        quote_request: &OrderQuoteRequest,
+    ) -> Result<OrderQuoteResponse, OrderQuoteError> {
+        let estimator = match &self.estimator_breakdown {
+            Some(estimator) => BreakdownEstimator {
+                inner: estimator.clone(),
+                estimates: Default::default(),
+            },
+            None => return self.calculate_quote(quote_request).await,
+        };
+        let response = self.calculate_quote_with(quote_request, &estimator).await?;
+        Ok(OrderQuoteResponse {
+            estimates: estimator.estimates.lock().unwrap().take(),
+            ..response
+        })
+    }
+
+    async fn calculate_quote_with(
+        &self,
+        quote_request: &OrderQuoteRequest,
+        price_estimator: &dyn PriceEstimating,
     ) -> Result<OrderQuoteResponse, OrderQuoteError> {
         tracing::debug!("Received quote request {:?}", quote_request);
         self.order_validator
@@ -220,9 +328,10 @@ impl OrderQuoter {
             .await
             .map_err(|err| OrderQuoteError::Order(ValidationError::Partial(err)))?;
         let fee_parameters = self
-            .calculate_fee_parameters(quote_request)
+            .calculate_fee_parameters(quote_request, price_estimator)
             .await
             .map_err(OrderQuoteError::Fee)?;
+        let id = self.save_quote(quote_request, &fee_parameters).await;
         Ok(OrderQuoteResponse {
             quote: OrderQuote {
                 sell_token: quote_request.sell_token,
@@ -240,16 +349,65 @@ impl OrderQuoter {
             },
             from: quote_request.from,
             expiration: fee_parameters.expiration,
+            id,
+            estimates: None,
+            applied_fee_rules: fee_parameters.applied_fee_rules,
         })
     }
 
+    /// Stores the quote so that orders can use its fee. Failing to do so only means that orders
+    /// can't reference the quote so the quote is still returned without an id.
+    async fn save_quote(
+        &self,
+        quote_request: &OrderQuoteRequest,
+        fee_parameters: &FeeParameters,
+    ) -> Option<QuoteId> {
+        let fee_calculator = match quote_request.price_quality {
+            PriceQuality::Fast => &self.fast_fee_calculator,
+            PriceQuality::Optimal => &self.fee_calculator,
+        };
+        let result = async {
+            let unsubsidized_fee_parameters = fee_calculator
+                .get_unsubsidized_min_fee(
+                    quote_request.fee_data(),
+                    quote_request.app_data,
+                    fee_parameters.fee_amount,
+                    quote_request.from,
+                )
+                .await
+                .map_err(|err| anyhow!("{:?}", err))?;
+            self.quotes
+                .save_quote(&QuoteData {
+                    owner: quote_request.from,
+                    sell_token: quote_request.sell_token,
+                    buy_token: quote_request.buy_token,
+                    sell_amount: fee_parameters.sell_amount,
+                    buy_amount: fee_parameters.buy_amount,
+                    fee_amount: fee_parameters.fee_amount,
+                    app_data: quote_request.app_data,
+                    kind: fee_parameters.kind,
+                    fee_parameters: unsubsidized_fee_parameters,
+                    expiration: fee_parameters.expiration,
+                })
+                .await
+        };
+        match result.await {
+            Ok(id) => Some(id),
+            Err(err) => {
+                tracing::warn!(?err, "failed to save quote");
+                None
+            }
+        }
+    }
+
     async fn calculate_fee_parameters(
         &self,
         quote_request: &OrderQuoteRequest,
+        price_estimator: &dyn PriceEstimating,
     ) -> Result<FeeParameters, FeeError> {
-        let (fee_calculator, price_estimator) = match quote_request.price_quality {
-            PriceQuality::Fast => (&self.fast_fee_calculator, &self.fast_price_estimator),
-            PriceQuality::Optimal => (&self.fee_calculator, &self.price_estimator),
+        let fee_calculator = match quote_request.price_quality {
+            PriceQuality::Fast => &self.fast_fee_calculator,
+            PriceQuality::Optimal => &self.fee_calculator,
         };
 
         Ok(match quote_request.side {
@@ -270,7 +428,7 @@ impl OrderQuoter {
                     in_amount: sell_amount_before_fee,
                     kind: OrderKind::Sell,
                 };
-                let ((fee, expiration), estimate) = try_join!(
+                let ((fee, expiration, applied_fee_rules), estimate) = try_join!(
                     fee_calculator.compute_subsidized_min_fee(
                         FeeData {
                             sell_token: quote_request.sell_token,
@@ -281,7 +439,7 @@ impl OrderQuoter {
                         quote_request.app_data,
                         quote_request.from,
                     ),
-                    single_estimate(price_estimator.as_ref(), &query)
+                    single_estimate(price_estimator, &query)
                 )
                 .map_err(FeeError::PriceEstimate)?;
                 let sell_amount_after_fee = sell_amount_before_fee
@@ -305,6 +463,7 @@ impl OrderQuoter {
                     fee_amount: fee,
                     expiration,
                     kind: OrderKind::Sell,
+                    applied_fee_rules,
                 }
             }
             OrderQuoteSide::Sell {
@@ -325,7 +484,7 @@ impl OrderQuoter {
                 };
 
                 // Since both futures are long running and independent, run concurrently
-                let ((fee, expiration), estimate) = try_join!(
+                let ((fee, expiration, applied_fee_rules), estimate) = try_join!(
                     fee_calculator.compute_subsidized_min_fee(
                         FeeData {
                             sell_token: quote_request.sell_token,
@@ -336,7 +495,7 @@ impl OrderQuoter {
                         quote_request.app_data,
                         quote_request.from,
                     ),
-                    single_estimate(price_estimator.as_ref(), &price_estimation_query)
+                    single_estimate(price_estimator, &price_estimation_query)
                 )
                 .map_err(FeeError::PriceEstimate)?;
                 FeeParameters {
@@ -345,6 +504,7 @@ impl OrderQuoter {
                     fee_amount: fee,
                     expiration,
                     kind: OrderKind::Sell,
+                    applied_fee_rules,
                 }
             }
             OrderQuoteSide::Buy {
@@ -362,7 +522,7 @@ impl OrderQuoter {
                 };
 
                 // Since both futures are long running and independent, run concurrently
-                let ((fee, expiration), estimate) = try_join!(
+                let ((fee, expiration, applied_fee_rules), estimate) = try_join!(
                     fee_calculator.compute_subsidized_min_fee(
                         FeeData {
                             sell_token: quote_request.sell_token,
@@ -373,7 +533,7 @@ impl OrderQuoter {
                         quote_request.app_data,
                         quote_request.from,
                     ),
-                    single_estimate(price_estimator.as_ref(), &price_estimation_query)
+                    single_estimate(price_estimator, &price_estimation_query)
                 )
                 .map_err(FeeError::PriceEstimate)?;
                 let sell_amount_after_fee = estimate.out_amount;
@@ -383,12 +543,36 @@ impl OrderQuoter {
                     fee_amount: fee,
                     expiration,
                     kind: OrderKind::Buy,
+                    applied_fee_rules,
                 }
             }
         })
     }
 }
 
+/// Runs the competition of the estimator breakdown and keeps the estimates of every estimator so
+/// that the verbose quote and its estimates come from the same competition.
+struct BreakdownEstimator {
+    inner: Arc<CompetitionPriceEstimator>,
+    estimates: Mutex<Option<Vec<EstimatorQuote>>>,
+}
+
+impl PriceEstimating for BreakdownEstimator {
+    fn estimates<'a>(
+        &'a self,
+        queries: &'a [price_estimation::Query],
+    ) -> BoxStream<'_, (usize, PriceEstimateResult)> {
+        // Quotes only estimate a single query.
+        futures::stream::iter(queries.iter().enumerate())
+            .then(move |(i, query)| async move {
+                let breakdown = self.inner.breakdown(query).await;
+                *self.estimates.lock().unwrap() = Some(EstimatorQuote::from_breakdown(&breakdown));
+                (i, breakdown.into_winner())
+            })
+            .boxed()
+    }
+}
+
 impl OrderQuoteRequest {
     /// This method is used by the old, deprecated, fee endpoint to convert {Buy, Sell}Requests
     pub fn new(sell_token: H160, buy_token: H160, side: OrderQuoteSide) -> Self {
@@ -400,6 +584,30 @@ impl OrderQuoteRequest {
             ..Default::default()
         }
     }
+
+    /// The fee data the fee for this request is computed for.
+    fn fee_data(&self) -> FeeData {
+        let (amount, kind) = match self.side {
+            OrderQuoteSide::Sell {
+                sell_amount: SellAmount::BeforeFee { value } | SellAmount::AfterFee { value },
+            } => (value, OrderKind::Sell),
+            OrderQuoteSide::Buy {
+                buy_amount_after_fee,
+            } => (buy_amount_after_fee, OrderKind::Buy),
+        };
+        FeeData {
+            sell_token: self.sell_token,
+            buy_token: self.buy_token,
+            amount,
+            kind,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct Query {
+    #[serde(default)]
+    verbose: bool,
 }
 
 fn post_quote_request() -> impl Filter<Extract = (OrderQuoteRequest,), Error = Rejection> + Clone {
@@ -410,17 +618,32 @@ fn post_quote_request() -> impl Filter<Extract = (OrderQuoteRequest,), Error = R
 
 pub fn post_quote(
     quoter: Arc<OrderQuoter>,
+    rate_limits: Arc<RateLimits>,
 ) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
-    post_quote_request().and_then(move |request: OrderQuoteRequest| {
-        let quoter = quoter.clone();
-        async move {
-            let result = quoter.calculate_quote(&request).await;
-            if let Err(err) = &result {
-                tracing::warn!(?err, ?request, "post_quote error");
-            }
-            Result::<_, Infallible>::Ok(convert_json_response(result))
-        }
-    })
+    post_quote_request()
+        .and(warp::query::<Query>())
+        .and(rate_limits.client_ip())
+        .and_then(
+            move |request: OrderQuoteRequest, query: Query, ip: Option<IpAddr>| {
+                let quoter = quoter.clone();
+                let rate_limits = rate_limits.clone();
+                async move {
+                    // The `from` address of a quote isn't verified so only the IP is limited.
+                    if let Err(reply) = rate_limits.check("post_quote", ip, None) {
+                        return Result::<_, Infallible>::Ok(reply);
+                    }
+                    let result = if query.verbose {
+                        quoter.calculate_verbose_quote(&request).await
+                    } else {
+                        quoter.calculate_quote(&request).await
+                    };
+                    if let Err(err) = &result {
+                        tracing::warn!(?err, ?request, "post_quote error");
+                    }
+                    Result::<_, Infallible>::Ok(convert_json_response(result))
+                }
+            },
+        )
 }
 
 #[cfg(test)]
@@ -428,13 +651,14 @@ mod tests {
     use super::*;
     use crate::{
         api::{order_validation::MockOrderValidating, response_body},
-        fee::MockMinFeeCalculating,
+        database::quotes::MockQuoteStoring,
+        fee::{self, MockMinFeeCalculating},
     };
     use anyhow::anyhow;
     use chrono::{NaiveDateTime, Utc};
     use futures::FutureExt;
     use serde_json::json;
-    use shared::price_estimation::mocks::FakePriceEstimator;
+    use shared::price_estimation::mocks::{FailingPriceEstimator, FakePriceEstimator};
     use warp::{test::request, Reply};
 
     #[test]
@@ -584,6 +808,9 @@ mod tests {
             quote,
             from: H160::zero(),
             expiration: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(0, 0), Utc),
+            id: Some(1),
+            estimates: None,
+            applied_fee_rules: vec!["partner".to_string()],
         };
         let response = convert_json_response::<OrderQuoteResponse, OrderQuoteError>(Ok(
             order_quote_response.clone(),
@@ -617,7 +844,7 @@ mod tests {
         let expiration = Utc::now();
         fee_calculator
             .expect_compute_subsidized_min_fee()
-            .returning(move |_, _, _| Ok((3.into(), expiration)));
+            .returning(move |_, _, _| Ok((3.into(), expiration, vec!["partner".to_string()])));
 
         let fee_calculator = Arc::new(fee_calculator);
         let price_estimator = FakePriceEstimator(price_estimation::Estimate {
@@ -635,9 +862,10 @@ mod tests {
             fee_calculator,
             Arc::new(price_estimator),
             Arc::new(MockOrderValidating::new()),
+            Arc::new(MockQuoteStoring::new()),
         ));
         let result = quoter
-            .calculate_fee_parameters(&sell_query)
+            .calculate_fee_parameters(&sell_query, quoter.price_estimator.as_ref())
             .now_or_never()
             .unwrap()
             .unwrap();
@@ -650,7 +878,8 @@ mod tests {
                 sell_amount: 7.into(),
                 fee_amount: 3.into(),
                 expiration,
-                kind: OrderKind::Sell
+                kind: OrderKind::Sell,
+                applied_fee_rules: vec!["partner".to_string()],
             }
         );
     }
@@ -661,7 +890,7 @@ mod tests {
         let expiration = Utc::now();
         fee_calculator
             .expect_compute_subsidized_min_fee()
-            .returning(move |_, _, _| Ok((3.into(), expiration)));
+            .returning(move |_, _, _| Ok((3.into(), expiration, Vec::new())));
 
         let fee_calculator = Arc::new(fee_calculator);
         let price_estimator = FakePriceEstimator(price_estimation::Estimate {
@@ -680,9 +909,10 @@ mod tests {
             fee_calculator,
             Arc::new(price_estimator),
             Arc::new(MockOrderValidating::new()),
+            Arc::new(MockQuoteStoring::new()),
         ));
         let result = quoter
-            .calculate_fee_parameters(&sell_query)
+            .calculate_fee_parameters(&sell_query, quoter.price_estimator.as_ref())
             .now_or_never()
             .unwrap()
             .unwrap();
@@ -693,7 +923,8 @@ mod tests {
                 sell_amount: 7.into(),
                 fee_amount: 3.into(),
                 expiration,
-                kind: OrderKind::Sell
+                kind: OrderKind::Sell,
+                applied_fee_rules: Vec::new(),
             }
         );
     }
@@ -704,7 +935,7 @@ mod tests {
         let expiration = Utc::now();
         fee_calculator
             .expect_compute_subsidized_min_fee()
-            .returning(move |_, _, _| Ok((3.into(), expiration)));
+            .returning(move |_, _, _| Ok((3.into(), expiration, Vec::new())));
 
         let fee_calculator = Arc::new(fee_calculator);
         let price_estimator = FakePriceEstimator(price_estimation::Estimate {
@@ -722,9 +953,10 @@ mod tests {
             fee_calculator,
             Arc::new(price_estimator),
             Arc::new(MockOrderValidating::new()),
+            Arc::new(MockQuoteStoring::new()),
         ));
         let result = quoter
-            .calculate_fee_parameters(&buy_query)
+            .calculate_fee_parameters(&buy_query, quoter.price_estimator.as_ref())
             .now_or_never()
             .unwrap()
             .unwrap();
@@ -737,7 +969,8 @@ mod tests {
                 sell_amount: 20.into(),
                 fee_amount: 3.into(),
                 expiration,
-                kind: OrderKind::Buy
+                kind: OrderKind::Buy,
+                applied_fee_rules: Vec::new(),
             }
         );
     }
@@ -762,9 +995,21 @@ mod tests {
         };
 
         let mut fee_calculator = MockMinFeeCalculating::new();
+        let expiration = Utc::now();
         fee_calculator
             .expect_compute_subsidized_min_fee()
-            .returning(move |_, _, _| Ok((3.into(), Utc::now())));
+            .returning(move |_, _, _| Ok((3.into(), expiration, Vec::new())));
+        let fee_parameters = fee::FeeParameters {
+            gas_amount: 4.,
+            gas_price: 5.,
+            sell_token_price: 6.,
+        };
+        fee_calculator
+            .expect_get_unsubsidized_min_fee()
+            .withf(|fee_data, _, subsidized_fee, _| {
+                fee_data.amount == 2.into() && *subsidized_fee == 3.into()
+            })
+            .returning(move |_, _, _, _| Ok(fee_parameters));
         let price_estimator = FakePriceEstimator(price_estimation::Estimate {
             out_amount: 14.into(),
             gas: 1000,
@@ -773,10 +1018,22 @@ mod tests {
         order_validator
             .expect_partial_validate()
             .returning(|_| Ok(()));
+        let mut quotes = MockQuoteStoring::new();
+        quotes
+            .expect_save_quote()
+            .withf(move |quote| {
+                quote.sell_amount == 14.into()
+                    && quote.buy_amount == 2.into()
+                    && quote.fee_amount == 3.into()
+                    && quote.fee_parameters == fee_parameters
+                    && quote.expiration == expiration
+            })
+            .returning(|_| Ok(42));
         let quoter = Arc::new(OrderQuoter::new(
             Arc::new(fee_calculator),
             Arc::new(price_estimator),
             Arc::new(order_validator),
+            Arc::new(quotes),
         ));
         let result = quoter.calculate_quote(&buy_request).await.unwrap();
 
@@ -795,5 +1052,71 @@ mod tests {
             buy_token_balance: Default::default(),
         };
         assert_eq!(result.quote, expected);
+        assert_eq!(result.id, Some(42));
+    }
+
+    #[tokio::test]
+    async fn calculate_verbose_quote() {
+        let sell_request = OrderQuoteRequest {
+            sell_token: H160::from_low_u64_be(1),
+            buy_token: H160::from_low_u64_be(2),
+            side: OrderQuoteSide::Sell {
+                sell_amount: SellAmount::AfterFee { value: 2.into() },
+            },
+            ..Default::default()
+        };
+
+        let mut fee_calculator = MockMinFeeCalculating::new();
+        fee_calculator
+            .expect_compute_subsidized_min_fee()
+            .returning(|_, _, _| Ok((3.into(), Utc::now(), Vec::new())));
+        fee_calculator
+            .expect_get_unsubsidized_min_fee()
+            .returning(|_, _, _, _| Ok(Default::default()));
+        let mut order_validator = MockOrderValidating::new();
+        order_validator
+            .expect_partial_validate()
+            .returning(|_| Ok(()));
+        let mut quotes = MockQuoteStoring::new();
+        quotes.expect_save_quote().returning(|_| Ok(42));
+        let estimate = price_estimation::Estimate {
+            out_amount: 14.into(),
+            gas: 1000,
+        };
+        let quoter = OrderQuoter::new(
+            Arc::new(fee_calculator),
+            Arc::new(FakePriceEstimator(price_estimation::Estimate {
+                out_amount: 10.into(),
+                gas: 1000,
+            })),
+            Arc::new(order_validator),
+            Arc::new(quotes),
+        );
+
+        let result = quoter.calculate_verbose_quote(&sell_request).await.unwrap();
+        assert_eq!(result.quote.buy_amount, 10.into());
+        assert_eq!(result.estimates, None);
+
+        let estimators: Vec<(String, Arc<dyn PriceEstimating>)> = vec![
+            ("Failing".to_string(), Arc::new(FailingPriceEstimator)),
+            ("Fake".to_string(), Arc::new(FakePriceEstimator(estimate))),
+        ];
+        let quoter =
+            quoter.with_estimator_breakdown(Arc::new(CompetitionPriceEstimator::new(estimators)));
+        // The quote uses the winner of the breakdown instead of running another competition.
+        let result = quoter.calculate_verbose_quote(&sell_request).await.unwrap();
+        assert_eq!(result.quote.buy_amount, 14.into());
+        assert_eq!(result.id, Some(42));
+        let estimates = result.estimates.unwrap();
+        assert_eq!(estimates.len(), 2);
+        assert_eq!(estimates[0].estimator, "Failing");
+        assert_eq!(estimates[0].out_amount, None);
+        assert!(estimates[0].error.is_some());
+        assert!(!estimates[0].winner);
+        assert_eq!(estimates[1].estimator, "Fake");
+        assert_eq!(estimates[1].out_amount, Some(14.into()));
+        assert_eq!(estimates[1].gas, Some(1000));
+        assert_eq!(estimates[1].error, None);
+        assert!(estimates[1].winner);
     }
 }
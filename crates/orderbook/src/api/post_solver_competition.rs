@@ -0,0 +1,133 @@
+use crate::{
+    api::{extract_payload_with_max_size, IntoWarpReply},
+    database::solver_competition::SolverCompetitionStoring,
+};
+use model::solver_competition::SolverCompetition;
+use std::{convert::Infallible, sync::Arc};
+use subtle::ConstantTimeEq;
+use warp::{hyper::StatusCode, reply::with_status, Filter, Rejection};
+
+/// Competitions contain every order of every proposed settlement so they get a larger payload
+/// limit than the other routes.
+const MAX_COMPETITION_PAYLOAD: u64 = 1024 * 1024 * 10;
+
+fn post_solver_competition_request(
+) -> impl Filter<Extract = (Option<String>, SolverCompetition), Error = Rejection> + Clone {
+    warp::path!("solver_competition")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(extract_payload_with_max_size(MAX_COMPETITION_PAYLOAD))
+}
+
+/// Only the driver is allowed to report competitions. If no secret is configured nobody is.
+///
+/// The secret can be sent as is or as a bearer token. It is compared in constant time so that the
+/// response time doesn't reveal how much of it was guessed correctly.
+fn is_authorized(expected: Option<&str>, authorization: Option<&str>) -> bool {
+    let (expected, authorization) = match (expected, authorization) {
+        (Some(expected), Some(authorization)) => (expected, authorization),
+        _ => return false,
+    };
+    let token = match authorization.split_once(' ') {
+        Some((scheme, token)) if scheme.eq_ignore_ascii_case("bearer") => token.trim_start(),
+        _ => authorization,
+    };
+    expected.as_bytes().ct_eq(token.as_bytes()).into()
+}
+
+pub fn post_solver_competition(
+    database: Arc<dyn SolverCompetitionStoring>,
+    auth: Option<String>,
+) -> impl Filter<Extract = (super::ApiReply,), Error = Rejection> + Clone {
+    let auth = Arc::new(auth);
+    post_solver_competition_request().and_then(
+        move |authorization: Option<String>, competition: SolverCompetition| {
+            let database = database.clone();
+            let auth = auth.clone();
+            async move {
+                if !is_authorized(auth.as_deref(), authorization.as_deref()) {
+                    return Result::<_, Infallible>::Ok(with_status(
+                        super::error("Unauthorized", "invalid solver competition authorization"),
+                        StatusCode::UNAUTHORIZED,
+                    ));
+                }
+                let result = database.save_solver_competition(&competition).await;
+                Result::<_, Infallible>::Ok(match result {
+                    Ok(()) => with_status(warp::reply::json(&()), StatusCode::CREATED),
+                    Err(err) => err.into_warp_reply(),
+                })
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::solver_competition::MockSolverCompetitionStoring;
+    use warp::{test::request, Reply};
+
+    #[tokio::test]
+    async fn post_solver_competition_request_ok() {
+        let competition = SolverCompetition {
+            auction_id: 1,
+            ..Default::default()
+        };
+        let (authorization, result) = request()
+            .path("/solver_competition")
+            .method("POST")
+            .header("authorization", "secret")
+            .json(&competition)
+            .filter(&post_solver_competition_request())
+            .await
+            .unwrap();
+        assert_eq!(authorization.as_deref(), Some("secret"));
+        assert_eq!(result, competition);
+    }
+
+    #[test]
+    fn checks_authorization() {
+        assert!(is_authorized(Some("secret"), Some("secret")));
+        assert!(!is_authorized(Some("secret"), Some("wrong")));
+        assert!(!is_authorized(Some("secret"), Some("secre")));
+        assert!(is_authorized(Some("secret"), Some("Bearer secret")));
+        assert!(is_authorized(Some("secret"), Some("bearer secret")));
+        assert!(!is_authorized(Some("secret"), Some("Bearer wrong")));
+        assert!(!is_authorized(Some("secret"), Some("Basic secret")));
+        assert!(!is_authorized(Some("secret"), None));
+        assert!(!is_authorized(None, Some("secret")));
+        assert!(!is_authorized(None, None));
+    }
+
+    #[tokio::test]
+    async fn only_stores_authorized_competitions() {
+        let mut database = MockSolverCompetitionStoring::new();
+        database
+            .expect_save_solver_competition()
+            .times(1)
+            .returning(|_| Ok(()));
+        let filter = post_solver_competition(Arc::new(database), Some("secret".to_string()));
+
+        let response = request()
+            .path("/solver_competition")
+            .method("POST")
+            .header("authorization", "wrong")
+            .json(&SolverCompetition::default())
+            .filter(&filter)
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = request()
+            .path("/solver_competition")
+            .method("POST")
+            .header("authorization", "Bearer secret")
+            .json(&SolverCompetition::default())
+            .filter(&filter)
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+}
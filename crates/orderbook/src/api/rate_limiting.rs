@@ -0,0 +1,359 @@
+use super::ApiReply;
+use anyhow::{ensure, Context, Result};
+use primitive_types::H160;
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    hash::Hash,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use warp::{hyper::StatusCode, reply::with_status, Filter};
+
+pub trait Metrics: Send + Sync {
+    fn request_rate_limited(&self, endpoint: &str, key: &str);
+}
+
+/// A token bucket limit: at most `capacity` requests at once with the bucket refilling completely
+/// over `period`.
+///
+/// Parsed from "<requests>/<seconds>" so "60/60" allows bursts of 60 requests and one request per
+/// second on average.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    pub capacity: u32,
+    pub period: Duration,
+}
+
+impl FromStr for RateLimit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (capacity, period) = s
+            .split_once('/')
+            .with_context(|| format!("rate limit \"{}\" is not <requests>/<seconds>", s))?;
+        let capacity: u32 = capacity
+            .trim()
+            .parse()
+            .with_context(|| format!("can not parse requests \"{}\" as u32", capacity))?;
+        let period: f64 = period
+            .trim()
+            .parse()
+            .with_context(|| format!("can not parse seconds \"{}\" as f64", period))?;
+        ensure!(capacity > 0, "rate limit must allow at least one request");
+        ensure!(
+            period.is_finite() && period > 0.,
+            "rate limit period must be positive"
+        );
+        Ok(Self {
+            capacity,
+            period: Duration::from_secs_f64(period),
+        })
+    }
+}
+
+/// Keeps one token bucket per key.
+pub struct RateLimiter<K> {
+    limit: RateLimit,
+    state: Mutex<RateLimiterState<K>>,
+}
+
+struct RateLimiterState<K> {
+    buckets: HashMap<K, Bucket>,
+    last_cleanup: Instant,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Mutex::new(RateLimiterState {
+                buckets: HashMap::new(),
+                last_cleanup: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes a token from the key's bucket. Returns false if the bucket is empty.
+    pub fn try_acquire(&self, key: K, now: Instant) -> bool {
+        self.try_acquire_many(key, 1, now)
+    }
+
+    /// Takes `tokens` tokens from the key's bucket. Returns false and takes nothing if the bucket
+    /// doesn't hold enough tokens.
+    pub fn try_acquire_many(&self, key: K, tokens: u32, now: Instant) -> bool {
+        let capacity = self.limit.capacity as f64;
+        let refill = |bucket: &Bucket| {
+            let elapsed = now.saturating_duration_since(bucket.updated);
+            let refilled = capacity * elapsed.as_secs_f64() / self.limit.period.as_secs_f64();
+            (bucket.tokens + refilled).min(capacity)
+        };
+
+        let mut state = self.state.lock().unwrap();
+        // A bucket that has not been used for a whole period is full again which is the same as
+        // not having a bucket at all. Dropping those keeps the map from growing without bound.
+        if now.saturating_duration_since(state.last_cleanup) >= self.limit.period {
+            state.buckets.retain(|_, bucket| refill(bucket) < capacity);
+            state.last_cleanup = now;
+        }
+
+        let bucket = state.buckets.entry(key).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        bucket.tokens = refill(bucket);
+        bucket.updated = now;
+        if bucket.tokens < tokens as f64 {
+            return false;
+        }
+        bucket.tokens -= tokens as f64;
+        true
+    }
+}
+
+/// The rate limits that apply to the expensive API routes.
+pub struct RateLimits {
+    per_owner: Option<RateLimiter<H160>>,
+    per_ip: Option<RateLimiter<IpAddr>>,
+    trusted_proxy_hops: usize,
+    metrics: Arc<dyn Metrics>,
+}
+
+impl RateLimits {
+    pub fn new(
+        per_owner: Option<RateLimit>,
+        per_ip: Option<RateLimit>,
+        trusted_proxy_hops: usize,
+        metrics: Arc<dyn Metrics>,
+    ) -> Self {
+        Self {
+            per_owner: per_owner.map(RateLimiter::new),
+            per_ip: per_ip.map(RateLimiter::new),
+            trusted_proxy_hops,
+            metrics,
+        }
+    }
+
+    /// Limits that never reject a request.
+    pub fn unlimited() -> Self {
+        Self::new(None, None, 0, Arc::new(crate::metrics::NoopMetrics))
+    }
+
+    /// Counts a request to the endpoint against the limits of the client IP and the order owner.
+    ///
+    /// The owner has to be verified, for example by recovering it from the order signature,
+    /// because otherwise clients could exhaust the limit of other owners.
+    ///
+    /// Returns the reply for the client if one of them is exceeded.
+    pub fn check(
+        &self,
+        endpoint: &str,
+        ip: Option<IpAddr>,
+        owner: Option<H160>,
+    ) -> Result<(), ApiReply> {
+        self.check_requests(endpoint, ip, owner, 1)
+    }
+
+    /// Like `check` but counts `requests` requests at once. Used for batch routes.
+    pub fn check_requests(
+        &self,
+        endpoint: &str,
+        ip: Option<IpAddr>,
+        owner: Option<H160>,
+        requests: u32,
+    ) -> Result<(), ApiReply> {
+        let now = Instant::now();
+        if let (Some(limiter), Some(ip)) = (&self.per_ip, ip) {
+            if !limiter.try_acquire_many(ip, requests, now) {
+                return Err(self.rate_limited(endpoint, "ip"));
+            }
+        }
+        if let (Some(limiter), Some(owner)) = (&self.per_owner, owner) {
+            if !limiter.try_acquire_many(owner, requests, now) {
+                return Err(self.rate_limited(endpoint, "owner"));
+            }
+        }
+        Ok(())
+    }
+
+    /// The IP address of the client.
+    ///
+    /// Behind trusted proxies the client address is the one the outermost trusted proxy added to
+    /// the `X-Forwarded-For` header. Each proxy appends the address it received the request from so
+    /// this is the entry `trusted_proxy_hops` from the end. Earlier entries are set by the client
+    /// and can't be trusted. Without trusted proxies the header is ignored.
+    pub fn client_ip(
+        &self,
+    ) -> impl Filter<Extract = (Option<IpAddr>,), Error = Infallible> + Clone {
+        let hops = self.trusted_proxy_hops;
+        warp::header::optional::<String>("x-forwarded-for")
+            .or(warp::any().map(|| None))
+            .unify()
+            .and(warp::addr::remote())
+            .map(
+                move |forwarded_for: Option<String>, remote: Option<SocketAddr>| {
+                    forwarded_ip(forwarded_for, hops).or_else(|| remote.map(|remote| remote.ip()))
+                },
+            )
+    }
+
+    fn rate_limited(&self, endpoint: &str, key: &str) -> ApiReply {
+        self.metrics.request_rate_limited(endpoint, key);
+        with_status(
+            super::error(
+                "TooManyRequests",
+                format!("rate limit per {} exceeded, try again later", key),
+            ),
+            StatusCode::TOO_MANY_REQUESTS,
+        )
+    }
+}
+
+fn forwarded_ip(forwarded_for: Option<String>, hops: usize) -> Option<IpAddr> {
+    let skip = hops.checked_sub(1)?;
+    forwarded_for?.rsplit(',').nth(skip)?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warp::{test::request, Reply};
+
+    #[test]
+    fn parse_rate_limit() {
+        assert_eq!(
+            "10/60".parse::<RateLimit>().unwrap(),
+            RateLimit {
+                capacity: 10,
+                period: Duration::from_secs(60),
+            }
+        );
+        assert_eq!(
+            "1 / 0.5".parse::<RateLimit>().unwrap(),
+            RateLimit {
+                capacity: 1,
+                period: Duration::from_millis(500),
+            }
+        );
+        assert!("10".parse::<RateLimit>().is_err());
+        assert!("0/60".parse::<RateLimit>().is_err());
+        assert!("10/0".parse::<RateLimit>().is_err());
+        assert!("10/-1".parse::<RateLimit>().is_err());
+    }
+
+    #[test]
+    fn token_bucket_refills_over_time() {
+        let limiter = RateLimiter::new(RateLimit {
+            capacity: 2,
+            period: Duration::from_secs(8),
+        });
+        let start = Instant::now();
+        assert!(limiter.try_acquire(0, start));
+        assert!(limiter.try_acquire(0, start));
+        assert!(!limiter.try_acquire(0, start));
+        // Other keys have their own bucket.
+        assert!(limiter.try_acquire(1, start));
+
+        // One token every 4 seconds.
+        assert!(!limiter.try_acquire(0, start + Duration::from_secs(2)));
+        assert!(limiter.try_acquire(0, start + Duration::from_secs(4)));
+        assert!(!limiter.try_acquire(0, start + Duration::from_secs(4)));
+
+        // Buckets never hold more than their capacity.
+        let later = start + Duration::from_secs(100);
+        assert!(limiter.try_acquire(0, later));
+        assert!(limiter.try_acquire(0, later));
+        assert!(!limiter.try_acquire(0, later));
+    }
+
+    #[test]
+    fn token_bucket_forgets_full_buckets() {
+        let limiter = RateLimiter::new(RateLimit {
+            capacity: 1,
+            period: Duration::from_secs(10),
+        });
+        let start = Instant::now();
+        for key in 0..10 {
+            assert!(limiter.try_acquire(key, start));
+        }
+        assert_eq!(limiter.state.lock().unwrap().buckets.len(), 10);
+        assert!(limiter.try_acquire(0, start + Duration::from_secs(20)));
+        assert_eq!(limiter.state.lock().unwrap().buckets.len(), 1);
+    }
+
+    #[test]
+    fn rate_limits_reply_with_too_many_requests() {
+        let limit = RateLimit {
+            capacity: 1,
+            period: Duration::from_secs(60),
+        };
+        let limits = RateLimits::new(
+            Some(limit),
+            Some(limit),
+            0,
+            Arc::new(crate::metrics::NoopMetrics),
+        );
+        let ip = Some(IpAddr::from([127, 0, 0, 1]));
+        let owner = Some(H160::from_low_u64_be(1));
+
+        assert!(limits.check("test", ip, owner).is_ok());
+        let reply = limits.check("test", ip, None).unwrap_err();
+        assert_eq!(
+            reply.into_response().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        let reply = limits.check("test", None, owner).unwrap_err();
+        assert_eq!(
+            reply.into_response().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert!(limits
+            .check("test", Some(IpAddr::from([127, 0, 0, 2])), None)
+            .is_ok());
+        assert!(RateLimits::unlimited().check("test", ip, owner).is_ok());
+    }
+
+    #[tokio::test]
+    async fn client_ip_uses_forwarded_address_of_trusted_proxies() {
+        let limits =
+            |hops| RateLimits::new(None, None, hops, Arc::new(crate::metrics::NoopMetrics));
+        let remote = SocketAddr::from(([3, 3, 3, 3], 80));
+        let client_ip = |hops| {
+            let filter = limits(hops).client_ip();
+            async move {
+                request()
+                    .header("x-forwarded-for", "1.1.1.1, 2.2.2.2")
+                    .remote_addr(remote)
+                    .filter(&filter)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        assert_eq!(client_ip(0).await, Some(IpAddr::from([3, 3, 3, 3])));
+        assert_eq!(client_ip(1).await, Some(IpAddr::from([2, 2, 2, 2])));
+        assert_eq!(client_ip(2).await, Some(IpAddr::from([1, 1, 1, 1])));
+        // The request didn't pass through as many proxies as expected.
+        assert_eq!(client_ip(3).await, Some(IpAddr::from([3, 3, 3, 3])));
+    }
+
+    #[test]
+    fn batches_count_every_request() {
+        let limiter = RateLimiter::new(RateLimit {
+            capacity: 3,
+            period: Duration::from_secs(60),
+        });
+        let now = Instant::now();
+        assert!(limiter.try_acquire_many(0, 2, now));
+        assert!(!limiter.try_acquire_many(0, 2, now));
+        assert!(limiter.try_acquire(0, now));
+        assert!(!limiter.try_acquire(0, now));
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{order_updates::OrderUpdate, orderbook::Orderbook};
+use futures::{Stream, StreamExt};
+use model::order::OrderUid;
+use primitive_types::H160;
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use warp::{
+    hyper::StatusCode,
+    reply::{with_status, Response},
+    sse, Filter, Rejection, Reply,
+};
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Query {
+    pub owner: Option<H160>,
+    pub order_uid: Option<OrderUid>,
+}
+
+impl Query {
+    fn matches(&self, update: &OrderUpdate) -> bool {
+        self.owner.map_or(true, |owner| owner == update.owner)
+            && self.order_uid.map_or(true, |uid| uid == update.uid)
+    }
+}
+
+fn stream_order_updates_request() -> impl Filter<Extract = (Query,), Error = Rejection> + Clone {
+    warp::path!("orders" / "updates")
+        .and(warp::get())
+        .and(warp::query::<Query>())
+}
+
+/// Turns the broadcasted order updates into server sent events for the ones matching the query.
+///
+/// Subscribers that fall behind miss updates. In that case they get a `lagged` event so that they
+/// know to refetch the orders they are interested in.
+fn order_update_events(
+    receiver: Receiver<OrderUpdate>,
+    query: Query,
+) -> impl Stream<Item = Result<sse::Event, warp::Error>> {
+    futures::stream::unfold(receiver, move |mut receiver| async move {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(update) if query.matches(&update) => {
+                    sse::Event::default().event("update").json_data(&update)
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => Ok(sse::Event::default()
+                    .event("lagged")
+                    .data(skipped.to_string())),
+                Err(RecvError::Closed) => return None,
+            };
+            return Some((event, receiver));
+        }
+    })
+}
+
+pub fn stream_order_updates(
+    orderbook: Arc<Orderbook>,
+) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone {
+    stream_order_updates_request().map(move |query: Query| {
+        if query.owner.is_none() && query.order_uid.is_none() {
+            return with_status(
+                super::error(
+                    "InvalidSubscription",
+                    "at least one of owner or orderUid must be specified",
+                ),
+                StatusCode::BAD_REQUEST,
+            )
+            .into_response();
+        }
+        let events = order_update_events(orderbook.subscribe_order_updates(), query);
+        sse::reply(sse::keep_alive().stream(events.boxed())).into_response()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_updates::{OrderUpdateKind, OrderUpdates};
+    use warp::test::request;
+
+    #[tokio::test]
+    async fn stream_order_updates_request_ok() {
+        let filter = stream_order_updates_request();
+
+        let owner = H160::from_low_u64_be(1);
+        let path = format!("/orders/updates?owner=0x{:x}", owner);
+        let result = request().path(&path).filter(&filter).await.unwrap();
+        assert_eq!(
+            result,
+            Query {
+                owner: Some(owner),
+                order_uid: None,
+            }
+        );
+
+        let uid = OrderUid([1; 56]);
+        let path = format!("/orders/updates?orderUid={}", uid);
+        let result = request().path(&path).filter(&filter).await.unwrap();
+        assert_eq!(
+            result,
+            Query {
+                owner: None,
+                order_uid: Some(uid),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn only_streams_matching_updates() {
+        let order_updates = OrderUpdates::default();
+        let owner = H160::from_low_u64_be(1);
+        let query = Query {
+            owner: Some(owner),
+            order_uid: None,
+        };
+        let events = order_update_events(order_updates.subscribe(), query);
+        futures::pin_mut!(events);
+
+        order_updates.notify(
+            OrderUid([1; 56]),
+            H160::from_low_u64_be(2),
+            OrderUpdateKind::Created,
+        );
+        order_updates.notify(OrderUid([2; 56]), owner, OrderUpdateKind::Cancelled);
+        drop(order_updates);
+
+        assert!(events.next().await.unwrap().is_ok());
+        assert!(events.next().await.is_none());
+    }
+}
@@ -1,12 +1,20 @@
-use std::{sync::Mutex, time::Duration};
+use std::{path::Path, sync::Mutex, time::Duration};
 
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use cached::{Cached, TimedSizedCache};
-use contracts::{CowProtocolToken, CowProtocolVirtualToken};
-use ethcontract::Web3;
+use contracts::{CowProtocolToken, CowProtocolVirtualToken, ERC20};
+use ethcontract::{
+    common::abi::{Function, ParamType, Token},
+    Web3,
+};
 use primitive_types::{H160, U256};
-use shared::transport::buffered::{Buffered, Configuration};
+use serde::Deserialize;
+use shared::{
+    transport::buffered::{Buffered, Configuration},
+    Web3Transport,
+};
 use std::collections::BTreeMap;
+use web3::types::CallRequest;
 
 const CACHE_SIZE: usize = 10_000;
 const CACHE_LIFESPAN: Duration = Duration::from_secs(60 * 60);
@@ -74,6 +82,14 @@ impl std::str::FromStr for SubsidyTiers {
     }
 }
 
+impl SubsidyTiers {
+    /// The factor of the highest tier whose threshold the balance reaches.
+    fn factor(&self, balance: U256) -> f64 {
+        let tier = self.0.range(..=balance).rev().next();
+        tier.map(|tier| *tier.1).unwrap_or(1.0)
+    }
+}
+
 pub struct CowSubsidyImpl {
     token: CowProtocolToken,
     vtoken: CowProtocolVirtualToken,
@@ -137,14 +153,208 @@ impl CowSubsidyImpl {
         )
         .await?;
         let combined = balance.saturating_add(vbalance);
-        let tier = self.subsidy_tiers.0.range(..=combined).rev().next();
-        let factor = tier.map(|tier| *tier.1).unwrap_or(1.0);
+        let factor = self.subsidy_tiers.factor(combined);
         tracing::debug!(?user, ?balance, ?vbalance, ?combined, ?factor);
         Ok(factor)
     }
 }
 
+/// A token whose balance counts towards the subsidy tiers.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SubsidyToken {
+    pub address: H160,
+    /// The balance is multiplied by this before it is added to the total.
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    /// ABI fragment of the view function returning the balance of a user. It takes the user's
+    /// address and returns a `uint256`. Defaults to ERC20 `balanceOf`.
+    #[serde(default)]
+    pub balance_function: Option<Function>,
+    /// ABI fragment of a view function converting the balance into an amount of the underlying
+    /// token like ERC-4626 `convertToAssets` for vaults and staking wrappers. It takes and returns a
+    /// `uint256`.
+    #[serde(default)]
+    pub conversion_function: Option<Function>,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+impl SubsidyToken {
+    fn validate(&self) -> Result<()> {
+        ensure!(
+            self.weight.is_finite() && self.weight >= 0.,
+            "weight of subsidy token {:?} must be a non-negative number",
+            self.address
+        );
+        let has_signature = |function: &Function, input: ParamType| {
+            function.inputs.iter().map(|param| &param.kind).eq([&input])
+                && function
+                    .outputs
+                    .iter()
+                    .map(|param| &param.kind)
+                    .eq([&ParamType::Uint(256)])
+        };
+        if let Some(function) = &self.balance_function {
+            ensure!(
+                has_signature(function, ParamType::Address),
+                "balance function {} of subsidy token {:?} must take an address and return a \
+                 uint256",
+                function.name,
+                self.address
+            );
+        }
+        if let Some(function) = &self.conversion_function {
+            ensure!(
+                has_signature(function, ParamType::Uint(256)),
+                "conversion function {} of subsidy token {:?} must take and return a uint256",
+                function.name,
+                self.address
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Reads the subsidy tokens from a JSON list.
+pub fn subsidy_tokens_from_file(path: &Path) -> Result<Vec<SubsidyToken>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read subsidy tokens from {:?}", path))?;
+    let tokens: Vec<SubsidyToken> = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse subsidy tokens from {:?}", path))?;
+    for token in &tokens {
+        token.validate()?;
+    }
+    Ok(tokens)
+}
+
+/// Like `CowSubsidyImpl` but for the weighted sum of the balances of any tokens.
+pub struct TokenHoldingSubsidy {
+    web3: Web3<Buffered<Web3Transport>>,
+    tokens: Vec<SubsidyToken>,
+    subsidy_tiers: SubsidyTiers,
+    cache: Mutex<TimedSizedCache<H160, f64>>,
+}
+
+#[async_trait::async_trait]
+impl CowSubsidy for TokenHoldingSubsidy {
+    async fn cow_subsidy_factor(&self, user: H160) -> Result<f64> {
+        if let Some(subsidy_factor) = self.cache.lock().unwrap().cache_get(&user).copied() {
+            return Ok(subsidy_factor);
+        }
+        let subsidy_factor = self.subsidy_factor_uncached(user).await?;
+        self.cache.lock().unwrap().cache_set(user, subsidy_factor);
+        Ok(subsidy_factor)
+    }
+}
+
+impl TokenHoldingSubsidy {
+    pub fn new(
+        web3: &shared::Web3,
+        tokens: Vec<SubsidyToken>,
+        subsidy_tiers: SubsidyTiers,
+    ) -> Self {
+        let cache = TimedSizedCache::with_size_and_lifespan_and_refresh(
+            CACHE_SIZE,
+            CACHE_LIFESPAN.as_secs(),
+            false,
+        );
+
+        // Batch the balance calls of all tokens and then the conversion calls.
+        let buffered = Buffered::with_config(
+            web3.transport().clone(),
+            Configuration {
+                max_concurrent_requests: None,
+                max_batch_len: tokens.len().max(1),
+                batch_delay: Duration::from_secs(1),
+            },
+        );
+
+        Self {
+            web3: Web3::new(buffered),
+            tokens,
+            subsidy_tiers,
+            cache: Mutex::new(cache),
+        }
+    }
+
+    async fn subsidy_factor_uncached(&self, user: H160) -> Result<f64> {
+        let balances = futures::future::try_join_all(
+            self.tokens
+                .iter()
+                .map(|token| self.token_balance(token, user)),
+        )
+        .await?;
+        let combined = weighted_total(
+            self.tokens
+                .iter()
+                .zip(&balances)
+                .map(|(token, balance)| (*balance, token.weight)),
+        );
+        let factor = self.subsidy_tiers.factor(combined);
+        tracing::debug!(?user, ?balances, ?combined, ?factor);
+        Ok(factor)
+    }
+
+    async fn token_balance(&self, token: &SubsidyToken, user: H160) -> Result<U256> {
+        let balance_function = match &token.balance_function {
+            Some(function) => function,
+            None => ERC20::raw_contract()
+                .abi
+                .function("balanceOf")
+                .expect("ERC20 has balanceOf"),
+        };
+        let balance = self
+            .call(token.address, balance_function, Token::Address(user))
+            .await?;
+        match &token.conversion_function {
+            Some(function) if !balance.is_zero() => {
+                self.call(token.address, function, Token::Uint(balance))
+                    .await
+            }
+            _ => Ok(balance),
+        }
+    }
+
+    async fn call(&self, address: H160, function: &Function, input: Token) -> Result<U256> {
+        let data = function.encode_input(&[input])?;
+        let output = self
+            .web3
+            .eth()
+            .call(
+                CallRequest {
+                    to: Some(address),
+                    data: Some(data.into()),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .with_context(|| format!("failed to call {} on {:?}", function.name, address))?;
+        match function.decode_output(&output.0)?.as_slice() {
+            [Token::Uint(value)] => Ok(*value),
+            tokens => Err(anyhow::anyhow!(
+                "unexpected output {:?} of {} on {:?}",
+                tokens,
+                function.name,
+                address
+            )),
+        }
+    }
+}
+
+fn weighted_total(balances: impl IntoIterator<Item = (U256, f64)>) -> U256 {
+    let total = balances
+        .into_iter()
+        .map(|(balance, weight)| balance.to_f64_lossy() * weight)
+        .sum::<f64>();
+    U256::from_f64_lossy(total)
+}
+
 #[cfg(test)]
+#[allow(clippy::float_cmp)]
 mod tests {
     use super::*;
     use hex_literal::hex;
@@ -174,4 +384,90 @@ mod tests {
             println!("{:?} {:?}", user, result);
         }
     }
+
+    #[test]
+    fn tier_factor() {
+        let tiers: SubsidyTiers = "10:0.75,150:0.5".parse().unwrap();
+        let cow = |amount: u64| U256::from(amount) * U256::exp10(18);
+        assert_eq!(tiers.factor(cow(0)), 1.0);
+        assert_eq!(tiers.factor(cow(10) - 1), 1.0);
+        assert_eq!(tiers.factor(cow(10)), 0.75);
+        assert_eq!(tiers.factor(cow(149)), 0.75);
+        assert_eq!(tiers.factor(cow(1000)), 0.5);
+    }
+
+    #[test]
+    fn parses_subsidy_tokens() {
+        let tokens: Vec<SubsidyToken> = serde_json::from_str(
+            r#"[
+                { "address": "0x0000000000000000000000000000000000000001" },
+                {
+                    "address": "0x0000000000000000000000000000000000000002",
+                    "weight": 0.5,
+                    "conversionFunction": {
+                        "type": "function",
+                        "name": "convertToAssets",
+                        "inputs": [{ "name": "shares", "type": "uint256" }],
+                        "outputs": [{ "name": "assets", "type": "uint256" }],
+                        "stateMutability": "view"
+                    }
+                }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(tokens[0].weight, 1.0);
+        assert!(tokens[0].balance_function.is_none());
+        assert_eq!(tokens[1].weight, 0.5);
+        assert_eq!(
+            tokens[1].conversion_function.as_ref().unwrap().name,
+            "convertToAssets"
+        );
+        for token in &tokens {
+            token.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_subsidy_tokens() {
+        let token = |json: &str| serde_json::from_str::<SubsidyToken>(json).unwrap();
+        assert!(token(
+            r#"{ "address": "0x0000000000000000000000000000000000000001", "weight": -1 }"#
+        )
+        .validate()
+        .is_err());
+        assert!(token(
+            r#"{
+                "address": "0x0000000000000000000000000000000000000001",
+                "balanceFunction": {
+                    "name": "balanceOf",
+                    "inputs": [{ "name": "account", "type": "address" }],
+                    "outputs": [{ "name": "", "type": "bool" }],
+                    "stateMutability": "view"
+                }
+            }"#
+        )
+        .validate()
+        .is_err());
+        assert!(token(
+            r#"{
+                "address": "0x0000000000000000000000000000000000000001",
+                "conversionFunction": {
+                    "name": "convertToAssets",
+                    "inputs": [{ "name": "account", "type": "address" }],
+                    "outputs": [{ "name": "", "type": "uint256" }],
+                    "stateMutability": "view"
+                }
+            }"#
+        )
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn weights_balances() {
+        assert_eq!(
+            weighted_total([(100.into(), 1.0), (100.into(), 0.5), (100.into(), 0.)]),
+            150.into()
+        );
+    }
 }
@@ -1,10 +1,26 @@
+pub mod auctions;
+#[cfg(test)]
+mod conformance;
 pub mod events;
+pub mod fee_reconciliations;
 pub mod fees;
 pub mod instrumented;
+pub mod memory;
+pub mod order_events;
 pub mod orders;
+pub mod quotes;
+pub mod solver_competition;
 pub mod trades;
 
+use self::{
+    auctions::AuctionStoring, fee_reconciliations::FeeReconciliationRetrieving,
+    order_events::OrderEventStoring, orders::OrderStoring, quotes::QuoteStoring,
+    solver_competition::SolverCompetitionStoring, trades::TradeRetrieving,
+};
+use crate::fee::MinFeeStoring;
 use anyhow::Result;
+use contracts::gpv2_settlement::Event as ContractEvent;
+use shared::{event_handling::EventStoring, maintenance::Maintaining, metrics::LivenessChecking};
 use sqlx::{Executor, PgPool, Row};
 use std::collections::HashMap;
 
@@ -13,7 +29,7 @@ use std::collections::HashMap;
 // enough anyway.
 
 // The names of all tables we use in the db.
-const ALL_TABLES: [&str; 7] = [
+const ALL_TABLES: [&str; 16] = [
     "orders",
     "trades",
     "invalidations",
@@ -21,12 +37,76 @@ const ALL_TABLES: [&str; 7] = [
     "settlements",
     "presignature_events",
     "order_fee_parameters",
+    "quotes",
+    "order_events",
+    "solver_competitions",
+    "archived_orders",
+    "archived_trades",
+    "archived_presignature_events",
+    "settlement_costs",
+    "fee_reconciliations",
+    "settlement_block_timestamps",
 ];
 
+/// Everything the orderbook stores. Implemented by `Postgres` and `InMemory` so that the backend
+/// can be chosen at runtime through the database url.
+pub trait Backend:
+    AuctionStoring
+    + EventStoring<ContractEvent>
+    + FeeReconciliationRetrieving
+    + MinFeeStoring
+    + OrderEventStoring
+    + OrderStoring
+    + QuoteStoring
+    + SolverCompetitionStoring
+    + TradeRetrieving
+    + Maintaining
+{
+    fn boxed_clone(&self) -> Box<dyn Backend>;
+}
+
+impl<T> Backend for T
+where
+    T: AuctionStoring
+        + EventStoring<ContractEvent>
+        + FeeReconciliationRetrieving
+        + MinFeeStoring
+        + OrderEventStoring
+        + OrderStoring
+        + QuoteStoring
+        + SolverCompetitionStoring
+        + TradeRetrieving
+        + Maintaining
+        + Clone
+        + 'static,
+{
+    fn boxed_clone(&self) -> Box<dyn Backend> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Backend> {
+    fn clone(&self) -> Self {
+        self.boxed_clone()
+    }
+}
+
 // The pool uses an Arc internally.
 #[derive(Clone)]
 pub struct Postgres {
     pool: PgPool,
+    /// Used for the read only queries of the API if set. Writes and queries that need to see the
+    /// latest writes like the solvable orders query always use the primary `pool`.
+    read_pool: Option<PgPool>,
+}
+
+/// The state of a connection pool for health checks and metrics.
+#[derive(Debug)]
+pub struct PoolStatus {
+    pub name: &'static str,
+    pub healthy: bool,
+    pub connections: u32,
+    pub idle_connections: usize,
 }
 
 // The implementation is split up into several modules which contain more public methods.
@@ -35,9 +115,48 @@ impl Postgres {
     pub fn new(uri: &str) -> Result<Self> {
         Ok(Self {
             pool: PgPool::connect_lazy(uri)?,
+            read_pool: None,
+        })
+    }
+
+    /// Like `new` but read only API queries go to the replica. These can lag behind the primary
+    /// so an order might not be found right after it was created.
+    pub fn with_read_replica(uri: &str, replica_uri: &str) -> Result<Self> {
+        Ok(Self {
+            pool: PgPool::connect_lazy(uri)?,
+            read_pool: Some(PgPool::connect_lazy(replica_uri)?),
         })
     }
 
+    fn read_pool(&self) -> &PgPool {
+        self.read_pool.as_ref().unwrap_or(&self.pool)
+    }
+
+    /// Checks that every pool can execute a query and reports its connections.
+    pub async fn pool_statuses(&self) -> Vec<PoolStatus> {
+        let mut pools = vec![("primary", &self.pool)];
+        if let Some(read_pool) = &self.read_pool {
+            pools.push(("replica", read_pool));
+        }
+        let mut statuses = Vec::new();
+        for (name, pool) in pools {
+            let healthy = match pool.execute("SELECT 1;").await {
+                Ok(_) => true,
+                Err(err) => {
+                    tracing::error!(?err, pool = name, "database health check failed");
+                    false
+                }
+            };
+            statuses.push(PoolStatus {
+                name,
+                healthy,
+                connections: pool.size(),
+                idle_connections: pool.num_idle(),
+            });
+        }
+        statuses
+    }
+
     /// Delete all data in the database. Only used by tests.
     pub async fn clear(&self) -> Result<()> {
         for table in ALL_TABLES.iter() {
@@ -63,6 +182,17 @@ impl Postgres {
     }
 }
 
+/// Alive if every connection pool can execute a query.
+#[async_trait::async_trait]
+impl LivenessChecking for Postgres {
+    async fn is_alive(&self) -> bool {
+        self.pool_statuses()
+            .await
+            .iter()
+            .all(|status| status.healthy)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -75,7 +205,7 @@ mod tests {
         db.clear().await.unwrap();
 
         let counts = db.count_rows_in_tables().await.unwrap();
-        assert_eq!(counts.len(), 7);
+        assert_eq!(counts.len(), 17);
         assert!(counts.iter().all(|(_, count)| *count == 0));
 
         db.insert_order(&Default::default(), Default::default())
@@ -84,4 +214,25 @@ mod tests {
         let counts = db.count_rows_in_tables().await.unwrap();
         assert_eq!(counts.get("orders"), Some(&1));
     }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_pool_statuses() {
+        let db = Postgres::new("postgresql://").unwrap();
+        let statuses = db.pool_statuses().await;
+        assert_eq!(statuses.len(), 1);
+        assert!(statuses[0].healthy);
+
+        let db = Postgres::with_read_replica("postgresql://", "postgresql://").unwrap();
+        let statuses = db.pool_statuses().await;
+        assert_eq!(
+            statuses
+                .iter()
+                .map(|status| status.name)
+                .collect::<Vec<_>>(),
+            ["primary", "replica"]
+        );
+        assert!(statuses.iter().all(|status| status.healthy));
+        assert!(db.is_alive().await);
+    }
 }
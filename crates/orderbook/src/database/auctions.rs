@@ -0,0 +1,42 @@
+use super::Postgres;
+use anyhow::{Context, Result};
+use std::convert::TryInto;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait AuctionStoring: Send + Sync {
+    /// Returns a new auction id that is larger than all previously returned ids.
+    async fn next_auction_id(&self) -> Result<u64>;
+}
+
+#[async_trait::async_trait]
+impl AuctionStoring for Postgres {
+    async fn next_auction_id(&self) -> Result<u64> {
+        const QUERY: &str = "SELECT nextval('auction_ids');";
+        let (id,): (i64,) = sqlx::query_as(QUERY)
+            .fetch_one(&self.pool)
+            .await
+            .context("next_auction_id failed")?;
+        id.try_into().context("auction id is negative")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_auction_ids_increase() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        let first = db.next_auction_id().await.unwrap();
+        let second = db.next_auction_id().await.unwrap();
+        assert!(second > first);
+
+        // Clearing the database doesn't reset the ids.
+        db.clear().await.unwrap();
+        assert!(db.next_auction_id().await.unwrap() > second);
+    }
+}
@@ -0,0 +1,837 @@
+//! Checks that all database backends behave the same by running one test suite against each of
+//! them. The `postgres` tests need a running database and are ignored by default like the other
+//! database tests.
+
+use super::{
+    auctions::AuctionStoring,
+    events::{Event, Invalidation, PreSignature, Settlement, Trade},
+    memory::InMemory,
+    order_events::{OrderEvent, OrderEventKind, OrderEventStoring},
+    orders::{InsertionError, OrderFilter, OrderStoring},
+    quotes::{QuoteData, QuoteStoring},
+    solver_competition::SolverCompetitionStoring,
+    trades::{MarketTradeFilter, TradeCursor, TradeFilter, TradeRetrieving},
+    Backend, Postgres,
+};
+use crate::fee::{FeeData, FeeParameters, MinFeeStoring};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use ethcontract::{H160, H256};
+use model::{
+    order::{Order, OrderCreation, OrderKind, OrderMetadata, OrderStatus, OrderUid},
+    signature::Signature,
+    solver_competition::SolverCompetition,
+};
+use shared::event_handling::{BlockNumber, EventIndex, EventStoring};
+
+#[async_trait::async_trait]
+trait TestDatabase: Backend + Clone {
+    async fn reset(&self);
+    async fn append(&self, events: Vec<(EventIndex, Event)>);
+    async fn replace(&self, from_block: u64, events: Vec<(EventIndex, Event)>);
+    async fn set_block_timestamp(&self, block_number: u64, timestamp: DateTime<Utc>);
+}
+
+#[async_trait::async_trait]
+impl TestDatabase for Postgres {
+    async fn reset(&self) {
+        self.clear().await.unwrap();
+    }
+
+    async fn append(&self, events: Vec<(EventIndex, Event)>) {
+        self.append_events_(events).await.unwrap();
+    }
+
+    async fn replace(&self, from_block: u64, events: Vec<(EventIndex, Event)>) {
+        self.replace_events_(from_block, events).await.unwrap();
+    }
+
+    async fn set_block_timestamp(&self, block_number: u64, timestamp: DateTime<Utc>) {
+        self.set_settlement_block_timestamp(block_number, timestamp)
+            .await
+            .unwrap();
+    }
+}
+
+#[async_trait::async_trait]
+impl TestDatabase for InMemory {
+    async fn reset(&self) {
+        self.clear();
+    }
+
+    async fn append(&self, events: Vec<(EventIndex, Event)>) {
+        self.append_events_(events).await.unwrap();
+    }
+
+    async fn replace(&self, from_block: u64, events: Vec<(EventIndex, Event)>) {
+        self.replace_events_(from_block, events).await.unwrap();
+    }
+
+    async fn set_block_timestamp(&self, block_number: u64, timestamp: DateTime<Utc>) {
+        self.set_settlement_block_timestamp(block_number, timestamp)
+            .await
+            .unwrap();
+    }
+}
+
+fn datetime(offset: u32) -> DateTime<Utc> {
+    DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(offset as i64, 0), Utc)
+}
+
+fn index(block_number: u64, log_index: u64) -> EventIndex {
+    EventIndex {
+        block_number,
+        log_index,
+    }
+}
+
+/// A sell order of 10 token 1 for 100 token 2 created `uid` seconds after the epoch.
+fn order(uid: u8, owner: u8) -> Order {
+    Order {
+        metadata: OrderMetadata {
+            uid: OrderUid([uid; 56]),
+            owner: H160::from_low_u64_be(owner as u64),
+            creation_date: datetime(uid as u32),
+            ..Default::default()
+        },
+        creation: OrderCreation {
+            sell_token: H160::from_low_u64_be(1),
+            buy_token: H160::from_low_u64_be(2),
+            sell_amount: 10.into(),
+            buy_amount: 100.into(),
+            valid_to: u32::MAX,
+            kind: OrderKind::Sell,
+            ..Default::default()
+        },
+    }
+}
+
+fn trade(order: &Order, sell_amount: u32, fee_amount: u32) -> Event {
+    Event::Trade(Trade {
+        order_uid: order.metadata.uid,
+        sell_amount_including_fee: sell_amount.into(),
+        buy_amount: (sell_amount * 10).into(),
+        fee_amount: fee_amount.into(),
+    })
+}
+
+fn uids(orders: &[Order]) -> Vec<OrderUid> {
+    orders.iter().map(|order| order.metadata.uid).collect()
+}
+
+fn sorted_uids(orders: &[Order]) -> Vec<[u8; 56]> {
+    let mut uids: Vec<_> = orders.iter().map(|order| order.metadata.uid.0).collect();
+    uids.sort_unstable();
+    uids
+}
+
+async fn insert_orders(db: &impl TestDatabase) {
+    let mut expired = order(4, 2);
+    expired.creation.valid_to = 1;
+    let mut presigned = order(5, 2);
+    presigned.creation.signature = Signature::PreSign(presigned.metadata.owner);
+    let mut buy = order(6, 3);
+    buy.creation.kind = OrderKind::Buy;
+    buy.creation.buy_token = H160::from_low_u64_be(3);
+    let orders = vec![
+        order(1, 1),
+        order(2, 1),
+        order(3, 2),
+        expired,
+        presigned,
+        buy,
+    ];
+    for order in &orders {
+        db.insert_order(order, Default::default()).await.unwrap();
+    }
+}
+
+async fn order_status(db: &impl TestDatabase, uid: u8) -> OrderStatus {
+    db.single_order_from_primary(&OrderUid([uid; 56]))
+        .await
+        .unwrap()
+        .unwrap()
+        .metadata
+        .status
+}
+
+async fn insertions(db: impl TestDatabase) {
+    db.reset().await;
+    db.insert_order(&order(1, 1), Default::default())
+        .await
+        .unwrap();
+    assert!(matches!(
+        db.insert_order(&order(1, 1), Default::default()).await,
+        Err(InsertionError::DuplicatedRecord)
+    ));
+
+    // Existing orders are skipped while the rest of the batch is inserted.
+    assert_eq!(
+        db.insert_orders(&[
+            (order(2, 1), Default::default()),
+            (order(1, 1), Default::default()),
+            (order(2, 1), Default::default()),
+        ])
+        .await
+        .unwrap(),
+        [true, false, false]
+    );
+    assert_eq!(
+        db.single_order(&order(2, 1).metadata.uid)
+            .await
+            .unwrap()
+            .unwrap(),
+        order(2, 1)
+    );
+
+    db.replace_order(&order(1, 1).metadata.uid, &order(3, 1), Default::default())
+        .await
+        .unwrap();
+    let old = db
+        .single_order(&order(1, 1).metadata.uid)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(old.metadata.status, OrderStatus::Cancelled);
+    let new = db
+        .single_order(&order(3, 1).metadata.uid)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(new, order(3, 1));
+
+    db.cancel_orders(vec![order(3, 1).metadata.uid], datetime(5))
+        .await
+        .unwrap();
+    let new = db
+        .single_order(&order(3, 1).metadata.uid)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(new.metadata.invalidated);
+
+    assert_eq!(
+        db.insert_orders(&[
+            (order(4, 1), Default::default()),
+            (order(5, 1), Default::default()),
+            (order(6, 1), Default::default()),
+        ])
+        .await
+        .unwrap(),
+        [true, true, true]
+    );
+    assert_eq!(order_status(&db, 4).await, OrderStatus::Open);
+    assert_eq!(order_status(&db, 5).await, OrderStatus::Open);
+
+    db.cancel_order(&OrderUid([4; 56]), datetime(6))
+        .await
+        .unwrap();
+    assert_eq!(order_status(&db, 4).await, OrderStatus::Cancelled);
+    assert_eq!(order_status(&db, 5).await, OrderStatus::Open);
+    db.cancel_orders(vec![OrderUid([5; 56]), OrderUid([6; 56])], datetime(6))
+        .await
+        .unwrap();
+    assert_eq!(order_status(&db, 5).await, OrderStatus::Cancelled);
+    assert_eq!(order_status(&db, 6).await, OrderStatus::Cancelled);
+
+    // Replacing with an existing order neither inserts nor cancels anything.
+    db.insert_order(&order(7, 1), Default::default())
+        .await
+        .unwrap();
+    assert!(matches!(
+        db.replace_order(&OrderUid([7; 56]), &order(1, 1), Default::default())
+            .await,
+        Err(InsertionError::DuplicatedRecord)
+    ));
+    assert_eq!(order_status(&db, 7).await, OrderStatus::Open);
+
+    // Missing orders are skipped and duplicated uids are returned once.
+    let mut uids = db
+        .orders_from_primary(&[
+            OrderUid([4; 56]),
+            OrderUid([8; 56]),
+            OrderUid([7; 56]),
+            OrderUid([4; 56]),
+        ])
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|order| order.metadata.uid)
+        .collect::<Vec<_>>();
+    uids.sort_by_key(|uid| uid.0);
+    assert_eq!(uids, [OrderUid([4; 56]), OrderUid([7; 56])]);
+}
+
+async fn order_filters(db: impl TestDatabase) {
+    db.reset().await;
+    insert_orders(&db).await;
+    db.append(vec![
+        (index(0, 0), trade(&order(1, 1), 10, 0)),
+        (
+            index(0, 1),
+            Event::Invalidation(Invalidation {
+                order_uid: order(2, 1).metadata.uid,
+            }),
+        ),
+    ])
+    .await;
+
+    let orders = |filter: OrderFilter| {
+        let db = db.clone();
+        async move { uids(&db.orders(&filter).await.unwrap()) }
+    };
+    let all = [6, 5, 4, 3, 2, 1].map(|uid| OrderUid([uid; 56])).to_vec();
+    assert_eq!(orders(Default::default()).await, all);
+    assert_eq!(
+        orders(OrderFilter {
+            min_valid_to: 2,
+            ..Default::default()
+        })
+        .await,
+        [6, 5, 3, 2, 1].map(|uid| OrderUid([uid; 56])).to_vec()
+    );
+    assert_eq!(
+        orders(OrderFilter {
+            owner: Some(H160::from_low_u64_be(1)),
+            ..Default::default()
+        })
+        .await,
+        [2, 1].map(|uid| OrderUid([uid; 56])).to_vec()
+    );
+    assert_eq!(
+        orders(OrderFilter {
+            buy_token: Some(H160::from_low_u64_be(3)),
+            ..Default::default()
+        })
+        .await,
+        vec![OrderUid([6; 56])]
+    );
+    assert_eq!(
+        orders(OrderFilter {
+            sell_token: Some(H160::from_low_u64_be(2)),
+            ..Default::default()
+        })
+        .await,
+        vec![]
+    );
+    assert_eq!(
+        orders(OrderFilter {
+            uid: Some(OrderUid([3; 56])),
+            ..Default::default()
+        })
+        .await,
+        vec![OrderUid([3; 56])]
+    );
+    assert_eq!(
+        orders(OrderFilter {
+            exclude_fully_executed: true,
+            exclude_invalidated: true,
+            exclude_presignature_pending: true,
+            ..Default::default()
+        })
+        .await,
+        [6, 4, 3].map(|uid| OrderUid([uid; 56])).to_vec()
+    );
+    assert_eq!(
+        orders(OrderFilter {
+            after: Some(OrderUid([4; 56])),
+            limit: Some(2),
+            ..Default::default()
+        })
+        .await,
+        [3, 2].map(|uid| OrderUid([uid; 56])).to_vec()
+    );
+    assert_eq!(
+        orders(OrderFilter {
+            after: Some(OrderUid([7; 56])),
+            ..Default::default()
+        })
+        .await,
+        vec![]
+    );
+
+    let user_orders = db
+        .user_orders(&H160::from_low_u64_be(2), None, 1, Some(1))
+        .await
+        .unwrap();
+    assert_eq!(uids(&user_orders), vec![OrderUid([4; 56])]);
+    let user_orders = db
+        .user_orders(&H160::from_low_u64_be(2), Some(OrderUid([5; 56])), 0, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        uids(&user_orders),
+        [4, 3].map(|uid| OrderUid([uid; 56])).to_vec()
+    );
+}
+
+async fn solvable_orders(db: impl TestDatabase) {
+    db.reset().await;
+    insert_orders(&db).await;
+    // A partial fill keeps the order solvable.
+    db.append(vec![
+        (index(1, 0), trade(&order(1, 1), 5, 1)),
+        (index(1, 1), trade(&order(2, 1), 10, 1)),
+        (
+            index(1, 2),
+            Event::Settlement(Settlement {
+                solver: H160::from_low_u64_be(1),
+                transaction_hash: H256::from_low_u64_be(1),
+            }),
+        ),
+        (
+            index(2, 0),
+            Event::Invalidation(Invalidation {
+                order_uid: order(3, 2).metadata.uid,
+            }),
+        ),
+    ])
+    .await;
+
+    let solvable = db.solvable_orders(2).await.unwrap();
+    assert_eq!(sorted_uids(&solvable.orders), vec![[1; 56], [6; 56]],);
+    assert_eq!(solvable.latest_settlement_block, 1);
+
+    let presigned = order(5, 2);
+    db.append(vec![(
+        index(3, 0),
+        Event::PreSignature(PreSignature {
+            owner: presigned.metadata.owner,
+            order_uid: presigned.metadata.uid,
+            signed: true,
+        }),
+    )])
+    .await;
+    let solvable = db.solvable_orders(2).await.unwrap();
+    assert_eq!(
+        sorted_uids(&solvable.orders),
+        vec![[1; 56], [5; 56], [6; 56]],
+    );
+
+    let partially_filled = db
+        .single_order(&order(1, 1).metadata.uid)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(partially_filled.metadata.executed_sell_amount, 5u32.into());
+    assert_eq!(
+        partially_filled.metadata.executed_sell_amount_before_fees,
+        4.into()
+    );
+    assert_eq!(partially_filled.metadata.executed_fee_amount, 1.into());
+    assert_eq!(partially_filled.metadata.executed_buy_amount, 50u32.into());
+    assert_eq!(partially_filled.metadata.status, OrderStatus::Open);
+    let cancelled = db
+        .single_order(&order(3, 2).metadata.uid)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(cancelled.metadata.status, OrderStatus::Cancelled);
+    let expired = db
+        .single_order(&order(4, 2).metadata.uid)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(expired.metadata.status, OrderStatus::Expired);
+
+    let orders = db.orders_for_tx(&H256::from_low_u64_be(1)).await.unwrap();
+    assert_eq!(sorted_uids(&orders), vec![[1; 56], [2; 56]]);
+}
+
+async fn trades(db: impl TestDatabase) {
+    db.reset().await;
+    insert_orders(&db).await;
+    db.append(vec![
+        (index(1, 0), trade(&order(1, 1), 5, 1)),
+        (
+            index(1, 1),
+            Event::Settlement(Settlement {
+                solver: H160::from_low_u64_be(1),
+                transaction_hash: H256::from_low_u64_be(1),
+            }),
+        ),
+        (index(2, 0), trade(&order(1, 1), 5, 1)),
+        (index(2, 1), trade(&order(3, 2), 10, 0)),
+        (index(3, 0), trade(&order(6, 3), 1, 0)),
+    ])
+    .await;
+    db.set_block_timestamp(1, datetime(100)).await;
+
+    let trades = |filter: TradeFilter| {
+        let db = db.clone();
+        async move {
+            db.trades(&filter)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|trade| (trade.block_number, trade.log_index))
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(
+        trades(Default::default()).await,
+        vec![(1, 0), (2, 0), (2, 1), (3, 0)]
+    );
+    assert_eq!(
+        trades(TradeFilter {
+            owner: Some(H160::from_low_u64_be(1)),
+            ..Default::default()
+        })
+        .await,
+        vec![(1, 0), (2, 0)]
+    );
+    assert_eq!(
+        trades(TradeFilter {
+            order_uid: Some(order(3, 2).metadata.uid),
+            ..Default::default()
+        })
+        .await,
+        vec![(2, 1)]
+    );
+    assert_eq!(
+        trades(TradeFilter {
+            after: Some(TradeCursor {
+                block_number: 2,
+                log_index: 0,
+            }),
+            limit: Some(1),
+            ..Default::default()
+        })
+        .await,
+        vec![(2, 1)]
+    );
+
+    let trade = db.trades(&Default::default()).await.unwrap().remove(0);
+    assert_eq!(trade.tx_hash, Some(H256::from_low_u64_be(1)));
+    assert_eq!(trade.sell_amount, 5u32.into());
+    assert_eq!(trade.sell_amount_before_fees, 4u32.into());
+    assert_eq!(trade.buy_amount, 50u32.into());
+    assert_eq!(trade.owner, H160::from_low_u64_be(1));
+
+    let market_trades = db
+        .market_trades(&MarketTradeFilter {
+            token_a: H160::from_low_u64_be(2),
+            token_b: H160::from_low_u64_be(1),
+            native_token: H160::from_low_u64_be(4),
+            from: datetime(0),
+            to: datetime(101),
+        })
+        .await
+        .unwrap();
+    assert_eq!(market_trades.len(), 1);
+    assert_eq!(market_trades[0].timestamp, datetime(100));
+    assert_eq!(market_trades[0].sell_amount, 4.into());
+
+    let executions = db
+        .order_executions(&H160::from_low_u64_be(1))
+        .await
+        .unwrap();
+    assert_eq!(executions.len(), 1);
+    assert_eq!(executions[0].executed_sell_amount, 8.into());
+    assert_eq!(executions[0].executed_buy_amount, 100.into());
+    assert_eq!(executions[0].executed_fee_amount, 2.into());
+
+    // Reindexing the settlement's block keeps its timestamp.
+    db.replace(
+        1,
+        vec![
+            (index(1, 0), trade(&order(1, 1), 5, 1)),
+            (
+                index(1, 1),
+                Event::Settlement(Settlement {
+                    solver: H160::from_low_u64_be(1),
+                    transaction_hash: H256::from_low_u64_be(1),
+                }),
+            ),
+        ],
+    )
+    .await;
+    let market_trades = db
+        .market_trades(&MarketTradeFilter {
+            token_a: H160::from_low_u64_be(2),
+            token_b: H160::from_low_u64_be(1),
+            native_token: H160::from_low_u64_be(4),
+            from: datetime(0),
+            to: datetime(101),
+        })
+        .await
+        .unwrap();
+    assert_eq!(market_trades.len(), 1);
+    assert_eq!(market_trades[0].timestamp, datetime(100));
+}
+
+async fn events(db: impl TestDatabase) {
+    db.reset().await;
+    insert_orders(&db).await;
+    assert_eq!(db.last_event_block().await.unwrap(), 0);
+
+    db.append(vec![
+        (index(1, 0), trade(&order(1, 1), 10, 0)),
+        (
+            index(3, 0),
+            Event::Invalidation(Invalidation {
+                order_uid: order(2, 1).metadata.uid,
+            }),
+        ),
+    ])
+    .await;
+    // Existing events are ignored.
+    db.append(vec![(index(1, 0), trade(&order(3, 2), 10, 0))])
+        .await;
+    assert_eq!(db.last_event_block().await.unwrap(), 3);
+    assert_eq!(db.trades(&Default::default()).await.unwrap().len(), 1);
+
+    db.clone()
+        .replace_events(
+            Vec::new(),
+            BlockNumber::Specific(2)..=BlockNumber::Latest(5),
+        )
+        .await
+        .unwrap();
+    assert_eq!(db.last_event_block().await.unwrap(), 1);
+    let reorged = db
+        .single_order(&order(2, 1).metadata.uid)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!reorged.metadata.invalidated);
+
+    // A specific end keeps the events after the range.
+    db.append(vec![(
+        index(3, 0),
+        Event::Invalidation(Invalidation {
+            order_uid: order(2, 1).metadata.uid,
+        }),
+    )])
+    .await;
+    db.clone()
+        .replace_events(
+            Vec::new(),
+            BlockNumber::Specific(1)..=BlockNumber::Specific(2),
+        )
+        .await
+        .unwrap();
+    assert_eq!(db.last_event_block().await.unwrap(), 3);
+    assert!(db.trades(&Default::default()).await.unwrap().is_empty());
+}
+
+async fn order_events(db: impl TestDatabase) {
+    db.reset().await;
+    insert_orders(&db).await;
+    let uid = order(1, 1).metadata.uid;
+    let event = |timestamp: u32, kind: OrderEventKind| OrderEvent {
+        timestamp: datetime(timestamp),
+        kind,
+    };
+    db.store_order_events(&[
+        (uid, event(2, OrderEventKind::Solvable)),
+        (uid, event(1, OrderEventKind::Created)),
+        (order(2, 1).metadata.uid, event(3, OrderEventKind::Created)),
+    ])
+    .await
+    .unwrap();
+    assert_eq!(
+        db.order_events(&uid).await.unwrap(),
+        vec![
+            event(1, OrderEventKind::Created),
+            event(2, OrderEventKind::Solvable)
+        ]
+    );
+
+    // Trades are recorded once no matter how often their event is indexed.
+    let traded = vec![(index(1, 0), trade(&order(1, 1), 10, 0))];
+    db.append(traded.clone()).await;
+    db.replace(1, traded).await;
+    let events = db.order_events(&uid).await.unwrap();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[2].kind, OrderEventKind::Traded);
+    assert!(db
+        .order_events(&order(3, 2).metadata.uid)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+async fn quotes(db: impl TestDatabase) {
+    db.reset().await;
+    let quote = |fee_amount: u32| QuoteData {
+        owner: H160::from_low_u64_be(1),
+        sell_token: H160::from_low_u64_be(2),
+        buy_token: H160::from_low_u64_be(3),
+        sell_amount: 4.into(),
+        buy_amount: 5.into(),
+        fee_amount: fee_amount.into(),
+        app_data: Default::default(),
+        kind: OrderKind::Sell,
+        fee_parameters: FeeParameters {
+            gas_amount: 6.,
+            gas_price: 7.,
+            sell_token_price: 8.,
+        },
+        expiration: datetime(10),
+    };
+    let first = db.save_quote(&quote(1)).await.unwrap();
+    let second = db.save_quote(&quote(2)).await.unwrap();
+    assert_ne!(first, second);
+
+    assert_eq!(
+        db.find_quote(first, datetime(0)).await.unwrap(),
+        Some(quote(1))
+    );
+    assert_eq!(
+        db.find_quote(second, datetime(10)).await.unwrap(),
+        Some(quote(2))
+    );
+    assert_eq!(db.find_quote(second, datetime(11)).await.unwrap(), None);
+    assert_eq!(
+        db.find_quote(first.max(second) + 1, datetime(0))
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+async fn solver_competitions(db: impl TestDatabase) {
+    db.reset().await;
+    let competition = |auction_id: u64, gas_price: f64, tx_hash: Option<u64>| SolverCompetition {
+        auction_id,
+        gas_price,
+        liquidity_collected_block: 1,
+        transaction_hash: tx_hash.map(H256::from_low_u64_be),
+        solutions: Vec::new(),
+    };
+    db.save_solver_competition(&competition(1, 1., None))
+        .await
+        .unwrap();
+    assert_eq!(
+        db.load_solver_competition(1).await.unwrap(),
+        Some(competition(1, 1., None))
+    );
+    assert_eq!(db.load_solver_competition(2).await.unwrap(), None);
+
+    // Runs that settled take precedence over later runs of the same auction.
+    db.save_solver_competition(&competition(1, 2., Some(1)))
+        .await
+        .unwrap();
+    db.save_solver_competition(&competition(1, 3., None))
+        .await
+        .unwrap();
+    assert_eq!(
+        db.load_solver_competition(1).await.unwrap(),
+        Some(competition(1, 2., Some(1)))
+    );
+    db.save_solver_competition(&competition(2, 4., None))
+        .await
+        .unwrap();
+    assert_eq!(
+        db.load_solver_competition(2).await.unwrap(),
+        Some(competition(2, 4., None))
+    );
+
+    assert_eq!(
+        db.load_solver_competition_by_tx_hash(&H256::from_low_u64_be(1))
+            .await
+            .unwrap(),
+        Some(competition(1, 2., Some(1)))
+    );
+    assert_eq!(
+        db.load_solver_competition_by_tx_hash(&H256::from_low_u64_be(2))
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+async fn auction_ids(db: impl TestDatabase) {
+    db.reset().await;
+    let first = db.next_auction_id().await.unwrap();
+    let second = db.next_auction_id().await.unwrap();
+    let third = db.next_auction_id().await.unwrap();
+    assert!(first < second);
+    assert!(second < third);
+}
+
+async fn fee_measurements(db: impl TestDatabase) {
+    db.reset().await;
+    let fee_data = |amount: u32| FeeData {
+        sell_token: H160::from_low_u64_be(1),
+        buy_token: H160::from_low_u64_be(2),
+        amount: amount.into(),
+        kind: OrderKind::Sell,
+    };
+    let fee = |gas_amount: f64| FeeParameters {
+        gas_amount,
+        gas_price: 1.,
+        sell_token_price: 1.,
+    };
+    db.save_fee_measurement(fee_data(10), datetime(10), fee(3.))
+        .await
+        .unwrap();
+    db.save_fee_measurement(fee_data(10), datetime(20), fee(2.))
+        .await
+        .unwrap();
+    db.save_fee_measurement(fee_data(20), datetime(20), fee(1.))
+        .await
+        .unwrap();
+
+    let exact = |amount, min_expiry| {
+        let db = db.clone();
+        async move {
+            db.find_measurement_exact(fee_data(amount), datetime(min_expiry))
+                .await
+                .unwrap()
+        }
+    };
+    assert_eq!(exact(10, 0).await, Some(fee(2.)));
+    assert_eq!(exact(10, 30).await, None);
+    assert_eq!(exact(15, 0).await, None);
+    assert_eq!(
+        db.find_measurement_including_larger_amount(fee_data(10), datetime(0))
+            .await
+            .unwrap(),
+        Some(fee(1.))
+    );
+    assert_eq!(
+        db.find_measurement_including_larger_amount(fee_data(21), datetime(0))
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+macro_rules! conformance_tests {
+    ($($name:ident),* $(,)?) => {
+        mod memory {
+            use super::*;
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name(InMemory::default()).await;
+                }
+            )*
+        }
+
+        mod postgres {
+            use super::*;
+            $(
+                #[tokio::test]
+                #[ignore]
+                async fn $name() {
+                    super::$name(Postgres::new("postgresql://").unwrap()).await;
+                }
+            )*
+        }
+    };
+}
+
+conformance_tests!(
+    insertions,
+    order_filters,
+    solvable_orders,
+    trades,
+    events,
+    order_events,
+    quotes,
+    solver_competitions,
+    auction_ids,
+    fee_measurements,
+);
@@ -1,6 +1,13 @@
-use super::Postgres;
+use super::{
+    order_events::{
+        delete_stale_contract_order_events, insert_contract_order_event, OrderEvent, OrderEventKind,
+    },
+    Postgres,
+};
 use crate::conversions::*;
 use anyhow::{anyhow, Context, Result};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
 use contracts::gpv2_settlement::{
     event_data::{
         OrderInvalidated as ContractInvalidation, PreSignature as ContractPreSignature,
@@ -11,11 +18,11 @@ use contracts::gpv2_settlement::{
 use ethcontract::{Event as EthContractEvent, EventMetadata, H160, H256, U256};
 use futures::FutureExt;
 use model::order::OrderUid;
-use shared::event_handling::{EventIndex, EventStoring};
+use shared::event_handling::{BlockNumber, EventIndex, EventStoring};
 use sqlx::{Connection, Executor, Transaction};
-use std::convert::TryInto;
+use std::{convert::TryInto, ops::RangeInclusive};
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Event {
     Trade(Trade),
     Invalidation(Invalidation),
@@ -23,7 +30,7 @@ pub enum Event {
     PreSignature(PreSignature),
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq)]
 pub struct Trade {
     pub order_uid: OrderUid,
     pub sell_amount_including_fee: U256,
@@ -31,18 +38,18 @@ pub struct Trade {
     pub fee_amount: U256,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq)]
 pub struct Invalidation {
     pub order_uid: OrderUid,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq)]
 pub struct Settlement {
     pub solver: H160,
     pub transaction_hash: H256,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq)]
 pub struct PreSignature {
     pub owner: H160,
     pub order_uid: OrderUid,
@@ -71,23 +78,181 @@ impl Postgres {
         &self,
         delete_from_block_number: u64,
         events: Vec<(EventIndex, Event)>,
+    ) -> Result<()> {
+        self.replace_events_between(delete_from_block_number, None, events)
+            .await
+    }
+
+    // Only deletes events up to and including `delete_to_block_number` if it is set.
+    async fn replace_events_between(
+        &self,
+        delete_from_block_number: u64,
+        delete_to_block_number: Option<u64>,
+        events: Vec<(EventIndex, Event)>,
     ) -> Result<()> {
         let mut connection = self.pool.acquire().await?;
         connection
             .transaction(move |transaction| {
                 async move {
-                    delete_events(transaction, delete_from_block_number)
-                        .await
-                        .context("delete_events failed")?;
+                    delete_events(
+                        transaction,
+                        delete_from_block_number,
+                        delete_to_block_number,
+                    )
+                    .await
+                    .context("delete_events failed")?;
                     append_events(transaction, events.as_slice())
                         .await
-                        .context("insert_events failed")
+                        .context("insert_events failed")?;
+                    delete_stale_contract_order_events(
+                        transaction,
+                        delete_from_block_number,
+                        delete_to_block_number,
+                    )
+                    .await
+                    .context("delete_stale_contract_order_events failed")
                 }
                 .boxed()
             })
             .await?;
         Ok(())
     }
+
+    /// The stored events in the block range ordered by block number and log index. Includes the
+    /// events of archived orders.
+    pub async fn events_in_block_range(
+        &self,
+        blocks: RangeInclusive<u64>,
+    ) -> Result<Vec<(EventIndex, Event)>> {
+        let from = *blocks.start() as i64;
+        let to = *blocks.end() as i64;
+        let mut events = Vec::new();
+
+        const QUERY_TRADES: &str = "\
+            SELECT block_number, log_index, order_uid, sell_amount, buy_amount, fee_amount \
+            FROM all_trades \
+            WHERE block_number BETWEEN $1 AND $2;";
+        let trades: Vec<(i64, i64, Vec<u8>, BigDecimal, BigDecimal, BigDecimal)> =
+            sqlx::query_as(QUERY_TRADES)
+                .bind(from)
+                .bind(to)
+                .fetch_all(&self.pool)
+                .await
+                .context("events_in_block_range trades failed")?;
+        for (block_number, log_index, order_uid, sell_amount, buy_amount, fee_amount) in trades {
+            let event = Trade {
+                order_uid: order_uid_from_vec(order_uid)?,
+                sell_amount_including_fee: big_decimal_to_u256(&sell_amount)
+                    .context("sell_amount is not U256")?,
+                buy_amount: big_decimal_to_u256(&buy_amount).context("buy_amount is not U256")?,
+                fee_amount: big_decimal_to_u256(&fee_amount).context("fee_amount is not U256")?,
+            };
+            events.push((event_index(block_number, log_index), Event::Trade(event)));
+        }
+
+        const QUERY_INVALIDATIONS: &str = "\
+            SELECT block_number, log_index, order_uid FROM invalidations \
+            WHERE block_number BETWEEN $1 AND $2;";
+        let invalidations: Vec<(i64, i64, Vec<u8>)> = sqlx::query_as(QUERY_INVALIDATIONS)
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.pool)
+            .await
+            .context("events_in_block_range invalidations failed")?;
+        for (block_number, log_index, order_uid) in invalidations {
+            let event = Invalidation {
+                order_uid: order_uid_from_vec(order_uid)?,
+            };
+            events.push((
+                event_index(block_number, log_index),
+                Event::Invalidation(event),
+            ));
+        }
+
+        const QUERY_SETTLEMENTS: &str = "\
+            SELECT block_number, log_index, solver, tx_hash FROM settlements \
+            WHERE block_number BETWEEN $1 AND $2;";
+        let settlements: Vec<(i64, i64, Vec<u8>, Vec<u8>)> = sqlx::query_as(QUERY_SETTLEMENTS)
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.pool)
+            .await
+            .context("events_in_block_range settlements failed")?;
+        for (block_number, log_index, solver, tx_hash) in settlements {
+            let event = Settlement {
+                solver: h160_from_vec(solver)?,
+                transaction_hash: h256_from_vec(tx_hash)?,
+            };
+            events.push((
+                event_index(block_number, log_index),
+                Event::Settlement(event),
+            ));
+        }
+
+        const QUERY_PRESIGNATURES: &str = "\
+            SELECT block_number, log_index, owner, order_uid, signed FROM all_presignature_events \
+            WHERE block_number BETWEEN $1 AND $2;";
+        let presignatures: Vec<(i64, i64, Vec<u8>, Vec<u8>, bool)> =
+            sqlx::query_as(QUERY_PRESIGNATURES)
+                .bind(from)
+                .bind(to)
+                .fetch_all(&self.pool)
+                .await
+                .context("events_in_block_range presignatures failed")?;
+        for (block_number, log_index, owner, order_uid, signed) in presignatures {
+            let event = PreSignature {
+                owner: h160_from_vec(owner)?,
+                order_uid: order_uid_from_vec(order_uid)?,
+                signed,
+            };
+            events.push((
+                event_index(block_number, log_index),
+                Event::PreSignature(event),
+            ));
+        }
+
+        events.sort_by_key(|(index, _)| (index.block_number, index.log_index));
+        Ok(events)
+    }
+
+    /// Block numbers of settlements whose block timestamp isn't known yet, lowest first.
+    pub async fn settlement_blocks_without_timestamp(&self, limit: u64) -> Result<Vec<u64>> {
+        const QUERY: &str = "\
+            SELECT DISTINCT s.block_number FROM settlements s \
+            WHERE NOT EXISTS ( \
+                SELECT 1 FROM settlement_block_timestamps b \
+                WHERE b.block_number = s.block_number \
+            ) \
+            ORDER BY s.block_number \
+            LIMIT $1;";
+        let blocks: Vec<(i64,)> = sqlx::query_as(QUERY)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await
+            .context("settlement_blocks_without_timestamp failed")?;
+        Ok(blocks
+            .into_iter()
+            .map(|(block_number,)| block_number as u64)
+            .collect())
+    }
+
+    pub async fn set_settlement_block_timestamp(
+        &self,
+        block_number: u64,
+        timestamp: DateTime<Utc>,
+    ) -> Result<()> {
+        const QUERY: &str = "\
+            INSERT INTO settlement_block_timestamps (block_number, block_timestamp) \
+            VALUES ($1, $2) \
+            ON CONFLICT (block_number) DO UPDATE SET block_timestamp = EXCLUDED.block_timestamp;";
+        sqlx::query(QUERY)
+            .bind(block_number as i64)
+            .bind(timestamp)
+            .execute(&self.pool)
+            .await
+            .context("set_settlement_block_timestamp failed")
+            .map(|_| ())
+    }
 }
 
 pub fn contract_to_db_events(
@@ -135,35 +300,74 @@ impl EventStoring<ContractEvent> for Postgres {
     async fn replace_events(
         &mut self,
         events: Vec<EthContractEvent<ContractEvent>>,
-        range: std::ops::RangeInclusive<shared::event_handling::BlockNumber>,
+        range: RangeInclusive<BlockNumber>,
     ) -> Result<()> {
-        self.replace_events_(range.start().to_u64(), contract_to_db_events(events)?)
-            .await
+        let delete_to_block_number = match range.end() {
+            BlockNumber::Specific(block_number) => Some(*block_number),
+            BlockNumber::Latest(_) => None,
+        };
+        self.replace_events_between(
+            range.start().to_u64(),
+            delete_to_block_number,
+            contract_to_db_events(events)?,
+        )
+        .await
     }
 }
 
+fn event_index(block_number: i64, log_index: i64) -> EventIndex {
+    EventIndex::new(block_number as u64, log_index as u64)
+}
+
+fn order_uid_from_vec(vec: Vec<u8>) -> Result<OrderUid> {
+    Ok(OrderUid(
+        vec.try_into()
+            .map_err(|_| anyhow!("order uid has wrong length"))?,
+    ))
+}
+
 async fn delete_events(
     transaction: &mut Transaction<'_, sqlx::Postgres>,
     delete_from_block_number: u64,
+    delete_to_block_number: Option<u64>,
 ) -> Result<(), sqlx::Error> {
-    const QUERY_INVALIDATION: &str = "DELETE FROM invalidations WHERE block_number >= $1;";
+    let to = delete_to_block_number.map(|block_number| block_number as i64);
+    const QUERY_INVALIDATION: &str = "DELETE FROM invalidations WHERE block_number >= $1 AND ($2 IS NULL OR block_number <= $2);";
     transaction
-        .execute(sqlx::query(QUERY_INVALIDATION).bind(delete_from_block_number as i64))
+        .execute(
+            sqlx::query(QUERY_INVALIDATION)
+                .bind(delete_from_block_number as i64)
+                .bind(to),
+        )
         .await?;
 
-    const QUERY_TRADE: &str = "DELETE FROM trades WHERE block_number >= $1;";
+    const QUERY_TRADE: &str =
+        "DELETE FROM trades WHERE block_number >= $1 AND ($2 IS NULL OR block_number <= $2);";
     transaction
-        .execute(sqlx::query(QUERY_TRADE).bind(delete_from_block_number as i64))
+        .execute(
+            sqlx::query(QUERY_TRADE)
+                .bind(delete_from_block_number as i64)
+                .bind(to),
+        )
         .await?;
 
-    const QUERY_SETTLEMENTS: &str = "DELETE FROM settlements WHERE block_number >= $1;";
+    const QUERY_SETTLEMENTS: &str =
+        "DELETE FROM settlements WHERE block_number >= $1 AND ($2 IS NULL OR block_number <= $2);";
     transaction
-        .execute(sqlx::query(QUERY_SETTLEMENTS).bind(delete_from_block_number as i64))
+        .execute(
+            sqlx::query(QUERY_SETTLEMENTS)
+                .bind(delete_from_block_number as i64)
+                .bind(to),
+        )
         .await?;
 
-    const QUERY_PRESIGNATURES: &str = "DELETE FROM presignature_events WHERE block_number >= $1;";
+    const QUERY_PRESIGNATURES: &str = "DELETE FROM presignature_events WHERE block_number >= $1 AND ($2 IS NULL OR block_number <= $2);";
     transaction
-        .execute(sqlx::query(QUERY_PRESIGNATURES).bind(delete_from_block_number as i64))
+        .execute(
+            sqlx::query(QUERY_PRESIGNATURES)
+                .bind(delete_from_block_number as i64)
+                .bind(to),
+        )
         .await?;
 
     Ok(())
@@ -206,7 +410,11 @@ async fn insert_invalidation(
                 .bind(event.order_uid.0.as_ref()),
         )
         .await?;
-    Ok(())
+    let order_event = OrderEvent {
+        timestamp: Utc::now(),
+        kind: OrderEventKind::Cancelled,
+    };
+    insert_contract_order_event(transaction, &event.order_uid, index, &order_event).await
 }
 
 async fn insert_trade(
@@ -214,8 +422,12 @@ async fn insert_trade(
     index: &EventIndex,
     event: &Trade,
 ) -> Result<(), sqlx::Error> {
+    // Trades of archived orders are already stored in the archive when their blocks get indexed
+    // again.
     const QUERY: &str = "\
-        INSERT INTO trades (block_number, log_index, order_uid, sell_amount, buy_amount, fee_amount) VALUES ($1, $2, $3, $4, $5, $6) \
+        INSERT INTO trades (block_number, log_index, order_uid, sell_amount, buy_amount, fee_amount) \
+        SELECT $1, $2, $3, $4, $5, $6 \
+        WHERE NOT EXISTS (SELECT 1 FROM archived_trades WHERE block_number = $1 AND log_index = $2) \
         ON CONFLICT DO NOTHING;";
     transaction
         .execute(
@@ -228,7 +440,11 @@ async fn insert_trade(
                 .bind(u256_to_big_decimal(&event.fee_amount)),
         )
         .await?;
-    Ok(())
+    let order_event = OrderEvent {
+        timestamp: Utc::now(),
+        kind: OrderEventKind::Traded,
+    };
+    insert_contract_order_event(transaction, &event.order_uid, index, &order_event).await
 }
 
 async fn insert_settlement(
@@ -257,7 +473,11 @@ async fn insert_presignature(
     event: &PreSignature,
 ) -> Result<(), sqlx::Error> {
     const QUERY: &str = "\
-        INSERT INTO presignature_events (block_number, log_index, owner, order_uid, signed) VALUES ($1, $2, $3, $4, $5) \
+        INSERT INTO presignature_events (block_number, log_index, owner, order_uid, signed) \
+        SELECT $1, $2, $3, $4, $5 \
+        WHERE NOT EXISTS ( \
+            SELECT 1 FROM archived_presignature_events WHERE block_number = $1 AND log_index = $2 \
+        ) \
         ON CONFLICT DO NOTHING;";
     transaction
         .execute(
@@ -340,6 +560,7 @@ fn convert_presignature(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::database::order_events::OrderEventStoring;
 
     #[tokio::test]
     #[ignore]
@@ -462,5 +683,136 @@ mod tests {
             .unwrap();
         }
         assert_eq!(db.last_event_block().await.unwrap(), 2);
+        // Both default events belong to the same order and only their first insertion counts.
+        let kinds = db
+            .order_events(&Default::default())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|event| event.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, [OrderEventKind::Traded, OrderEventKind::Cancelled]);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_replaced_events_record_order_events_once() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+        let events = || {
+            vec![
+                (EventIndex::new(2, 0), Event::Trade(Default::default())),
+                (
+                    EventIndex::new(2, 1),
+                    Event::Invalidation(Default::default()),
+                ),
+            ]
+        };
+        db.replace_events_(0, events()).await.unwrap();
+        let order_events = db.order_events(&Default::default()).await.unwrap();
+        assert_eq!(order_events.len(), 2);
+
+        // Indexing the same blocks again keeps the original order events.
+        db.replace_events_(1, events()).await.unwrap();
+        assert_eq!(
+            db.order_events(&Default::default()).await.unwrap(),
+            order_events
+        );
+
+        // A reorg removes the order events of the events that no longer exist.
+        db.replace_events_(
+            1,
+            vec![(EventIndex::new(2, 0), Event::Trade(Default::default()))],
+        )
+        .await
+        .unwrap();
+        let traded: Vec<_> = order_events
+            .into_iter()
+            .filter(|event| event.kind == OrderEventKind::Traded)
+            .collect();
+        assert_eq!(db.order_events(&Default::default()).await.unwrap(), traded);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_events_in_block_range() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        let settlement = || {
+            Event::Settlement(Settlement {
+                solver: H160::from_low_u64_be(1),
+                transaction_hash: H256::from_low_u64_be(2),
+            })
+        };
+        let trade = || {
+            Event::Trade(Trade {
+                order_uid: OrderUid([3; 56]),
+                sell_amount_including_fee: 4.into(),
+                buy_amount: 5.into(),
+                fee_amount: 6.into(),
+            })
+        };
+        let presignature = || {
+            Event::PreSignature(PreSignature {
+                owner: H160::from_low_u64_be(7),
+                order_uid: OrderUid([8; 56]),
+                signed: true,
+            })
+        };
+        db.append_events_(vec![
+            (
+                EventIndex::new(1, 0),
+                Event::Invalidation(Invalidation::default()),
+            ),
+            (EventIndex::new(2, 1), settlement()),
+            (EventIndex::new(2, 0), trade()),
+            (EventIndex::new(3, 0), presignature()),
+            (
+                EventIndex::new(4, 0),
+                Event::Invalidation(Invalidation::default()),
+            ),
+        ])
+        .await
+        .unwrap();
+
+        let events = db.events_in_block_range(2..=3).await.unwrap();
+        let keys = events
+            .iter()
+            .map(|(index, _)| (index.block_number, index.log_index))
+            .collect::<Vec<_>>();
+        assert_eq!(keys, [(2, 0), (2, 1), (3, 0)]);
+        assert_eq!(events[0].1, trade());
+        assert_eq!(events[1].1, settlement());
+        assert_eq!(events[2].1, presignature());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_replace_events_with_specific_end() {
+        let mut db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        db.append_events_(vec![
+            (EventIndex::new(1, 0), Event::Trade(Trade::default())),
+            (EventIndex::new(2, 0), Event::Trade(Trade::default())),
+            (EventIndex::new(3, 0), Event::Trade(Trade::default())),
+        ])
+        .await
+        .unwrap();
+        db.replace_events(
+            Vec::new(),
+            BlockNumber::Specific(2)..=BlockNumber::Specific(2),
+        )
+        .await
+        .unwrap();
+        let blocks = db
+            .events_in_block_range(0..=10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(index, _)| index.block_number)
+            .collect::<Vec<_>>();
+        assert_eq!(blocks, [1, 3]);
     }
 }
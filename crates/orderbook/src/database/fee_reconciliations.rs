@@ -0,0 +1,357 @@
+use super::Postgres;
+use crate::conversions::*;
+use anyhow::{anyhow, Context, Result};
+use ethcontract::{H160, H256, U256};
+use futures::FutureExt;
+use serde::Serialize;
+use sqlx::{Connection, Executor};
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait FeeReconciliationRetrieving: Send + Sync {
+    /// The error distribution over all reconciled orders followed by the distribution of every
+    /// token pair, ordered by the number of orders descending.
+    async fn fee_accuracy(&self) -> Result<Vec<FeeAccuracy>>;
+}
+
+/// Distribution of the relative fee error `estimated / realized - 1` so positive errors mean that
+/// we estimated a higher fee than the order's share of the settlement cost.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeAccuracy {
+    /// Not set for the distribution over all token pairs.
+    pub sell_token: Option<H160>,
+    pub buy_token: Option<H160>,
+    pub count: u64,
+    pub mean_error: f64,
+    pub error_percentiles: ErrorPercentiles,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ErrorPercentiles {
+    pub p10: f64,
+    pub p25: f64,
+    pub p50: f64,
+    pub p75: f64,
+    pub p90: f64,
+}
+
+/// The gas that the recent single trade settlements of a token pair used.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TradeGas {
+    pub sell_token: H160,
+    pub buy_token: H160,
+    pub trades: u64,
+    pub median_gas: f64,
+}
+
+impl Postgres {
+    /// Hashes of settlement transactions whose cost hasn't been recorded yet, oldest first.
+    pub async fn settlements_without_cost(&self, limit: u64) -> Result<Vec<H256>> {
+        const QUERY: &str = "\
+            SELECT s.tx_hash FROM settlements s \
+            WHERE NOT EXISTS (SELECT 1 FROM settlement_costs c WHERE c.tx_hash = s.tx_hash) \
+            ORDER BY s.block_number, s.log_index \
+            LIMIT $1;";
+        let hashes: Vec<(Vec<u8>,)> = sqlx::query_as(QUERY)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await
+            .context("settlements_without_cost failed")?;
+        hashes
+            .into_iter()
+            .map(|(hash,)| h256_from_vec(hash))
+            .collect()
+    }
+
+    /// Records the cost of the settlement and reconciles it with the estimated fees of the
+    /// settlement's trades in one transaction.
+    ///
+    /// Trades of orders without stored fee parameters count towards splitting the cost but are not
+    /// reconciled.
+    pub async fn record_settlement_cost(
+        &self,
+        tx_hash: H256,
+        gas_used: U256,
+        effective_gas_price: U256,
+    ) -> Result<()> {
+        const INSERT_COST: &str = "\
+            INSERT INTO settlement_costs (tx_hash, gas_used, effective_gas_price) \
+            VALUES ($1, $2, $3) \
+            ON CONFLICT DO NOTHING;";
+        // The trades of a settlement are the trade events emitted after the previous settlement
+        // event of the same block.
+        const INSERT_RECONCILIATIONS: &str = "\
+            WITH settlement AS ( \
+                SELECT block_number, log_index FROM settlements WHERE tx_hash = $1 \
+            ), \
+            settlement_trades AS ( \
+                SELECT t.order_uid FROM trades t, settlement s \
+                WHERE t.block_number = s.block_number \
+                AND t.log_index < s.log_index \
+                AND t.log_index > COALESCE(( \
+                    SELECT MAX(p.log_index) FROM settlements p \
+                    WHERE p.block_number = s.block_number AND p.log_index < s.log_index \
+                ), -1) \
+            ) \
+            INSERT INTO fee_reconciliations ( \
+                order_uid, tx_hash, sell_token, buy_token, estimated_fee, realized_fee, \
+                settlement_trades \
+            ) \
+            SELECT \
+                t.order_uid, $1, o.sell_token, o.buy_token, f.gas_amount * f.gas_price, \
+                $2 / (SELECT COUNT(*) FROM settlement_trades), \
+                (SELECT COUNT(*) FROM settlement_trades) \
+            FROM settlement_trades t \
+            JOIN orders o ON o.uid = t.order_uid \
+            JOIN order_fee_parameters f ON f.order_uid = t.order_uid \
+            ON CONFLICT DO NOTHING;";
+        let cost = gas_used.to_f64_lossy() * effective_gas_price.to_f64_lossy();
+        let mut connection = self.pool.acquire().await?;
+        connection
+            .transaction(move |transaction| {
+                async move {
+                    transaction
+                        .execute(
+                            sqlx::query(INSERT_COST)
+                                .bind(tx_hash.as_bytes())
+                                .bind(u256_to_big_decimal(&gas_used))
+                                .bind(u256_to_big_decimal(&effective_gas_price)),
+                        )
+                        .await?;
+                    transaction
+                        .execute(
+                            sqlx::query(INSERT_RECONCILIATIONS)
+                                .bind(tx_hash.as_bytes())
+                                .bind(cost),
+                        )
+                        .await?;
+                    Ok(())
+                }
+                .boxed()
+            })
+            .await
+            .context("record_settlement_cost failed")
+    }
+
+    /// Records that the cost of the settlement can't be determined so that it is no longer
+    /// returned by `settlements_without_cost`. Its trades are not reconciled.
+    pub async fn record_settlement_cost_failure(&self, tx_hash: H256) -> Result<()> {
+        const QUERY: &str = "\
+            INSERT INTO settlement_costs (tx_hash, gas_used, effective_gas_price) \
+            VALUES ($1, NULL, NULL) \
+            ON CONFLICT DO NOTHING;";
+        sqlx::query(QUERY)
+            .bind(tx_hash.as_bytes())
+            .execute(&self.pool)
+            .await
+            .context("record_settlement_cost_failure failed")
+            .map(|_| ())
+    }
+
+    /// The median gas of the `recent_trades` most recent reconciled trades of every token pair that
+    /// has at least `min_trades` reconciled trades.
+    ///
+    /// Only trades that were settled alone are considered because the cost of batched settlements
+    /// is split evenly which says little about how much gas each of their trades needed.
+    pub async fn trade_gas(&self, recent_trades: u64, min_trades: u64) -> Result<Vec<TradeGas>> {
+        const QUERY: &str = "\
+            SELECT sell_token, buy_token, COUNT(*), \
+                percentile_cont(0.5) WITHIN GROUP (ORDER BY gas) \
+            FROM ( \
+                SELECT \
+                    r.sell_token, r.buy_token, \
+                    r.realized_fee / c.effective_gas_price::double precision AS gas, \
+                    ROW_NUMBER() OVER ( \
+                        PARTITION BY r.sell_token, r.buy_token \
+                        ORDER BY s.block_number DESC, s.log_index DESC \
+                    ) AS recency \
+                FROM fee_reconciliations r \
+                JOIN settlement_costs c ON c.tx_hash = r.tx_hash \
+                JOIN settlements s ON s.tx_hash = r.tx_hash \
+                WHERE c.effective_gas_price > 0 AND r.settlement_trades = 1 \
+            ) AS gas \
+            WHERE recency <= $1 \
+            GROUP BY sell_token, buy_token \
+            HAVING COUNT(*) >= $2;";
+        let rows: Vec<(Vec<u8>, Vec<u8>, i64, f64)> = sqlx::query_as(QUERY)
+            .bind(recent_trades as i64)
+            .bind(min_trades as i64)
+            .fetch_all(&self.pool)
+            .await
+            .context("trade_gas failed")?;
+        rows.into_iter()
+            .map(|(sell_token, buy_token, trades, median_gas)| {
+                Ok(TradeGas {
+                    sell_token: h160_from_vec(sell_token)?,
+                    buy_token: h160_from_vec(buy_token)?,
+                    trades: trades as u64,
+                    median_gas,
+                })
+            })
+            .collect()
+    }
+}
+
+type FeeAccuracyRow = (Option<Vec<u8>>, Option<Vec<u8>>, i64, f64, Vec<f64>);
+
+fn fee_accuracy_from_row(row: FeeAccuracyRow) -> Result<FeeAccuracy> {
+    let (sell_token, buy_token, count, mean_error, percentiles) = row;
+    let percentiles = match percentiles.as_slice() {
+        &[p10, p25, p50, p75, p90] => ErrorPercentiles {
+            p10,
+            p25,
+            p50,
+            p75,
+            p90,
+        },
+        _ => return Err(anyhow!("unexpected number of percentiles")),
+    };
+    Ok(FeeAccuracy {
+        sell_token: sell_token.map(h160_from_vec).transpose()?,
+        buy_token: buy_token.map(h160_from_vec).transpose()?,
+        count: count as u64,
+        mean_error,
+        error_percentiles: percentiles,
+    })
+}
+
+#[async_trait::async_trait]
+impl FeeReconciliationRetrieving for Postgres {
+    async fn fee_accuracy(&self) -> Result<Vec<FeeAccuracy>> {
+        const QUERY: &str = "\
+            SELECT \
+                sell_token, buy_token, COUNT(*), AVG(error), \
+                percentile_cont(ARRAY[0.1, 0.25, 0.5, 0.75, 0.9]) WITHIN GROUP (ORDER BY error) \
+            FROM ( \
+                SELECT sell_token, buy_token, estimated_fee / realized_fee - 1 AS error \
+                FROM fee_reconciliations \
+                WHERE realized_fee > 0 \
+            ) AS errors \
+            GROUP BY GROUPING SETS ((sell_token, buy_token), ()) \
+            HAVING COUNT(*) > 0 \
+            ORDER BY COUNT(*) DESC, sell_token NULLS FIRST, buy_token NULLS FIRST;";
+        let rows: Vec<FeeAccuracyRow> = sqlx::query_as(QUERY)
+            .fetch_all(self.read_pool())
+            .await
+            .context("fee_accuracy failed")?;
+        rows.into_iter().map(fee_accuracy_from_row).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{
+            events::{Event, Settlement, Trade},
+            orders::OrderStoring,
+        },
+        fee::FeeParameters,
+    };
+    use model::order::{Order, OrderCreation, OrderMetadata, OrderUid};
+    use shared::event_handling::EventIndex;
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_reconcile_settlement_costs() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        let order = |uid: u8, sell_token: u64| Order {
+            metadata: OrderMetadata {
+                uid: OrderUid([uid; 56]),
+                ..Default::default()
+            },
+            creation: OrderCreation {
+                sell_token: H160::from_low_u64_be(sell_token),
+                buy_token: H160::from_low_u64_be(10),
+                ..Default::default()
+            },
+        };
+        let fee = |gas_amount: f64| FeeParameters {
+            gas_amount,
+            gas_price: 1.,
+            sell_token_price: 1.,
+        };
+        db.insert_order(&order(1, 1), fee(100.)).await.unwrap();
+        db.insert_order(&order(2, 2), fee(300.)).await.unwrap();
+        db.insert_order(&order(3, 2), fee(100.)).await.unwrap();
+
+        let trade = |uid: u8| {
+            Event::Trade(Trade {
+                order_uid: OrderUid([uid; 56]),
+                ..Default::default()
+            })
+        };
+        let settlement = |tx_hash: u64| {
+            Event::Settlement(Settlement {
+                solver: Default::default(),
+                transaction_hash: H256::from_low_u64_be(tx_hash),
+            })
+        };
+        db.append_events_(vec![
+            // The first settlement has two trades, one of them without an order.
+            (EventIndex::new(1, 0), trade(1)),
+            (EventIndex::new(1, 1), trade(4)),
+            (EventIndex::new(1, 2), settlement(1)),
+            (EventIndex::new(1, 3), trade(2)),
+            (EventIndex::new(1, 4), settlement(2)),
+            (EventIndex::new(2, 0), trade(3)),
+            (EventIndex::new(2, 1), settlement(3)),
+        ])
+        .await
+        .unwrap();
+
+        assert_eq!(
+            db.settlements_without_cost(2).await.unwrap(),
+            [H256::from_low_u64_be(1), H256::from_low_u64_be(2)]
+        );
+        db.record_settlement_cost(H256::from_low_u64_be(1), 100.into(), 2.into())
+            .await
+            .unwrap();
+        db.record_settlement_cost(H256::from_low_u64_be(2), 200.into(), 1.into())
+            .await
+            .unwrap();
+        db.record_settlement_cost(H256::from_low_u64_be(3), 200.into(), 1.into())
+            .await
+            .unwrap();
+        assert!(db.settlements_without_cost(10).await.unwrap().is_empty());
+
+        // A settlement without a usable receipt is skipped and doesn't affect the statistics.
+        db.append_events_(vec![
+            (EventIndex::new(3, 0), trade(1)),
+            (EventIndex::new(3, 1), settlement(4)),
+        ])
+        .await
+        .unwrap();
+        db.record_settlement_cost_failure(H256::from_low_u64_be(4))
+            .await
+            .unwrap();
+        assert!(db.settlements_without_cost(10).await.unwrap().is_empty());
+
+        // Order 1 estimated 100 for a share of 100, order 2 300 for 200 and order 3 100 for 200.
+        let accuracy = db.fee_accuracy().await.unwrap();
+        assert_eq!(accuracy.len(), 3);
+        assert_eq!(accuracy[0].sell_token, None);
+        assert_eq!(accuracy[0].count, 3);
+        assert!((accuracy[0].mean_error - 0.).abs() < 1e-9);
+        assert!((accuracy[0].error_percentiles.p50 - 0.).abs() < 1e-9);
+        assert_eq!(accuracy[1].sell_token, Some(H160::from_low_u64_be(2)));
+        assert_eq!(accuracy[1].count, 2);
+        assert_eq!(accuracy[2].sell_token, Some(H160::from_low_u64_be(1)));
+        assert!((accuracy[2].mean_error - 0.).abs() < 1e-9);
+
+        // The first settlement batched two trades so only the others, which used 200 gas for one
+        // trade, are learned from.
+        let trade_gas = db.trade_gas(10, 1).await.unwrap();
+        assert_eq!(trade_gas.len(), 1);
+        assert_eq!(trade_gas[0].sell_token, H160::from_low_u64_be(2));
+        assert_eq!(trade_gas[0].trades, 2);
+        assert!((trade_gas[0].median_gas - 200.).abs() < 1e-9);
+
+        // Only the most recent trade of the second pair is considered.
+        let trade_gas = db.trade_gas(1, 2).await.unwrap();
+        assert!(trade_gas.is_empty());
+    }
+}
@@ -126,9 +126,13 @@ impl Postgres {
 #[async_trait::async_trait]
 impl Maintaining for Postgres {
     async fn run_maintenance(&self) -> Result<()> {
-        self.remove_expired_fee_measurements(Utc::now())
+        let now = Utc::now();
+        self.remove_expired_fee_measurements(now)
+            .await
+            .context("fee measurement maintenance error")?;
+        self.remove_expired_quotes(now)
             .await
-            .context("fee measurement maintenance error")
+            .context("quote maintenance error")
     }
 }
 
@@ -1,4 +1,13 @@
-use super::{orders::OrderStoring, trades::TradeRetrieving, Postgres};
+use super::{
+    auctions::AuctionStoring,
+    fee_reconciliations::{FeeAccuracy, FeeReconciliationRetrieving},
+    order_events::{OrderEvent, OrderEventStoring},
+    orders::OrderStoring,
+    quotes::{QuoteId, QuoteStoring},
+    solver_competition::SolverCompetitionStoring,
+    trades::TradeRetrieving,
+    Backend,
+};
 use crate::fee::{FeeParameters, MinFeeStoring};
 use ethcontract::H256;
 use model::order::Order;
@@ -6,15 +15,14 @@ use prometheus::Histogram;
 use shared::{event_handling::EventStoring, maintenance::Maintaining};
 use std::sync::Arc;
 
-// The pool uses an Arc internally.
 #[derive(Clone)]
 pub struct Instrumented {
-    inner: Postgres,
+    inner: Box<dyn Backend>,
     metrics: Arc<dyn Metrics>,
 }
 
 impl Instrumented {
-    pub fn new(inner: Postgres, metrics: Arc<dyn Metrics>) -> Self {
+    pub fn new(inner: Box<dyn Backend>, metrics: Arc<dyn Metrics>) -> Self {
         Self { inner, metrics }
     }
 }
@@ -23,6 +31,17 @@ pub trait Metrics: Send + Sync {
     fn database_query_histogram(&self, label: &str) -> Histogram;
 }
 
+#[async_trait::async_trait]
+impl AuctionStoring for Instrumented {
+    async fn next_auction_id(&self) -> anyhow::Result<u64> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("next_auction_id")
+            .start_timer();
+        self.inner.next_auction_id().await
+    }
+}
+
 #[async_trait::async_trait]
 impl EventStoring<contracts::gpv2_settlement::Event> for Instrumented {
     async fn replace_events(
@@ -103,6 +122,51 @@ impl MinFeeStoring for Instrumented {
     }
 }
 
+#[async_trait::async_trait]
+impl QuoteStoring for Instrumented {
+    async fn save_quote(&self, quote: &super::quotes::QuoteData) -> anyhow::Result<QuoteId> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("save_quote")
+            .start_timer();
+        self.inner.save_quote(quote).await
+    }
+
+    async fn find_quote(
+        &self,
+        id: QuoteId,
+        min_expiry: chrono::DateTime<chrono::Utc>,
+    ) -> anyhow::Result<Option<super::quotes::QuoteData>> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("find_quote")
+            .start_timer();
+        self.inner.find_quote(id, min_expiry).await
+    }
+}
+
+#[async_trait::async_trait]
+impl OrderEventStoring for Instrumented {
+    async fn store_order_events(
+        &self,
+        events: &[(model::order::OrderUid, OrderEvent)],
+    ) -> anyhow::Result<()> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("store_order_events")
+            .start_timer();
+        self.inner.store_order_events(events).await
+    }
+
+    async fn order_events(&self, uid: &model::order::OrderUid) -> anyhow::Result<Vec<OrderEvent>> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("order_events")
+            .start_timer();
+        self.inner.order_events(uid).await
+    }
+}
+
 #[async_trait::async_trait]
 impl OrderStoring for Instrumented {
     async fn insert_order(
@@ -117,6 +181,17 @@ impl OrderStoring for Instrumented {
         self.inner.insert_order(order, fee).await
     }
 
+    async fn insert_orders(
+        &self,
+        orders: &[(model::order::Order, FeeParameters)],
+    ) -> anyhow::Result<Vec<bool>, sqlx::Error> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("insert_orders")
+            .start_timer();
+        self.inner.insert_orders(orders).await
+    }
+
     async fn cancel_order(
         &self,
         order_uid: &model::order::OrderUid,
@@ -129,6 +204,33 @@ impl OrderStoring for Instrumented {
         self.inner.cancel_order(order_uid, now).await
     }
 
+    async fn cancel_orders(
+        &self,
+        order_uids: Vec<model::order::OrderUid>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> anyhow::Result<()> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("cancel_orders")
+            .start_timer();
+        self.inner.cancel_orders(order_uids, now).await
+    }
+
+    async fn replace_order(
+        &self,
+        old_order: &model::order::OrderUid,
+        new_order: &model::order::Order,
+        new_fee: FeeParameters,
+    ) -> anyhow::Result<(), super::orders::InsertionError> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("replace_order")
+            .start_timer();
+        self.inner
+            .replace_order(old_order, new_order, new_fee)
+            .await
+    }
+
     async fn orders(
         &self,
         filter: &super::orders::OrderFilter,
@@ -159,6 +261,28 @@ impl OrderStoring for Instrumented {
         self.inner.single_order(uid).await
     }
 
+    async fn single_order_from_primary(
+        &self,
+        uid: &model::order::OrderUid,
+    ) -> anyhow::Result<Option<model::order::Order>> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("single_order_from_primary")
+            .start_timer();
+        self.inner.single_order_from_primary(uid).await
+    }
+
+    async fn orders_from_primary(
+        &self,
+        uids: &[model::order::OrderUid],
+    ) -> anyhow::Result<Vec<model::order::Order>> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("orders_from_primary")
+            .start_timer();
+        self.inner.orders_from_primary(uids).await
+    }
+
     async fn solvable_orders(
         &self,
         min_valid_to: u32,
@@ -173,6 +297,7 @@ impl OrderStoring for Instrumented {
     async fn user_orders(
         &self,
         owner: &ethcontract::H160,
+        after: Option<model::order::OrderUid>,
         offset: u64,
         limit: Option<u64>,
     ) -> anyhow::Result<Vec<model::order::Order>> {
@@ -180,7 +305,7 @@ impl OrderStoring for Instrumented {
             .metrics
             .database_query_histogram("user_orders")
             .start_timer();
-        self.inner.user_orders(owner, offset, limit).await
+        self.inner.user_orders(owner, after, offset, limit).await
     }
 }
 
@@ -196,6 +321,75 @@ impl TradeRetrieving for Instrumented {
             .start_timer();
         self.inner.trades(filter).await
     }
+
+    async fn market_trades(
+        &self,
+        filter: &super::trades::MarketTradeFilter,
+    ) -> anyhow::Result<Vec<super::trades::MarketTrade>> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("market_trades")
+            .start_timer();
+        self.inner.market_trades(filter).await
+    }
+
+    async fn order_executions(
+        &self,
+        owner: &ethcontract::H160,
+    ) -> anyhow::Result<Vec<super::trades::OrderExecution>> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("order_executions")
+            .start_timer();
+        self.inner.order_executions(owner).await
+    }
+}
+
+#[async_trait::async_trait]
+impl SolverCompetitionStoring for Instrumented {
+    async fn save_solver_competition(
+        &self,
+        competition: &model::solver_competition::SolverCompetition,
+    ) -> anyhow::Result<()> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("save_solver_competition")
+            .start_timer();
+        self.inner.save_solver_competition(competition).await
+    }
+
+    async fn load_solver_competition(
+        &self,
+        auction_id: u64,
+    ) -> anyhow::Result<Option<model::solver_competition::SolverCompetition>> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("load_solver_competition")
+            .start_timer();
+        self.inner.load_solver_competition(auction_id).await
+    }
+
+    async fn load_solver_competition_by_tx_hash(
+        &self,
+        tx_hash: &H256,
+    ) -> anyhow::Result<Option<model::solver_competition::SolverCompetition>> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("load_solver_competition_by_tx_hash")
+            .start_timer();
+        self.inner.load_solver_competition_by_tx_hash(tx_hash).await
+    }
+}
+
+#[async_trait::async_trait]
+impl FeeReconciliationRetrieving for Instrumented {
+    async fn fee_accuracy(&self) -> anyhow::Result<Vec<FeeAccuracy>> {
+        let _timer = self
+            .metrics
+            .database_query_histogram("fee_accuracy")
+            .start_timer();
+        self.inner.fee_accuracy().await
+    }
 }
 
 #[async_trait::async_trait]
@@ -0,0 +1,855 @@
+//! A database backend that keeps everything in memory.
+//!
+//! It behaves like `Postgres` for everything the orderbook stores but nothing survives a restart
+//! and queries scan all rows. This is meant for local development and tests that should not need a
+//! running Postgres instance. Maintenance jobs that work on `Postgres` directly like archival or
+//! marking expired orders are not supported.
+
+use super::{
+    auctions::AuctionStoring,
+    events::{contract_to_db_events, Event, Invalidation, PreSignature, Settlement, Trade},
+    fee_reconciliations::{FeeAccuracy, FeeReconciliationRetrieving},
+    order_events::{OrderEvent, OrderEventKind, OrderEventStoring},
+    orders::{
+        is_buy_order_filled, is_sell_order_filled, InsertionError, OrderFilter, OrderStoring,
+        SolvableOrders,
+    },
+    quotes::{QuoteData, QuoteId, QuoteStoring},
+    solver_competition::SolverCompetitionStoring,
+    trades::{MarketTrade, MarketTradeFilter, OrderExecution, TradeFilter, TradeRetrieving},
+};
+use crate::{
+    conversions::*,
+    fee::{FeeData, FeeParameters, MinFeeStoring},
+};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use contracts::gpv2_settlement::Event as ContractEvent;
+use ethcontract::{Event as EthContractEvent, H160, H256, U256};
+use model::{
+    order::{Order, OrderKind, OrderStatus, OrderUid, BUY_ETH_ADDRESS},
+    signature::SigningScheme,
+    solver_competition::SolverCompetition,
+    trade::Trade as ModelTrade,
+};
+use num::Zero;
+use shared::{
+    event_handling::{BlockNumber, EventIndex, EventStoring},
+    maintenance::Maintaining,
+};
+use sqlx::types::BigDecimal;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::RangeInclusive,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// Rows of the event tables are keyed by block number and log index like in the database.
+type EventKey = (u64, u64);
+
+#[derive(Clone, Default)]
+pub struct InMemory {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    orders: HashMap<OrderUid, StoredOrder>,
+    trades: BTreeMap<EventKey, Trade>,
+    invalidations: BTreeMap<EventKey, Invalidation>,
+    settlements: BTreeMap<EventKey, Settlement>,
+    /// Not an event table so replacing events keeps the timestamps.
+    settlement_block_timestamps: HashMap<u64, DateTime<Utc>>,
+    presignatures: BTreeMap<EventKey, PreSignature>,
+    order_events: Vec<(OrderUid, OrderEvent)>,
+    /// Order events caused by trade and invalidation events.
+    contract_order_events: BTreeMap<EventKey, (OrderUid, OrderEvent)>,
+    fee_measurements: Vec<(FeeData, DateTime<Utc>, FeeParameters)>,
+    quotes: BTreeMap<QuoteId, QuoteData>,
+    /// One entry per solver run in the order they were stored.
+    solver_competitions: Vec<SolverCompetition>,
+    last_auction_id: u64,
+}
+
+struct StoredOrder {
+    order: Order,
+    cancellation_timestamp: Option<DateTime<Utc>>,
+}
+
+/// The values `ORDERS_SELECT` computes for an order.
+struct OrderAggregates {
+    sum_sell: BigDecimal,
+    sum_buy: BigDecimal,
+    sum_fee: BigDecimal,
+    invalidated: bool,
+    presignature_pending: bool,
+}
+
+impl OrderAggregates {
+    fn is_fully_executed(&self, order: &Order) -> bool {
+        match order.creation.kind {
+            OrderKind::Sell => self.sum_sell >= u256_to_big_decimal(&order.creation.sell_amount),
+            OrderKind::Buy => self.sum_buy >= u256_to_big_decimal(&order.creation.buy_amount),
+        }
+    }
+}
+
+impl InMemory {
+    fn state(&self) -> MutexGuard<State> {
+        self.state.lock().unwrap()
+    }
+
+    pub fn clear(&self) {
+        *self.state() = Default::default();
+    }
+
+    // All insertions happen atomically.
+    pub async fn append_events_(&self, events: Vec<(EventIndex, Event)>) -> Result<()> {
+        self.state().append_events(events);
+        Ok(())
+    }
+
+    // The deletion and all insertions happen atomically.
+    pub async fn replace_events_(
+        &self,
+        delete_from_block_number: u64,
+        events: Vec<(EventIndex, Event)>,
+    ) -> Result<()> {
+        self.state()
+            .replace_events(delete_from_block_number, None, events);
+        Ok(())
+    }
+
+    pub async fn set_settlement_block_timestamp(
+        &self,
+        block_number: u64,
+        timestamp: DateTime<Utc>,
+    ) -> Result<()> {
+        self.state()
+            .settlement_block_timestamps
+            .insert(block_number, timestamp);
+        Ok(())
+    }
+}
+
+impl State {
+    fn append_events(&mut self, events: Vec<(EventIndex, Event)>) {
+        // Like the database we ignore events that already exist so that concurrent updates do not
+        // fail.
+        for (index, event) in events {
+            let key = (index.block_number, index.log_index);
+            match event {
+                Event::Trade(event) => {
+                    let uid = self.trades.entry(key).or_insert(event).order_uid;
+                    self.insert_contract_order_event(key, uid, OrderEventKind::Traded);
+                }
+                Event::Invalidation(event) => {
+                    let uid = self.invalidations.entry(key).or_insert(event).order_uid;
+                    self.insert_contract_order_event(key, uid, OrderEventKind::Cancelled);
+                }
+                Event::Settlement(settlement) => {
+                    self.settlements.entry(key).or_insert(settlement);
+                }
+                Event::PreSignature(event) => {
+                    self.presignatures.entry(key).or_insert(event);
+                }
+            }
+        }
+    }
+
+    fn delete_events(
+        &mut self,
+        delete_from_block_number: u64,
+        delete_to_block_number: Option<u64>,
+    ) {
+        fn delete<T>(rows: &mut BTreeMap<EventKey, T>, from: u64, to: Option<u64>) {
+            let mut deleted = rows.split_off(&(from, 0));
+            if let Some(to) = to.and_then(|to| to.checked_add(1)) {
+                rows.append(&mut deleted.split_off(&(to, 0)));
+            }
+        }
+        let (from, to) = (delete_from_block_number, delete_to_block_number);
+        delete(&mut self.trades, from, to);
+        delete(&mut self.invalidations, from, to);
+        delete(&mut self.settlements, from, to);
+        delete(&mut self.presignatures, from, to);
+    }
+
+    fn replace_events(
+        &mut self,
+        delete_from_block_number: u64,
+        delete_to_block_number: Option<u64>,
+        events: Vec<(EventIndex, Event)>,
+    ) {
+        self.delete_events(delete_from_block_number, delete_to_block_number);
+        self.append_events(events);
+        // Like the database keep the order events of events that still exist and remove the rest.
+        let (trades, invalidations) = (&self.trades, &self.invalidations);
+        self.contract_order_events
+            .retain(|(block_number, log_index), (uid, event)| {
+                let key = (*block_number, *log_index);
+                if *block_number < delete_from_block_number
+                    || matches!(delete_to_block_number, Some(to) if *block_number > to)
+                {
+                    return true;
+                }
+                match event.kind {
+                    OrderEventKind::Traded => {
+                        trades.get(&key).map(|trade| trade.order_uid) == Some(*uid)
+                    }
+                    OrderEventKind::Cancelled => {
+                        invalidations
+                            .get(&key)
+                            .map(|invalidation| invalidation.order_uid)
+                            == Some(*uid)
+                    }
+                    _ => true,
+                }
+            });
+    }
+
+    fn last_event_block(&self) -> u64 {
+        [
+            self.trades.keys().next_back(),
+            self.settlements.keys().next_back(),
+            self.invalidations.keys().next_back(),
+            self.presignatures.keys().next_back(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|(block_number, _)| *block_number)
+        .max()
+        .unwrap_or(0)
+    }
+
+    fn insert_contract_order_event(&mut self, key: EventKey, uid: OrderUid, kind: OrderEventKind) {
+        self.contract_order_events.entry(key).or_insert_with(|| {
+            let event = OrderEvent {
+                timestamp: Utc::now(),
+                kind,
+            };
+            (uid, event)
+        });
+    }
+
+    fn insert_order(&mut self, order: &Order) -> Result<(), InsertionError> {
+        if self.orders.contains_key(&order.metadata.uid) {
+            return Err(InsertionError::DuplicatedRecord);
+        }
+        self.orders.insert(
+            order.metadata.uid,
+            StoredOrder {
+                order: order.clone(),
+                cancellation_timestamp: None,
+            },
+        );
+        let event = OrderEvent {
+            timestamp: order.metadata.creation_date,
+            kind: OrderEventKind::Created,
+        };
+        self.order_events.push((order.metadata.uid, event));
+        Ok(())
+    }
+
+    // Like the database we do not overwrite previous cancellations.
+    fn cancel_order(&mut self, uid: &OrderUid, now: DateTime<Utc>) {
+        if let Some(stored) = self.orders.get_mut(uid) {
+            stored.cancellation_timestamp.get_or_insert(now);
+        }
+    }
+
+    fn trades_of<'a>(&'a self, uid: &'a OrderUid) -> impl Iterator<Item = &'a Trade> + 'a {
+        self.trades
+            .values()
+            .filter(move |trade| trade.order_uid == *uid)
+    }
+
+    fn aggregates(&self, stored: &StoredOrder) -> OrderAggregates {
+        let uid = &stored.order.metadata.uid;
+        let sum = |amount: fn(&Trade) -> U256| {
+            self.trades_of(uid).fold(BigDecimal::zero(), |sum, trade| {
+                sum + u256_to_big_decimal(&amount(trade))
+            })
+        };
+        let invalidated = stored.cancellation_timestamp.is_some()
+            || self
+                .invalidations
+                .values()
+                .any(|invalidation| invalidation.order_uid == *uid);
+        let presignature_pending = stored.order.creation.signature.scheme()
+            == SigningScheme::PreSign
+            && self
+                .presignatures
+                .values()
+                .filter(|presignature| presignature.order_uid == *uid)
+                .last()
+                .map(|presignature| !presignature.signed)
+                .unwrap_or(true);
+        OrderAggregates {
+            sum_sell: sum(|trade| trade.sell_amount_including_fee),
+            sum_buy: sum(|trade| trade.buy_amount),
+            sum_fee: sum(|trade| trade.fee_amount),
+            invalidated,
+            presignature_pending,
+        }
+    }
+
+    /// Returns the order with the metadata that is computed from the events filled in.
+    fn order(&self, stored: &StoredOrder, aggregates: OrderAggregates) -> Result<Order> {
+        let mut order = stored.order.clone();
+        let status = order_status(&order, &aggregates);
+        let metadata = &mut order.metadata;
+        metadata.available_balance = Default::default();
+        metadata.executed_buy_amount = big_decimal_to_big_uint(&aggregates.sum_buy)
+            .context("executed buy amount is not an unsigned integer")?;
+        metadata.executed_sell_amount = big_decimal_to_big_uint(&aggregates.sum_sell)
+            .context("executed sell amount is not an unsigned integer")?;
+        metadata.executed_sell_amount_before_fees =
+            big_decimal_to_u256(&(aggregates.sum_sell - &aggregates.sum_fee))
+                .context("executed sell amount before fees does not fit in a u256")?;
+        metadata.executed_fee_amount = big_decimal_to_u256(&aggregates.sum_fee)
+            .context("executed fee amount is not a valid u256")?;
+        metadata.invalidated = aggregates.invalidated;
+        metadata.status = status;
+        Ok(order)
+    }
+
+    /// Orders that are not filtered out by `filter` with their aggregates computed.
+    fn select_orders(
+        &self,
+        filter: impl Fn(&Order, &OrderAggregates) -> bool,
+    ) -> Vec<(&StoredOrder, OrderAggregates)> {
+        self.orders
+            .values()
+            .map(|stored| (stored, self.aggregates(stored)))
+            .filter(|(stored, aggregates)| filter(&stored.order, aggregates))
+            .collect()
+    }
+
+    /// Whether the order comes after the cursor order when ordering by creation date descending.
+    /// Nothing comes after a cursor that does not exist.
+    fn is_after(&self, order: &Order, cursor: &OrderUid) -> bool {
+        match self.orders.get(cursor) {
+            Some(cursor) => newest_first(order, &cursor.order) == Ordering::Greater,
+            None => false,
+        }
+    }
+
+    /// The hash of the settlement transaction emitted after the trade in the same block.
+    fn settlement_tx_hash(&self, (block_number, log_index): EventKey) -> Option<H256> {
+        self.settlements
+            .range((block_number, log_index + 1)..=(block_number, u64::MAX))
+            .next()
+            .map(|(_, settlement)| settlement.transaction_hash)
+    }
+}
+
+fn order_status(order: &Order, aggregates: &OrderAggregates) -> OrderStatus {
+    let filled = match order.creation.kind {
+        OrderKind::Buy => is_buy_order_filled(
+            &u256_to_big_decimal(&order.creation.buy_amount),
+            &aggregates.sum_buy,
+        ),
+        OrderKind::Sell => is_sell_order_filled(
+            &u256_to_big_decimal(&order.creation.sell_amount),
+            &aggregates.sum_sell,
+            &aggregates.sum_fee,
+        ),
+    };
+    if filled {
+        return OrderStatus::Fulfilled;
+    }
+    if aggregates.invalidated {
+        return OrderStatus::Cancelled;
+    }
+    if (order.creation.valid_to as i64) < Utc::now().timestamp() {
+        return OrderStatus::Expired;
+    }
+    if aggregates.presignature_pending {
+        return OrderStatus::PresignaturePending;
+    }
+    OrderStatus::Open
+}
+
+/// Orders by creation date descending and uid descending.
+fn newest_first(a: &Order, b: &Order) -> Ordering {
+    (b.metadata.creation_date, b.metadata.uid.0).cmp(&(a.metadata.creation_date, a.metadata.uid.0))
+}
+
+fn into_orders(
+    state: &State,
+    mut selected: Vec<(&StoredOrder, OrderAggregates)>,
+) -> Result<Vec<Order>> {
+    selected.sort_by(|(a, _), (b, _)| newest_first(&a.order, &b.order));
+    selected
+        .into_iter()
+        .map(|(stored, aggregates)| state.order(stored, aggregates))
+        .collect()
+}
+
+#[async_trait::async_trait]
+impl OrderStoring for InMemory {
+    async fn insert_order(&self, order: &Order, _: FeeParameters) -> Result<(), InsertionError> {
+        self.state().insert_order(order)
+    }
+
+    async fn insert_orders(
+        &self,
+        orders: &[(Order, FeeParameters)],
+    ) -> Result<Vec<bool>, sqlx::Error> {
+        let mut state = self.state();
+        Ok(orders
+            .iter()
+            .map(|(order, _)| state.insert_order(order).is_ok())
+            .collect())
+    }
+
+    async fn cancel_order(&self, order_uid: &OrderUid, now: DateTime<Utc>) -> Result<()> {
+        self.state().cancel_order(order_uid, now);
+        Ok(())
+    }
+
+    async fn cancel_orders(&self, order_uids: Vec<OrderUid>, now: DateTime<Utc>) -> Result<()> {
+        let mut state = self.state();
+        for order_uid in &order_uids {
+            state.cancel_order(order_uid, now);
+        }
+        Ok(())
+    }
+
+    async fn replace_order(
+        &self,
+        old_order: &OrderUid,
+        new_order: &Order,
+        _: FeeParameters,
+    ) -> Result<(), InsertionError> {
+        let mut state = self.state();
+        if state.orders.contains_key(&new_order.metadata.uid) {
+            return Err(InsertionError::DuplicatedRecord);
+        }
+        state.cancel_order(old_order, new_order.metadata.creation_date);
+        state.insert_order(new_order)
+    }
+
+    async fn orders(&self, filter: &OrderFilter) -> Result<Vec<Order>> {
+        let state = self.state();
+        let selected = state.select_orders(|order, aggregates| {
+            order.creation.valid_to >= filter.min_valid_to
+                && filter
+                    .owner
+                    .map_or(true, |owner| order.metadata.owner == owner)
+                && filter
+                    .sell_token
+                    .map_or(true, |token| order.creation.sell_token == token)
+                && filter
+                    .buy_token
+                    .map_or(true, |token| order.creation.buy_token == token)
+                && filter.uid.map_or(true, |uid| order.metadata.uid == uid)
+                && filter
+                    .after
+                    .map_or(true, |cursor| state.is_after(order, &cursor))
+                && !(filter.exclude_fully_executed && aggregates.is_fully_executed(order))
+                && !(filter.exclude_invalidated && aggregates.invalidated)
+                && !(filter.exclude_presignature_pending && aggregates.presignature_pending)
+        });
+        let mut orders = into_orders(&state, selected)?;
+        if let Some(limit) = filter.limit {
+            orders.truncate(limit as usize);
+        }
+        Ok(orders)
+    }
+
+    async fn orders_for_tx(&self, tx_hash: &H256) -> Result<Vec<Order>> {
+        // Like the database this returns an order once for every one of its trades in a block
+        // that contains the settlement.
+        let state = self.state();
+        let mut orders = Vec::new();
+        for (&(block_number, _), trade) in &state.trades {
+            let stored = match state.orders.get(&trade.order_uid) {
+                Some(stored) => stored,
+                None => continue,
+            };
+            let settlements = state
+                .settlements
+                .range((block_number, 0)..=(block_number, u64::MAX))
+                .filter(|(_, settlement)| settlement.transaction_hash == *tx_hash);
+            for _ in settlements {
+                orders.push(state.order(stored, state.aggregates(stored))?);
+            }
+        }
+        Ok(orders)
+    }
+
+    async fn single_order(&self, uid: &OrderUid) -> Result<Option<Order>> {
+        let state = self.state();
+        state
+            .orders
+            .get(uid)
+            .map(|stored| state.order(stored, state.aggregates(stored)))
+            .transpose()
+    }
+
+    async fn single_order_from_primary(&self, uid: &OrderUid) -> Result<Option<Order>> {
+        self.single_order(uid).await
+    }
+
+    async fn orders_from_primary(&self, uids: &[OrderUid]) -> Result<Vec<Order>> {
+        let state = self.state();
+        uids.iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .filter_map(|uid| state.orders.get(uid))
+            .map(|stored| state.order(stored, state.aggregates(stored)))
+            .collect()
+    }
+
+    async fn solvable_orders(&self, min_valid_to: u32) -> Result<SolvableOrders> {
+        let state = self.state();
+        let selected = state.select_orders(|order, aggregates| {
+            order.creation.valid_to >= min_valid_to
+                && !aggregates.is_fully_executed(order)
+                && !aggregates.invalidated
+                && !aggregates.presignature_pending
+        });
+        let orders = into_orders(&state, selected)?;
+        let latest_settlement_block = state
+            .settlements
+            .keys()
+            .next_back()
+            .map(|(block_number, _)| *block_number)
+            .unwrap_or(0);
+        Ok(SolvableOrders {
+            orders,
+            latest_settlement_block,
+        })
+    }
+
+    async fn user_orders(
+        &self,
+        owner: &H160,
+        after: Option<OrderUid>,
+        offset: u64,
+        limit: Option<u64>,
+    ) -> Result<Vec<Order>> {
+        let state = self.state();
+        let selected = state.select_orders(|order, _| {
+            order.metadata.owner == *owner
+                && after.map_or(true, |cursor| state.is_after(order, &cursor))
+        });
+        Ok(into_orders(&state, selected)?
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl TradeRetrieving for InMemory {
+    async fn trades(&self, filter: &TradeFilter) -> Result<Vec<ModelTrade>> {
+        let state = self.state();
+        let after = filter
+            .after
+            .map(|cursor| (cursor.block_number, cursor.log_index));
+        let mut trades = Vec::new();
+        for (&key, trade) in &state.trades {
+            let order = match state.orders.get(&trade.order_uid) {
+                Some(stored) => &stored.order,
+                None => continue,
+            };
+            if filter
+                .owner
+                .map_or(false, |owner| order.metadata.owner != owner)
+                || filter.order_uid.map_or(false, |uid| trade.order_uid != uid)
+                || after.map_or(false, |after| key <= after)
+            {
+                continue;
+            }
+            if filter
+                .limit
+                .map_or(false, |limit| trades.len() as u64 >= limit)
+            {
+                break;
+            }
+            let sell_amount = u256_to_big_decimal(&trade.sell_amount_including_fee);
+            let sell_amount_before_fees = &sell_amount - u256_to_big_decimal(&trade.fee_amount);
+            trades.push(ModelTrade {
+                block_number: key.0,
+                log_index: key.1,
+                order_uid: trade.order_uid,
+                buy_amount: u256_to_big_uint(&trade.buy_amount),
+                sell_amount: u256_to_big_uint(&trade.sell_amount_including_fee),
+                sell_amount_before_fees: big_decimal_to_big_uint(&sell_amount_before_fees)
+                    .ok_or_else(|| anyhow!("sell_amount_before_fees is not an unsigned integer"))?,
+                owner: order.metadata.owner,
+                buy_token: order.creation.buy_token,
+                sell_token: order.creation.sell_token,
+                tx_hash: state.settlement_tx_hash(key),
+            });
+        }
+        Ok(trades)
+    }
+
+    async fn market_trades(&self, filter: &MarketTradeFilter) -> Result<Vec<MarketTrade>> {
+        let state = self.state();
+        let is_buy_token = |token: H160, other: H160| {
+            token == other || (other == filter.native_token && token == BUY_ETH_ADDRESS)
+        };
+        let mut trades = Vec::new();
+        for (&(block_number, _), trade) in &state.trades {
+            let order = match state.orders.get(&trade.order_uid) {
+                Some(stored) => &stored.order,
+                None => continue,
+            };
+            let timestamp = match state.settlement_block_timestamps.get(&block_number) {
+                Some(timestamp) => *timestamp,
+                None => continue,
+            };
+            let (sell_token, buy_token) = (order.creation.sell_token, order.creation.buy_token);
+            let is_market = (sell_token == filter.token_a
+                && is_buy_token(buy_token, filter.token_b))
+                || (sell_token == filter.token_b && is_buy_token(buy_token, filter.token_a));
+            if !is_market || timestamp < filter.from || timestamp >= filter.to {
+                continue;
+            }
+            let sell_amount = u256_to_big_decimal(&trade.sell_amount_including_fee)
+                - u256_to_big_decimal(&trade.fee_amount);
+            trades.push(MarketTrade {
+                timestamp,
+                sell_token,
+                buy_token,
+                sell_amount: big_decimal_to_u256(&sell_amount)
+                    .ok_or_else(|| anyhow!("sell_amount is not U256"))?,
+                buy_amount: trade.buy_amount,
+            });
+        }
+        Ok(trades)
+    }
+
+    async fn order_executions(&self, owner: &H160) -> Result<Vec<OrderExecution>> {
+        let state = self.state();
+        state
+            .select_orders(|order, _| {
+                order.metadata.owner == *owner && state.trades_of(&order.metadata.uid).count() > 0
+            })
+            .into_iter()
+            .map(|(stored, aggregates)| {
+                let order = &stored.order;
+                Ok(OrderExecution {
+                    sell_token: order.creation.sell_token,
+                    buy_token: order.creation.buy_token,
+                    kind: order.creation.kind,
+                    sell_amount: order.creation.sell_amount,
+                    buy_amount: order.creation.buy_amount,
+                    executed_sell_amount: big_decimal_to_u256(
+                        &(aggregates.sum_sell - &aggregates.sum_fee),
+                    )
+                    .ok_or_else(|| anyhow!("executed_sell_amount is not U256"))?,
+                    executed_buy_amount: big_decimal_to_u256(&aggregates.sum_buy)
+                        .ok_or_else(|| anyhow!("executed_buy_amount is not U256"))?,
+                    executed_fee_amount: big_decimal_to_u256(&aggregates.sum_fee)
+                        .ok_or_else(|| anyhow!("executed_fee_amount is not U256"))?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl EventStoring<ContractEvent> for InMemory {
+    async fn last_event_block(&self) -> Result<u64> {
+        Ok(self.state().last_event_block())
+    }
+
+    async fn append_events(&mut self, events: Vec<EthContractEvent<ContractEvent>>) -> Result<()> {
+        self.append_events_(contract_to_db_events(events)?).await
+    }
+
+    async fn replace_events(
+        &mut self,
+        events: Vec<EthContractEvent<ContractEvent>>,
+        range: RangeInclusive<BlockNumber>,
+    ) -> Result<()> {
+        let delete_to_block_number = match range.end() {
+            BlockNumber::Specific(block_number) => Some(*block_number),
+            BlockNumber::Latest(_) => None,
+        };
+        let events = contract_to_db_events(events)?;
+        self.state()
+            .replace_events(range.start().to_u64(), delete_to_block_number, events);
+        Ok(())
+    }
+}
+
+impl InMemory {
+    fn find_measurement(
+        &self,
+        fee_data: FeeData,
+        min_expiry: DateTime<Utc>,
+        amount_matches: impl Fn(&FeeData) -> bool,
+    ) -> Option<FeeParameters> {
+        let cost = |fee: &FeeParameters| fee.gas_amount * fee.gas_price * fee.sell_token_price;
+        self.state()
+            .fee_measurements
+            .iter()
+            .filter(|(data, expiry, _)| {
+                data.sell_token == fee_data.sell_token
+                    && data.buy_token == fee_data.buy_token
+                    && data.kind == fee_data.kind
+                    && amount_matches(data)
+                    && *expiry >= min_expiry
+            })
+            .map(|(_, _, fee)| *fee)
+            .min_by(|a, b| cost(a).partial_cmp(&cost(b)).unwrap_or(Ordering::Equal))
+    }
+}
+
+#[async_trait::async_trait]
+impl MinFeeStoring for InMemory {
+    async fn save_fee_measurement(
+        &self,
+        fee_data: FeeData,
+        expiry: DateTime<Utc>,
+        estimate: FeeParameters,
+    ) -> Result<()> {
+        self.state()
+            .fee_measurements
+            .push((fee_data, expiry, estimate));
+        Ok(())
+    }
+
+    async fn find_measurement_exact(
+        &self,
+        fee_data: FeeData,
+        min_expiry: DateTime<Utc>,
+    ) -> Result<Option<FeeParameters>> {
+        Ok(self.find_measurement(fee_data, min_expiry, |data| data.amount == fee_data.amount))
+    }
+
+    async fn find_measurement_including_larger_amount(
+        &self,
+        fee_data: FeeData,
+        min_expiry: DateTime<Utc>,
+    ) -> Result<Option<FeeParameters>> {
+        Ok(self.find_measurement(fee_data, min_expiry, |data| data.amount >= fee_data.amount))
+    }
+}
+
+#[async_trait::async_trait]
+impl Maintaining for InMemory {
+    async fn run_maintenance(&self) -> Result<()> {
+        let now = Utc::now();
+        let mut state = self.state();
+        state
+            .fee_measurements
+            .retain(|(_, expiry, _)| *expiry >= now);
+        let referenced: HashSet<QuoteId> = state
+            .orders
+            .values()
+            .filter_map(|order| order.order.metadata.quote_id)
+            .collect();
+        state
+            .quotes
+            .retain(|id, quote| quote.expiration >= now || referenced.contains(id));
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl QuoteStoring for InMemory {
+    async fn save_quote(&self, quote: &QuoteData) -> Result<QuoteId> {
+        let mut state = self.state();
+        let id = state.quotes.keys().next_back().copied().unwrap_or(0) + 1;
+        state.quotes.insert(id, quote.clone());
+        Ok(id)
+    }
+
+    async fn find_quote(
+        &self,
+        id: QuoteId,
+        min_expiry: DateTime<Utc>,
+    ) -> Result<Option<QuoteData>> {
+        Ok(self
+            .state()
+            .quotes
+            .get(&id)
+            .filter(|quote| quote.expiration >= min_expiry)
+            .cloned())
+    }
+}
+
+#[async_trait::async_trait]
+impl OrderEventStoring for InMemory {
+    async fn store_order_events(&self, events: &[(OrderUid, OrderEvent)]) -> Result<()> {
+        self.state().order_events.extend_from_slice(events);
+        Ok(())
+    }
+
+    async fn order_events(&self, uid: &OrderUid) -> Result<Vec<OrderEvent>> {
+        let state = self.state();
+        let mut events: Vec<_> = state
+            .order_events
+            .iter()
+            .chain(state.contract_order_events.values())
+            .filter(|(event_uid, _)| event_uid == uid)
+            .map(|(_, event)| *event)
+            .collect();
+        events.sort_by_key(|event| event.timestamp);
+        Ok(events)
+    }
+}
+
+// Settlement costs are only recorded by the fee reconciler which needs `Postgres`.
+#[async_trait::async_trait]
+impl FeeReconciliationRetrieving for InMemory {
+    async fn fee_accuracy(&self) -> Result<Vec<FeeAccuracy>> {
+        Err(anyhow!(
+            "fee accuracy is only available with the postgres database"
+        ))
+    }
+}
+
+#[async_trait::async_trait]
+impl SolverCompetitionStoring for InMemory {
+    async fn save_solver_competition(&self, competition: &SolverCompetition) -> Result<()> {
+        self.state().solver_competitions.push(competition.clone());
+        Ok(())
+    }
+
+    async fn load_solver_competition(&self, auction_id: u64) -> Result<Option<SolverCompetition>> {
+        let state = self.state();
+        let mut competitions = state
+            .solver_competitions
+            .iter()
+            .rev()
+            .filter(|competition| competition.auction_id == auction_id);
+        Ok(competitions
+            .clone()
+            .find(|competition| competition.transaction_hash.is_some())
+            .or_else(|| competitions.next())
+            .cloned())
+    }
+
+    async fn load_solver_competition_by_tx_hash(
+        &self,
+        tx_hash: &H256,
+    ) -> Result<Option<SolverCompetition>> {
+        Ok(self
+            .state()
+            .solver_competitions
+            .iter()
+            .rev()
+            .find(|competition| competition.transaction_hash == Some(*tx_hash))
+            .cloned())
+    }
+}
+
+#[async_trait::async_trait]
+impl AuctionStoring for InMemory {
+    async fn next_auction_id(&self) -> Result<u64> {
+        let mut state = self.state();
+        state.last_auction_id += 1;
+        Ok(state.last_auction_id)
+    }
+}
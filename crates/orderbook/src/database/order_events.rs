@@ -0,0 +1,279 @@
+use super::Postgres;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::FutureExt;
+use model::order::OrderUid;
+use serde::Serialize;
+use shared::event_handling::EventIndex;
+use sqlx::{Connection, Executor, Transaction};
+
+/// Something that happened to an order.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderEvent {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: OrderEventKind,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum OrderEventKind {
+    Created,
+    /// The order became part of the auction.
+    Solvable,
+    /// The order is open but was left out of the auction.
+    Filtered {
+        reason: FilterReason,
+    },
+    Traded,
+    Cancelled,
+    Expired,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, sqlx::Type)]
+#[sqlx(type_name = "OrderFilterReason")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "camelCase")]
+pub enum FilterReason {
+    BannedUser,
+    UnsupportedToken,
+    InvalidSignature,
+    InsufficientBalance,
+    MissingNativePrice,
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait OrderEventStoring: Send + Sync {
+    /// Stores all events in a single transaction.
+    async fn store_order_events(&self, events: &[(OrderUid, OrderEvent)]) -> Result<()>;
+    /// All events of the order ordered by time (oldest events first).
+    async fn order_events(&self, uid: &OrderUid) -> Result<Vec<OrderEvent>>;
+}
+
+#[derive(sqlx::Type)]
+#[sqlx(type_name = "OrderEventLabel")]
+#[sqlx(rename_all = "lowercase")]
+enum DbOrderEventLabel {
+    Created,
+    Solvable,
+    Filtered,
+    Traded,
+    Cancelled,
+    Expired,
+}
+
+#[derive(sqlx::FromRow)]
+struct OrderEventRow {
+    timestamp: DateTime<Utc>,
+    label: DbOrderEventLabel,
+    filter_reason: Option<FilterReason>,
+}
+
+impl OrderEventRow {
+    fn into_event(self) -> Result<OrderEvent> {
+        let kind = match self.label {
+            DbOrderEventLabel::Created => OrderEventKind::Created,
+            DbOrderEventLabel::Solvable => OrderEventKind::Solvable,
+            DbOrderEventLabel::Filtered => OrderEventKind::Filtered {
+                reason: self
+                    .filter_reason
+                    .context("filtered order event without reason")?,
+            },
+            DbOrderEventLabel::Traded => OrderEventKind::Traded,
+            DbOrderEventLabel::Cancelled => OrderEventKind::Cancelled,
+            DbOrderEventLabel::Expired => OrderEventKind::Expired,
+        };
+        Ok(OrderEvent {
+            timestamp: self.timestamp,
+            kind,
+        })
+    }
+}
+
+/// Inserts the event as part of a larger transaction so that it is only stored if the change it
+/// describes is.
+pub(super) async fn insert_order_event(
+    transaction: &mut Transaction<'_, sqlx::Postgres>,
+    uid: &OrderUid,
+    event: &OrderEvent,
+) -> Result<(), sqlx::Error> {
+    insert_order_event_(transaction, uid, None, event).await
+}
+
+/// Like `insert_order_event` for events caused by a contract event. Does nothing if there already
+/// is an order event for the contract event so that indexing the same event again is idempotent.
+pub(super) async fn insert_contract_order_event(
+    transaction: &mut Transaction<'_, sqlx::Postgres>,
+    uid: &OrderUid,
+    index: &EventIndex,
+    event: &OrderEvent,
+) -> Result<(), sqlx::Error> {
+    insert_order_event_(transaction, uid, Some(index), event).await
+}
+
+async fn insert_order_event_(
+    transaction: &mut Transaction<'_, sqlx::Postgres>,
+    uid: &OrderUid,
+    index: Option<&EventIndex>,
+    event: &OrderEvent,
+) -> Result<(), sqlx::Error> {
+    const QUERY: &str = "\
+        INSERT INTO order_events \
+            (order_uid, timestamp, label, filter_reason, block_number, log_index) \
+        VALUES ($1, $2, $3, $4, $5, $6) \
+        ON CONFLICT (order_uid, block_number, log_index) DO NOTHING;";
+    let (label, filter_reason) = match event.kind {
+        OrderEventKind::Created => (DbOrderEventLabel::Created, None),
+        OrderEventKind::Solvable => (DbOrderEventLabel::Solvable, None),
+        OrderEventKind::Filtered { reason } => (DbOrderEventLabel::Filtered, Some(reason)),
+        OrderEventKind::Traded => (DbOrderEventLabel::Traded, None),
+        OrderEventKind::Cancelled => (DbOrderEventLabel::Cancelled, None),
+        OrderEventKind::Expired => (DbOrderEventLabel::Expired, None),
+    };
+    transaction
+        .execute(
+            sqlx::query(QUERY)
+                .bind(uid.0.as_ref())
+                .bind(event.timestamp)
+                .bind(label)
+                .bind(filter_reason)
+                .bind(index.map(|index| index.block_number as i64))
+                .bind(index.map(|index| index.log_index as i64)),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Deletes the order events of contract events in the block range that no longer exist, for
+/// example because of a reorg. Has to run after the events of the range have been replaced.
+pub(super) async fn delete_stale_contract_order_events(
+    transaction: &mut Transaction<'_, sqlx::Postgres>,
+    delete_from_block_number: u64,
+    delete_to_block_number: Option<u64>,
+) -> Result<(), sqlx::Error> {
+    const QUERY: &str = "\
+        DELETE FROM order_events e \
+        WHERE e.block_number >= $1 AND ($2 IS NULL OR e.block_number <= $2) \
+        AND NOT EXISTS ( \
+            SELECT 1 FROM all_trades t \
+            WHERE e.label = 'traded' AND t.block_number = e.block_number \
+            AND t.log_index = e.log_index AND t.order_uid = e.order_uid \
+        ) \
+        AND NOT EXISTS ( \
+            SELECT 1 FROM invalidations i \
+            WHERE e.label = 'cancelled' AND i.block_number = e.block_number \
+            AND i.log_index = e.log_index AND i.order_uid = e.order_uid \
+        );";
+    transaction
+        .execute(
+            sqlx::query(QUERY)
+                .bind(delete_from_block_number as i64)
+                .bind(delete_to_block_number.map(|block_number| block_number as i64)),
+        )
+        .await?;
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl OrderEventStoring for Postgres {
+    async fn store_order_events(&self, events: &[(OrderUid, OrderEvent)]) -> Result<()> {
+        let events = events.to_vec();
+        let mut connection = self.pool.acquire().await?;
+        connection
+            .transaction(move |transaction| {
+                async move {
+                    for (uid, event) in &events {
+                        insert_order_event(transaction, uid, event).await?;
+                    }
+                    Ok::<_, sqlx::Error>(())
+                }
+                .boxed()
+            })
+            .await
+            .context("store_order_events failed")
+    }
+
+    async fn order_events(&self, uid: &OrderUid) -> Result<Vec<OrderEvent>> {
+        const QUERY: &str = "\
+            SELECT timestamp, label, filter_reason FROM order_events \
+            WHERE order_uid = $1 \
+            ORDER BY timestamp ASC;";
+        let rows: Vec<OrderEventRow> = sqlx::query_as(QUERY)
+            .bind(uid.0.as_ref())
+            .fetch_all(&self.pool)
+            .await
+            .context("order_events failed")?;
+        rows.into_iter().map(OrderEventRow::into_event).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDateTime};
+
+    #[test]
+    fn serialize_order_event() {
+        let event = OrderEvent {
+            timestamp: DateTime::from_utc(NaiveDateTime::from_timestamp(0, 0), Utc),
+            kind: OrderEventKind::Filtered {
+                reason: FilterReason::MissingNativePrice,
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "timestamp": "1970-01-01T00:00:00Z",
+                "type": "filtered",
+                "reason": "missingNativePrice",
+            })
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_order_events_roundtrip() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        let now = DateTime::from_utc(NaiveDateTime::from_timestamp(1_600_000_000, 0), Utc);
+        let uid = OrderUid::from_integer(0);
+        let events = vec![
+            OrderEvent {
+                timestamp: now + Duration::seconds(1),
+                kind: OrderEventKind::Filtered {
+                    reason: FilterReason::InsufficientBalance,
+                },
+            },
+            OrderEvent {
+                timestamp: now,
+                kind: OrderEventKind::Created,
+            },
+        ];
+        db.store_order_events(&[
+            (uid, events[0]),
+            (uid, events[1]),
+            (
+                OrderUid::from_integer(1),
+                OrderEvent {
+                    timestamp: now,
+                    kind: OrderEventKind::Traded,
+                },
+            ),
+        ])
+        .await
+        .unwrap();
+
+        assert_eq!(
+            db.order_events(&uid).await.unwrap(),
+            vec![events[1], events[0]]
+        );
+        assert!(db
+            .order_events(&OrderUid::from_integer(2))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,4 +1,7 @@
-use super::*;
+use super::{
+    order_events::{insert_order_event, OrderEvent, OrderEventKind},
+    *,
+};
 use crate::{conversions::*, fee::FeeParameters};
 use anyhow::{anyhow, Context as _, Result};
 use chrono::{DateTime, Utc};
@@ -15,24 +18,52 @@ use model::{
 };
 use num::Zero;
 use primitive_types::H160;
-use sqlx::{types::BigDecimal, Connection};
-use std::{borrow::Cow, convert::TryInto};
+use sqlx::{types::BigDecimal, Connection, PgPool};
+use std::convert::TryInto;
 
 #[cfg_attr(test, mockall::automock)]
 #[async_trait::async_trait]
 pub trait OrderStoring: Send + Sync {
     async fn insert_order(&self, order: &Order, fee: FeeParameters) -> Result<(), InsertionError>;
+    /// Inserts all orders that do not exist yet in a single transaction. Returns for every order
+    /// whether it was inserted. Existing orders are skipped instead of failing the batch.
+    async fn insert_orders(
+        &self,
+        orders: &[(Order, FeeParameters)],
+    ) -> Result<Vec<bool>, sqlx::Error>;
     async fn cancel_order(&self, order_uid: &OrderUid, now: DateTime<Utc>) -> Result<()>;
+    /// Cancels all orders in a single transaction.
+    async fn cancel_orders(&self, order_uids: Vec<OrderUid>, now: DateTime<Utc>) -> Result<()>;
+    /// Cancels the old order and inserts the new order in a single transaction. The old order is
+    /// cancelled at the creation time of the new order.
+    async fn replace_order(
+        &self,
+        old_order: &OrderUid,
+        new_order: &Order,
+        new_fee: FeeParameters,
+    ) -> Result<(), InsertionError>;
     // Legacy generic orders route that we are phasing out.
     async fn orders(&self, filter: &OrderFilter) -> Result<Vec<Order>>;
     async fn orders_for_tx(&self, tx_hash: &H256) -> Result<Vec<Order>>;
+    /// Also finds archived orders.
     async fn single_order(&self, uid: &OrderUid) -> Result<Option<Order>>;
+    /// Like `single_order` but never uses a read replica. Used when the order is about to be
+    /// modified so that a lagging replica can't hide recent changes.
+    async fn single_order_from_primary(&self, uid: &OrderUid) -> Result<Option<Order>>;
+    /// Like `single_order_from_primary` for multiple orders in a single query. Orders that don't
+    /// exist are missing from the result which is in no particular order.
+    async fn orders_from_primary(&self, uids: &[OrderUid]) -> Result<Vec<Order>>;
     /// Orders that are solvable: minimum valid to, not fully executed, not invalidated.
+    ///
+    /// Unlike the other queries this never uses a read replica because the solvable orders must
+    /// not lag behind the latest events.
     async fn solvable_orders(&self, min_valid_to: u32) -> Result<SolvableOrders>;
     /// All orders of a single user ordered by creation date descending (newest orders first).
+    /// When `after` is set only orders that come after that order in this ordering are returned.
     async fn user_orders(
         &self,
         owner: &H160,
+        after: Option<OrderUid>,
         offset: u64,
         limit: Option<u64>,
     ) -> Result<Vec<Order>>;
@@ -56,6 +87,10 @@ pub struct OrderFilter {
     pub exclude_unsupported_tokens: bool,
     pub exclude_presignature_pending: bool,
     pub uid: Option<OrderUid>,
+    /// Cursor for pagination. Orders are ordered by creation date descending and uid and only
+    /// the orders after this one are returned.
+    pub after: Option<OrderUid>,
+    pub limit: Option<u64>,
 }
 
 #[derive(sqlx::Type)]
@@ -74,7 +109,7 @@ impl DbOrderKind {
         }
     }
 
-    fn into(self) -> OrderKind {
+    pub fn into(self) -> OrderKind {
         match self {
             Self::Buy => OrderKind::Buy,
             Self::Sell => OrderKind::Sell,
@@ -144,6 +179,7 @@ impl DbBuyTokenDestination {
 pub enum DbSigningScheme {
     Eip712,
     EthSign,
+    Eip1271,
     PreSign,
 }
 
@@ -152,6 +188,7 @@ impl DbSigningScheme {
         match signing_scheme {
             SigningScheme::Eip712 => Self::Eip712,
             SigningScheme::EthSign => Self::EthSign,
+            SigningScheme::Eip1271 => Self::Eip1271,
             SigningScheme::PreSign => Self::PreSign,
         }
     }
@@ -160,6 +197,7 @@ impl DbSigningScheme {
         match self {
             Self::Eip712 => SigningScheme::Eip712,
             Self::EthSign => SigningScheme::EthSign,
+            Self::Eip1271 => SigningScheme::Eip1271,
             Self::PreSign => SigningScheme::PreSign,
         }
     }
@@ -201,6 +239,7 @@ const ORDERS_SELECT: &str = "\
     o.uid, o.owner, o.creation_timestamp, o.sell_token, o.buy_token, o.sell_amount, o.buy_amount, \
     o.valid_to, o.app_data, o.fee_amount, o.full_fee_amount, o.kind, o.partially_fillable, o.signature, \
     o.receiver, o.signing_scheme, o.settlement_contract, o.sell_token_balance, o.buy_token_balance, \
+    o.quote_id, \
     (SELECT COALESCE(SUM(t.buy_amount), 0) FROM trades t WHERE t.order_uid = o.uid) AS sum_buy, \
     (SELECT COALESCE(SUM(t.sell_amount), 0) FROM trades t WHERE t.order_uid = o.uid) AS sum_sell, \
     (SELECT COALESCE(SUM(t.fee_amount), 0) FROM trades t WHERE t.order_uid = o.uid) AS sum_fee, \
@@ -224,17 +263,42 @@ async fn insert_order(
     order: &Order,
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
 ) -> Result<(), InsertionError> {
+    if try_insert_order(order, transaction).await? {
+        Ok(())
+    } else {
+        Err(InsertionError::DuplicatedRecord)
+    }
+}
+
+/// Inserts the order unless an order with the same uid already exists. Returns whether the order
+/// was inserted.
+async fn try_insert_order(
+    order: &Order,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<bool, sqlx::Error> {
+    // The primary key only prevents duplicates of live orders so archived orders have to be
+    // checked explicitly.
+    const ARCHIVED_QUERY: &str = "SELECT EXISTS (SELECT 1 FROM archived_orders WHERE uid = $1);";
+    let (archived,): (bool,) = sqlx::query_as(ARCHIVED_QUERY)
+        .bind(order.metadata.uid.0.as_ref())
+        .fetch_one(&mut *transaction)
+        .await?;
+    if archived {
+        return Ok(false);
+    }
     const QUERY: &str = "\
             INSERT INTO orders (
                 uid, owner, creation_timestamp, sell_token, buy_token, receiver, sell_amount, buy_amount, \
                 valid_to, app_data, fee_amount, kind, partially_fillable, signature, signing_scheme, \
-                settlement_contract, sell_token_balance, buy_token_balance, full_fee_amount) \
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19);";
+                settlement_contract, sell_token_balance, buy_token_balance, full_fee_amount, quote_id) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20) \
+            ON CONFLICT (uid) DO NOTHING \
+            RETURNING uid;";
     let receiver = order
         .creation
         .receiver
         .map(|address| address.as_bytes().to_vec());
-    sqlx::query(QUERY)
+    let inserted: Option<(Vec<u8>,)> = sqlx::query_as(QUERY)
         .bind(order.metadata.uid.0.as_ref())
         .bind(order.metadata.owner.as_bytes())
         .bind(order.metadata.creation_date)
@@ -256,24 +320,25 @@ async fn insert_order(
             order.creation.buy_token_balance,
         ))
         .bind(u256_to_big_decimal(&order.metadata.full_fee_amount))
-        .execute(transaction)
-        .await
-        .map(|_| ())
-        .map_err(|err| {
-            if let sqlx::Error::Database(db_err) = &err {
-                if let Some(Cow::Borrowed("23505")) = db_err.code() {
-                    return InsertionError::DuplicatedRecord;
-                }
-            }
-            InsertionError::DbError(err)
-        })
+        .bind(order.metadata.quote_id)
+        .fetch_optional(&mut *transaction)
+        .await?;
+    if inserted.is_none() {
+        return Ok(false);
+    }
+    let event = OrderEvent {
+        timestamp: order.metadata.creation_date,
+        kind: OrderEventKind::Created,
+    };
+    insert_order_event(transaction, &order.metadata.uid, &event).await?;
+    Ok(true)
 }
 
 async fn insert_fee(
     uid: &OrderUid,
     fee: &FeeParameters,
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
-) -> Result<(), InsertionError> {
+) -> Result<(), sqlx::Error> {
     const QUERY: &str = "\
                 INSERT INTO order_fee_parameters (\
                     order_uid, gas_amount, gas_price, sell_token_price) \
@@ -287,9 +352,17 @@ async fn insert_fee(
         .execute(transaction)
         .await
         .map(|_| ())
-        .map_err(InsertionError::DbError)
 }
 
+// We do not overwrite previously cancelled orders,
+// but this query does allow the user to soft cancel
+// an order that has already been invalidated on-chain.
+const CANCEL_ORDER_QUERY: &str = "\
+    UPDATE orders
+    SET cancellation_timestamp = $1 \
+    WHERE uid = $2\
+    AND cancellation_timestamp IS NULL;";
+
 #[async_trait::async_trait]
 impl OrderStoring for Postgres {
     async fn insert_order(&self, order: &Order, fee: FeeParameters) -> Result<(), InsertionError> {
@@ -307,16 +380,32 @@ impl OrderStoring for Postgres {
             .await
     }
 
+    async fn insert_orders(
+        &self,
+        orders: &[(Order, FeeParameters)],
+    ) -> Result<Vec<bool>, sqlx::Error> {
+        let orders = orders.to_vec();
+        let mut connection = self.pool.acquire().await?;
+        connection
+            .transaction(move |transaction| {
+                async move {
+                    let mut inserted = Vec::with_capacity(orders.len());
+                    for (order, fee) in &orders {
+                        let is_new = try_insert_order(order, transaction).await?;
+                        if is_new {
+                            insert_fee(&order.metadata.uid, fee, transaction).await?;
+                        }
+                        inserted.push(is_new);
+                    }
+                    Ok(inserted)
+                }
+                .boxed()
+            })
+            .await
+    }
+
     async fn cancel_order(&self, order_uid: &OrderUid, now: DateTime<Utc>) -> Result<()> {
-        // We do not overwrite previously cancelled orders,
-        // but this query does allow the user to soft cancel
-        // an order that has already been invalidated on-chain.
-        const QUERY: &str = "\
-            UPDATE orders
-            SET cancellation_timestamp = $1 \
-            WHERE uid = $2\
-            AND cancellation_timestamp IS NULL;";
-        sqlx::query(QUERY)
+        sqlx::query(CANCEL_ORDER_QUERY)
             .bind(now)
             .bind(order_uid.0.as_ref())
             .execute(&self.pool)
@@ -325,6 +414,52 @@ impl OrderStoring for Postgres {
             .map(|_| ())
     }
 
+    async fn cancel_orders(&self, order_uids: Vec<OrderUid>, now: DateTime<Utc>) -> Result<()> {
+        let mut connection = self.pool.acquire().await?;
+        connection
+            .transaction(move |transaction| {
+                async move {
+                    for order_uid in &order_uids {
+                        sqlx::query(CANCEL_ORDER_QUERY)
+                            .bind(now)
+                            .bind(order_uid.0.as_ref())
+                            .execute(&mut *transaction)
+                            .await?;
+                    }
+                    Ok::<_, sqlx::Error>(())
+                }
+                .boxed()
+            })
+            .await
+            .context("cancel_orders failed")
+    }
+
+    async fn replace_order(
+        &self,
+        old_order: &OrderUid,
+        new_order: &Order,
+        new_fee: FeeParameters,
+    ) -> Result<(), InsertionError> {
+        let old_order = *old_order;
+        let new_order = new_order.clone();
+        let mut connection = self.pool.acquire().await?;
+        connection
+            .transaction(move |transaction| {
+                async move {
+                    sqlx::query(CANCEL_ORDER_QUERY)
+                        .bind(new_order.metadata.creation_date)
+                        .bind(old_order.0.as_ref())
+                        .execute(&mut *transaction)
+                        .await?;
+                    insert_order(&new_order, transaction).await?;
+                    insert_fee(&new_order.metadata.uid, &new_fee, transaction).await?;
+                    Ok(())
+                }
+                .boxed()
+            })
+            .await
+    }
+
     async fn orders(&self, filter: &OrderFilter) -> Result<Vec<Order>> {
         // The `or`s in the `where` clause are there so that each filter is ignored when not set.
         // We use a subquery instead of a `having` clause in the inner query because we would not be
@@ -339,7 +474,10 @@ impl OrderStoring for Postgres {
                     ($2 IS NULL OR o.owner = $2) AND \
                     ($3 IS NULL OR o.sell_token = $3) AND \
                     ($4 IS NULL OR o.buy_token = $4) AND \
-                    ($5 IS NULL OR o.uid = $5) ",
+                    ($5 IS NULL OR o.uid = $5) AND \
+                    ($9 IS NULL OR (o.creation_timestamp, o.uid) < ( \
+                        SELECT creation_timestamp, uid FROM orders WHERE uid = $9 \
+                    )) ",
             ") AS unfiltered \
             WHERE \
                 ($6 OR CASE kind \
@@ -347,7 +485,9 @@ impl OrderStoring for Postgres {
                     WHEN 'buy' THEN sum_buy < buy_amount \
                 END) AND \
                 ($7 OR NOT invalidated) AND \
-                ($8 OR NOT presignature_pending);"
+                ($8 OR NOT presignature_pending) \
+            ORDER BY creation_timestamp DESC, uid DESC \
+            LIMIT $10;"
         );
         sqlx::query_as(QUERY)
             .bind(filter.min_valid_to as i64)
@@ -358,7 +498,9 @@ impl OrderStoring for Postgres {
             .bind(!filter.exclude_fully_executed)
             .bind(!filter.exclude_invalidated)
             .bind(!filter.exclude_presignature_pending)
-            .fetch(&self.pool)
+            .bind(filter.after.as_ref().map(|uid| uid.0.as_ref()))
+            .bind(filter.limit.map(|limit| limit as i64))
+            .fetch(self.read_pool())
             .err_into()
             .and_then(|row: OrdersQueryRow| async move { row.into_order() })
             .try_collect()
@@ -402,7 +544,7 @@ impl OrderStoring for Postgres {
         );
         sqlx::query_as(QUERY)
             .bind(tx_hash.0.as_ref())
-            .fetch(&self.pool)
+            .fetch(self.read_pool())
             .err_into()
             .and_then(|row: OrdersQueryRow| async move { row.into_order() })
             .try_collect()
@@ -410,17 +552,30 @@ impl OrderStoring for Postgres {
     }
 
     async fn single_order(&self, uid: &OrderUid) -> Result<Option<Order>> {
+        self.single_order_(self.read_pool(), uid).await
+    }
+
+    async fn single_order_from_primary(&self, uid: &OrderUid) -> Result<Option<Order>> {
+        self.single_order_(&self.pool, uid).await
+    }
+
+    async fn orders_from_primary(&self, uids: &[OrderUid]) -> Result<Vec<Order>> {
         #[rustfmt::skip]
         const QUERY: &str = concatcp!(
+            "WITH trades AS (SELECT * FROM all_trades WHERE order_uid = ANY($1)), ",
+            "presignature_events AS (SELECT * FROM all_presignature_events WHERE order_uid = ANY($1)) ",
             "SELECT ", ORDERS_SELECT,
-            "FROM ", ORDERS_FROM,
-            "WHERE o.uid = $1 ",
+            "FROM all_orders o ",
+            "WHERE o.uid = ANY($1) ",
         );
-        let order = sqlx::query_as(QUERY)
-            .bind(uid.0.as_ref())
-            .fetch_optional(&self.pool)
-            .await?;
-        order.map(OrdersQueryRow::into_order).transpose()
+        let uids = uids.iter().map(|uid| uid.0.to_vec()).collect::<Vec<_>>();
+        sqlx::query_as(QUERY)
+            .bind(uids)
+            .fetch(&self.pool)
+            .err_into()
+            .and_then(|row: OrdersQueryRow| async move { row.into_order() })
+            .try_collect()
+            .await
     }
 
     async fn solvable_orders(&self, min_valid_to: u32) -> Result<SolvableOrders> {
@@ -429,7 +584,7 @@ impl OrderStoring for Postgres {
             "SELECT * FROM ( ",
                 "SELECT ", ORDERS_SELECT,
                 "FROM ", ORDERS_FROM,
-                "WHERE o.valid_to >= $1 ",
+                "WHERE o.valid_to >= $1 AND o.expiration_timestamp IS NULL ",
             ") AS unfiltered \
             WHERE \
                 CASE kind \
@@ -469,22 +624,25 @@ impl OrderStoring for Postgres {
     async fn user_orders(
         &self,
         owner: &H160,
+        after: Option<OrderUid>,
         offset: u64,
         limit: Option<u64>,
     ) -> Result<Vec<Order>> {
-        // As a future consideration for this query we could move from offset to an approach called
-        // keyset pagination where the offset is identified by "key" of the previous query. In our
-        // case that would be the lowest creation_timestamp. This way the database can start
-        // immediately at the offset through the index without enumerating the first N elements
-        // before as is the case with OFFSET.
-        // On the other hand that approach is less flexible so we will consider if we see that these
-        // queries are taking too long in practice.
+        // With `after` set this is keyset pagination where the offset is identified by the "key"
+        // of the last order of the previous query. This way the database can start immediately at
+        // the cursor through the index without enumerating the first N elements before as is the
+        // case with OFFSET which we keep supporting for backwards compatibility.
+        // The uid is part of the key so that orders with the same creation timestamp are not
+        // skipped. If the cursor order does not exist nothing is returned.
         #[rustfmt::skip]
         const QUERY: &str = concatcp!(
             "SELECT ", ORDERS_SELECT,
             "FROM ", ORDERS_FROM,
-            "WHERE o.owner = $1 ",
-            "ORDER BY o.creation_timestamp DESC ",
+            "WHERE o.owner = $1 AND \
+                ($4 IS NULL OR (o.creation_timestamp, o.uid) < ( \
+                    SELECT creation_timestamp, uid FROM orders WHERE uid = $4 \
+                )) ",
+            "ORDER BY o.creation_timestamp DESC, o.uid DESC ",
             "LIMIT $2 ",
             "OFFSET $3 ",
         );
@@ -492,7 +650,8 @@ impl OrderStoring for Postgres {
             .bind(owner.as_bytes())
             .bind(limit.map(|limit| limit as i64))
             .bind(offset as i64)
-            .fetch(&self.pool)
+            .bind(after.as_ref().map(|uid| uid.0.as_ref()))
+            .fetch(self.read_pool())
             .err_into()
             .and_then(|row: OrdersQueryRow| async move { row.into_order() })
             .try_collect()
@@ -500,6 +659,132 @@ impl OrderStoring for Postgres {
     }
 }
 
+impl Postgres {
+    async fn single_order_(&self, pool: &PgPool, uid: &OrderUid) -> Result<Option<Order>> {
+        // The trades and presignature events of archived orders are archived too. The expressions
+        // shadow the live tables so that `ORDERS_SELECT` includes them.
+        #[rustfmt::skip]
+        const QUERY: &str = concatcp!(
+            "WITH trades AS (SELECT * FROM all_trades WHERE order_uid = $1), ",
+            "presignature_events AS (SELECT * FROM all_presignature_events WHERE order_uid = $1) ",
+            "SELECT ", ORDERS_SELECT,
+            "FROM all_orders o ",
+            "WHERE o.uid = $1 ",
+        );
+        let order = sqlx::query_as(QUERY)
+            .bind(uid.0.as_ref())
+            .fetch_optional(pool)
+            .await?;
+        order.map(OrdersQueryRow::into_order).transpose()
+    }
+
+    /// Marks all orders whose `valid_to` is before `now` as expired and stores an expired order
+    /// event for the ones that were neither filled nor cancelled. Returns these orders with their
+    /// owner.
+    ///
+    /// Filled and cancelled orders get marked too so that they are no longer part of the index of
+    /// unexpired orders.
+    pub async fn mark_expired_orders(&self, now: DateTime<Utc>) -> Result<Vec<(OrderUid, H160)>> {
+        const QUERY: &str = "\
+            UPDATE orders o \
+            SET expiration_timestamp = $1 \
+            WHERE o.expiration_timestamp IS NULL AND o.valid_to < $2 \
+            RETURNING o.uid, o.owner, ( \
+                o.cancellation_timestamp IS NULL AND \
+                NOT EXISTS (SELECT 1 FROM invalidations i WHERE i.order_uid = o.uid) AND \
+                CASE o.kind \
+                    WHEN 'sell' THEN o.sell_amount > \
+                        (SELECT COALESCE(SUM(t.sell_amount), 0) FROM trades t WHERE t.order_uid = o.uid) \
+                    WHEN 'buy' THEN o.buy_amount > \
+                        (SELECT COALESCE(SUM(t.buy_amount), 0) FROM trades t WHERE t.order_uid = o.uid) \
+                END \
+            ) AS open;";
+        let mut connection = self.pool.acquire().await?;
+        connection
+            .transaction(move |transaction| {
+                async move {
+                    let rows: Vec<(Vec<u8>, Vec<u8>, bool)> = sqlx::query_as(QUERY)
+                        .bind(now)
+                        .bind(now.timestamp())
+                        .fetch_all(&mut *transaction)
+                        .await?;
+                    let mut expired = Vec::new();
+                    for (uid, owner, open) in rows {
+                        if !open {
+                            continue;
+                        }
+                        let uid = OrderUid(
+                            uid.try_into()
+                                .map_err(|_| anyhow!("order uid has wrong length"))?,
+                        );
+                        let event = OrderEvent {
+                            timestamp: now,
+                            kind: OrderEventKind::Expired,
+                        };
+                        insert_order_event(transaction, &uid, &event).await?;
+                        expired.push((uid, h160_from_vec(owner)?));
+                    }
+                    Ok::<_, anyhow::Error>(expired)
+                }
+                .boxed()
+            })
+            .await
+            .context("mark_expired_orders failed")
+    }
+
+    /// Moves up to `limit` orders that were created before `cutoff` and can no longer be traded
+    /// into the archive together with their trades and presignature events. These are orders that are fully executed,
+    /// cancelled or expired before `cutoff`. Returns the number of archived orders.
+    pub async fn archive_orders(&self, cutoff: DateTime<Utc>, limit: u64) -> Result<u64> {
+        // Statements in `WITH` are executed exactly once even if their result isn't used so this
+        // moves the orders, their trades and presignature events atomically.
+        #[rustfmt::skip]
+        const QUERY: &str = concatcp!(
+            "WITH archived AS ( ",
+                "DELETE FROM orders WHERE uid IN ( ",
+                    "SELECT uid FROM ( ",
+                        "SELECT ", ORDERS_SELECT, ", o.expiration_timestamp ",
+                        "FROM ", ORDERS_FROM,
+                        "WHERE o.creation_timestamp < $1 ",
+                    ") AS unfiltered \
+                    WHERE \
+                        expiration_timestamp < $1 OR \
+                        invalidated OR \
+                        CASE kind \
+                            WHEN 'sell' THEN sum_sell >= sell_amount \
+                            WHEN 'buy' THEN sum_buy >= buy_amount \
+                        END \
+                    LIMIT $2 \
+                ) \
+                RETURNING * \
+            ), \
+            archived_trades_ AS ( \
+                DELETE FROM trades WHERE order_uid IN (SELECT uid FROM archived) \
+                RETURNING * \
+            ), \
+            inserted_trades AS ( \
+                INSERT INTO archived_trades SELECT * FROM archived_trades_ \
+            ), \
+            archived_presignature_events_ AS ( \
+                DELETE FROM presignature_events WHERE order_uid IN (SELECT uid FROM archived) \
+                RETURNING * \
+            ), \
+            inserted_presignature_events AS ( \
+                INSERT INTO archived_presignature_events \
+                SELECT * FROM archived_presignature_events_ \
+            ) \
+            INSERT INTO archived_orders SELECT * FROM archived RETURNING uid;"
+        );
+        let archived: Vec<(Vec<u8>,)> = sqlx::query_as(QUERY)
+            .bind(cutoff)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await
+            .context("archive_orders failed")?;
+        Ok(archived.len() as u64)
+    }
+}
+
 #[derive(sqlx::FromRow)]
 struct OrdersQueryRow {
     uid: Vec<u8>,
@@ -525,6 +810,7 @@ struct OrdersQueryRow {
     settlement_contract: Vec<u8>,
     sell_token_balance: DbSellTokenSource,
     buy_token_balance: DbBuyTokenDestination,
+    quote_id: Option<i64>,
     presignature_pending: bool,
 }
 
@@ -581,6 +867,7 @@ impl OrdersQueryRow {
             settlement_contract: h160_from_vec(self.settlement_contract)?,
             full_fee_amount: big_decimal_to_u256(&self.full_fee_amount)
                 .ok_or_else(|| anyhow!("full_fee_amount is not U256"))?,
+            quote_id: self.quote_id,
         };
         let signing_scheme = self.signing_scheme.into();
         let order_creation = OrderCreation {
@@ -612,7 +899,7 @@ impl OrdersQueryRow {
     }
 }
 
-fn is_sell_order_filled(
+pub(super) fn is_sell_order_filled(
     amount: &BigDecimal,
     executed_amount: &BigDecimal,
     executed_fee: &BigDecimal,
@@ -624,14 +911,16 @@ fn is_sell_order_filled(
     total_amount == *amount
 }
 
-fn is_buy_order_filled(amount: &BigDecimal, executed_amount: &BigDecimal) -> bool {
+pub(super) fn is_buy_order_filled(amount: &BigDecimal, executed_amount: &BigDecimal) -> bool {
     !executed_amount.is_zero() && *amount == *executed_amount
 }
 
 #[cfg(test)]
 mod tests {
     use super::events::*;
+    use super::order_events::OrderEventStoring;
     use super::*;
+    use crate::database::trades::{TradeFilter, TradeRetrieving};
     use chrono::{Duration, NaiveDateTime};
     use num::BigUint;
     use primitive_types::U256;
@@ -670,6 +959,7 @@ mod tests {
             settlement_contract: vec![0; 20],
             sell_token_balance: DbSellTokenSource::External,
             buy_token_balance: DbBuyTokenDestination::Internal,
+            quote_id: None,
             presignature_pending: false,
         };
 
@@ -868,6 +1158,46 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_insert_orders_skips_duplicates() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        let order = |uid: u32| Order {
+            metadata: OrderMetadata {
+                uid: OrderUid::from_integer(uid),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let inserted = db
+            .insert_orders(&[
+                (order(0), Default::default()),
+                (order(1), Default::default()),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(inserted, [true, true]);
+        assert_eq!(db.orders(&OrderFilter::default()).await.unwrap().len(), 2);
+
+        // The duplicated order is skipped without affecting the rest of the batch.
+        let inserted = db
+            .insert_orders(&[
+                (order(2), Default::default()),
+                (order(1), Default::default()),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(inserted, [true, false]);
+        assert!(db
+            .single_order(&OrderUid::from_integer(2))
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(db.orders(&OrderFilter::default()).await.unwrap().len(), 3);
+    }
+
     #[tokio::test]
     #[ignore]
     #[allow(clippy::float_cmp)]
@@ -902,6 +1232,7 @@ mod tests {
         for signing_scheme in &[
             SigningScheme::Eip712,
             SigningScheme::EthSign,
+            SigningScheme::Eip1271,
             SigningScheme::PreSign,
         ] {
             db.clear().await.unwrap();
@@ -990,6 +1321,84 @@ mod tests {
         assert_eq!(first_cancellation, second_cancellation);
     }
 
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_cancel_orders() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        let uids = [OrderUid::from_integer(0), OrderUid::from_integer(1)];
+        for uid in &uids[..] {
+            let order = Order {
+                metadata: OrderMetadata {
+                    uid: *uid,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            db.insert_order(&order, Default::default()).await.unwrap();
+        }
+
+        db.cancel_orders(uids.to_vec(), Utc::now()).await.unwrap();
+        for uid in &uids {
+            let order = db.single_order(uid).await.unwrap().unwrap();
+            assert!(order.metadata.invalidated);
+        }
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_replace_order() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        let old_order = Order {
+            metadata: OrderMetadata {
+                uid: OrderUid::from_integer(0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        db.insert_order(&old_order, Default::default())
+            .await
+            .unwrap();
+
+        // Replacing with an order that already exists fails and leaves the old order untouched.
+        let result = db
+            .replace_order(&old_order.metadata.uid, &old_order, Default::default())
+            .await;
+        assert!(matches!(result, Err(InsertionError::DuplicatedRecord)));
+        let order = db
+            .single_order(&old_order.metadata.uid)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!order.metadata.invalidated);
+
+        let new_order = Order {
+            metadata: OrderMetadata {
+                uid: OrderUid::from_integer(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        db.replace_order(&old_order.metadata.uid, &new_order, Default::default())
+            .await
+            .unwrap();
+        let order = db
+            .single_order(&old_order.metadata.uid)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(order.metadata.invalidated);
+        let order = db
+            .single_order(&new_order.metadata.uid)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!order.metadata.invalidated);
+    }
+
     #[tokio::test]
     #[ignore]
     async fn postgres_filter_orders_by_address() {
@@ -1522,6 +1931,206 @@ mod tests {
         assert!(get_order(3).await.is_some());
     }
 
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_mark_expired_orders() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        let order = |uid: u8, valid_to: u32| Order {
+            metadata: OrderMetadata {
+                uid: OrderUid([uid; 56]),
+                owner: H160([uid; 20]),
+                ..Default::default()
+            },
+            creation: OrderCreation {
+                kind: OrderKind::Sell,
+                sell_amount: 10.into(),
+                buy_amount: 100.into(),
+                valid_to,
+                ..Default::default()
+            },
+        };
+        let expired = order(1, 1);
+        let cancelled = order(2, 1);
+        let open = order(3, 10);
+        for order in [&expired, &cancelled, &open] {
+            db.insert_order(order, Default::default()).await.unwrap();
+        }
+        db.cancel_order(&cancelled.metadata.uid, datetime(0))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            db.mark_expired_orders(datetime(5)).await.unwrap(),
+            vec![(expired.metadata.uid, expired.metadata.owner)]
+        );
+        assert!(db
+            .mark_expired_orders(datetime(5))
+            .await
+            .unwrap()
+            .is_empty());
+
+        let events = db.order_events(&expired.metadata.uid).await.unwrap();
+        assert_eq!(
+            events.last().unwrap(),
+            &OrderEvent {
+                timestamp: datetime(5),
+                kind: OrderEventKind::Expired,
+            }
+        );
+        let events = db.order_events(&cancelled.metadata.uid).await.unwrap();
+        assert!(!events
+            .iter()
+            .any(|event| event.kind == OrderEventKind::Expired));
+
+        let solvable = db.solvable_orders(0).await.unwrap().orders;
+        assert_eq!(solvable.len(), 1);
+        assert_eq!(solvable[0].metadata.uid, open.metadata.uid);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_archive_orders() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        let order = |uid: u8, valid_to: u32| Order {
+            metadata: OrderMetadata {
+                uid: OrderUid([uid; 56]),
+                owner: H160([uid; 20]),
+                creation_date: datetime(0),
+                ..Default::default()
+            },
+            creation: OrderCreation {
+                kind: OrderKind::Sell,
+                sell_amount: 10.into(),
+                buy_amount: 100.into(),
+                valid_to,
+                ..Default::default()
+            },
+        };
+        let filled = order(1, 100);
+        let cancelled = order(2, 100);
+        let expired = order(3, 1);
+        let open = order(4, 100);
+        for order in [&filled, &cancelled, &expired, &open] {
+            db.insert_order(order, Default::default()).await.unwrap();
+        }
+        db.append_events_(vec![
+            (
+                EventIndex {
+                    block_number: 0,
+                    log_index: 0,
+                },
+                Event::Trade(Trade {
+                    order_uid: filled.metadata.uid,
+                    sell_amount_including_fee: 10.into(),
+                    ..Default::default()
+                }),
+            ),
+            (
+                EventIndex {
+                    block_number: 0,
+                    log_index: 1,
+                },
+                Event::PreSignature(PreSignature {
+                    order_uid: filled.metadata.uid,
+                    signed: true,
+                    ..Default::default()
+                }),
+            ),
+        ])
+        .await
+        .unwrap();
+        db.cancel_order(&cancelled.metadata.uid, datetime(0))
+            .await
+            .unwrap();
+        db.mark_expired_orders(datetime(2)).await.unwrap();
+
+        // Nothing was created before the cutoff.
+        assert_eq!(db.archive_orders(datetime(0), 10).await.unwrap(), 0);
+        // Respects the limit.
+        assert_eq!(db.archive_orders(datetime(5), 2).await.unwrap(), 2);
+        assert_eq!(db.archive_orders(datetime(5), 10).await.unwrap(), 1);
+        assert_eq!(db.archive_orders(datetime(5), 10).await.unwrap(), 0);
+
+        let counts = db.count_rows_in_tables().await.unwrap();
+        assert_eq!(counts.get("orders"), Some(&1));
+        assert_eq!(counts.get("archived_orders"), Some(&3));
+        assert_eq!(counts.get("trades"), Some(&0));
+        assert_eq!(counts.get("archived_trades"), Some(&1));
+        assert_eq!(counts.get("presignature_events"), Some(&0));
+        assert_eq!(counts.get("archived_presignature_events"), Some(&1));
+
+        let archived = db
+            .single_order(&filled.metadata.uid)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(archived.metadata.executed_sell_amount, BigUint::from(10u8));
+        assert_eq!(archived.metadata.status, OrderStatus::Fulfilled);
+        let trades = db
+            .trades(&TradeFilter {
+                order_uid: Some(filled.metadata.uid),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(trades.len(), 1);
+        assert!(db.single_order(&open.metadata.uid).await.unwrap().is_some());
+
+        // Archived orders cannot be created again.
+        assert!(matches!(
+            db.insert_order(&filled, Default::default()).await,
+            Err(InsertionError::DuplicatedRecord)
+        ));
+        assert!(matches!(
+            db.replace_order(&open.metadata.uid, &expired, Default::default())
+                .await,
+            Err(InsertionError::DuplicatedRecord)
+        ));
+        assert_eq!(
+            db.count_rows_in_tables().await.unwrap().get("orders"),
+            Some(&1)
+        );
+
+        // Indexing the blocks again doesn't duplicate archived events.
+        db.replace_events_(
+            0,
+            vec![
+                (
+                    EventIndex {
+                        block_number: 0,
+                        log_index: 0,
+                    },
+                    Event::Trade(Trade {
+                        order_uid: filled.metadata.uid,
+                        sell_amount_including_fee: 10.into(),
+                        ..Default::default()
+                    }),
+                ),
+                (
+                    EventIndex {
+                        block_number: 0,
+                        log_index: 1,
+                    },
+                    Event::PreSignature(PreSignature {
+                        order_uid: filled.metadata.uid,
+                        signed: true,
+                        ..Default::default()
+                    }),
+                ),
+            ],
+        )
+        .await
+        .unwrap();
+        let counts = db.count_rows_in_tables().await.unwrap();
+        assert_eq!(counts.get("trades"), Some(&0));
+        assert_eq!(counts.get("presignature_events"), Some(&0));
+        assert_eq!(db.events_in_block_range(0..=0).await.unwrap().len(), 2);
+    }
+
     #[tokio::test]
     #[ignore]
     async fn postgres_single_order() {
@@ -1783,20 +2392,70 @@ mod tests {
             db.insert_order(order, Default::default()).await.unwrap();
         }
 
-        let result = db.user_orders(&owners[0], 0, None).await.unwrap();
+        let result = db.user_orders(&owners[0], None, 0, None).await.unwrap();
         assert_eq!(result, vec![orders[0].clone(), orders[2].clone()]);
 
-        let result = db.user_orders(&owners[1], 0, None).await.unwrap();
+        let result = db.user_orders(&owners[1], None, 0, None).await.unwrap();
         assert_eq!(result, vec![orders[1].clone(), orders[3].clone()]);
 
-        let result = db.user_orders(&owners[0], 0, Some(1)).await.unwrap();
+        let result = db.user_orders(&owners[0], None, 0, Some(1)).await.unwrap();
         assert_eq!(result, vec![orders[0].clone()]);
 
-        let result = db.user_orders(&owners[0], 1, Some(1)).await.unwrap();
+        let result = db.user_orders(&owners[0], None, 1, Some(1)).await.unwrap();
         assert_eq!(result, vec![orders[2].clone()]);
 
-        let result = db.user_orders(&owners[0], 2, Some(1)).await.unwrap();
+        let result = db.user_orders(&owners[0], None, 2, Some(1)).await.unwrap();
         assert_eq!(result, vec![]);
+
+        let result = db
+            .user_orders(&owners[0], Some(orders[0].metadata.uid), 0, Some(1))
+            .await
+            .unwrap();
+        assert_eq!(result, vec![orders[2].clone()]);
+
+        let result = db
+            .user_orders(&owners[0], Some(orders[2].metadata.uid), 0, None)
+            .await
+            .unwrap();
+        assert_eq!(result, vec![]);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_orders_cursor_pagination() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        // Orders 1 and 2 have the same creation date so they are ordered by uid.
+        let order = |uid: u32, creation_date: u32| Order {
+            metadata: OrderMetadata {
+                uid: OrderUid::from_integer(uid),
+                creation_date: datetime(creation_date),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let orders = [order(0, 2), order(2, 1), order(1, 1), order(3, 0)];
+        for order in &orders {
+            db.insert_order(order, Default::default()).await.unwrap();
+        }
+
+        let page = |after: Option<usize>| {
+            let db = db.clone();
+            let after = after.map(|i| orders[i].metadata.uid);
+            async move {
+                db.orders(&OrderFilter {
+                    after,
+                    limit: Some(2),
+                    ..Default::default()
+                })
+                .await
+                .unwrap()
+            }
+        };
+        assert_eq!(page(None).await, orders[0..2]);
+        assert_eq!(page(Some(1)).await, orders[2..4]);
+        assert_eq!(page(Some(3)).await, []);
     }
 
     #[tokio::test]
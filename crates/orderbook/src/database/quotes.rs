@@ -0,0 +1,236 @@
+use super::{orders::DbOrderKind, Postgres};
+use crate::{conversions::*, fee::FeeParameters};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use model::{app_id::AppId, order::OrderKind};
+use primitive_types::{H160, U256};
+use sqlx::types::BigDecimal;
+use std::convert::TryInto;
+
+pub type QuoteId = i64;
+
+/// A quote as it was given to a user together with the fee parameters that were used to compute
+/// its fee.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuoteData {
+    pub owner: H160,
+    pub sell_token: H160,
+    pub buy_token: H160,
+    pub sell_amount: U256,
+    pub buy_amount: U256,
+    /// The subsidized fee amount that was quoted.
+    pub fee_amount: U256,
+    pub app_data: AppId,
+    pub kind: OrderKind,
+    pub fee_parameters: FeeParameters,
+    pub expiration: DateTime<Utc>,
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait QuoteStoring: Send + Sync {
+    /// Stores the quote and returns the id it can be referenced by.
+    async fn save_quote(&self, quote: &QuoteData) -> Result<QuoteId>;
+
+    /// Returns the quote with the given id if it exists and expires after `min_expiry`.
+    async fn find_quote(&self, id: QuoteId, min_expiry: DateTime<Utc>)
+        -> Result<Option<QuoteData>>;
+}
+
+#[derive(sqlx::FromRow)]
+struct QuoteRow {
+    owner: Vec<u8>,
+    sell_token: Vec<u8>,
+    buy_token: Vec<u8>,
+    sell_amount: BigDecimal,
+    buy_amount: BigDecimal,
+    fee_amount: BigDecimal,
+    app_data: Vec<u8>,
+    order_kind: DbOrderKind,
+    gas_amount: f64,
+    gas_price: f64,
+    sell_token_price: f64,
+    expiration_timestamp: DateTime<Utc>,
+}
+
+impl QuoteRow {
+    fn into_quote(self) -> Result<QuoteData> {
+        Ok(QuoteData {
+            owner: h160_from_vec(self.owner)?,
+            sell_token: h160_from_vec(self.sell_token)?,
+            buy_token: h160_from_vec(self.buy_token)?,
+            sell_amount: big_decimal_to_u256(&self.sell_amount)
+                .ok_or_else(|| anyhow!("sell_amount is not U256"))?,
+            buy_amount: big_decimal_to_u256(&self.buy_amount)
+                .ok_or_else(|| anyhow!("buy_amount is not U256"))?,
+            fee_amount: big_decimal_to_u256(&self.fee_amount)
+                .ok_or_else(|| anyhow!("fee_amount is not U256"))?,
+            app_data: AppId(
+                self.app_data
+                    .try_into()
+                    .map_err(|_| anyhow!("app_data is not [u8; 32]"))?,
+            ),
+            kind: self.order_kind.into(),
+            fee_parameters: FeeParameters {
+                gas_amount: self.gas_amount,
+                gas_price: self.gas_price,
+                sell_token_price: self.sell_token_price,
+            },
+            expiration: self.expiration_timestamp,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl QuoteStoring for Postgres {
+    async fn save_quote(&self, quote: &QuoteData) -> Result<QuoteId> {
+        const QUERY: &str = "\
+            INSERT INTO quotes (\
+                owner, sell_token, buy_token, sell_amount, buy_amount, fee_amount, app_data, \
+                order_kind, gas_amount, gas_price, sell_token_price, expiration_timestamp) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) \
+            RETURNING id;";
+        let (id,): (QuoteId,) = sqlx::query_as(QUERY)
+            .bind(quote.owner.as_bytes())
+            .bind(quote.sell_token.as_bytes())
+            .bind(quote.buy_token.as_bytes())
+            .bind(u256_to_big_decimal(&quote.sell_amount))
+            .bind(u256_to_big_decimal(&quote.buy_amount))
+            .bind(u256_to_big_decimal(&quote.fee_amount))
+            .bind(&quote.app_data.0[..])
+            .bind(DbOrderKind::from(quote.kind))
+            .bind(quote.fee_parameters.gas_amount)
+            .bind(quote.fee_parameters.gas_price)
+            .bind(quote.fee_parameters.sell_token_price)
+            .bind(quote.expiration)
+            .fetch_one(&self.pool)
+            .await
+            .context("save_quote failed")?;
+        Ok(id)
+    }
+
+    async fn find_quote(
+        &self,
+        id: QuoteId,
+        min_expiry: DateTime<Utc>,
+    ) -> Result<Option<QuoteData>> {
+        const QUERY: &str = "\
+            SELECT * FROM quotes \
+            WHERE id = $1 AND expiration_timestamp >= $2;";
+        let row: Option<QuoteRow> = sqlx::query_as(QUERY)
+            .bind(id)
+            .bind(min_expiry)
+            .fetch_optional(&self.pool)
+            .await
+            .context("find_quote failed")?;
+        row.map(QuoteRow::into_quote).transpose()
+    }
+}
+
+impl Postgres {
+    pub async fn remove_expired_quotes(&self, max_expiry: DateTime<Utc>) -> Result<()> {
+        // Quotes that orders were created from are kept so that the order can still be related to
+        // its quote.
+        const QUERY: &str = "\
+            DELETE FROM quotes \
+            WHERE \
+                expiration_timestamp < $1 AND \
+                NOT EXISTS (SELECT 1 FROM all_orders WHERE quote_id = quotes.id);";
+        sqlx::query(QUERY)
+            .bind(max_expiry)
+            .execute(&self.pool)
+            .await
+            .context("remove_expired_quotes failed")
+            .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::orders::OrderStoring;
+    use chrono::{Duration, NaiveDateTime};
+    use model::order::{Order, OrderMetadata};
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_save_and_find_quote() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        let now = DateTime::from_utc(NaiveDateTime::from_timestamp(1_600_000_000, 0), Utc);
+        let quote = QuoteData {
+            owner: H160::from_low_u64_be(1),
+            sell_token: H160::from_low_u64_be(2),
+            buy_token: H160::from_low_u64_be(3),
+            sell_amount: 4.into(),
+            buy_amount: 5.into(),
+            fee_amount: 6.into(),
+            app_data: AppId([7; 32]),
+            kind: OrderKind::Buy,
+            fee_parameters: FeeParameters {
+                gas_amount: 8.,
+                gas_price: 9.,
+                sell_token_price: 10.,
+            },
+            expiration: now,
+        };
+        let id = db.save_quote(&quote).await.unwrap();
+
+        assert_eq!(db.find_quote(id, now).await.unwrap(), Some(quote.clone()));
+        assert_eq!(db.find_quote(id + 1, now).await.unwrap(), None);
+        assert_eq!(
+            db.find_quote(id, now + Duration::seconds(1)).await.unwrap(),
+            None
+        );
+
+        db.remove_expired_quotes(now + Duration::seconds(1))
+            .await
+            .unwrap();
+        assert_eq!(
+            db.find_quote(id, now - Duration::seconds(1)).await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_keeps_expired_quotes_of_orders() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        let now = DateTime::from_utc(NaiveDateTime::from_timestamp(1_600_000_000, 0), Utc);
+        let quote = QuoteData {
+            owner: H160::from_low_u64_be(1),
+            sell_token: H160::from_low_u64_be(2),
+            buy_token: H160::from_low_u64_be(3),
+            sell_amount: 4.into(),
+            buy_amount: 5.into(),
+            fee_amount: 6.into(),
+            app_data: AppId([7; 32]),
+            kind: OrderKind::Sell,
+            fee_parameters: Default::default(),
+            expiration: now,
+        };
+        let referenced = db.save_quote(&quote).await.unwrap();
+        let unreferenced = db.save_quote(&quote).await.unwrap();
+        let order = Order {
+            metadata: OrderMetadata {
+                quote_id: Some(referenced),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        db.insert_order(&order, Default::default()).await.unwrap();
+
+        db.remove_expired_quotes(now + Duration::seconds(1))
+            .await
+            .unwrap();
+        let min_expiry = now - Duration::seconds(1);
+        assert_eq!(
+            db.find_quote(referenced, min_expiry).await.unwrap(),
+            Some(quote)
+        );
+        assert_eq!(db.find_quote(unreferenced, min_expiry).await.unwrap(), None);
+    }
+}
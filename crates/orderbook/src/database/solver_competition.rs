@@ -0,0 +1,142 @@
+use super::Postgres;
+use anyhow::{Context, Result};
+use ethcontract::H256;
+use model::solver_competition::SolverCompetition;
+use std::convert::TryInto;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait SolverCompetitionStoring: Send + Sync {
+    /// Stores the competition of one solver run. Runs for the same auction are all kept.
+    async fn save_solver_competition(&self, competition: &SolverCompetition) -> Result<()>;
+
+    /// Returns the most recent competition of the auction that submitted a settlement or, if there
+    /// is none, the most recent competition of the auction.
+    async fn load_solver_competition(&self, auction_id: u64) -> Result<Option<SolverCompetition>>;
+
+    /// Returns the competition whose winning settlement was submitted in the transaction.
+    async fn load_solver_competition_by_tx_hash(
+        &self,
+        tx_hash: &H256,
+    ) -> Result<Option<SolverCompetition>>;
+}
+
+fn auction_id_to_i64(auction_id: u64) -> Result<i64> {
+    auction_id
+        .try_into()
+        .context("auction id does not fit into i64")
+}
+
+fn parse_competition(json: Option<(String,)>) -> Result<Option<SolverCompetition>> {
+    json.map(|(json,)| serde_json::from_str(&json).context("invalid solver competition json"))
+        .transpose()
+}
+
+#[async_trait::async_trait]
+impl SolverCompetitionStoring for Postgres {
+    async fn save_solver_competition(&self, competition: &SolverCompetition) -> Result<()> {
+        const QUERY: &str = "\
+            INSERT INTO solver_competitions (auction_id, tx_hash, json) \
+            VALUES ($1, $2, $3::jsonb);";
+        let json = serde_json::to_string(competition)?;
+        sqlx::query(QUERY)
+            .bind(auction_id_to_i64(competition.auction_id)?)
+            .bind(
+                competition
+                    .transaction_hash
+                    .as_ref()
+                    .map(|hash| hash.as_bytes()),
+            )
+            .bind(json)
+            .execute(&self.pool)
+            .await
+            .context("save_solver_competition failed")
+            .map(|_| ())
+    }
+
+    async fn load_solver_competition(&self, auction_id: u64) -> Result<Option<SolverCompetition>> {
+        const QUERY: &str = "\
+            SELECT json::text FROM solver_competitions \
+            WHERE auction_id = $1 \
+            ORDER BY tx_hash IS NULL, id DESC \
+            LIMIT 1;";
+        let json = sqlx::query_as(QUERY)
+            .bind(auction_id_to_i64(auction_id)?)
+            .fetch_optional(&self.pool)
+            .await
+            .context("load_solver_competition failed")?;
+        parse_competition(json)
+    }
+
+    async fn load_solver_competition_by_tx_hash(
+        &self,
+        tx_hash: &H256,
+    ) -> Result<Option<SolverCompetition>> {
+        const QUERY: &str = "\
+            SELECT json::text FROM solver_competitions \
+            WHERE tx_hash = $1 \
+            ORDER BY id DESC \
+            LIMIT 1;";
+        let json = sqlx::query_as(QUERY)
+            .bind(tx_hash.as_bytes())
+            .fetch_optional(&self.pool)
+            .await
+            .context("load_solver_competition_by_tx_hash failed")?;
+        parse_competition(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::solver_competition::SolverSettlement;
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_save_and_load_solver_competition() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        let mut competition = SolverCompetition {
+            auction_id: 1,
+            gas_price: 2.,
+            liquidity_collected_block: 3,
+            transaction_hash: None,
+            solutions: vec![SolverSettlement {
+                solver: "naive".to_string(),
+                ..Default::default()
+            }],
+        };
+        db.save_solver_competition(&competition).await.unwrap();
+        assert_eq!(
+            db.load_solver_competition(1).await.unwrap(),
+            Some(competition.clone())
+        );
+        assert_eq!(db.load_solver_competition(2).await.unwrap(), None);
+
+        let tx_hash = H256::from_low_u64_be(4);
+        competition.transaction_hash = Some(tx_hash);
+        db.save_solver_competition(&competition).await.unwrap();
+        // A later run of the same auction doesn't replace the one that settled.
+        let mut later = competition.clone();
+        later.transaction_hash = None;
+        later.gas_price = 5.;
+        db.save_solver_competition(&later).await.unwrap();
+        assert_eq!(
+            db.load_solver_competition(1).await.unwrap(),
+            Some(competition.clone())
+        );
+        assert_eq!(
+            db.load_solver_competition_by_tx_hash(&tx_hash)
+                .await
+                .unwrap(),
+            Some(competition.clone())
+        );
+        assert_eq!(
+            db.load_solver_competition_by_tx_hash(&H256::from_low_u64_be(5))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -1,15 +1,31 @@
-use crate::conversions::{big_decimal_to_big_uint, h160_from_vec, h256_from_vec};
-use crate::database::Postgres;
+use crate::conversions::{
+    big_decimal_to_big_uint, big_decimal_to_u256, h160_from_vec, h256_from_vec,
+};
+use crate::database::{orders::DbOrderKind, Postgres};
 use anyhow::{anyhow, Context, Result};
-use ethcontract::H160;
+use chrono::{DateTime, Utc};
+use ethcontract::{H160, U256};
 use futures::stream::TryStreamExt;
-use model::{order::OrderUid, trade::Trade};
+use model::{
+    order::{OrderKind, OrderUid, BUY_ETH_ADDRESS},
+    trade::Trade,
+};
 use sqlx::types::BigDecimal;
-use std::convert::TryInto;
+use std::{convert::TryInto, str::FromStr};
 
+#[cfg_attr(test, mockall::automock)]
 #[async_trait::async_trait]
 pub trait TradeRetrieving: Send + Sync {
+    /// Includes the trades of archived orders.
     async fn trades(&self, filter: &TradeFilter) -> Result<Vec<Trade>>;
+    /// Trades between the two tokens in either direction ordered by block number and log index.
+    ///
+    /// Only includes trades whose settlement block timestamp is known. Orders buying ETH are
+    /// included if one of the tokens is the native token and are returned with `BUY_ETH_ADDRESS`
+    /// as their buy token.
+    async fn market_trades(&self, filter: &MarketTradeFilter) -> Result<Vec<MarketTrade>>;
+    /// The limit and the total executed amounts of all of the owner's orders that were traded.
+    async fn order_executions(&self, owner: &H160) -> Result<Vec<OrderExecution>>;
 }
 
 /// Any default value means that this field is unfiltered.
@@ -17,6 +33,65 @@ pub trait TradeRetrieving: Send + Sync {
 pub struct TradeFilter {
     pub owner: Option<H160>,
     pub order_uid: Option<OrderUid>,
+    /// Cursor for pagination. Trades are ordered by block number and log index and only the
+    /// trades after this one are returned.
+    pub after: Option<TradeCursor>,
+    pub limit: Option<u64>,
+}
+
+/// Identifies a trade by the position of its event. Formatted as `<block number>-<log index>`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TradeCursor {
+    pub block_number: u64,
+    pub log_index: u64,
+}
+
+impl FromStr for TradeCursor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (block_number, log_index) = s
+            .split_once('-')
+            .context("trade cursor must be block number and log index separated by a dash")?;
+        Ok(Self {
+            block_number: block_number.parse().context("invalid block number")?,
+            log_index: log_index.parse().context("invalid log index")?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarketTradeFilter {
+    pub token_a: H160,
+    pub token_b: H160,
+    pub native_token: H160,
+    /// Inclusive lower bound of the block timestamp.
+    pub from: DateTime<Utc>,
+    /// Exclusive upper bound of the block timestamp.
+    pub to: DateTime<Utc>,
+}
+
+/// The amounts a trade exchanged excluding the fee.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarketTrade {
+    pub timestamp: DateTime<Utc>,
+    pub sell_token: H160,
+    pub buy_token: H160,
+    pub sell_amount: U256,
+    pub buy_amount: U256,
+}
+
+/// Executed amounts are summed over all trades of the order and exclude the fee.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OrderExecution {
+    pub sell_token: H160,
+    pub buy_token: H160,
+    pub kind: OrderKind,
+    pub sell_amount: U256,
+    pub buy_amount: U256,
+    pub executed_sell_amount: U256,
+    pub executed_buy_amount: U256,
+    pub executed_fee_amount: U256,
 }
 
 #[async_trait::async_trait]
@@ -34,7 +109,7 @@ impl TradeRetrieving for Postgres {
                 o.buy_token, \
                 o.sell_token, \
                 settlement.tx_hash \
-            FROM trades t \
+            FROM all_trades t \
             LEFT OUTER JOIN LATERAL ( \
                 SELECT tx_hash FROM settlements s \
                 WHERE s.block_number = t.block_number \
@@ -42,24 +117,151 @@ impl TradeRetrieving for Postgres {
                 ORDER BY s.log_index ASC \
                 LIMIT 1 \
             ) AS settlement ON true \
-            JOIN orders o \
+            JOIN all_orders o \
             ON o.uid = t.order_uid \
             WHERE \
                 o.uid IS NOT null \
             AND \
                 ($1 IS NULL OR o.owner = $1) \
             AND \
-                ($2 IS NULL OR o.uid = $2);";
+                ($2 IS NULL OR o.uid = $2) \
+            AND \
+                ($3::bigint IS NULL OR (t.block_number, t.log_index) > ($3, $4)) \
+            ORDER BY t.block_number, t.log_index \
+            LIMIT $5;";
 
         sqlx::query_as(QUERY)
             .bind(filter.owner.as_ref().map(|h160| h160.as_bytes()))
             .bind(filter.order_uid.as_ref().map(|uid| uid.0.as_ref()))
-            .fetch(&self.pool)
+            .bind(filter.after.map(|cursor| cursor.block_number as i64))
+            .bind(filter.after.map(|cursor| cursor.log_index as i64))
+            .bind(filter.limit.map(|limit| limit as i64))
+            .fetch(self.read_pool())
             .err_into()
             .and_then(|row: TradesQueryRow| async move { row.into_trade() })
             .try_collect()
             .await
     }
+
+    async fn market_trades(&self, filter: &MarketTradeFilter) -> Result<Vec<MarketTrade>> {
+        const QUERY: &str = "\
+            SELECT \
+                settlement.block_timestamp, \
+                o.sell_token, \
+                o.buy_token, \
+                t.sell_amount - t.fee_amount AS sell_amount, \
+                t.buy_amount \
+            FROM all_trades t \
+            JOIN settlement_block_timestamps settlement \
+            ON settlement.block_number = t.block_number \
+            JOIN all_orders o \
+            ON o.uid = t.order_uid \
+            WHERE \
+                ( \
+                    (o.sell_token = $1 AND (o.buy_token = $2 OR ($2 = $3 AND o.buy_token = $4))) \
+                OR \
+                    (o.sell_token = $2 AND (o.buy_token = $1 OR ($1 = $3 AND o.buy_token = $4))) \
+                ) \
+            AND \
+                settlement.block_timestamp >= $5 \
+            AND \
+                settlement.block_timestamp < $6 \
+            ORDER BY t.block_number, t.log_index;";
+
+        sqlx::query_as(QUERY)
+            .bind(filter.token_a.as_bytes())
+            .bind(filter.token_b.as_bytes())
+            .bind(filter.native_token.as_bytes())
+            .bind(BUY_ETH_ADDRESS.as_bytes())
+            .bind(filter.from)
+            .bind(filter.to)
+            .fetch(self.read_pool())
+            .err_into()
+            .and_then(|row: MarketTradesQueryRow| async move { row.into_market_trade() })
+            .try_collect()
+            .await
+    }
+
+    async fn order_executions(&self, owner: &H160) -> Result<Vec<OrderExecution>> {
+        const QUERY: &str = "\
+            SELECT \
+                o.sell_token, \
+                o.buy_token, \
+                o.kind, \
+                o.sell_amount, \
+                o.buy_amount, \
+                SUM(t.sell_amount - t.fee_amount) AS executed_sell_amount, \
+                SUM(t.buy_amount) AS executed_buy_amount, \
+                SUM(t.fee_amount) AS executed_fee_amount \
+            FROM all_orders o \
+            JOIN all_trades t \
+            ON t.order_uid = o.uid \
+            WHERE o.owner = $1 \
+            GROUP BY o.uid;";
+
+        sqlx::query_as(QUERY)
+            .bind(owner.as_bytes())
+            .fetch(self.read_pool())
+            .err_into()
+            .and_then(|row: OrderExecutionsQueryRow| async move { row.into_order_execution() })
+            .try_collect()
+            .await
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct OrderExecutionsQueryRow {
+    sell_token: Vec<u8>,
+    buy_token: Vec<u8>,
+    kind: DbOrderKind,
+    sell_amount: BigDecimal,
+    buy_amount: BigDecimal,
+    executed_sell_amount: BigDecimal,
+    executed_buy_amount: BigDecimal,
+    executed_fee_amount: BigDecimal,
+}
+
+impl OrderExecutionsQueryRow {
+    fn into_order_execution(self) -> Result<OrderExecution> {
+        Ok(OrderExecution {
+            sell_token: h160_from_vec(self.sell_token)?,
+            buy_token: h160_from_vec(self.buy_token)?,
+            kind: self.kind.into(),
+            sell_amount: big_decimal_to_u256(&self.sell_amount)
+                .ok_or_else(|| anyhow!("sell_amount is not U256"))?,
+            buy_amount: big_decimal_to_u256(&self.buy_amount)
+                .ok_or_else(|| anyhow!("buy_amount is not U256"))?,
+            executed_sell_amount: big_decimal_to_u256(&self.executed_sell_amount)
+                .ok_or_else(|| anyhow!("executed_sell_amount is not U256"))?,
+            executed_buy_amount: big_decimal_to_u256(&self.executed_buy_amount)
+                .ok_or_else(|| anyhow!("executed_buy_amount is not U256"))?,
+            executed_fee_amount: big_decimal_to_u256(&self.executed_fee_amount)
+                .ok_or_else(|| anyhow!("executed_fee_amount is not U256"))?,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct MarketTradesQueryRow {
+    block_timestamp: DateTime<Utc>,
+    sell_token: Vec<u8>,
+    buy_token: Vec<u8>,
+    sell_amount: BigDecimal,
+    buy_amount: BigDecimal,
+}
+
+impl MarketTradesQueryRow {
+    fn into_market_trade(self) -> Result<MarketTrade> {
+        Ok(MarketTrade {
+            timestamp: self.block_timestamp,
+            sell_token: h160_from_vec(self.sell_token)?,
+            buy_token: h160_from_vec(self.buy_token)?,
+            sell_amount: big_decimal_to_u256(&self.sell_amount)
+                .ok_or_else(|| anyhow!("sell_amount is not U256"))?,
+            buy_amount: big_decimal_to_u256(&self.buy_amount)
+                .ok_or_else(|| anyhow!("buy_amount is not U256"))?,
+        })
+    }
 }
 
 #[derive(sqlx::FromRow)]
@@ -120,6 +322,7 @@ mod tests {
         events::{Event, Settlement as DbSettlement, Trade as DbTrade},
         orders::OrderStoring,
     };
+    use chrono::{Duration, NaiveDateTime};
     use ethcontract::H256;
     use model::{
         order::{Order, OrderCreation, OrderMetadata},
@@ -197,6 +400,20 @@ mod tests {
         assert_eq!(filtered, expected);
     }
 
+    #[test]
+    fn parse_trade_cursor() {
+        assert_eq!(
+            "12-3".parse::<TradeCursor>().unwrap(),
+            TradeCursor {
+                block_number: 12,
+                log_index: 3,
+            }
+        );
+        assert!("12".parse::<TradeCursor>().is_err());
+        assert!("12-".parse::<TradeCursor>().is_err());
+        assert!("-3".parse::<TradeCursor>().is_err());
+    }
+
     // Testing trades without corresponding settlement events
     #[tokio::test]
     #[ignore]
@@ -525,4 +742,224 @@ mod tests {
         .await;
         assert_trades(&db, &TradeFilter::default(), &[trade_a, trade_b]).await;
     }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_trades_cursor_pagination() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+        let (owners, order_ids) = generate_owners_and_order_ids(1, 3).await;
+
+        let mut trades = Vec::new();
+        for (i, (block_number, log_index)) in [(0, 1), (1, 0), (1, 2)].into_iter().enumerate() {
+            trades.push(
+                add_order_and_trade(
+                    &db,
+                    owners[0],
+                    order_ids[i],
+                    EventIndex {
+                        block_number,
+                        log_index,
+                    },
+                    None,
+                )
+                .await,
+            );
+        }
+
+        let filter = |after: Option<TradeCursor>| TradeFilter {
+            owner: Some(owners[0]),
+            after,
+            limit: Some(2),
+            ..Default::default()
+        };
+        assert_trades(&db, &filter(None), &trades[0..2]).await;
+        assert_trades(
+            &db,
+            &filter(Some(TradeCursor {
+                block_number: 1,
+                log_index: 0,
+            })),
+            &trades[2..],
+        )
+        .await;
+        assert_trades(
+            &db,
+            &filter(Some(TradeCursor {
+                block_number: 1,
+                log_index: 2,
+            })),
+            &[],
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_market_trades() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        let weth = H160::from_low_u64_be(1);
+        let token = H160::from_low_u64_be(2);
+        let other = H160::from_low_u64_be(3);
+        let add = |uid: u8, sell_token: H160, buy_token: H160, block_number: u64| {
+            let db = &db;
+            async move {
+                let order_uid = OrderUid([uid; 56]);
+                let order = Order {
+                    metadata: OrderMetadata {
+                        uid: order_uid,
+                        ..Default::default()
+                    },
+                    creation: OrderCreation {
+                        sell_token,
+                        buy_token,
+                        ..Default::default()
+                    },
+                };
+                db.insert_order(&order, Default::default()).await.unwrap();
+                db.append_events_(vec![
+                    (
+                        EventIndex {
+                            block_number,
+                            log_index: 0,
+                        },
+                        Event::Trade(DbTrade {
+                            order_uid,
+                            sell_amount_including_fee: 10.into(),
+                            buy_amount: 20.into(),
+                            fee_amount: 1.into(),
+                        }),
+                    ),
+                    (
+                        EventIndex {
+                            block_number,
+                            log_index: 1,
+                        },
+                        Event::Settlement(Default::default()),
+                    ),
+                ])
+                .await
+                .unwrap();
+            }
+        };
+        add(0, weth, token, 1).await;
+        add(1, token, BUY_ETH_ADDRESS, 2).await;
+        add(2, token, other, 3).await;
+        add(3, token, weth, 4).await;
+
+        let start = DateTime::from_utc(NaiveDateTime::from_timestamp(1_600_000_000, 0), Utc);
+        let filter = MarketTradeFilter {
+            token_a: token,
+            token_b: weth,
+            native_token: weth,
+            from: start,
+            to: start + Duration::seconds(3),
+        };
+        // Trades are only returned once their block timestamp is known.
+        assert!(db.market_trades(&filter).await.unwrap().is_empty());
+        assert_eq!(
+            db.settlement_blocks_without_timestamp(3).await.unwrap(),
+            vec![1, 2, 3]
+        );
+        for block in 1..=4 {
+            db.set_settlement_block_timestamp(block, start + Duration::seconds(block as i64 - 1))
+                .await
+                .unwrap();
+        }
+        assert!(db
+            .settlement_blocks_without_timestamp(3)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let trade = |timestamp: i64, sell_token: H160, buy_token: H160| MarketTrade {
+            timestamp: start + Duration::seconds(timestamp),
+            sell_token,
+            buy_token,
+            sell_amount: 9.into(),
+            buy_amount: 20.into(),
+        };
+        assert_eq!(
+            db.market_trades(&filter).await.unwrap(),
+            vec![trade(0, weth, token), trade(1, token, BUY_ETH_ADDRESS)]
+        );
+        assert_eq!(
+            db.market_trades(&MarketTradeFilter {
+                token_b: other,
+                to: start + Duration::seconds(4),
+                ..filter
+            })
+            .await
+            .unwrap(),
+            vec![trade(2, token, other)]
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_order_executions() {
+        let db = Postgres::new("postgresql://").unwrap();
+        db.clear().await.unwrap();
+
+        let owner = H160::from_low_u64_be(1);
+        let insert_order = |uid: u8, owner: H160| {
+            let db = &db;
+            async move {
+                let order = Order {
+                    metadata: OrderMetadata {
+                        uid: OrderUid([uid; 56]),
+                        owner,
+                        ..Default::default()
+                    },
+                    creation: OrderCreation {
+                        sell_token: H160::from_low_u64_be(2),
+                        buy_token: H160::from_low_u64_be(3),
+                        sell_amount: 100.into(),
+                        buy_amount: 50.into(),
+                        kind: OrderKind::Sell,
+                        partially_fillable: true,
+                        ..Default::default()
+                    },
+                };
+                db.insert_order(&order, Default::default()).await.unwrap();
+            }
+        };
+        insert_order(0, owner).await;
+        insert_order(1, owner).await;
+        insert_order(2, H160::from_low_u64_be(4)).await;
+
+        let trade = |uid: u8, log_index: u64| {
+            (
+                EventIndex {
+                    block_number: 0,
+                    log_index,
+                },
+                Event::Trade(DbTrade {
+                    order_uid: OrderUid([uid; 56]),
+                    sell_amount_including_fee: 31.into(),
+                    buy_amount: 20.into(),
+                    fee_amount: 1.into(),
+                }),
+            )
+        };
+        db.append_events_(vec![trade(0, 0), trade(0, 1), trade(2, 2)])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            db.order_executions(&owner).await.unwrap(),
+            vec![OrderExecution {
+                sell_token: H160::from_low_u64_be(2),
+                buy_token: H160::from_low_u64_be(3),
+                kind: OrderKind::Sell,
+                sell_amount: 100.into(),
+                buy_amount: 50.into(),
+                executed_sell_amount: 60.into(),
+                executed_buy_amount: 40.into(),
+                executed_fee_amount: 2.into(),
+            }]
+        );
+    }
 }
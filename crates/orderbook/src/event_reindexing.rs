@@ -0,0 +1,240 @@
+use crate::{
+    database::{
+        events::{contract_to_db_events, Event},
+        Postgres,
+    },
+    event_updater::GPv2SettlementContract,
+};
+use anyhow::{ensure, Result};
+use contracts::GPv2Settlement;
+use ethcontract::dyns::DynWeb3;
+use shared::event_handling::{BlockNumber, EventHandler, EventIndex, EventStoring};
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+/// Re-fetches the settlement contract events of historical blocks to repair the event tables.
+///
+/// The regular `EventUpdater` only replaces the most recent blocks to handle reorgs. This compares
+/// the events of an arbitrary block range chunk by chunk with the rows that are stored and
+/// optionally replaces the stored events of chunks that differ.
+pub struct EventReindexer {
+    handler: EventHandler<DynWeb3, GPv2SettlementContract, Postgres>,
+    database: Postgres,
+}
+
+#[derive(Debug)]
+pub enum EventDifference {
+    /// The event was stored but is not emitted on chain.
+    Removed(EventIndex, Event),
+    /// The event is emitted on chain but was not stored.
+    Added(EventIndex, Event),
+    /// The stored event differs from the one emitted on chain.
+    Changed {
+        index: EventIndex,
+        stored: Event,
+        indexed: Event,
+    },
+}
+
+impl EventDifference {
+    fn index(&self) -> &EventIndex {
+        match self {
+            EventDifference::Removed(index, _) => index,
+            EventDifference::Added(index, _) => index,
+            EventDifference::Changed { index, .. } => index,
+        }
+    }
+}
+
+impl EventReindexer {
+    pub fn new(contract: GPv2Settlement, database: Postgres) -> Self {
+        Self {
+            handler: EventHandler::new(
+                contract.raw_instance().web3(),
+                GPv2SettlementContract::new(contract),
+                database.clone(),
+                None,
+            ),
+            database,
+        }
+    }
+
+    /// Compares the events of the block range in chunks of `chunk_size` blocks with the events
+    /// on chain and returns how they differ. With `apply` the stored events of chunks with
+    /// differences get replaced, otherwise nothing is written.
+    ///
+    /// Every chunk is replaced in its own transaction so a failure leaves the earlier chunks
+    /// reindexed.
+    pub async fn reindex(
+        &mut self,
+        blocks: RangeInclusive<u64>,
+        chunk_size: u64,
+        apply: bool,
+    ) -> Result<Vec<EventDifference>> {
+        ensure!(chunk_size > 0, "chunk size must not be zero");
+        let mut differences = Vec::new();
+        for chunk in chunks(blocks, chunk_size) {
+            let events = self.handler.events_in_block_range(chunk.clone()).await?;
+            let indexed = contract_to_db_events(events.clone())?;
+            let stored = self.database.events_in_block_range(chunk.clone()).await?;
+            let chunk_differences = diff_events(stored, indexed);
+            tracing::info!(
+                "blocks {:?} have {} differences",
+                chunk,
+                chunk_differences.len()
+            );
+            if apply && !chunk_differences.is_empty() {
+                let range =
+                    BlockNumber::Specific(*chunk.start())..=BlockNumber::Specific(*chunk.end());
+                self.database.replace_events(events, range).await?;
+                tracing::info!("replaced events of blocks {:?}", chunk);
+            }
+            differences.extend(chunk_differences);
+        }
+        Ok(differences)
+    }
+}
+
+fn chunks(
+    blocks: RangeInclusive<u64>,
+    chunk_size: u64,
+) -> impl Iterator<Item = RangeInclusive<u64>> {
+    let end = *blocks.end();
+    let mut next = Some(*blocks.start()).filter(|start| *start <= end);
+    std::iter::from_fn(move || {
+        let start = next?;
+        let chunk_end = start.saturating_add(chunk_size - 1).min(end);
+        next = chunk_end.checked_add(1).filter(|next| *next <= end);
+        Some(start..=chunk_end)
+    })
+}
+
+fn diff_events(
+    stored: Vec<(EventIndex, Event)>,
+    indexed: Vec<(EventIndex, Event)>,
+) -> Vec<EventDifference> {
+    let key = |index: &EventIndex| (index.block_number, index.log_index);
+    let mut stored = stored
+        .into_iter()
+        .map(|(index, event)| (key(&index), (index, event)))
+        .collect::<BTreeMap<_, _>>();
+    let mut differences = Vec::new();
+    for (index, event) in indexed {
+        match stored.remove(&key(&index)) {
+            None => differences.push(EventDifference::Added(index, event)),
+            Some((_, stored)) if stored != event => differences.push(EventDifference::Changed {
+                index,
+                stored,
+                indexed: event,
+            }),
+            Some(_) => (),
+        }
+    }
+    differences.extend(
+        stored
+            .into_values()
+            .map(|(index, event)| EventDifference::Removed(index, event)),
+    );
+    differences.sort_by_key(|difference| key(difference.index()));
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::events::{Invalidation, PreSignature, Settlement, Trade};
+    use model::order::OrderUid;
+    use primitive_types::H160;
+
+    #[test]
+    fn splits_blocks_into_chunks() {
+        assert_eq!(
+            chunks(0..=10, 4).collect::<Vec<_>>(),
+            vec![0..=3, 4..=7, 8..=10]
+        );
+        assert_eq!(chunks(5..=5, 4).collect::<Vec<_>>(), vec![5..=5]);
+        assert_eq!(chunks(6..=5, 4).count(), 0);
+        assert_eq!(
+            chunks(u64::MAX - 1..=u64::MAX, 1).collect::<Vec<_>>(),
+            vec![u64::MAX - 1..=u64::MAX - 1, u64::MAX..=u64::MAX]
+        );
+    }
+
+    #[test]
+    fn diffs_events() {
+        let invalidation = |uid: u8| {
+            Event::Invalidation(Invalidation {
+                order_uid: OrderUid([uid; 56]),
+            })
+        };
+        let stored = vec![
+            (EventIndex::new(1, 0), Event::Trade(Trade::default())),
+            (EventIndex::new(1, 1), invalidation(1)),
+            (EventIndex::new(2, 0), invalidation(2)),
+        ];
+        let indexed = vec![
+            (EventIndex::new(1, 0), Event::Trade(Trade::default())),
+            (EventIndex::new(1, 1), invalidation(3)),
+            (EventIndex::new(3, 0), invalidation(4)),
+        ];
+        let differences = diff_events(stored, indexed);
+        assert_eq!(differences.len(), 3);
+        assert!(matches!(
+            &differences[0],
+            EventDifference::Changed { index, stored, indexed }
+                if index.log_index == 1 && *stored == invalidation(1) && *indexed == invalidation(3)
+        ));
+        assert!(matches!(
+            &differences[1],
+            EventDifference::Removed(index, event)
+                if index.block_number == 2 && *event == invalidation(2)
+        ));
+        assert!(matches!(
+            &differences[2],
+            EventDifference::Added(index, event)
+                if index.block_number == 3 && *event == invalidation(4)
+        ));
+    }
+
+    #[test]
+    fn diffs_every_kind_of_event() {
+        let settlement = |solver: u64| {
+            Event::Settlement(Settlement {
+                solver: H160::from_low_u64_be(solver),
+                ..Default::default()
+            })
+        };
+        let presignature = |signed: bool| {
+            Event::PreSignature(PreSignature {
+                signed,
+                ..Default::default()
+            })
+        };
+        let stored = vec![
+            (EventIndex::new(1, 0), settlement(1)),
+            (EventIndex::new(1, 1), presignature(true)),
+            (EventIndex::new(1, 2), Event::Trade(Trade::default())),
+        ];
+        let indexed = vec![
+            (EventIndex::new(1, 0), settlement(2)),
+            (EventIndex::new(1, 1), presignature(false)),
+            (EventIndex::new(1, 2), Event::Trade(Trade::default())),
+            (EventIndex::new(1, 3), settlement(1)),
+        ];
+        let differences = diff_events(stored, indexed);
+        assert_eq!(differences.len(), 3);
+        assert!(matches!(
+            &differences[0],
+            EventDifference::Changed { stored, indexed, .. }
+                if *stored == settlement(1) && *indexed == settlement(2)
+        ));
+        assert!(matches!(
+            &differences[1],
+            EventDifference::Changed { stored, indexed, .. }
+                if *stored == presignature(true) && *indexed == presignature(false)
+        ));
+        assert!(matches!(
+            &differences[2],
+            EventDifference::Added(index, event) if index.log_index == 3 && *event == settlement(1)
+        ));
+    }
+}
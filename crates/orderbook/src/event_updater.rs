@@ -19,6 +19,12 @@ impl_event_retrieving! {
     pub GPv2SettlementContract for gpv2_settlement
 }
 
+impl GPv2SettlementContract {
+    pub fn new(contract: GPv2Settlement) -> Self {
+        Self(contract)
+    }
+}
+
 impl<Database> EventUpdater<Database>
 where
     Database: EventStoring<ContractEvent>,
@@ -26,7 +32,7 @@ where
     pub fn new(contract: GPv2Settlement, db: Database, start_sync_at_block: Option<u64>) -> Self {
         Self(Mutex::new(EventHandler::new(
             contract.raw_instance().web3(),
-            GPv2SettlementContract(contract),
+            GPv2SettlementContract::new(contract),
             db,
             start_sync_at_block,
         )))
@@ -0,0 +1,46 @@
+use crate::{
+    database::Postgres,
+    order_updates::{OrderUpdateKind, OrderUpdates},
+};
+use anyhow::Result;
+use chrono::Utc;
+use shared::maintenance::Maintaining;
+use std::sync::Arc;
+
+/// Marks orders as expired in the database once their `valid_to` passed.
+///
+/// The order status is derived from `valid_to` when reading orders anyway. Marking them explicitly
+/// records when the order expired in its order events, notifies subscribers of order updates and
+/// keeps expired orders out of the solvable orders index.
+pub struct ExpirySweeper {
+    database: Postgres,
+    order_updates: Arc<OrderUpdates>,
+}
+
+impl ExpirySweeper {
+    pub fn new(database: Postgres, order_updates: Arc<OrderUpdates>) -> Self {
+        Self {
+            database,
+            order_updates,
+        }
+    }
+
+    async fn sweep(&self) -> Result<()> {
+        let expired = self.database.mark_expired_orders(Utc::now()).await?;
+        if !expired.is_empty() {
+            tracing::debug!("marked {} orders as expired", expired.len());
+        }
+        for (uid, owner) in expired {
+            self.order_updates
+                .notify(uid, owner, OrderUpdateKind::Expired);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Maintaining for ExpirySweeper {
+    async fn run_maintenance(&self) -> Result<()> {
+        self.sweep().await
+    }
+}
@@ -18,9 +18,14 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use crate::cow_subsidy::CowSubsidy;
+use crate::{
+    cow_subsidy::CowSubsidy,
+    fee_rules::{self, FeeContext, FeeRules},
+    gas_model::GasModel,
+};
 
-pub type Measurement = (U256, DateTime<Utc>);
+/// The subsidized fee, until when it is valid and the names of the fee rules that were applied.
+pub type Measurement = (U256, DateTime<Utc>, Vec<String>);
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -40,8 +45,12 @@ impl Default for PriceQuality {
 /// Given an estimated fee for a trade, the mimimum fee required for an order is
 /// computed using the following formula:
 /// ```text
-/// (estimated_fee_in_eth - fee_discount) * fee_factor * (partner_additional_fee_factor || 1.0)
+/// max(estimated_fee_in_eth - fee_discount, min_discounted_fee)
+///     * fee_factor * (partner_additional_fee_factor || 1.0) * cow_factor
 /// ```
+/// and afterwards adjusted by the actions of the matching fee rules in order. The rules see the
+/// result of the formula so for example a rule's discount is not multiplied by the factors and a
+/// rule's cap also limits fees that the factors increased.
 pub struct FeeSubsidyConfiguration {
     /// A flat discount nominated in the native token to discount from fees.
     ///
@@ -65,6 +74,9 @@ pub struct FeeSubsidyConfiguration {
     ///
     /// Fee factors are applied **after** flat fee discounts.
     pub partner_additional_fee_factors: HashMap<AppId, f64>,
+
+    /// Rules that are applied **after** all of the above.
+    pub rules: FeeRules,
 }
 
 impl Default for FeeSubsidyConfiguration {
@@ -74,6 +86,7 @@ impl Default for FeeSubsidyConfiguration {
             fee_factor: 1.,
             min_discounted_fee: 0.,
             partner_additional_fee_factors: HashMap::new(),
+            rules: Default::default(),
         }
     }
 }
@@ -88,9 +101,10 @@ pub struct MinFeeCalculator {
     native_price_estimator: Arc<dyn NativePriceEstimating>,
     cow_subsidy: Arc<dyn CowSubsidy>,
     liquidity_order_owners: HashSet<H160>,
+    gas_model: Arc<dyn GasModel>,
 }
 
-#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, Eq, Hash, PartialEq)]
 pub struct FeeData {
     pub sell_token: H160,
     pub buy_token: H160,
@@ -135,9 +149,48 @@ impl FeeParameters {
     fn apply_fee_factor(
         &self,
         config: &FeeSubsidyConfiguration,
-        app_data: AppId,
+        context: &FeeContext,
         cow_factor: f64,
     ) -> U256 {
+        self.apply_fee_factor_and_rules(config, context, cow_factor)
+            .0
+    }
+
+    /// The lowest fee `apply_fee_factor` computes at any time in `[since, context.time]` for either
+    /// the owner of the context or the zero address that fee requests without an owner use.
+    ///
+    /// Validation uses this so that fees issued before a fee rule started or stopped applying, or
+    /// issued without knowing the owner, are still accepted while they are valid.
+    fn min_fee_since(
+        &self,
+        config: &FeeSubsidyConfiguration,
+        context: &FeeContext,
+        cow_factor: f64,
+        since: DateTime<Utc>,
+    ) -> U256 {
+        config
+            .rules
+            .times_between(since, context.time)
+            .into_iter()
+            .flat_map(|time| {
+                [context.owner, H160::zero()].map(|owner| FeeContext {
+                    owner,
+                    time,
+                    ..*context
+                })
+            })
+            .map(|context| self.apply_fee_factor(config, &context, cow_factor))
+            .min()
+            .expect("times always include the start of the interval")
+    }
+
+    /// Like `apply_fee_factor` but also returns the names of the fee rules that fired.
+    fn apply_fee_factor_and_rules<'a>(
+        &self,
+        config: &'a FeeSubsidyConfiguration,
+        context: &FeeContext,
+        cow_factor: f64,
+    ) -> (U256, Vec<&'a str>) {
         let fee_in_eth = self.gas_amount * self.gas_price;
         let mut discounted_fee_in_eth = fee_in_eth - config.fee_discount;
         if discounted_fee_in_eth < config.min_discounted_fee {
@@ -151,12 +204,20 @@ impl FeeParameters {
 
         let factor = config
             .partner_additional_fee_factors
-            .get(&app_data)
+            .get(&context.app_data)
             .copied()
             .unwrap_or(1.0)
             * config.fee_factor
             * cow_factor;
-        U256::from_f64_lossy((discounted_fee_in_eth * factor / self.sell_token_price).ceil())
+        let (fee_in_eth, rules) = config.rules.apply(
+            discounted_fee_in_eth * factor,
+            context,
+            self.sell_token_price,
+        );
+        (
+            U256::from_f64_lossy((fee_in_eth / self.sell_token_price).ceil()),
+            rules,
+        )
     }
 }
 
@@ -259,6 +320,7 @@ impl MinFeeCalculator {
         native_price_estimator: Arc<dyn NativePriceEstimating>,
         cow_subsidy: Arc<dyn CowSubsidy>,
         liquidity_order_owners: HashSet<H160>,
+        gas_model: Arc<dyn GasModel>,
     ) -> Self {
         Self {
             price_estimator,
@@ -270,10 +332,14 @@ impl MinFeeCalculator {
             native_price_estimator,
             cow_subsidy,
             liquidity_order_owners,
+            gas_model,
         }
     }
 
     /// Computes unsubsidized min fee.
+    ///
+    /// The gas amount comes from the gas model and falls back to the gas reported by the price
+    /// estimator for token pairs the model doesn't know.
     async fn compute_unsubsidized_min_fee(
         &self,
         fee_data: FeeData,
@@ -292,7 +358,11 @@ impl MinFeeCalculator {
             native_single_estimate(self.native_price_estimator.as_ref(), &fee_data.sell_token),
         )?;
         let gas_price = gas_estimate.effective_gas_price();
-        let gas_amount = buy_token_estimate.gas as f64;
+        let estimated_gas = buy_token_estimate.gas as f64;
+        let learned_gas =
+            self.gas_model
+                .trade_gas(fee_data.sell_token, fee_data.buy_token, estimated_gas);
+        let gas_amount = learned_gas.unwrap_or(estimated_gas);
         let fee_parameters = FeeParameters {
             gas_amount,
             gas_price,
@@ -302,13 +372,26 @@ impl MinFeeCalculator {
         let fee_in_eth = gas_price * gas_amount;
         let fee_in_sell_token = fee_parameters.amount_in_sell_token();
         tracing::debug!(
-            ?fee_data, %gas_price, %gas_amount, %sell_token_price,
+            ?fee_data, %gas_price, %gas_amount, ?learned_gas, %sell_token_price,
             %fee_in_eth, %fee_in_sell_token,
             "unsubsidized fee amount"
         );
 
         Ok(fee_parameters)
     }
+
+    /// The native price of the buy token if fee rules need it to match the size of a buy order.
+    async fn buy_token_price(
+        &self,
+        fee_data: &FeeData,
+    ) -> Result<Option<f64>, PriceEstimationError> {
+        if fee_data.kind == OrderKind::Sell || !self.fee_subsidy.rules.has_size_conditions() {
+            return Ok(None);
+        }
+        native_single_estimate(self.native_price_estimator.as_ref(), &fee_data.buy_token)
+            .await
+            .map(Some)
+    }
 }
 
 #[async_trait::async_trait]
@@ -320,10 +403,10 @@ impl MinFeeCalculating for MinFeeCalculator {
         user: H160,
     ) -> Result<Measurement, PriceEstimationError> {
         if fee_data.buy_token == fee_data.sell_token {
-            return Ok((U256::zero(), MAX_DATETIME));
+            return Ok((U256::zero(), MAX_DATETIME, Vec::new()));
         }
         if self.liquidity_order_owners.contains(&user) {
-            return Ok((U256::zero(), MAX_DATETIME));
+            return Ok((U256::zero(), MAX_DATETIME, Vec::new()));
         }
 
         ensure_token_supported(fee_data.sell_token, self.bad_token_detector.as_ref()).await?;
@@ -365,17 +448,33 @@ impl MinFeeCalculating for MinFeeCalculator {
             }
         };
 
-        let (cow_factor, unsubsidized_min_fee) =
-            futures::future::try_join(cow_factor, unsubsidized_min_fee).await?;
+        let (cow_factor, unsubsidized_min_fee, buy_token_price) = futures::try_join!(
+            cow_factor,
+            unsubsidized_min_fee,
+            self.buy_token_price(&fee_data)
+        )?;
 
-        let subsidized_min_fee =
-            unsubsidized_min_fee.apply_fee_factor(&self.fee_subsidy, app_data, cow_factor);
+        let context = FeeContext {
+            fee_data,
+            app_data,
+            owner: user,
+            time: now,
+            buy_token_price,
+        };
+        let (subsidized_min_fee, rules) = unsubsidized_min_fee.apply_fee_factor_and_rules(
+            &self.fee_subsidy,
+            &context,
+            cow_factor,
+        );
+        let rules = rules.into_iter().map(String::from).collect::<Vec<_>>();
+        fee_rules::record_applied(&rules);
         tracing::debug!(
+            ?rules,
             "computed subsidized fee of {:?}",
             (subsidized_min_fee, fee_data.sell_token),
         );
 
-        Ok((subsidized_min_fee, official_valid_until))
+        Ok((subsidized_min_fee, official_valid_until, rules))
     }
 
     async fn get_unsubsidized_min_fee(
@@ -389,11 +488,29 @@ impl MinFeeCalculating for MinFeeCalculator {
             return Ok(FeeParameters::default());
         }
 
-        let cow_factor = self.cow_subsidy.cow_subsidy_factor(user);
+        let now = (self.now)();
+        let cow_factor = self
+            .cow_subsidy
+            .cow_subsidy_factor(user)
+            .err_into::<GetUnsubsidizedMinFeeError>();
         let past_fee = self
             .measurements
-            .find_measurement_including_larger_amount(fee_data, (self.now)());
-        let (cow_factor, past_fee) = futures::future::try_join(cow_factor, past_fee).await?;
+            .find_measurement_including_larger_amount(fee_data, now)
+            .err_into::<GetUnsubsidizedMinFeeError>();
+        let buy_token_price = self
+            .buy_token_price(&fee_data)
+            .map_err(GetUnsubsidizedMinFeeError::PriceEstimationError);
+        let (cow_factor, past_fee, buy_token_price) =
+            futures::try_join!(cow_factor, past_fee, buy_token_price)?;
+        let context = FeeContext {
+            fee_data,
+            app_data,
+            owner: user,
+            time: now,
+            buy_token_price,
+        };
+        // Fees are valid for a while after they were issued with the rules at that time.
+        let issued_since = now - Duration::seconds(STANDARD_VALIDITY_FOR_FEE_IN_SEC);
         // When validating we allow fees taken for larger amounts because as the amount increases
         // the fee increases too because it is worth to trade off more gas use for a slightly better
         // price. Thus it is acceptable if the new order has an amount <= an existing fee
@@ -406,7 +523,8 @@ impl MinFeeCalculating for MinFeeCalculator {
         // have been picked.
         if let Some(past_fee) = past_fee {
             tracing::debug!("found past fee {:?}", past_fee);
-            if subsidized_fee >= past_fee.apply_fee_factor(&self.fee_subsidy, app_data, cow_factor)
+            if subsidized_fee
+                >= past_fee.min_fee_since(&self.fee_subsidy, &context, cow_factor, issued_since)
             {
                 tracing::debug!("given fee matches past fee");
                 return Ok(past_fee);
@@ -420,7 +538,9 @@ impl MinFeeCalculating for MinFeeCalculator {
             .await
             .map_err(GetUnsubsidizedMinFeeError::PriceEstimationError)?;
         tracing::debug!("estimated new fee {:?}", current_fee);
-        if subsidized_fee >= current_fee.apply_fee_factor(&self.fee_subsidy, app_data, cow_factor) {
+        if subsidized_fee
+            >= current_fee.min_fee_since(&self.fee_subsidy, &context, cow_factor, issued_since)
+        {
             tracing::debug!("given fee matches new fee");
             Ok(current_fee)
         } else {
@@ -496,7 +616,7 @@ impl MinFeeStoring for InMemoryFeeStore {
 #[cfg(test)]
 #[allow(clippy::float_cmp)]
 mod tests {
-    use chrono::Duration;
+    use chrono::{Duration, TimeZone};
     use futures::FutureExt;
     use gas_estimation::{gas_price::EstimatedGasPrice, GasPrice1559};
     use maplit::{hashmap, hashset};
@@ -508,10 +628,24 @@ mod tests {
     };
     use std::sync::Arc;
 
-    use crate::cow_subsidy::FixedCowSubsidy;
+    use crate::{
+        cow_subsidy::FixedCowSubsidy,
+        fee_rules::{Action, Conditions, FeeRule},
+        gas_model::{MockGasModel, NoGasModel},
+    };
 
     use super::*;
 
+    fn fee_context(app_data: AppId) -> FeeContext {
+        FeeContext {
+            fee_data: Default::default(),
+            app_data,
+            owner: Default::default(),
+            time: Utc::now(),
+            buy_token_price: None,
+        }
+    }
+
     fn create_default_native_token_estimator(
         price_estimator: Arc<dyn PriceEstimating>,
     ) -> Arc<dyn NativePriceEstimating> {
@@ -538,6 +672,7 @@ mod tests {
                 native_price_estimator: create_default_native_token_estimator(price_estimator),
                 cow_subsidy: Arc::new(FixedCowSubsidy::default()),
                 liquidity_order_owners: Default::default(),
+                gas_model: Arc::new(NoGasModel),
             }
         }
     }
@@ -574,7 +709,7 @@ mod tests {
             sell_token: token,
             ..Default::default()
         };
-        let (fee, expiry) = fee_estimator
+        let (fee, expiry, _) = fee_estimator
             .compute_subsidized_min_fee(fee_data, Default::default(), Default::default())
             .await
             .unwrap();
@@ -633,7 +768,7 @@ mod tests {
             sell_token: token,
             ..Default::default()
         };
-        let (fee, _) = fee_estimator
+        let (fee, _, _) = fee_estimator
             .compute_subsidized_min_fee(fee_data, Default::default(), Default::default())
             .await
             .unwrap();
@@ -686,6 +821,7 @@ mod tests {
             native_price_estimator,
             cow_subsidy: Arc::new(FixedCowSubsidy::default()),
             liquidity_order_owners: Default::default(),
+            gas_model: Arc::new(NoGasModel),
         };
 
         // Selling unsupported token
@@ -764,8 +900,9 @@ mod tests {
             native_price_estimator,
             cow_subsidy: Arc::new(FixedCowSubsidy(0.5)),
             liquidity_order_owners: Default::default(),
+            gas_model: Arc::new(NoGasModel),
         };
-        let (fee, _) = fee_estimator
+        let (fee, _, _) = fee_estimator
             .compute_subsidized_min_fee(fee_data, app_data, user)
             .await
             .unwrap();
@@ -789,7 +926,7 @@ mod tests {
 
         // repeat without user so no extra cow subsidy
         fee_estimator.cow_subsidy = Arc::new(FixedCowSubsidy(1.0));
-        let (fee_2, _) = fee_estimator
+        let (fee_2, _, _) = fee_estimator
             .compute_subsidized_min_fee(fee_data, app_data, user)
             .await
             .unwrap();
@@ -867,9 +1004,10 @@ mod tests {
             native_price_estimator,
             cow_subsidy: Arc::new(FixedCowSubsidy::default()),
             liquidity_order_owners: Default::default(),
+            gas_model: Arc::new(NoGasModel),
         };
 
-        let (fee, _) = fee_estimator
+        let (fee, _, _) = fee_estimator
             .compute_subsidized_min_fee(fee_data, app_data, Default::default())
             .await
             .unwrap();
@@ -880,7 +1018,7 @@ mod tests {
             )
         );
 
-        let (fee, _) = fee_estimator
+        let (fee, _, _) = fee_estimator
             .compute_subsidized_min_fee(fee_data, Default::default(), Default::default())
             .await
             .unwrap();
@@ -906,7 +1044,11 @@ mod tests {
         };
 
         assert_eq!(
-            unsubsidized.apply_fee_factor(&fee_configuration, Default::default(), 1.0),
+            unsubsidized.apply_fee_factor(
+                &fee_configuration,
+                &fee_context(Default::default()),
+                1.0
+            ),
             // Note that the fee factor is applied to the minimum discounted fee!
             500_000.into(),
         );
@@ -928,20 +1070,190 @@ mod tests {
             partner_additional_fee_factors: maplit::hashmap! {
                 app_id => 0.1,
             },
+            rules: Default::default(),
         };
 
         // (100G - 50G) * 0.5
         assert_eq!(
-            unsubsidized.apply_fee_factor(&fee_configuration, Default::default(), 1.0),
+            unsubsidized.apply_fee_factor(
+                &fee_configuration,
+                &fee_context(Default::default()),
+                1.0
+            ),
             25_000_000_000_000u64.into()
         );
         // Additionally multiply with 0.1 if partner app id is used
         assert_eq!(
-            unsubsidized.apply_fee_factor(&fee_configuration, app_id, 1.0),
+            unsubsidized.apply_fee_factor(&fee_configuration, &fee_context(app_id), 1.0),
             2_500_000_000_000u64.into()
         );
     }
 
+    #[test]
+    fn test_apply_fee_factor_with_rules() {
+        let unsubsidized = FeeParameters {
+            gas_amount: 100_000.,
+            gas_price: 1_000_000_000.,
+            sell_token_price: 0.5,
+        };
+
+        let app_id = AppId([1u8; 32]);
+        let fee_configuration = FeeSubsidyConfiguration {
+            fee_factor: 0.5,
+            rules: FeeRules::new(vec![FeeRule {
+                name: "partner".to_string(),
+                conditions: Conditions {
+                    app_data: hashset! { app_id },
+                    ..Default::default()
+                },
+                action: Action::Cap(10_000_000_000_000.),
+            }])
+            .unwrap(),
+            ..Default::default()
+        };
+
+        // 100G * 0.5 / 0.5
+        assert_eq!(
+            unsubsidized.apply_fee_factor(
+                &fee_configuration,
+                &fee_context(Default::default()),
+                1.0
+            ),
+            100_000_000_000_000u64.into()
+        );
+        // Capped at 10G in native token before converting to sell token
+        assert_eq!(
+            unsubsidized.apply_fee_factor_and_rules(&fee_configuration, &fee_context(app_id), 1.0),
+            (20_000_000_000_000u64.into(), vec!["partner"])
+        );
+    }
+
+    #[test]
+    fn fee_rules_apply_after_discount_and_factors() {
+        let unsubsidized = FeeParameters {
+            gas_amount: 100.,
+            gas_price: 1.,
+            sell_token_price: 1.,
+        };
+        let app_id = AppId([1u8; 32]);
+        let rule = |name: &str, action| FeeRule {
+            name: name.to_string(),
+            conditions: Default::default(),
+            action,
+        };
+        let fee_configuration = |rules| FeeSubsidyConfiguration {
+            fee_discount: 90.,
+            min_discounted_fee: 20.,
+            fee_factor: 2.,
+            partner_additional_fee_factors: hashmap! { app_id => 1.5 },
+            rules: FeeRules::new(rules).unwrap(),
+        };
+
+        // max(100 - 90, 20) * 2 * 1.5 * 0.5 = 30
+        assert_eq!(
+            unsubsidized.apply_fee_factor(&fee_configuration(vec![]), &fee_context(app_id), 0.5),
+            30.into()
+        );
+        // A rule discount is subtracted from the multiplied fee and not limited by the minimum
+        // discounted fee.
+        assert_eq!(
+            unsubsidized.apply_fee_factor(
+                &fee_configuration(vec![rule("discount", Action::Discount(25.))]),
+                &fee_context(app_id),
+                0.5
+            ),
+            5.into()
+        );
+        // A rule cap limits the fee that the factors increased.
+        assert_eq!(
+            unsubsidized.apply_fee_factor(
+                &fee_configuration(vec![
+                    rule("factor", Action::Factor(2.)),
+                    rule("cap", Action::Cap(50.))
+                ]),
+                &fee_context(app_id),
+                1.
+            ),
+            50.into()
+        );
+    }
+
+    #[test]
+    fn min_fee_since_accepts_fees_issued_under_previous_rules() {
+        let unsubsidized = FeeParameters {
+            gas_amount: 100.,
+            gas_price: 1.,
+            sell_token_price: 1.,
+        };
+        let now = Utc.timestamp(1_000, 0);
+        let owner = H160::from_low_u64_be(1);
+        let fee_configuration = FeeSubsidyConfiguration {
+            rules: FeeRules::new(vec![
+                FeeRule {
+                    name: "surcharge".to_string(),
+                    conditions: Conditions {
+                        valid_from: Some(now - Duration::seconds(30)),
+                        ..Default::default()
+                    },
+                    action: Action::Factor(2.),
+                },
+                FeeRule {
+                    name: "owner surcharge".to_string(),
+                    conditions: Conditions {
+                        owners: hashset! { owner },
+                        ..Default::default()
+                    },
+                    action: Action::Factor(3.),
+                },
+            ])
+            .unwrap(),
+            ..Default::default()
+        };
+        let context = FeeContext {
+            time: now,
+            ..fee_context(Default::default())
+        };
+
+        assert_eq!(
+            unsubsidized.apply_fee_factor(&fee_configuration, &context, 1.),
+            200.into()
+        );
+        // Fees issued before the surcharge started are still valid.
+        assert_eq!(
+            unsubsidized.min_fee_since(
+                &fee_configuration,
+                &context,
+                1.,
+                now - Duration::seconds(60)
+            ),
+            100.into()
+        );
+        assert_eq!(
+            unsubsidized.min_fee_since(
+                &fee_configuration,
+                &context,
+                1.,
+                now - Duration::seconds(10)
+            ),
+            200.into()
+        );
+        // Fees issued without an owner don't include the owner's surcharge.
+        let owner_context = FeeContext { owner, ..context };
+        assert_eq!(
+            unsubsidized.apply_fee_factor(&fee_configuration, &owner_context, 1.),
+            600.into()
+        );
+        assert_eq!(
+            unsubsidized.min_fee_since(
+                &fee_configuration,
+                &owner_context,
+                1.,
+                now - Duration::seconds(10)
+            ),
+            200.into()
+        );
+    }
+
     #[test]
     fn fee_rounds_up() {
         let fee_data = FeeData {
@@ -973,8 +1285,9 @@ mod tests {
             native_price_estimator,
             cow_subsidy: Arc::new(FixedCowSubsidy::default()),
             liquidity_order_owners: Default::default(),
+            gas_model: Arc::new(NoGasModel),
         };
-        let (fee, _) = fee_estimator
+        let (fee, _, _) = fee_estimator
             .compute_subsidized_min_fee(fee_data, Default::default(), Default::default())
             .now_or_never()
             .unwrap()
@@ -1021,7 +1334,7 @@ mod tests {
                 .now_or_never()
                 .unwrap()
                 .unwrap(),
-            (U256::from(0), MAX_DATETIME),
+            (U256::from(0), MAX_DATETIME, Vec::new()),
         );
         assert_eq!(
             fee_estimator
@@ -1041,4 +1354,54 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn uses_learned_gas_for_known_token_pairs() {
+        let known_pair = FeeData {
+            sell_token: H160([1; 20]),
+            buy_token: H160([2; 20]),
+            ..Default::default()
+        };
+        let unknown_pair = FeeData {
+            buy_token: H160([3; 20]),
+            ..known_pair
+        };
+        let mut gas_model = MockGasModel::new();
+        gas_model
+            .expect_trade_gas()
+            .returning(move |sell_token, buy_token, estimated_gas| {
+                assert_eq!(estimated_gas, 9.);
+                (sell_token == known_pair.sell_token && buy_token == known_pair.buy_token)
+                    .then(|| 5.)
+            });
+        let fee_estimator = MinFeeCalculator {
+            gas_model: Arc::new(gas_model),
+            ..MinFeeCalculator::new_for_test(
+                Arc::new(FakeGasPriceEstimator(Arc::new(Mutex::new(
+                    EstimatedGasPrice {
+                        legacy: 1.0,
+                        eip1559: None,
+                    },
+                )))),
+                Arc::new(FakePriceEstimator(price_estimation::Estimate {
+                    out_amount: 1.into(),
+                    gas: 9,
+                })),
+                Box::new(Utc::now),
+            )
+        };
+
+        let (fee, _, _) = fee_estimator
+            .compute_subsidized_min_fee(known_pair, Default::default(), Default::default())
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(fee, 5.into());
+        let (fee, _, _) = fee_estimator
+            .compute_subsidized_min_fee(unknown_pair, Default::default(), Default::default())
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(fee, 9.into());
+    }
 }
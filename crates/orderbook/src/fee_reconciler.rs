@@ -0,0 +1,73 @@
+use crate::database::Postgres;
+use anyhow::{Context, Result};
+use ethcontract::{H256, U256};
+use shared::{maintenance::Maintaining, Web3};
+
+/// How many settlements are reconciled per maintenance run so that catching up on old settlements
+/// doesn't delay the other maintenance tasks.
+const SETTLEMENTS_PER_UPDATE: u64 = 100;
+
+/// Compares the fees we estimated for traded orders with what their settlements actually cost.
+///
+/// The cost comes from the gas used and the effective gas price of the settlement's transaction
+/// receipt and is split evenly across the settlement's trades. A missing receipt is retried in the
+/// next maintenance run because the node might not have caught up yet. Settlements whose receipt is
+/// incomplete are recorded without a cost so that they don't block the following settlements.
+pub struct FeeReconciler {
+    database: Postgres,
+    web3: Web3,
+}
+
+impl FeeReconciler {
+    pub fn new(database: Postgres, web3: Web3) -> Self {
+        Self { database, web3 }
+    }
+
+    async fn update(&self) -> Result<()> {
+        let settlements = self
+            .database
+            .settlements_without_cost(SETTLEMENTS_PER_UPDATE)
+            .await?;
+        for tx_hash in settlements {
+            match self.settlement_cost(tx_hash).await? {
+                Ok((gas_used, effective_gas_price)) => {
+                    self.database
+                        .record_settlement_cost(tx_hash, gas_used, effective_gas_price)
+                        .await?
+                }
+                Err(reason) => {
+                    tracing::warn!(?tx_hash, %reason, "cannot reconcile settlement");
+                    self.database
+                        .record_settlement_cost_failure(tx_hash)
+                        .await?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The gas used and effective gas price of the settlement transaction. The outer error is worth
+    /// retrying (node errors and missing receipts), the inner one means that the receipt can't be
+    /// used.
+    async fn settlement_cost(&self, tx_hash: H256) -> Result<Result<(U256, U256), &'static str>> {
+        let receipt = self
+            .web3
+            .eth()
+            .transaction_receipt(tx_hash)
+            .await
+            .with_context(|| format!("failed to fetch receipt of {:?}", tx_hash))?
+            .with_context(|| format!("no receipt for {:?}", tx_hash))?;
+        Ok(match (receipt.gas_used, receipt.effective_gas_price) {
+            (Some(gas_used), Some(effective_gas_price)) => Ok((gas_used, effective_gas_price)),
+            (None, _) => Err("receipt has no gas used"),
+            (_, None) => Err("receipt has no effective gas price"),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Maintaining for FeeReconciler {
+    async fn run_maintenance(&self) -> Result<()> {
+        self.update().await
+    }
+}
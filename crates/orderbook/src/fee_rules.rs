@@ -0,0 +1,385 @@
+use crate::fee::FeeData;
+use anyhow::{ensure, Context, Result};
+use chrono::{DateTime, Utc};
+use model::{app_id::AppId, order::OrderKind};
+use primitive_types::H160;
+use serde::Deserialize;
+use shared::metrics::get_metric_storage_registry;
+use std::{collections::HashSet, path::Path};
+
+/// Fee subsidy rules read from a JSON file.
+///
+/// The actions of all rules whose conditions match are applied in the order of the file. They are
+/// applied to the fee in native token after the discount, the minimum discounted fee and the
+/// factors configured through the command line (see `FeeSubsidyConfiguration`) so a `discount`
+/// action subtracts from the already multiplied fee and a `cap` limits the final fee.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(transparent)]
+pub struct FeeRules(Vec<FeeRule>);
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct FeeRule {
+    /// Identifies the rule in logs and metrics.
+    pub name: String,
+    #[serde(default)]
+    pub conditions: Conditions,
+    pub action: Action,
+}
+
+/// All conditions have to match for a rule to apply. Empty lists and missing bounds match
+/// everything.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct Conditions {
+    pub app_data: HashSet<AppId>,
+    pub owners: HashSet<H160>,
+    pub sell_tokens: HashSet<H160>,
+    pub buy_tokens: HashSet<H160>,
+    /// Bounds on the traded amount in native token atoms. That is the sell amount of sell orders
+    /// and the buy amount of buy orders converted with the native price of the respective token.
+    pub min_native_amount: Option<f64>,
+    pub max_native_amount: Option<f64>,
+    /// The rule applies from `valid_from` (inclusive) to `valid_to` (exclusive).
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_to: Option<DateTime<Utc>>,
+}
+
+/// Amounts are in native token atoms.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub enum Action {
+    /// Subtracts a flat amount from the fee without going below 0.
+    Discount(f64),
+    /// Multiplies the fee.
+    Factor(f64),
+    /// Limits the fee to at most this amount.
+    Cap(f64),
+}
+
+/// Everything about a fee computation that rules can match on.
+#[derive(Clone, Copy, Debug)]
+pub struct FeeContext {
+    pub fee_data: FeeData,
+    pub app_data: AppId,
+    pub owner: H160,
+    pub time: DateTime<Utc>,
+    /// The native price of the buy token. Only needed to match size bounds for buy orders.
+    pub buy_token_price: Option<f64>,
+}
+
+impl FeeRules {
+    pub fn new(rules: Vec<FeeRule>) -> Result<Self> {
+        for rule in &rules {
+            let amount = match rule.action {
+                Action::Discount(amount) | Action::Factor(amount) | Action::Cap(amount) => amount,
+            };
+            ensure!(
+                amount.is_finite() && amount >= 0.,
+                "action of fee rule {} must be a non-negative number",
+                rule.name
+            );
+        }
+        Ok(Self(rules))
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read fee rules from {:?}", path))?;
+        let rules = serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse fee rules from {:?}", path))?;
+        Self::new(rules)
+    }
+
+    /// Applies the matching rules to the fee in native token. Returns the new fee and the names of
+    /// the rules that fired.
+    ///
+    /// Fees are also recomputed when orders are validated so this doesn't count the fired rules.
+    /// That is up to the caller through `record_applied` once the fee is actually issued.
+    pub fn apply(
+        &self,
+        fee_in_eth: f64,
+        context: &FeeContext,
+        sell_token_price: f64,
+    ) -> (f64, Vec<&str>) {
+        let native_amount = match context.fee_data.kind {
+            OrderKind::Sell => Some(context.fee_data.amount.to_f64_lossy() * sell_token_price),
+            OrderKind::Buy => context
+                .buy_token_price
+                .map(|price| context.fee_data.amount.to_f64_lossy() * price),
+        };
+        let mut fee = fee_in_eth;
+        let mut fired = Vec::new();
+        for rule in self
+            .0
+            .iter()
+            .filter(|rule| rule.conditions.matches(context, native_amount))
+        {
+            fee = match rule.action {
+                Action::Discount(discount) => (fee - discount).max(0.),
+                Action::Factor(factor) => fee * factor,
+                Action::Cap(cap) => fee.min(cap),
+            };
+            fired.push(rule.name.as_str());
+        }
+        (fee, fired)
+    }
+
+    /// Whether any rule has size bounds which need the native price of the buy token for buy orders.
+    pub fn has_size_conditions(&self) -> bool {
+        self.0.iter().any(|rule| {
+            rule.conditions.min_native_amount.is_some()
+                || rule.conditions.max_native_amount.is_some()
+        })
+    }
+
+    /// Times in `[from, to]` at which the set of rules with a matching time window can change.
+    /// Every set of rules that applies at some point of the interval applies at one of these times.
+    pub fn times_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        let boundaries = self
+            .0
+            .iter()
+            .flat_map(|rule| [rule.conditions.valid_from, rule.conditions.valid_to])
+            .flatten()
+            .filter(|time| from < *time && *time <= to);
+        std::iter::once(from).chain(boundaries).collect()
+    }
+}
+
+impl Conditions {
+    fn matches(&self, context: &FeeContext, native_amount: Option<f64>) -> bool {
+        fn contains<T: Eq + std::hash::Hash>(set: &HashSet<T>, value: &T) -> bool {
+            set.is_empty() || set.contains(value)
+        }
+        let within_size = match (self.min_native_amount, self.max_native_amount) {
+            (None, None) => true,
+            (min, max) => native_amount.map_or(false, |amount| {
+                min.map_or(true, |min| amount >= min) && max.map_or(true, |max| amount <= max)
+            }),
+        };
+        contains(&self.app_data, &context.app_data)
+            && contains(&self.owners, &context.owner)
+            && contains(&self.sell_tokens, &context.fee_data.sell_token)
+            && contains(&self.buy_tokens, &context.fee_data.buy_token)
+            && within_size
+            && self.valid_from.map_or(true, |from| context.time >= from)
+            && self.valid_to.map_or(true, |to| context.time < to)
+    }
+}
+
+#[derive(prometheus_metric_storage::MetricStorage, Clone, Debug)]
+#[metric(subsystem = "fee_rules")]
+struct Metrics {
+    /// Number of issued fees a fee rule applied to.
+    #[metric(labels("rule"))]
+    applied_rules: prometheus::IntCounterVec,
+}
+
+/// Counts the rules that fired for an issued fee.
+pub fn record_applied(rules: &[String]) {
+    for rule in rules {
+        metrics()
+            .applied_rules
+            .with_label_values(&[rule.as_str()])
+            .inc();
+    }
+}
+
+fn metrics() -> &'static Metrics {
+    Metrics::instance(get_metric_storage_registry())
+        .expect("unexpected error getting metrics instance")
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn context() -> FeeContext {
+        FeeContext {
+            fee_data: FeeData {
+                sell_token: H160::from_low_u64_be(1),
+                buy_token: H160::from_low_u64_be(2),
+                amount: 1000.into(),
+                kind: OrderKind::Sell,
+            },
+            app_data: AppId([1; 32]),
+            owner: H160::from_low_u64_be(3),
+            time: Utc.timestamp(1_000, 0),
+            buy_token_price: None,
+        }
+    }
+
+    fn rule(name: &str, conditions: Conditions, action: Action) -> FeeRule {
+        FeeRule {
+            name: name.to_string(),
+            conditions,
+            action,
+        }
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rules: Vec<FeeRule> = serde_json::from_str(
+            r#"[
+                {
+                    "name": "partner",
+                    "conditions": {
+                        "appData": ["0x0101010101010101010101010101010101010101010101010101010101010101"],
+                        "sellTokens": ["0x0000000000000000000000000000000000000001"],
+                        "minNativeAmount": 1e18,
+                        "validTo": "2022-06-01T00:00:00Z"
+                    },
+                    "action": { "factor": 0.5 }
+                },
+                { "name": "cap", "action": { "cap": 1e15 } }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(
+            rules[0].conditions.app_data,
+            HashSet::from([AppId([1; 32])])
+        );
+        assert_eq!(rules[0].conditions.min_native_amount, Some(1e18));
+        assert_eq!(
+            rules[0].conditions.valid_to,
+            Some(Utc.ymd(2022, 6, 1).and_hms(0, 0, 0))
+        );
+        assert_eq!(rules[0].action, Action::Factor(0.5));
+        assert_eq!(rules[1].conditions, Conditions::default());
+        assert_eq!(rules[1].action, Action::Cap(1e15));
+
+        assert!(serde_json::from_str::<Vec<FeeRule>>(
+            r#"[{ "name": "unknown", "action": { "refund": 1 } }]"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_negative_actions() {
+        assert!(FeeRules::new(vec![rule(
+            "negative",
+            Default::default(),
+            Action::Factor(-1.)
+        )])
+        .is_err());
+        assert!(
+            FeeRules::new(vec![rule("nan", Default::default(), Action::Cap(f64::NAN))]).is_err()
+        );
+    }
+
+    #[test]
+    fn applies_matching_rules_in_order() {
+        let rules = FeeRules::new(vec![
+            rule("discount", Default::default(), Action::Discount(10.)),
+            rule(
+                "other owner",
+                Conditions {
+                    owners: HashSet::from([H160::from_low_u64_be(4)]),
+                    ..Default::default()
+                },
+                Action::Factor(0.),
+            ),
+            rule(
+                "pair",
+                Conditions {
+                    sell_tokens: HashSet::from([H160::from_low_u64_be(1)]),
+                    buy_tokens: HashSet::from([H160::from_low_u64_be(2)]),
+                    ..Default::default()
+                },
+                Action::Factor(0.5),
+            ),
+            rule("cap", Default::default(), Action::Cap(30.)),
+        ])
+        .unwrap();
+        // (100 - 10) * 0.5 capped at 30
+        let (fee, fired) = rules.apply(100., &context(), 1.);
+        assert_eq!(fee, 30.);
+        assert_eq!(fired, ["discount", "pair", "cap"]);
+
+        let (fee, fired) = rules.apply(5., &context(), 1.);
+        assert_eq!(fee, 0.);
+        assert_eq!(fired, ["discount", "pair", "cap"]);
+    }
+
+    #[test]
+    fn matches_size_and_time_window() {
+        let conditions = Conditions {
+            min_native_amount: Some(500.),
+            max_native_amount: Some(2000.),
+            valid_from: Some(Utc.timestamp(1_000, 0)),
+            valid_to: Some(Utc.timestamp(2_000, 0)),
+            ..Default::default()
+        };
+        let context = context();
+        // The sell amount of 1000 is worth 1000 * price in native token.
+        assert!(conditions.matches(&context, Some(1000.)));
+        assert!(!conditions.matches(&context, Some(100.)));
+        assert!(!conditions.matches(&context, Some(3000.)));
+        assert!(!conditions.matches(&context, None));
+        assert!(!conditions.matches(
+            &FeeContext {
+                time: Utc.timestamp(2_000, 0),
+                ..context
+            },
+            Some(1000.)
+        ));
+
+        let rules = FeeRules::new(vec![rule("size", conditions, Action::Factor(0.5))]).unwrap();
+        assert!(rules.has_size_conditions());
+        assert_eq!(rules.apply(100., &context, 1.).0, 50.);
+        assert_eq!(rules.apply(100., &context, 0.1).0, 100.);
+
+        // The buy amount of 1000 is worth 1000 * buy token price in native token.
+        let buy_order = FeeContext {
+            fee_data: FeeData {
+                kind: OrderKind::Buy,
+                ..context.fee_data
+            },
+            buy_token_price: Some(1.),
+            ..context
+        };
+        assert_eq!(rules.apply(100., &buy_order, 0.1).0, 50.);
+        let small_buy_order = FeeContext {
+            buy_token_price: Some(0.1),
+            ..buy_order
+        };
+        assert_eq!(rules.apply(100., &small_buy_order, 1.).0, 100.);
+        let unknown_buy_order = FeeContext {
+            buy_token_price: None,
+            ..buy_order
+        };
+        assert_eq!(rules.apply(100., &unknown_buy_order, 1.).0, 100.);
+    }
+
+    #[test]
+    fn times_between_rule_boundaries() {
+        let rules = FeeRules::new(vec![
+            rule(
+                "window",
+                Conditions {
+                    valid_from: Some(Utc.timestamp(1_000, 0)),
+                    valid_to: Some(Utc.timestamp(2_000, 0)),
+                    ..Default::default()
+                },
+                Action::Factor(0.5),
+            ),
+            rule("always", Default::default(), Action::Cap(1.)),
+        ])
+        .unwrap();
+        assert_eq!(
+            rules.times_between(Utc.timestamp(500, 0), Utc.timestamp(3_000, 0)),
+            [
+                Utc.timestamp(500, 0),
+                Utc.timestamp(1_000, 0),
+                Utc.timestamp(2_000, 0)
+            ]
+        );
+        assert_eq!(
+            rules.times_between(Utc.timestamp(1_000, 0), Utc.timestamp(1_500, 0)),
+            [Utc.timestamp(1_000, 0)]
+        );
+    }
+}
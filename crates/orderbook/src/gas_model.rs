@@ -0,0 +1,99 @@
+use crate::{database::Postgres, fee_reconciler::FeeReconciler};
+use anyhow::Result;
+use primitive_types::H160;
+use shared::{maintenance::Maintaining, Web3};
+use std::{collections::HashMap, sync::Mutex};
+
+/// How far the learned gas of a pair may deviate from the gas reported by the price estimator. The
+/// learned gas is clamped to `[estimated / MAX_DEVIATION, estimated * MAX_DEVIATION]` so that a
+/// few unusual settlements can't make fees absurdly cheap or expensive.
+const MAX_DEVIATION: f64 = 2.;
+
+/// Estimates how much gas a trade of a token pair uses in a settlement.
+#[cfg_attr(test, mockall::automock)]
+pub trait GasModel: Send + Sync + 'static {
+    /// Returns `None` if the model knows nothing about the pair in which case `estimated_gas`, the
+    /// gas reported by the price estimator, should be used.
+    fn trade_gas(&self, sell_token: H160, buy_token: H160, estimated_gas: f64) -> Option<f64>;
+}
+
+/// Always defers to the price estimator.
+pub struct NoGasModel;
+
+impl GasModel for NoGasModel {
+    fn trade_gas(&self, _: H160, _: H160, _: f64) -> Option<f64> {
+        None
+    }
+}
+
+/// Learns the gas of token pairs from the realized costs of past settlements.
+///
+/// The gas of a pair is the gas of past settlements that traded only that pair as recorded by the
+/// `FeeReconciler`, which the model runs itself before every update. We don't index which
+/// interactions a settlement executed so the model can only distinguish token pairs, not the way
+/// they were traded, which is why the learned gas is bounded by the estimated gas.
+pub struct LearnedGasModel {
+    database: Postgres,
+    reconciler: FeeReconciler,
+    recent_trades: u64,
+    min_trades: u64,
+    gas: Mutex<HashMap<(H160, H160), f64>>,
+}
+
+impl LearnedGasModel {
+    /// Uses the median gas of the `recent_trades` most recent trades of each pair with at least
+    /// `min_trades` trades.
+    pub fn new(database: Postgres, web3: Web3, recent_trades: u64, min_trades: u64) -> Self {
+        Self {
+            reconciler: FeeReconciler::new(database.clone(), web3),
+            database,
+            recent_trades,
+            min_trades,
+            gas: Default::default(),
+        }
+    }
+
+    async fn update(&self) -> Result<()> {
+        self.reconciler.run_maintenance().await?;
+        let gas = self
+            .database
+            .trade_gas(self.recent_trades, self.min_trades)
+            .await?
+            .into_iter()
+            .map(|gas| ((gas.sell_token, gas.buy_token), gas.median_gas))
+            .collect::<HashMap<_, _>>();
+        tracing::debug!("learned gas of {} token pairs", gas.len());
+        *self.gas.lock().unwrap() = gas;
+        Ok(())
+    }
+}
+
+impl GasModel for LearnedGasModel {
+    fn trade_gas(&self, sell_token: H160, buy_token: H160, estimated_gas: f64) -> Option<f64> {
+        let learned = *self.gas.lock().unwrap().get(&(sell_token, buy_token))?;
+        Some(bounded(learned, estimated_gas))
+    }
+}
+
+fn bounded(learned_gas: f64, estimated_gas: f64) -> f64 {
+    learned_gas.clamp(estimated_gas / MAX_DEVIATION, estimated_gas * MAX_DEVIATION)
+}
+
+#[async_trait::async_trait]
+impl Maintaining for LearnedGasModel {
+    async fn run_maintenance(&self) -> Result<()> {
+        self.update().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn learned_gas_is_bounded_by_estimate() {
+        assert_eq!(bounded(150., 100.), 150.);
+        assert_eq!(bounded(10., 100.), 50.);
+        assert_eq!(bounded(1000., 100.), 200.);
+    }
+}
@@ -1,21 +1,41 @@
+pub mod access_lists;
 pub mod account_balances;
 pub mod api;
 pub mod conversions;
 pub mod cow_subsidy;
 pub mod database;
+pub mod event_reindexing;
 pub mod event_updater;
+pub mod expiry_sweeper;
 pub mod fee;
+pub mod fee_reconciler;
+pub mod fee_rules;
+pub mod gas_model;
 pub mod gas_price;
 pub mod metrics;
+pub mod order_archiver;
+pub mod order_updates;
 pub mod orderbook;
+pub mod settlement_timestamps;
+pub mod signature_validator;
 pub mod solvable_orders;
 
-use crate::{api::post_quote::OrderQuoter, orderbook::Orderbook};
+use crate::{
+    api::{
+        get_account_stats::AccountStatistics, get_native_price::NativePrices,
+        post_quote::OrderQuoter, rate_limiting::RateLimits,
+    },
+    orderbook::Orderbook,
+};
 use anyhow::{anyhow, Context as _, Result};
 use contracts::GPv2Settlement;
-use database::trades::TradeRetrieving;
+use database::{
+    fee_reconciliations::FeeReconciliationRetrieving, solver_competition::SolverCompetitionStoring,
+    trades::TradeRetrieving,
+};
 use futures::Future;
 use model::DomainSeparator;
+use primitive_types::H160;
 use std::{net::SocketAddr, sync::Arc};
 use tokio::{task, task::JoinHandle};
 use warp::Filter;
@@ -24,10 +44,29 @@ pub fn serve_api(
     database: Arc<dyn TradeRetrieving>,
     orderbook: Arc<Orderbook>,
     quoter: Arc<OrderQuoter>,
+    rate_limits: Arc<RateLimits>,
+    native_prices: Arc<NativePrices>,
+    account_statistics: Arc<AccountStatistics>,
+    solver_competition: Arc<dyn SolverCompetitionStoring>,
+    solver_competition_auth: Option<String>,
+    fee_reconciliations: Arc<dyn FeeReconciliationRetrieving>,
+    native_token: H160,
     address: SocketAddr,
     shutdown_receiver: impl Future<Output = ()> + Send + 'static,
 ) -> JoinHandle<()> {
-    let filter = api::handle_all_routes(database, orderbook, quoter).boxed();
+    let filter = api::handle_all_routes(
+        database,
+        orderbook,
+        quoter,
+        rate_limits,
+        native_prices,
+        account_statistics,
+        solver_competition,
+        solver_competition_auth,
+        fee_reconciliations,
+        native_token,
+    )
+    .boxed();
     tracing::info!(%address, "serving order book");
     let (_, server) = warp::serve(filter).bind_with_graceful_shutdown(address, shutdown_receiver);
     task::spawn(server)
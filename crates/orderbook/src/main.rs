@@ -1,5 +1,5 @@
 use anyhow::{anyhow, Context, Result};
-use clap::{ArgEnum, Parser};
+use clap::{ArgEnum, Parser, Subcommand};
 use contracts::{
     BalancerV2Vault, CowProtocolToken, CowProtocolVirtualToken, GPv2Settlement, IUniswapV3Factory,
     WETH9,
@@ -11,16 +11,35 @@ use model::{
     DomainSeparator,
 };
 use orderbook::{
+    access_lists::{AccessLists, AccessListsReloader, BannedUsers},
     account_balances::Web3BalanceFetcher,
-    api::{order_validation::OrderValidator, post_quote::OrderQuoter},
-    cow_subsidy::{CowSubsidy, CowSubsidyImpl, FixedCowSubsidy, SubsidyTiers},
-    database::{self, orders::OrderFilter, Postgres},
+    api::{
+        get_account_stats::{AccountStatistics, UsdReference},
+        get_native_price::NativePrices,
+        order_validation::OrderValidator,
+        post_quote::OrderQuoter,
+        rate_limiting::{RateLimit, RateLimits},
+    },
+    cow_subsidy::{
+        subsidy_tokens_from_file, CowSubsidy, CowSubsidyImpl, FixedCowSubsidy, SubsidyTiers,
+        TokenHoldingSubsidy,
+    },
+    database::{self, memory::InMemory, orders::OrderFilter, Backend, Postgres},
+    event_reindexing::EventReindexer,
     event_updater::EventUpdater,
+    expiry_sweeper::ExpirySweeper,
     fee::{FeeSubsidyConfiguration, MinFeeCalculator},
+    fee_reconciler::FeeReconciler,
+    fee_rules::FeeRules,
+    gas_model::{GasModel, LearnedGasModel, NoGasModel},
     gas_price::InstrumentedGasEstimator,
     metrics::Metrics,
+    order_archiver::OrderArchiver,
+    order_updates::{OrderUpdateEventStore, OrderUpdates},
     orderbook::Orderbook,
     serve_api,
+    settlement_timestamps::SettlementTimestampUpdater,
+    signature_validator::Web3SignatureValidator,
     solvable_orders::SolvableOrdersCache,
     verify_deployed_contract_constants,
 };
@@ -39,7 +58,7 @@ use shared::{
     current_block::current_block_stream,
     http_solver::{DefaultHttpSolverApi, SolverConfig},
     maintenance::ServiceMaintenance,
-    metrics::{serve_metrics, setup_metrics_registry, DEFAULT_METRICS_PORT},
+    metrics::{serve_metrics, setup_metrics_registry, LivenessChecking, DEFAULT_METRICS_PORT},
     network::network_name,
     oneinch_api::OneInchClientImpl,
     paraswap_api::DefaultParaswapApi,
@@ -64,11 +83,14 @@ use shared::{
         uniswap_v2::pool_cache::PoolCache,
         BaselineSource, PoolAggregator,
     },
-    token_info::{CachedTokenInfoFetcher, TokenInfoFetcher},
+    token_info::{CachedTokenInfoFetcher, TokenInfoFetcher, TokenInfoFetching},
     transport::{create_instrumented_transport, http::HttpTransport},
     zeroex_api::DefaultZeroExApi,
 };
-use std::{collections::HashMap, net::SocketAddr, num::NonZeroUsize, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap, net::SocketAddr, num::NonZeroUsize, ops::RangeInclusive, path::PathBuf,
+    sync::Arc, time::Duration,
+};
 use tokio::task;
 use url::Url;
 
@@ -81,9 +103,15 @@ struct Arguments {
     bind_address: SocketAddr,
 
     /// Url of the Postgres database. By default connects to locally running postgres.
+    /// `memory://` keeps everything in memory instead which is useful for local development.
     #[clap(long, env, default_value = "postgresql://")]
     db_url: Url,
 
+    /// Url of a read replica of the Postgres database. If set the read only order and trade
+    /// queries of the API use the replica while writes and the solvable orders stay on `db_url`.
+    #[clap(long, env)]
+    db_read_url: Option<Url>,
+
     /// Skip syncing past events (useful for local deployments)
     #[clap(long)]
     skip_event_sync: bool,
@@ -125,6 +153,12 @@ struct Arguments {
     #[clap(long, env, use_value_delimiter = true)]
     allowed_tokens: Vec<H160>,
 
+    /// Path to a JSON file with `bannedUsers`, `unsupportedTokens` and `allowedTokens` lists that
+    /// extend the three lists above. The file is read again on every new block so that the lists
+    /// can be changed without restarting the service.
+    #[clap(long, env)]
+    access_lists_file: Option<PathBuf>,
+
     /// The number of pairs that are automatically updated in the pool cache.
     #[clap(long, env, default_value = "200")]
     pool_cache_lru_size: usize,
@@ -136,6 +170,23 @@ struct Arguments {
     #[clap(long, env, parse(try_from_str), default_value = "false")]
     enable_presign_orders: bool,
 
+    /// Token bucket rate limit per order owner for creating orders in the format
+    /// "<requests>/<seconds>". Allows bursts of up to <requests> requests which get refilled over
+    /// <seconds>. Orders in a batch count individually.
+    #[clap(long, env)]
+    owner_rate_limit: Option<RateLimit>,
+
+    /// Token bucket rate limit per client IP for creating orders, requesting quotes and market
+    /// estimates in the same format as `--owner-rate-limit`.
+    #[clap(long, env)]
+    ip_rate_limit: Option<RateLimit>,
+
+    /// The number of reverse proxies in front of the API that append the address they received a
+    /// request from to the `X-Forwarded-For` header. The client IP for rate limiting is only taken
+    /// from that header if this is set. Otherwise the address of the connection is used.
+    #[clap(long, env, default_value = "0")]
+    trusted_proxy_hops: usize,
+
     /// If solvable orders haven't been successfully update in this time in seconds attempting
     /// to get them errors and our liveness check fails.
     #[clap(
@@ -190,6 +241,32 @@ struct Arguments {
     #[clap(long, env)]
     cow_fee_factors: Option<SubsidyTiers>,
 
+    /// Path to a JSON list of tokens whose weighted balances are summed up and mapped through the
+    /// cow fee factors instead of the COW + VCOW balance. Every entry has an `address`, an optional
+    /// `weight` (default 1) and optional ABI fragments for a `balanceFunction` (default ERC20
+    /// `balanceOf`) and a `conversionFunction` like ERC-4626 `convertToAssets`.
+    #[clap(long, env)]
+    subsidy_tokens_file: Option<PathBuf>,
+
+    /// Path to a JSON file with fee subsidy rules. Every rule has a `name`, `conditions` on
+    /// `appData`, `owners`, `sellTokens`, `buyTokens`, `minNativeAmount`, `maxNativeAmount`,
+    /// `validFrom` and `validTo` and an `action` that is one of `discount`, `factor` or `cap`.
+    /// Rules are applied in order after the fee discount, minimum discounted fee and all fee
+    /// factors above including the cow fee factors.
+    #[clap(long, env)]
+    fee_rules_file: Option<PathBuf>,
+
+    /// Use the gas that past settlements of a single trade of a token pair used instead of the gas
+    /// reported by the price estimator for pairs with at least this many such settlements. The
+    /// learned gas stays within a factor of 2 of the estimated gas. Requires a Postgres database.
+    /// Disabled if unset.
+    #[clap(long, env)]
+    gas_model_min_trades: Option<u64>,
+
+    /// How many of the most recent trades of a token pair the gas model learns from.
+    #[clap(long, env, default_value = "100")]
+    gas_model_recent_trades: u64,
+
     /// The API endpoint to call the mip v2 solver for price estimation
     #[clap(long, env)]
     quasimodo_solver_url: Option<Url>,
@@ -203,6 +280,16 @@ struct Arguments {
     )]
     native_price_cache_max_age_secs: Duration,
 
+    /// How long native prices that API routes request for arbitrary tokens stay cached. Unlike the
+    /// prices for auctions these are not kept up to date in the background.
+    #[clap(
+        long,
+        env,
+        default_value = "10",
+        parse(try_from_str = shared::arguments::duration_from_seconds),
+    )]
+    api_native_price_cache_max_age_secs: Duration,
+
     /// How many cached native token prices can be updated at most in one maintenance cycle.
     #[clap(long, env, default_value = "3")]
     native_price_cache_max_update_size: usize,
@@ -217,6 +304,26 @@ struct Arguments {
     )]
     native_price_estimators: Vec<PriceEstimatorType>,
 
+    /// A USD stable coin whose native price is used to convert the account statistics to USD.
+    #[clap(long, env)]
+    usd_reference_token: Option<H160>,
+
+    /// The secret the driver has to send in the authorization header, as is or as a bearer token,
+    /// when reporting the solver competition of an auction. Reporting is disabled if this is not
+    /// set.
+    #[clap(long, env)]
+    solver_competition_auth: Option<String>,
+
+    /// Orders that are fully executed, cancelled or expired and were created longer than this many
+    /// seconds ago are moved into the archive tables together with their trades. Archival is
+    /// disabled if this is not set.
+    #[clap(
+        long,
+        env,
+        parse(try_from_str = shared::arguments::duration_from_seconds),
+    )]
+    order_archive_age: Option<Duration>,
+
     /// The amount in native tokens atoms to use for price estimation. Should be reasonably large so
     /// that small pools do not influence the prices. If not set a reasonable default is used based
     /// on network id.
@@ -244,6 +351,31 @@ struct Arguments {
     /// will not have any further effect.
     #[clap(long, env, default_value = "2")]
     fast_price_estimation_results_required: NonZeroUsize,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Re-fetches the settlement contract events of a block range and reports how they differ from
+    /// the stored events. Exits afterwards instead of running the API.
+    ReindexEvents {
+        #[clap(long)]
+        from_block: u64,
+
+        #[clap(long)]
+        to_block: u64,
+
+        /// How many blocks get compared and replaced in one database transaction.
+        #[clap(long, default_value = "1000")]
+        chunk_size: u64,
+
+        /// Replace the stored events of blocks that differ with the events on chain. Without this
+        /// the differences are only reported.
+        #[clap(long)]
+        apply: bool,
+    },
 }
 
 pub async fn database_metrics(metrics: Arc<Metrics>, database: Postgres) -> ! {
@@ -256,10 +388,34 @@ pub async fn database_metrics(metrics: Arc<Metrics>, database: Postgres) -> ! {
             }
             Err(err) => tracing::error!(?err, "failed to update db metrics"),
         };
+        for status in database.pool_statuses().await {
+            metrics.set_pool_status(&status);
+        }
         tokio::time::sleep(Duration::from_secs(10)).await;
     }
 }
 
+async fn reindex_events(
+    contract: GPv2Settlement,
+    database: Postgres,
+    blocks: RangeInclusive<u64>,
+    chunk_size: u64,
+    apply: bool,
+) {
+    let differences = EventReindexer::new(contract, database)
+        .reindex(blocks, chunk_size, apply)
+        .await
+        .expect("failed to reindex events");
+    for difference in &differences {
+        tracing::warn!(?difference, "event on chain differs from stored event");
+    }
+    tracing::info!(
+        "reindexing found {} differences, applied: {}",
+        differences.len(),
+        apply
+    );
+}
+
 #[tokio::main]
 async fn main() {
     let args = Arguments::parse();
@@ -327,9 +483,46 @@ async fn main() {
         .await
         .expect("Deployed contract constants don't match the ones in this binary");
     let domain_separator = DomainSeparator::new(chain_id, settlement_contract.address());
-    let postgres = Postgres::new(args.db_url.as_str()).expect("failed to create database");
+    // Maintenance that works on the tables directly is only available with Postgres.
+    let postgres = match args.db_url.scheme() {
+        "memory" => None,
+        _ => Some(
+            match &args.db_read_url {
+                Some(read_url) => {
+                    Postgres::with_read_replica(args.db_url.as_str(), read_url.as_str())
+                }
+                None => Postgres::new(args.db_url.as_str()),
+            }
+            .expect("failed to create database"),
+        ),
+    };
+    if let Some(Command::ReindexEvents {
+        from_block,
+        to_block,
+        chunk_size,
+        apply,
+    }) = args.command
+    {
+        let postgres = postgres.expect("reindexing events requires a postgres database");
+        reindex_events(
+            settlement_contract,
+            postgres,
+            from_block..=to_block,
+            chunk_size,
+            apply,
+        )
+        .await;
+        return;
+    }
+    let backend: Box<dyn Backend> = match &postgres {
+        Some(postgres) => Box::new(postgres.clone()),
+        None => {
+            tracing::warn!("using in-memory database, nothing is persisted");
+            Box::new(InMemory::default())
+        }
+    };
     let database = Arc::new(database::instrumented::Instrumented::new(
-        postgres.clone(),
+        backend,
         metrics.clone(),
     ));
 
@@ -343,9 +536,10 @@ async fn main() {
         None
     };
 
+    let order_updates = Arc::new(OrderUpdates::default());
     let event_updater = Arc::new(EventUpdater::new(
         settlement_contract.clone(),
-        database.as_ref().clone(),
+        OrderUpdateEventStore::new(database.as_ref().clone(), order_updates.clone()),
         sync_start,
     ));
     let balance_fetcher = Arc::new(Web3BalanceFetcher::new(
@@ -354,6 +548,7 @@ async fn main() {
         vault_relayer,
         settlement_contract.address(),
     ));
+    let signature_validator = Arc::new(Web3SignatureValidator::new(web3.clone()));
 
     let gas_price_estimator = Arc::new(InstrumentedGasEstimator::new(
         shared::gas_price_estimation::create_priority_estimator(
@@ -424,18 +619,33 @@ async fn main() {
         Box::new(trace_call_detector),
         args.token_quality_cache_expiry,
     );
-    let bad_token_detector = Arc::new(
-        ListBasedDetector::new(
-            allowed_tokens,
-            unsupported_tokens,
-            if args.skip_trace_api {
-                UnknownTokenStrategy::Allow
-            } else {
-                UnknownTokenStrategy::Forward(Box::new(caching_detector))
+    let token_lists = Arc::new(ListBasedDetector::new(
+        allowed_tokens.clone(),
+        unsupported_tokens.clone(),
+        if args.skip_trace_api {
+            UnknownTokenStrategy::Allow
+        } else {
+            UnknownTokenStrategy::Forward(Box::new(caching_detector))
+        },
+    ));
+    let bad_token_detector = Arc::new(token_lists.clone().instrumented());
+    let banned_users = Arc::new(BannedUsers::new(
+        args.banned_users.iter().copied().collect(),
+    ));
+    let access_lists_reloader = args.access_lists_file.clone().map(|path| {
+        let reloader = Arc::new(AccessListsReloader::new(
+            path,
+            AccessLists {
+                banned_users: args.banned_users.iter().copied().collect(),
+                unsupported_tokens: unsupported_tokens.into_iter().collect(),
+                allowed_tokens: allowed_tokens.into_iter().collect(),
             },
-        )
-        .instrumented(),
-    );
+            banned_users.clone(),
+            token_lists,
+        ));
+        reloader.reload().expect("failed to load access lists");
+        reloader
+    });
 
     let current_block_stream =
         current_block_stream(web3.clone(), args.shared.block_stream_poll_interval_seconds)
@@ -587,14 +797,28 @@ async fn main() {
         args.fast_price_estimation_results_required,
     ))));
 
+    // Verbose quotes query the estimators individually so each of them has to be sanitized.
+    let estimator_breakdown = Arc::new(CompetitionPriceEstimator::new(
+        args.price_estimators
+            .iter()
+            .map(|estimator| {
+                let (name, instance) = get_or_create_base_estimator(*estimator);
+                let instance: Arc<dyn PriceEstimating> = Arc::new(sanitized(Box::new(instance)));
+                (name, instance)
+            })
+            .collect(),
+    ));
+
+    let native_price_competition: Arc<dyn PriceEstimating> =
+        Arc::new(sanitized(Box::new(CompetitionPriceEstimator::new(
+            args.native_price_estimators
+                .iter()
+                .map(|estimator| create_base_estimator(*estimator))
+                .collect(),
+        ))));
     let native_price_estimator = Arc::new(CachingNativePriceEstimator::new(
         Box::new(NativePriceEstimator::new(
-            Arc::new(sanitized(Box::new(CompetitionPriceEstimator::new(
-                args.native_price_estimators
-                    .iter()
-                    .map(|estimator| create_base_estimator(*estimator))
-                    .collect(),
-            )))),
+            native_price_competition,
             native_token.address(),
             native_token_price_estimation_amount,
         )),
@@ -605,6 +829,42 @@ async fn main() {
         Duration::from_secs(1),
         Some(args.native_price_cache_max_update_size),
     );
+    // Shared by the routes that request prices of arbitrary tokens so that repeated requests for
+    // the same tokens don't each cause new estimates. Separate from the cache whose prices are kept
+    // up to date for auctions.
+    let api_native_price_estimator = Arc::new(CachingNativePriceEstimator::new(
+        Box::new(NativePriceEstimator::new(
+            native_price_competition.clone(),
+            native_token.address(),
+            native_token_price_estimation_amount,
+        )),
+        args.api_native_price_cache_max_age_secs,
+        metrics.clone(),
+    ));
+    api_native_price_estimator.spawn_pruning_task(args.api_native_price_cache_max_age_secs);
+    let native_prices = Arc::new(NativePrices::new(
+        native_price_estimator.clone(),
+        api_native_price_estimator.clone(),
+    ));
+    let usd_reference = match args.usd_reference_token {
+        Some(token) => {
+            let decimals = token_info_fetcher
+                .get_token_infos(&[token])
+                .await
+                .get(&token)
+                .and_then(|info| info.decimals)
+                .expect("failed to get decimals of the USD reference token");
+            Some(UsdReference { token, decimals })
+        }
+        None => None,
+    };
+    let account_statistics = Arc::new(AccountStatistics::new(
+        database.clone(),
+        api_native_price_estimator.clone(),
+        native_token.address(),
+        usd_reference,
+        current_block_stream.clone(),
+    ));
 
     let cow_token = match CowProtocolToken::deployed(&web3).await {
         Err(DeployError::NotFound(_)) => None,
@@ -619,8 +879,20 @@ async fn main() {
         (Some(token), Some(vtoken)) => Some((token, vtoken)),
         _ => panic!("should either have both cow token contracts or none"),
     };
-    let cow_subsidy = match cow_tokens {
-        Some((token, vtoken)) => {
+    let subsidy_tokens = args
+        .subsidy_tokens_file
+        .as_deref()
+        .map(|path| subsidy_tokens_from_file(path).expect("failed to load subsidy tokens"));
+    let cow_subsidy = match (subsidy_tokens, cow_tokens) {
+        (Some(tokens), _) => {
+            tracing::debug!("using configured tokens for subsidy");
+            Arc::new(TokenHoldingSubsidy::new(
+                &web3,
+                tokens,
+                args.cow_fee_factors.unwrap_or_default(),
+            )) as Arc<dyn CowSubsidy>
+        }
+        (None, Some((token, vtoken))) => {
             tracing::debug!("using cow token contracts for subsidy");
             Arc::new(CowSubsidyImpl::new(
                 token,
@@ -628,12 +900,32 @@ async fn main() {
                 args.cow_fee_factors.unwrap_or_default(),
             )) as Arc<dyn CowSubsidy>
         }
-        None => {
+        (None, None) => {
             tracing::debug!("disabling cow subsidy because contracts not found on network");
             Arc::new(FixedCowSubsidy(1.0)) as Arc<dyn CowSubsidy>
         }
     };
 
+    let fee_rules = args
+        .fee_rules_file
+        .as_deref()
+        .map(|path| FeeRules::from_file(path).expect("failed to load fee rules"))
+        .unwrap_or_default();
+    let learned_gas_model = args.gas_model_min_trades.map(|min_trades| {
+        let postgres = postgres
+            .clone()
+            .expect("the gas model requires a postgres database");
+        Arc::new(LearnedGasModel::new(
+            postgres,
+            web3.clone(),
+            args.gas_model_recent_trades,
+            min_trades,
+        ))
+    });
+    let gas_model = match &learned_gas_model {
+        Some(model) => model.clone() as Arc<dyn GasModel>,
+        None => Arc::new(NoGasModel) as Arc<dyn GasModel>,
+    };
     let create_fee_calculator = |price_estimator: Arc<dyn PriceEstimating>| {
         Arc::new(MinFeeCalculator::new(
             price_estimator.clone(),
@@ -645,10 +937,12 @@ async fn main() {
                 min_discounted_fee: args.min_discounted_fee,
                 fee_factor: args.fee_factor,
                 partner_additional_fee_factors: args.partner_additional_fee_factors.clone(),
+                rules: fee_rules.clone(),
             },
             native_price_estimator.clone(),
             cow_subsidy.clone(),
             args.shared.liquidity_order_owners.iter().copied().collect(),
+            gas_model.clone(),
         ))
     };
     let fee_calculator = create_fee_calculator(price_estimator.clone());
@@ -657,12 +951,15 @@ async fn main() {
     let solvable_orders_cache = SolvableOrdersCache::new(
         args.min_order_validity_period,
         database.clone(),
-        args.banned_users.iter().copied().collect(),
+        banned_users.clone(),
         balance_fetcher.clone(),
         bad_token_detector.clone(),
+        signature_validator.clone(),
         current_block_stream.clone(),
         native_price_estimator,
         metrics.clone(),
+        database.clone(),
+        database.clone(),
     );
     let block = current_block_stream.borrow().number.unwrap().as_u64();
     solvable_orders_cache
@@ -672,12 +969,20 @@ async fn main() {
     let order_validator = Arc::new(OrderValidator::new(
         Box::new(web3.clone()),
         native_token.clone(),
-        args.banned_users.iter().copied().collect(),
+        banned_users,
         args.shared.liquidity_order_owners.into_iter().collect(),
         args.min_order_validity_period,
         fee_calculator.clone(),
         bad_token_detector.clone(),
         balance_fetcher,
+        signature_validator.clone(),
+        database.clone(),
+    ));
+    let rate_limits = Arc::new(RateLimits::new(
+        args.owner_rate_limit,
+        args.ip_rate_limit,
+        args.trusted_proxy_hops,
+        metrics.clone(),
     ));
     let orderbook = Arc::new(Orderbook::new(
         domain_separator,
@@ -688,28 +993,71 @@ async fn main() {
         solvable_orders_cache.clone(),
         args.solvable_orders_max_update_age,
         order_validator.clone(),
+        signature_validator,
+        order_updates.clone(),
+        database.clone(),
+        rate_limits.clone(),
     ));
     let mut service_maintainer = ServiceMaintenance {
-        maintainers: vec![
-            database.clone(),
-            event_updater,
-            pool_fetcher,
-            solvable_orders_cache,
-        ],
+        maintainers: vec![database.clone(), event_updater],
     };
+    if let Some(postgres) = &postgres {
+        service_maintainer
+            .maintainers
+            .push(Arc::new(SettlementTimestampUpdater::new(
+                postgres.clone(),
+                web3.clone(),
+            )));
+        service_maintainer
+            .maintainers
+            .push(Arc::new(ExpirySweeper::new(
+                postgres.clone(),
+                order_updates,
+            )));
+        // The gas model reconciles fees itself so that it always learns from up to date costs.
+        match learned_gas_model {
+            Some(gas_model) => service_maintainer.maintainers.push(gas_model),
+            None => service_maintainer
+                .maintainers
+                .push(Arc::new(FeeReconciler::new(postgres.clone(), web3.clone()))),
+        }
+    }
+    service_maintainer.maintainers.push(pool_fetcher);
+    service_maintainer.maintainers.push(solvable_orders_cache);
     if let Some(balancer) = balancer_pool_fetcher {
         service_maintainer.maintainers.push(balancer);
     }
+    if let (Some(postgres), Some(archive_age)) = (&postgres, args.order_archive_age) {
+        service_maintainer
+            .maintainers
+            .push(Arc::new(OrderArchiver::new(postgres.clone(), archive_age)));
+    }
+    if let Some(reloader) = access_lists_reloader {
+        service_maintainer.maintainers.push(reloader);
+    }
     check_database_connection(orderbook.as_ref()).await;
     let quoter = Arc::new(
-        OrderQuoter::new(fee_calculator, price_estimator, order_validator)
-            .with_fast_quotes(fast_fee_calculator, fast_price_estimator),
+        OrderQuoter::new(
+            fee_calculator,
+            price_estimator,
+            order_validator,
+            database.clone(),
+        )
+        .with_fast_quotes(fast_fee_calculator, fast_price_estimator)
+        .with_estimator_breakdown(estimator_breakdown),
     );
     let (shutdown_sender, shutdown_receiver) = tokio::sync::oneshot::channel();
     let serve_api = serve_api(
         database.clone(),
         orderbook.clone(),
         quoter,
+        rate_limits,
+        native_prices,
+        account_statistics,
+        database.clone(),
+        args.solver_competition_auth,
+        database.clone(),
+        native_token.address(),
         args.bind_address,
         async {
             let _ = shutdown_receiver.await;
@@ -717,12 +1065,21 @@ async fn main() {
     );
     let maintenance_task =
         task::spawn(service_maintainer.run_maintenance_on_new_block(current_block_stream));
-    let db_metrics_task = task::spawn(database_metrics(metrics, postgres));
+    let mut liveness: Vec<Arc<dyn LivenessChecking>> = vec![orderbook.clone()];
+    if let Some(postgres) = &postgres {
+        liveness.push(Arc::new(postgres.clone()));
+    }
+    let db_metrics_task = task::spawn(async move {
+        match postgres {
+            Some(postgres) => database_metrics(metrics, postgres).await,
+            None => futures::future::pending().await,
+        }
+    });
 
     let mut metrics_address = args.bind_address;
     metrics_address.set_port(DEFAULT_METRICS_PORT);
     tracing::info!(%metrics_address, "serving metrics");
-    let metrics_task = serve_metrics(orderbook, metrics_address);
+    let metrics_task = serve_metrics(Arc::new(liveness), metrics_address);
 
     futures::pin_mut!(serve_api);
     tokio::select! {
@@ -1,3 +1,4 @@
+use crate::database::PoolStatus;
 use anyhow::Result;
 use gas_estimation::EstimatedGasPrice;
 use prometheus::{
@@ -16,6 +17,8 @@ use std::time::Duration;
 
 pub struct Metrics {
     db_table_row_count: IntGaugeVec,
+    db_pool_connections: IntGaugeVec,
+    db_pool_healthy: IntGaugeVec,
     /// Outgoing RPC request metrics
     rpc_requests: HistogramVec,
     pool_cache_hits: IntCounter,
@@ -32,6 +35,8 @@ pub struct Metrics {
     auction_filtered_orders: IntGauge,
     auction_errored_price_estimates: IntCounter,
     auction_price_estimate_timeouts: IntCounter,
+    /// API requests rejected because of rate limits
+    api_rate_limited_requests: IntCounterVec,
 }
 
 impl Metrics {
@@ -44,6 +49,24 @@ impl Metrics {
         )?;
         registry.register(Box::new(db_table_row_count.clone()))?;
 
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new(
+                "db_pool_connections",
+                "Number of connections in a database pool by state.",
+            ),
+            &["pool", "state"],
+        )?;
+        registry.register(Box::new(db_pool_connections.clone()))?;
+
+        let db_pool_healthy = IntGaugeVec::new(
+            Opts::new(
+                "db_pool_healthy",
+                "Whether the last health check of a database pool succeeded.",
+            ),
+            &["pool"],
+        )?;
+        registry.register(Box::new(db_pool_healthy.clone()))?;
+
         let opts = HistogramOpts::new(
             "transport_requests",
             "RPC Request durations labelled by method",
@@ -122,8 +145,19 @@ impl Metrics {
         )?;
         registry.register(Box::new(auction_price_estimate_timeouts.clone()))?;
 
+        let api_rate_limited_requests = IntCounterVec::new(
+            Opts::new(
+                "api_rate_limited_requests",
+                "Number of API requests that were rejected because of a rate limit.",
+            ),
+            &["endpoint", "key"],
+        )?;
+        registry.register(Box::new(api_rate_limited_requests.clone()))?;
+
         Ok(Self {
             db_table_row_count,
+            db_pool_connections,
+            db_pool_healthy,
             rpc_requests,
             pool_cache_hits,
             pool_cache_misses,
@@ -137,6 +171,7 @@ impl Metrics {
             auction_filtered_orders,
             auction_errored_price_estimates,
             auction_price_estimate_timeouts,
+            api_rate_limited_requests,
         })
     }
 
@@ -145,6 +180,20 @@ impl Metrics {
             .with_label_values(&[table])
             .set(count);
     }
+
+    pub fn set_pool_status(&self, status: &PoolStatus) {
+        let idle = status.idle_connections as i64;
+        let active = status.connections as i64 - idle;
+        self.db_pool_connections
+            .with_label_values(&[status.name, "idle"])
+            .set(idle);
+        self.db_pool_connections
+            .with_label_values(&[status.name, "active"])
+            .set(active);
+        self.db_pool_healthy
+            .with_label_values(&[status.name])
+            .set(status.healthy as i64);
+    }
 }
 
 impl TransportMetrics for Metrics {
@@ -181,6 +230,14 @@ impl crate::solvable_orders::AuctionMetrics for Metrics {
     }
 }
 
+impl crate::api::rate_limiting::Metrics for Metrics {
+    fn request_rate_limited(&self, endpoint: &str, key: &str) {
+        self.api_rate_limited_requests
+            .with_label_values(&[endpoint, key])
+            .inc();
+    }
+}
+
 impl crate::database::instrumented::Metrics for Metrics {
     fn database_query_histogram(&self, label: &str) -> Histogram {
         self.database_queries.with_label_values(&[label])
@@ -241,3 +298,11 @@ pub struct NoopMetrics;
 impl crate::solvable_orders::AuctionMetrics for NoopMetrics {
     fn auction_updated(&self, _: u64, _: u64, _: u64, _: bool) {}
 }
+
+impl crate::api::rate_limiting::Metrics for NoopMetrics {
+    fn request_rate_limited(&self, _: &str, _: &str) {}
+}
+
+impl shared::price_estimation::native_price_cache::Metrics for NoopMetrics {
+    fn native_price_cache(&self, _: usize, _: usize) {}
+}
@@ -0,0 +1,44 @@
+use crate::database::Postgres;
+use anyhow::Result;
+use chrono::Utc;
+use shared::maintenance::Maintaining;
+use std::time::Duration;
+
+/// How many orders are archived at most per maintenance run so that a large backlog doesn't block
+/// the database for long.
+const BATCH_SIZE: u64 = 1000;
+
+/// Moves orders that can no longer be traded and their trades into the archive tables once they
+/// are older than the configured age.
+///
+/// Archived orders are still returned when querying a single order or trades but they no longer
+/// slow down the queries over all live orders.
+pub struct OrderArchiver {
+    database: Postgres,
+    archive_age: Duration,
+}
+
+impl OrderArchiver {
+    pub fn new(database: Postgres, archive_age: Duration) -> Self {
+        Self {
+            database,
+            archive_age,
+        }
+    }
+
+    async fn archive(&self) -> Result<()> {
+        let cutoff = Utc::now() - chrono::Duration::from_std(self.archive_age)?;
+        let archived = self.database.archive_orders(cutoff, BATCH_SIZE).await?;
+        if archived > 0 {
+            tracing::debug!("archived {} orders", archived);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Maintaining for OrderArchiver {
+    async fn run_maintenance(&self) -> Result<()> {
+        self.archive().await
+    }
+}
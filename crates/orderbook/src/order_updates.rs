@@ -0,0 +1,374 @@
+use crate::database::events::{contract_to_db_events, Event};
+use anyhow::Result;
+use contracts::gpv2_settlement::Event as ContractEvent;
+use ethcontract::Event as EthContractEvent;
+use model::{order::OrderUid, u256_decimal};
+use primitive_types::{H160, U256};
+use serde::Serialize;
+use shared::event_handling::{BlockNumber, EventIndex, EventStoring, MAX_REORG_BLOCK_COUNT};
+use std::{collections::HashMap, ops::RangeInclusive, sync::Arc};
+use tokio::sync::broadcast;
+
+/// How many updates are buffered per subscriber before it starts missing updates.
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// A change to an order that is pushed to subscribed API clients.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderUpdate {
+    pub uid: OrderUid,
+    pub owner: H160,
+    #[serde(flatten)]
+    pub kind: OrderUpdateKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum OrderUpdateKind {
+    Created,
+    Cancelled,
+    Presignature {
+        signed: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    Trade {
+        block_number: u64,
+        log_index: u64,
+        #[serde(with = "u256_decimal")]
+        sell_amount: U256,
+        #[serde(with = "u256_decimal")]
+        buy_amount: U256,
+        #[serde(with = "u256_decimal")]
+        fee_amount: U256,
+    },
+    Expired,
+}
+
+/// Broadcasts order updates to all current subscribers.
+pub struct OrderUpdates {
+    sender: broadcast::Sender<OrderUpdate>,
+}
+
+impl Default for OrderUpdates {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl OrderUpdates {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn notify(&self, uid: OrderUid, owner: H160, kind: OrderUpdateKind) {
+        // Sending only fails when nobody is subscribed which is fine.
+        let _ = self.sender.send(OrderUpdate { uid, owner, kind });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<OrderUpdate> {
+        self.sender.subscribe()
+    }
+}
+
+/// Wraps a settlement contract event store and emits order updates for the events that were
+/// successfully stored.
+///
+/// The event handler replaces the most recent blocks on every update so replaced events are
+/// compared with the events stored since the previous replacement and only new or changed events
+/// are emitted.
+///
+/// Only events of the last `MAX_REORG_BLOCK_COUNT` blocks can be replaced so older events are
+/// neither kept nor emitted. This also keeps the initial sync from emitting the whole history.
+pub struct OrderUpdateEventStore<Database> {
+    inner: Database,
+    order_updates: Arc<OrderUpdates>,
+    /// Recent events by block number and log index.
+    known_events: HashMap<(u64, u64), Event>,
+    /// The latest block of the most recent replacement.
+    latest_block: u64,
+}
+
+impl<Database> OrderUpdateEventStore<Database> {
+    pub fn new(inner: Database, order_updates: Arc<OrderUpdates>) -> Self {
+        Self {
+            inner,
+            order_updates,
+            known_events: HashMap::new(),
+            latest_block: 0,
+        }
+    }
+
+    fn replaced(&mut self, events: Vec<(EventIndex, Event)>, latest_block: u64) {
+        self.latest_block = latest_block;
+        let previous = std::mem::take(&mut self.known_events);
+        let events = self.recent(events);
+        self.notify(
+            events
+                .iter()
+                .filter(|(index, event)| previous.get(&key(index)) != Some(event)),
+        );
+        self.known_events = events
+            .into_iter()
+            .map(|(index, event)| (key(&index), event))
+            .collect();
+    }
+
+    fn appended(&mut self, events: Vec<(EventIndex, Event)>) {
+        let events = self.recent(events);
+        self.notify(&events);
+        self.known_events.extend(
+            events
+                .into_iter()
+                .map(|(index, event)| (key(&index), event)),
+        );
+    }
+
+    fn recent(&self, mut events: Vec<(EventIndex, Event)>) -> Vec<(EventIndex, Event)> {
+        let oldest_block = self.latest_block.saturating_sub(MAX_REORG_BLOCK_COUNT);
+        events.retain(|(index, _)| index.block_number >= oldest_block);
+        events
+    }
+
+    fn notify<'a>(&self, events: impl IntoIterator<Item = &'a (EventIndex, Event)>) {
+        for (index, event) in events {
+            let (uid, owner, kind) = match event {
+                Event::Trade(trade) => (
+                    trade.order_uid,
+                    trade.order_uid.parts().1,
+                    OrderUpdateKind::Trade {
+                        block_number: index.block_number,
+                        log_index: index.log_index,
+                        sell_amount: trade.sell_amount_including_fee,
+                        buy_amount: trade.buy_amount,
+                        fee_amount: trade.fee_amount,
+                    },
+                ),
+                Event::Invalidation(invalidation) => (
+                    invalidation.order_uid,
+                    invalidation.order_uid.parts().1,
+                    OrderUpdateKind::Cancelled,
+                ),
+                Event::PreSignature(presignature) => (
+                    presignature.order_uid,
+                    presignature.owner,
+                    OrderUpdateKind::Presignature {
+                        signed: presignature.signed,
+                    },
+                ),
+                Event::Settlement(_) => continue,
+            };
+            self.order_updates.notify(uid, owner, kind);
+        }
+    }
+}
+
+fn key(index: &EventIndex) -> (u64, u64) {
+    (index.block_number, index.log_index)
+}
+
+#[async_trait::async_trait]
+impl<Database> EventStoring<ContractEvent> for OrderUpdateEventStore<Database>
+where
+    Database: EventStoring<ContractEvent>,
+{
+    async fn replace_events(
+        &mut self,
+        events: Vec<EthContractEvent<ContractEvent>>,
+        range: RangeInclusive<BlockNumber>,
+    ) -> Result<()> {
+        let db_events = contract_to_db_events(events.clone())?;
+        let latest_block = range.end().to_u64();
+        self.inner.replace_events(events, range).await?;
+        self.replaced(db_events, latest_block);
+        Ok(())
+    }
+
+    async fn append_events(&mut self, events: Vec<EthContractEvent<ContractEvent>>) -> Result<()> {
+        let db_events = contract_to_db_events(events.clone())?;
+        self.inner.append_events(events).await?;
+        self.appended(db_events);
+        Ok(())
+    }
+
+    async fn last_event_block(&self) -> Result<u64> {
+        self.inner.last_event_block().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::events::{Invalidation, PreSignature, Settlement, Trade};
+    use model::order::OrderCreation;
+    use std::collections::HashSet;
+
+    #[test]
+    fn serialize_order_update() {
+        let update = OrderUpdate {
+            uid: OrderUid([0x11; 56]),
+            owner: H160([0x22; 20]),
+            kind: OrderUpdateKind::Trade {
+                block_number: 1,
+                log_index: 2,
+                sell_amount: 3.into(),
+                buy_amount: 4.into(),
+                fee_amount: 5.into(),
+            },
+        };
+        let expected = serde_json::json!({
+            "uid": OrderUid([0x11; 56]).to_string(),
+            "owner": "0x2222222222222222222222222222222222222222",
+            "type": "trade",
+            "blockNumber": 1,
+            "logIndex": 2,
+            "sellAmount": "3",
+            "buyAmount": "4",
+            "feeAmount": "5",
+        });
+        assert_eq!(serde_json::to_value(&update).unwrap(), expected);
+
+        let update = OrderUpdate {
+            kind: OrderUpdateKind::Expired,
+            ..update
+        };
+        assert_eq!(serde_json::to_value(&update).unwrap()["type"], "expired");
+    }
+
+    #[test]
+    fn notifies_subscribers_of_stored_events() {
+        let order_updates = Arc::new(OrderUpdates::default());
+        let mut receiver = order_updates.subscribe();
+        let store = OrderUpdateEventStore::new((), order_updates);
+
+        let owner = H160([0x22; 20]);
+        let uid = OrderCreation::default().uid(&Default::default(), &owner);
+        store.notify(&[
+            (
+                EventIndex::new(1, 0),
+                Event::Settlement(Settlement::default()),
+            ),
+            (
+                EventIndex::new(1, 1),
+                Event::Trade(Trade {
+                    order_uid: uid,
+                    sell_amount_including_fee: 3.into(),
+                    buy_amount: 4.into(),
+                    fee_amount: 5.into(),
+                }),
+            ),
+            (
+                EventIndex::new(2, 0),
+                Event::PreSignature(PreSignature {
+                    owner,
+                    order_uid: uid,
+                    signed: false,
+                }),
+            ),
+        ]);
+
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            OrderUpdate {
+                uid,
+                owner,
+                kind: OrderUpdateKind::Trade {
+                    block_number: 1,
+                    log_index: 1,
+                    sell_amount: 3.into(),
+                    buy_amount: 4.into(),
+                    fee_amount: 5.into(),
+                },
+            }
+        );
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            OrderUpdate {
+                uid,
+                owner,
+                kind: OrderUpdateKind::Presignature { signed: false },
+            }
+        );
+        assert!(receiver.try_recv().is_err());
+    }
+    #[test]
+    fn notifies_replaced_events_once() {
+        let order_updates = Arc::new(OrderUpdates::default());
+        let mut receiver = order_updates.subscribe();
+        let mut store = OrderUpdateEventStore::new((), order_updates);
+
+        let invalidation = |uid: u8| {
+            Event::Invalidation(Invalidation {
+                order_uid: OrderUid([uid; 56]),
+            })
+        };
+        let cancelled = |uid: u8| OrderUpdate {
+            uid: OrderUid([uid; 56]),
+            owner: OrderUid([uid; 56]).parts().1,
+            kind: OrderUpdateKind::Cancelled,
+        };
+
+        store.appended(vec![(EventIndex::new(1, 0), invalidation(1))]);
+        assert_eq!(receiver.try_recv().unwrap(), cancelled(1));
+
+        store.replaced(
+            vec![
+                (EventIndex::new(1, 0), invalidation(1)),
+                (EventIndex::new(2, 0), invalidation(2)),
+            ],
+            2,
+        );
+        assert_eq!(receiver.try_recv().unwrap(), cancelled(2));
+        assert!(receiver.try_recv().is_err());
+
+        // A reorg changed the event in block 2.
+        store.replaced(
+            vec![
+                (EventIndex::new(1, 0), invalidation(1)),
+                (EventIndex::new(2, 0), invalidation(3)),
+            ],
+            2,
+        );
+        assert_eq!(receiver.try_recv().unwrap(), cancelled(3));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn ignores_events_older_than_reorg_window() {
+        let order_updates = Arc::new(OrderUpdates::default());
+        let mut receiver = order_updates.subscribe();
+        let mut store = OrderUpdateEventStore::new((), order_updates);
+
+        let invalidation = |uid: u8| {
+            Event::Invalidation(Invalidation {
+                order_uid: OrderUid([uid; 56]),
+            })
+        };
+        let cancelled = |uid: u8| OrderUpdate {
+            uid: OrderUid([uid; 56]),
+            owner: OrderUid([uid; 56]).parts().1,
+            kind: OrderUpdateKind::Cancelled,
+        };
+
+        let latest_block = 100;
+        let oldest_block = latest_block - MAX_REORG_BLOCK_COUNT;
+        store.replaced(
+            vec![
+                (EventIndex::new(oldest_block - 1, 0), invalidation(1)),
+                (EventIndex::new(oldest_block, 0), invalidation(2)),
+            ],
+            latest_block,
+        );
+        store.appended(vec![
+            (EventIndex::new(1, 0), invalidation(3)),
+            (EventIndex::new(latest_block, 0), invalidation(4)),
+        ]);
+        assert_eq!(receiver.try_recv().unwrap(), cancelled(2));
+        assert_eq!(receiver.try_recv().unwrap(), cancelled(4));
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(
+            store.known_events.keys().copied().collect::<HashSet<_>>(),
+            HashSet::from([(oldest_block, 0), (latest_block, 0)])
+        );
+    }
+}
@@ -1,6 +1,14 @@
 use crate::{
-    api::order_validation::{OrderValidating, OrderValidator, ValidationError},
-    database::orders::{InsertionError, OrderFilter, OrderStoring},
+    api::{
+        order_validation::{OrderValidating, OrderValidator, ValidationError},
+        rate_limiting::RateLimits,
+    },
+    database::{
+        order_events::{OrderEvent, OrderEventKind, OrderEventStoring},
+        orders::{InsertionError, OrderFilter, OrderStoring},
+    },
+    order_updates::{OrderUpdate, OrderUpdateKind, OrderUpdates},
+    signature_validator::{SignatureCheck, SignatureValidating, SignatureValidationError},
     solvable_orders::{SolvableOrders, SolvableOrdersCache},
 };
 use anyhow::{ensure, Context, Result};
@@ -8,14 +16,21 @@ use chrono::Utc;
 use ethcontract::H256;
 use model::{
     auction::Auction,
-    order::{Order, OrderCancellation, OrderCreationPayload, OrderStatus, OrderUid},
-    signature::SigningScheme,
+    order::{
+        Order, OrderCancellation, OrderCancellations, OrderCreationPayload, OrderStatus, OrderUid,
+    },
+    signature::{hashed_eip712_message, Signature, SigningScheme},
     DomainSeparator,
 };
 use primitive_types::H160;
 use shared::{bad_token::BadTokenDetecting, metrics::LivenessChecking};
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 use thiserror::Error;
+use tokio::sync::broadcast;
 
 #[derive(Debug, Error)]
 pub enum AddOrderError {
@@ -25,6 +40,12 @@ pub enum AddOrderError {
     OrderValidation(ValidationError),
     #[error("unsupported signature kind")]
     UnsupportedSignature,
+    #[error("replaced order can not be cancelled: {0}")]
+    InvalidReplacement(OrderCancellationError),
+    #[error("orders in a batch can not replace other orders")]
+    UnsupportedReplacement,
+    #[error("rate limit per owner exceeded")]
+    RateLimited,
     #[error("database error: {0}")]
     Database(#[from] sqlx::Error),
 }
@@ -67,6 +88,15 @@ pub enum OrderCancellationError {
     Other(#[from] anyhow::Error),
 }
 
+impl From<SignatureValidationError> for OrderCancellationError {
+    fn from(err: SignatureValidationError) -> Self {
+        match err {
+            SignatureValidationError::Invalid => Self::InvalidSignature,
+            SignatureValidationError::Other(err) => Self::Other(err),
+        }
+    }
+}
+
 pub struct Orderbook {
     domain_separator: DomainSeparator,
     settlement_contract: H160,
@@ -76,6 +106,10 @@ pub struct Orderbook {
     solvable_orders: Arc<SolvableOrdersCache>,
     solvable_orders_max_update_age: Duration,
     order_validator: Arc<OrderValidator>,
+    signature_validator: Arc<dyn SignatureValidating>,
+    order_updates: Arc<OrderUpdates>,
+    order_events: Arc<dyn OrderEventStoring>,
+    rate_limits: Arc<RateLimits>,
 }
 
 impl Orderbook {
@@ -89,6 +123,10 @@ impl Orderbook {
         solvable_orders: Arc<SolvableOrdersCache>,
         solvable_orders_max_update_age: Duration,
         order_validator: Arc<OrderValidator>,
+        signature_validator: Arc<dyn SignatureValidating>,
+        order_updates: Arc<OrderUpdates>,
+        order_events: Arc<dyn OrderEventStoring>,
+        rate_limits: Arc<RateLimits>,
     ) -> Self {
         Self {
             domain_separator,
@@ -99,6 +137,10 @@ impl Orderbook {
             solvable_orders,
             solvable_orders_max_update_age,
             order_validator,
+            signature_validator,
+            order_updates,
+            order_events,
+            rate_limits,
         }
     }
 
@@ -107,75 +149,339 @@ impl Orderbook {
         payload: OrderCreationPayload,
     ) -> Result<OrderUid, AddOrderError> {
         let order_creation = payload.order_creation;
-        // Eventually we will support all Signature types and can remove this.
-        if !matches!(
-            (
-                order_creation.signature.scheme(),
-                self.enable_presign_orders
-            ),
-            (SigningScheme::Eip712 | SigningScheme::EthSign, _) | (SigningScheme::PreSign, true)
-        ) {
-            return Err(AddOrderError::UnsupportedSignature);
-        }
+        self.ensure_supported_signing_scheme(order_creation.signature.scheme())?;
 
         let (order, fee) = self
             .order_validator
             .validate_and_construct_order(
                 order_creation,
                 payload.from,
+                payload.quote_id,
                 &self.domain_separator,
                 self.settlement_contract,
             )
             .await?;
+        self.check_owner_rate_limit("create_order", &order)?;
 
-        self.database.insert_order(&order, fee).await?;
+        match payload.replaces {
+            Some(old_order) => {
+                self.ensure_replaceable(&old_order, order.metadata.owner)
+                    .await
+                    .map_err(AddOrderError::InvalidReplacement)?;
+                self.database.replace_order(&old_order, &order, fee).await?;
+                // The update we request below might take a while so make sure a solver can't
+                // fill the old order in the meantime.
+                self.solvable_orders.remove_order(&old_order);
+                self.order_updates.notify(
+                    old_order,
+                    order.metadata.owner,
+                    OrderUpdateKind::Cancelled,
+                );
+                self.store_cancellation_events(&[old_order]).await;
+            }
+            None => self.database.insert_order(&order, fee).await?,
+        }
         self.solvable_orders.request_update();
+        self.order_updates.notify(
+            order.metadata.uid,
+            order.metadata.owner,
+            OrderUpdateKind::Created,
+        );
 
         Ok(order.metadata.uid)
     }
 
+    /// Checks that the order an order creation wants to replace belongs to the same owner and
+    /// could be cancelled.
+    async fn ensure_replaceable(
+        &self,
+        uid: &OrderUid,
+        owner: H160,
+    ) -> Result<(), OrderCancellationError> {
+        let order = self
+            .database
+            .single_order_from_primary(uid)
+            .await?
+            .ok_or(OrderCancellationError::OrderNotFound)?;
+        if order.metadata.owner != owner {
+            return Err(OrderCancellationError::WrongOwner);
+        }
+        ensure_cancellable(&order)
+    }
+
+    /// Validates and inserts a batch of orders. Orders that fail validation are reported
+    /// individually while all valid orders are inserted in a single database transaction.
+    pub async fn add_orders(
+        &self,
+        payloads: Vec<OrderCreationPayload>,
+    ) -> Result<Vec<Result<OrderUid, AddOrderError>>, AddOrderError> {
+        let mut results = Vec::with_capacity(payloads.len());
+        let mut to_validate = Vec::new();
+        for payload in payloads {
+            // Replacements need their own transaction.
+            if payload.replaces.is_some() {
+                results.push(Err(AddOrderError::UnsupportedReplacement));
+                continue;
+            }
+            match self.ensure_supported_signing_scheme(payload.order_creation.signature.scheme()) {
+                Ok(()) => {
+                    to_validate.push((payload.order_creation, payload.from, payload.quote_id));
+                    results.push(Ok(None));
+                }
+                Err(err) => results.push(Err(err)),
+            }
+        }
+
+        let mut validated = self
+            .order_validator
+            .validate_and_construct_orders(
+                to_validate,
+                &self.domain_separator,
+                self.settlement_contract,
+            )
+            .await
+            .into_iter();
+        let mut uids = HashSet::new();
+        let mut orders = Vec::new();
+        for result in results.iter_mut().filter(|result| result.is_ok()) {
+            let (order, fee) = match validated.next().expect("one result per validated order") {
+                Ok(order) => order,
+                Err(err) => {
+                    *result = Err(err.into());
+                    continue;
+                }
+            };
+            if !uids.insert(order.metadata.uid) {
+                *result = Err(AddOrderError::DuplicatedOrder);
+                continue;
+            }
+            if let Err(err) = self.check_owner_rate_limit("create_orders", &order) {
+                *result = Err(err);
+                continue;
+            }
+            *result = Ok(Some(orders.len()));
+            orders.push((order, fee));
+        }
+
+        // Orders that already exist are skipped by the database and reported as duplicates.
+        let inserted = self.database.insert_orders(&orders).await?;
+
+        if inserted.contains(&true) {
+            self.solvable_orders.request_update();
+        }
+        for ((order, _), inserted) in orders.iter().zip(&inserted) {
+            if *inserted {
+                self.order_updates.notify(
+                    order.metadata.uid,
+                    order.metadata.owner,
+                    OrderUpdateKind::Created,
+                );
+            }
+        }
+
+        Ok(results
+            .into_iter()
+            .map(|result| {
+                let index = result?.expect("valid orders have an index");
+                if inserted[index] {
+                    Ok(orders[index].0.metadata.uid)
+                } else {
+                    Err(AddOrderError::DuplicatedOrder)
+                }
+            })
+            .collect())
+    }
+
+    /// Counts a validated order against the rate limit of its owner. Validation verified the owner
+    /// of signed orders but presigned orders can claim any owner until the presignature gets
+    /// indexed so they are not counted.
+    fn check_owner_rate_limit(&self, endpoint: &str, order: &Order) -> Result<(), AddOrderError> {
+        if order.creation.signature.scheme() == SigningScheme::PreSign {
+            return Ok(());
+        }
+        self.rate_limits
+            .check(endpoint, None, Some(order.metadata.owner))
+            .map_err(|_| AddOrderError::RateLimited)
+    }
+
+    fn ensure_supported_signing_scheme(
+        &self,
+        signing_scheme: SigningScheme,
+    ) -> Result<(), AddOrderError> {
+        // Eventually we will support all Signature types and can remove this.
+        if !matches!(
+            (signing_scheme, self.enable_presign_orders),
+            (
+                SigningScheme::Eip712 | SigningScheme::EthSign | SigningScheme::Eip1271,
+                _
+            ) | (SigningScheme::PreSign, true)
+        ) {
+            return Err(AddOrderError::UnsupportedSignature);
+        }
+        Ok(())
+    }
+
     pub async fn cancel_order(
         &self,
         cancellation: OrderCancellation,
     ) -> Result<(), OrderCancellationError> {
-        // TODO - Would like to use get_order_by_uid, but not implemented on self
-        let orders = self
-            .get_orders(&OrderFilter {
-                uid: Some(cancellation.order_uid),
-                ..Default::default()
-            })
-            .await?;
-        // Could be that order doesn't exist and is not fetched.
-        let order = orders
-            .first()
+        let order = self
+            .database
+            .single_order_from_primary(&cancellation.order_uid)
+            .await?
             .ok_or(OrderCancellationError::OrderNotFound)?;
 
-        match order.metadata.status {
-            OrderStatus::PresignaturePending => return Err(OrderCancellationError::OnChainOrder),
-            OrderStatus::Open if !order.creation.signature.scheme().is_ecdsa_scheme() => {
-                return Err(OrderCancellationError::OnChainOrder);
+        ensure_cancellable(&order)?;
+
+        match &cancellation.signature {
+            Signature::Eip1271(signature) => {
+                // Smart contract owners can't be recovered from the signature so we ask the owner
+                // contract of the order whether it accepts the cancellation.
+                let hash =
+                    hashed_eip712_message(&self.domain_separator, &cancellation.hash_struct());
+                self.signature_validator
+                    .validate_signature(order.metadata.owner, hash, signature)
+                    .await?;
+            }
+            _ => {
+                let signer = cancellation
+                    .validate(&self.domain_separator)
+                    .ok_or(OrderCancellationError::InvalidSignature)?;
+                if signer != order.metadata.owner {
+                    return Err(OrderCancellationError::WrongOwner);
+                };
             }
-            OrderStatus::Fulfilled => return Err(OrderCancellationError::OrderFullyExecuted),
-            OrderStatus::Cancelled => return Err(OrderCancellationError::AlreadyCancelled),
-            OrderStatus::Expired => return Err(OrderCancellationError::OrderExpired),
-            _ => {}
         }
 
-        let signer = cancellation
-            .validate(&self.domain_separator)
-            .ok_or(OrderCancellationError::InvalidSignature)?;
-        if signer != order.metadata.owner {
-            return Err(OrderCancellationError::WrongOwner);
-        };
-
         // order is already known to exist in DB at this point, and signer is
         // known to be correct!
         self.database
             .cancel_order(&order.metadata.uid, Utc::now())
             .await?;
+        self.order_updates.notify(
+            order.metadata.uid,
+            order.metadata.owner,
+            OrderUpdateKind::Cancelled,
+        );
+        self.store_cancellation_events(&[order.metadata.uid]).await;
         Ok(())
     }
 
+    /// Cancels multiple orders of the same owner with a single signature. Orders that can not be
+    /// cancelled are reported individually while all others are cancelled in a single database
+    /// transaction.
+    pub async fn cancel_orders(
+        &self,
+        cancellations: OrderCancellations,
+    ) -> Result<Vec<(OrderUid, Result<(), OrderCancellationError>)>, OrderCancellationError> {
+        let orders = self
+            .database
+            .orders_from_primary(&cancellations.order_uids)
+            .await?
+            .into_iter()
+            .map(|order| (order.metadata.uid, order))
+            .collect::<HashMap<_, _>>();
+        let authorized_owners = self
+            .authorized_owners(&cancellations, orders.values())
+            .await?;
+
+        let mut results = Vec::with_capacity(cancellations.order_uids.len());
+        let mut cancelled = HashSet::new();
+        for uid in cancellations.order_uids {
+            let result = match orders.get(&uid) {
+                None => Err(OrderCancellationError::OrderNotFound),
+                Some(order) if !authorized_owners.contains(&order.metadata.owner) => {
+                    Err(match cancellations.signature {
+                        Signature::Eip1271(_) => OrderCancellationError::InvalidSignature,
+                        _ => OrderCancellationError::WrongOwner,
+                    })
+                }
+                Some(order) => ensure_cancellable(order),
+            };
+            if result.is_ok() {
+                cancelled.insert(uid);
+            }
+            results.push((uid, result));
+        }
+
+        let uids = cancelled.into_iter().collect::<Vec<_>>();
+        self.database
+            .cancel_orders(uids.clone(), Utc::now())
+            .await?;
+        for uid in &uids {
+            self.order_updates
+                .notify(*uid, orders[uid].metadata.owner, OrderUpdateKind::Cancelled);
+        }
+        self.store_cancellation_events(&uids).await;
+        Ok(results)
+    }
+
+    /// The owners whose orders the cancellations are allowed to cancel. That is the signer for
+    /// ECDSA signatures and all owner contracts that accept EIP-1271 signatures.
+    async fn authorized_owners(
+        &self,
+        cancellations: &OrderCancellations,
+        orders: impl Iterator<Item = &Order>,
+    ) -> Result<HashSet<H160>, OrderCancellationError> {
+        let signature = match &cancellations.signature {
+            Signature::Eip1271(signature) => signature,
+            _ => {
+                let signer = cancellations
+                    .validate(&self.domain_separator)
+                    .ok_or(OrderCancellationError::InvalidSignature)?;
+                return Ok(HashSet::from([signer]));
+            }
+        };
+
+        // Smart contract owners can't be recovered from the signature so we ask every owner
+        // contract of the orders whether it accepts the cancellations.
+        let hash = hashed_eip712_message(&self.domain_separator, &cancellations.hash_struct());
+        let owners = orders
+            .map(|order| order.metadata.owner)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let checks = owners
+            .iter()
+            .map(|owner| SignatureCheck {
+                owner: *owner,
+                hash,
+                signature: signature.clone(),
+            })
+            .collect();
+        let results = self.signature_validator.validate_signatures(checks).await;
+        let mut authorized = HashSet::new();
+        for (owner, result) in owners.into_iter().zip(results) {
+            match result {
+                Ok(()) => {
+                    authorized.insert(owner);
+                }
+                Err(SignatureValidationError::Invalid) => (),
+                Err(SignatureValidationError::Other(err)) => return Err(err.into()),
+            }
+        }
+        Ok(authorized)
+    }
+
+    /// The cancellation already happened at this point and order events are only informational
+    /// so failing to store them is not an error.
+    async fn store_cancellation_events(&self, uids: &[OrderUid]) {
+        let timestamp = Utc::now();
+        let events = uids
+            .iter()
+            .map(|uid| {
+                let event = OrderEvent {
+                    timestamp,
+                    kind: OrderEventKind::Cancelled,
+                };
+                (*uid, event)
+            })
+            .collect::<Vec<_>>();
+        if let Err(err) = self.order_events.store_order_events(&events).await {
+            tracing::warn!(?err, "failed to store order cancellation events");
+        }
+    }
+
     pub async fn get_orders(&self, filter: &OrderFilter) -> Result<Vec<Order>> {
         let mut orders = self.database.orders(filter).await?;
         // This filter is deprecated so filtering solvable orders is a bit awkward but we'll support
@@ -207,6 +513,10 @@ impl Orderbook {
         Ok(Some(order))
     }
 
+    pub async fn get_order_events(&self, uid: &OrderUid) -> Result<Vec<OrderEvent>> {
+        self.order_events.order_events(uid).await
+    }
+
     pub async fn get_orders_for_tx(&self, hash: &H256) -> Result<Vec<Order>> {
         let mut orders = self.database.orders_for_tx(hash).await?;
         set_available_balances(orders.as_mut_slice(), &self.solvable_orders);
@@ -231,15 +541,20 @@ impl Orderbook {
         Ok(auction)
     }
 
+    pub fn subscribe_order_updates(&self) -> broadcast::Receiver<OrderUpdate> {
+        self.order_updates.subscribe()
+    }
+
     pub async fn get_user_orders(
         &self,
         owner: &H160,
+        after: Option<OrderUid>,
         offset: u64,
         limit: u64,
     ) -> Result<Vec<Order>> {
         let mut orders = self
             .database
-            .user_orders(owner, offset, Some(limit))
+            .user_orders(owner, after, offset, Some(limit))
             .await
             .context("get_user_orders error")?;
         set_available_balances(orders.as_mut_slice(), &self.solvable_orders);
@@ -280,6 +595,19 @@ fn set_available_balances(orders: &mut [Order], cache: &SolvableOrdersCache) {
     }
 }
 
+fn ensure_cancellable(order: &Order) -> Result<(), OrderCancellationError> {
+    match order.metadata.status {
+        OrderStatus::PresignaturePending => Err(OrderCancellationError::OnChainOrder),
+        OrderStatus::Open if !order.creation.signature.scheme().is_ecdsa_scheme() => {
+            Err(OrderCancellationError::OnChainOrder)
+        }
+        OrderStatus::Fulfilled => Err(OrderCancellationError::OrderFullyExecuted),
+        OrderStatus::Cancelled => Err(OrderCancellationError::AlreadyCancelled),
+        OrderStatus::Expired => Err(OrderCancellationError::OrderExpired),
+        _ => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
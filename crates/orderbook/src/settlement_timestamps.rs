@@ -0,0 +1,64 @@
+use crate::database::Postgres;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use ethcontract::{BlockId, BlockNumber};
+use shared::{maintenance::Maintaining, Web3};
+use std::convert::TryInto;
+
+/// How many blocks are looked up per maintenance run so that catching up on old settlements
+/// doesn't delay the other maintenance tasks.
+const BLOCKS_PER_UPDATE: u64 = 100;
+
+/// Stores the block timestamps of indexed settlements.
+///
+/// Events don't include the timestamp of their block so it is fetched separately for every block
+/// that contains a settlement.
+pub struct SettlementTimestampUpdater {
+    database: Postgres,
+    web3: Web3,
+}
+
+impl SettlementTimestampUpdater {
+    pub fn new(database: Postgres, web3: Web3) -> Self {
+        Self { database, web3 }
+    }
+
+    async fn update(&self) -> Result<()> {
+        let blocks = self
+            .database
+            .settlement_blocks_without_timestamp(BLOCKS_PER_UPDATE)
+            .await?;
+        for block_number in blocks {
+            let timestamp = self.block_timestamp(block_number).await?;
+            self.database
+                .set_settlement_block_timestamp(block_number, timestamp)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn block_timestamp(&self, block_number: u64) -> Result<DateTime<Utc>> {
+        let block = self
+            .web3
+            .eth()
+            .block(BlockId::Number(BlockNumber::Number(block_number.into())))
+            .await
+            .with_context(|| format!("failed to fetch block {}", block_number))?
+            .with_context(|| format!("block {} does not exist", block_number))?;
+        let timestamp: i64 = block
+            .timestamp
+            .try_into()
+            .map_err(|_| anyhow!("timestamp of block {} is too large", block_number))?;
+        Ok(DateTime::from_utc(
+            NaiveDateTime::from_timestamp(timestamp, 0),
+            Utc,
+        ))
+    }
+}
+
+#[async_trait::async_trait]
+impl Maintaining for SettlementTimestampUpdater {
+    async fn run_maintenance(&self) -> Result<()> {
+        self.update().await
+    }
+}
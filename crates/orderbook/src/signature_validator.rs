@@ -0,0 +1,127 @@
+use anyhow::Result;
+use contracts::ERC1271SignatureValidator;
+use ethcontract::{batch::CallBatch, errors::MethodError, Bytes};
+use hex_literal::hex;
+use primitive_types::H160;
+use shared::{ethcontract_error::EthcontractErrorType, transport::MAX_BATCH_SIZE, Web3};
+
+/// The value `isValidSignature` returns for valid signatures as specified in
+/// <https://eips.ethereum.org/EIPS/eip-1271>.
+pub const MAGIC_VALUE: [u8; 4] = hex!("1626ba7e");
+
+#[derive(Debug)]
+pub enum SignatureValidationError {
+    /// The owner contract did not return the magic value or reverted.
+    Invalid,
+    Other(anyhow::Error),
+}
+
+impl From<anyhow::Error> for SignatureValidationError {
+    fn from(err: anyhow::Error) -> Self {
+        Self::Other(err)
+    }
+}
+
+/// An EIP-1271 signature that should be verified by its owner contract.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignatureCheck {
+    pub owner: H160,
+    pub hash: [u8; 32],
+    pub signature: Vec<u8>,
+}
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait SignatureValidating: Send + Sync {
+    // Check that the owner contract considers the signature valid for the given EIP-712 message
+    // hash. Used for EIP-1271 signatures where the owner can not be recovered off-chain.
+    async fn validate_signature(
+        &self,
+        owner: H160,
+        hash: [u8; 32],
+        signature: &[u8],
+    ) -> Result<(), SignatureValidationError>;
+
+    /// Like `validate_signature` for many signatures at once. Returns the results in the order of
+    /// the checks.
+    async fn validate_signatures(
+        &self,
+        checks: Vec<SignatureCheck>,
+    ) -> Vec<Result<(), SignatureValidationError>>;
+}
+
+pub struct Web3SignatureValidator {
+    web3: Web3,
+}
+
+impl Web3SignatureValidator {
+    pub fn new(web3: Web3) -> Self {
+        Self { web3 }
+    }
+}
+
+#[async_trait::async_trait]
+impl SignatureValidating for Web3SignatureValidator {
+    async fn validate_signature(
+        &self,
+        owner: H160,
+        hash: [u8; 32],
+        signature: &[u8],
+    ) -> Result<(), SignatureValidationError> {
+        let instance = ERC1271SignatureValidator::at(&self.web3, owner);
+        let result = instance
+            .is_valid_signature(Bytes(hash), Bytes(signature.to_vec()))
+            .call()
+            .await;
+        validation_result(result)
+    }
+
+    async fn validate_signatures(
+        &self,
+        checks: Vec<SignatureCheck>,
+    ) -> Vec<Result<(), SignatureValidationError>> {
+        let mut batch = CallBatch::new(self.web3.transport());
+        let calls = checks
+            .into_iter()
+            .map(|check| {
+                ERC1271SignatureValidator::at(&self.web3, check.owner)
+                    .is_valid_signature(Bytes(check.hash), Bytes(check.signature))
+                    .batch_call(&mut batch)
+            })
+            .collect::<Vec<_>>();
+        batch.execute_all(MAX_BATCH_SIZE).await;
+        let mut results = Vec::with_capacity(calls.len());
+        for call in calls {
+            results.push(validation_result(call.await));
+        }
+        results
+    }
+}
+
+fn validation_result(
+    result: Result<Bytes<[u8; 4]>, MethodError>,
+) -> Result<(), SignatureValidationError> {
+    match result {
+        Ok(Bytes(magic_value)) if magic_value == MAGIC_VALUE => Ok(()),
+        Ok(_) => Err(SignatureValidationError::Invalid),
+        Err(err) => match EthcontractErrorType::classify(&err) {
+            // Reverts, invalid opcodes or undecodable return data all mean that the owner does not
+            // accept the signature (or is not a contract at all).
+            EthcontractErrorType::Contract => Err(SignatureValidationError::Invalid),
+            EthcontractErrorType::Node => Err(SignatureValidationError::Other(err.into())),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_value_is_is_valid_signature_selector() {
+        assert_eq!(
+            MAGIC_VALUE,
+            web3::signing::keccak256(b"isValidSignature(bytes32,bytes)")[..4]
+        );
+    }
+}
@@ -1,11 +1,22 @@
 use crate::{
+    access_lists::BannedUsers,
     account_balances::{BalanceFetching, Query},
-    database::orders::OrderStoring,
+    database::{
+        auctions::AuctionStoring,
+        order_events::{FilterReason, OrderEvent, OrderEventKind, OrderEventStoring},
+        orders::OrderStoring,
+    },
     orderbook::filter_unsupported_tokens,
+    signature_validator::{SignatureCheck, SignatureValidating, SignatureValidationError},
 };
 use anyhow::{Context as _, Result};
+use chrono::Utc;
 use futures::StreamExt;
-use model::{auction::Auction, order::Order};
+use model::{
+    auction::Auction,
+    order::{Order, OrderUid},
+    signature::Signature,
+};
 use primitive_types::{H160, U256};
 use shared::{
     bad_token::BadTokenDetecting, current_block::CurrentBlockStream, maintenance::Maintaining,
@@ -43,13 +54,20 @@ pub trait AuctionMetrics: Send + Sync + 'static {
 pub struct SolvableOrdersCache {
     min_order_validity_period: Duration,
     database: Arc<dyn OrderStoring>,
-    banned_users: HashSet<H160>,
+    banned_users: Arc<BannedUsers>,
     balance_fetcher: Arc<dyn BalanceFetching>,
     bad_token_detector: Arc<dyn BadTokenDetecting>,
+    signature_validator: Arc<dyn SignatureValidating>,
     notify: Notify,
     cache: Mutex<Inner>,
     native_price_estimator: Arc<dyn NativePriceEstimating>,
     auction_metrics: Arc<dyn AuctionMetrics>,
+    removed_orders: Mutex<HashSet<OrderUid>>,
+    order_events: Arc<dyn OrderEventStoring>,
+    /// Whether open orders were solvable or filtered in the last update so that we only store an
+    /// order event when this changes.
+    order_states: Mutex<HashMap<OrderUid, OrderEventKind>>,
+    auctions: Arc<dyn AuctionStoring>,
 }
 
 type Balances = HashMap<Query, U256>;
@@ -73,12 +91,15 @@ impl SolvableOrdersCache {
     pub fn new(
         min_order_validity_period: Duration,
         database: Arc<dyn OrderStoring>,
-        banned_users: HashSet<H160>,
+        banned_users: Arc<BannedUsers>,
         balance_fetcher: Arc<dyn BalanceFetching>,
         bad_token_detector: Arc<dyn BadTokenDetecting>,
+        signature_validator: Arc<dyn SignatureValidating>,
         current_block: CurrentBlockStream,
         native_price_estimator: Arc<dyn NativePriceEstimating>,
         auction_metrics: Arc<dyn AuctionMetrics>,
+        order_events: Arc<dyn OrderEventStoring>,
+        auctions: Arc<dyn AuctionStoring>,
     ) -> Arc<Self> {
         let self_ = Arc::new(Self {
             min_order_validity_period,
@@ -86,6 +107,7 @@ impl SolvableOrdersCache {
             banned_users,
             balance_fetcher,
             bad_token_detector,
+            signature_validator,
             notify: Default::default(),
             cache: Mutex::new(Inner {
                 orders: SolvableOrders {
@@ -96,6 +118,7 @@ impl SolvableOrdersCache {
                 },
                 balances: Default::default(),
                 auction: Auction {
+                    id: 0,
                     block: 0,
                     latest_settlement_block: 0,
                     orders: Default::default(),
@@ -104,6 +127,10 @@ impl SolvableOrdersCache {
             }),
             native_price_estimator,
             auction_metrics,
+            removed_orders: Default::default(),
+            order_events,
+            order_states: Default::default(),
+            auctions,
         });
         tokio::task::spawn(update_task(Arc::downgrade(&self_), current_block));
         self_
@@ -130,12 +157,39 @@ impl SolvableOrdersCache {
         self.notify.notify_one();
     }
 
+    /// Immediately removes an order that is no longer solvable from the cached orders and auction.
+    ///
+    /// The order is also kept out of an update that is already in progress and might have read
+    /// the order from the database before it became unsolvable.
+    pub fn remove_order(&self, uid: &OrderUid) {
+        self.removed_orders.lock().unwrap().insert(*uid);
+        let mut cache = self.cache.lock().unwrap();
+        cache
+            .orders
+            .orders
+            .retain(|order| order.metadata.uid != *uid);
+        cache
+            .auction
+            .orders
+            .retain(|order| order.metadata.uid != *uid);
+    }
+
     /// Manually update solvable orders. Usually called by the background updating task.
     pub async fn update(&self, block: u64) -> Result<()> {
         let min_valid_to = now_in_epoch_seconds() + self.min_order_validity_period.as_secs() as u32;
         let db_solvable_orders = self.database.solvable_orders(min_valid_to).await?;
-        let orders = filter_banned_user_orders(db_solvable_orders.orders, &self.banned_users);
+        let orders = filter_removed_orders(
+            db_solvable_orders.orders,
+            &mut self.removed_orders.lock().unwrap(),
+        );
+        let mut filtered_orders = FilteredOrders::new(&orders);
+        let orders = filter_banned_user_orders(orders, &self.banned_users.get());
+        filtered_orders.record(&orders, FilterReason::BannedUser);
         let orders = filter_unsupported_tokens(orders, self.bad_token_detector.as_ref()).await?;
+        filtered_orders.record(&orders, FilterReason::UnsupportedToken);
+        let orders =
+            filter_invalid_signature_orders(orders, self.signature_validator.as_ref()).await;
+        filtered_orders.record(&orders, FilterReason::InvalidSignature);
 
         // If we update due to an explicit notification we can reuse existing balances as they
         // cannot have changed.
@@ -167,6 +221,7 @@ impl SolvableOrdersCache {
         }
 
         let mut orders = solvable_orders(orders, &new_balances);
+        filtered_orders.record(&orders, FilterReason::InsufficientBalance);
         for order in &mut orders {
             let query = Query::from_order(order);
             order.metadata.available_balance = new_balances.get(&query).copied();
@@ -180,14 +235,28 @@ impl SolvableOrdersCache {
             self.auction_metrics.as_ref(),
         )
         .await;
+        filtered_orders.record(&orders, FilterReason::MissingNativePrice);
+        self.store_order_state_changes(filtered_orders.into_states(&orders))
+            .await;
+        let id = self
+            .auctions
+            .next_auction_id()
+            .await
+            .context("next_auction_id")?;
+        let mut cache = self.cache.lock().unwrap();
+        // Orders that were removed while this update was in progress must not be written back.
+        let orders = {
+            let removed = self.removed_orders.lock().unwrap();
+            filter_removed_orders_(orders, &removed)
+        };
         let auction = Auction {
+            id,
             block,
             latest_settlement_block: db_solvable_orders.latest_settlement_block,
             orders: orders.clone(),
             prices,
         };
-
-        *self.cache.lock().unwrap() = Inner {
+        *cache = Inner {
             orders: SolvableOrders {
                 orders,
                 update_time: Instant::now(),
@@ -200,6 +269,98 @@ impl SolvableOrdersCache {
 
         Ok(())
     }
+
+    /// Stores events for the orders whose state differs from the last update.
+    async fn store_order_state_changes(&self, states: HashMap<OrderUid, OrderEventKind>) {
+        let timestamp = Utc::now();
+        let events = changed_order_states(&mut self.order_states.lock().unwrap(), states)
+            .into_iter()
+            .map(|(uid, kind)| (uid, OrderEvent { timestamp, kind }))
+            .collect::<Vec<_>>();
+        self.store_order_events(&events).await;
+    }
+
+    /// Order events are only informational so failing to store them doesn't fail the update.
+    async fn store_order_events(&self, events: &[(OrderUid, OrderEvent)]) {
+        if events.is_empty() {
+            return;
+        }
+        if let Err(err) = self.order_events.store_order_events(events).await {
+            tracing::warn!(?err, "failed to store order events");
+        }
+    }
+}
+
+/// Remembers which filtering step removed which orders from the solvable orders.
+struct FilteredOrders {
+    remaining: HashSet<OrderUid>,
+    filtered: HashMap<OrderUid, FilterReason>,
+}
+
+impl FilteredOrders {
+    fn new(orders: &[Order]) -> Self {
+        Self {
+            remaining: order_uids(orders),
+            filtered: Default::default(),
+        }
+    }
+
+    /// Records the orders that were removed since the last call as filtered for the reason.
+    fn record(&mut self, orders: &[Order], reason: FilterReason) {
+        let remaining = order_uids(orders);
+        for uid in self.remaining.difference(&remaining) {
+            self.filtered.insert(*uid, reason);
+        }
+        self.remaining = remaining;
+    }
+
+    /// The state of every order that was considered in this update.
+    fn into_states(self, solvable_orders: &[Order]) -> HashMap<OrderUid, OrderEventKind> {
+        self.filtered
+            .into_iter()
+            .map(|(uid, reason)| (uid, OrderEventKind::Filtered { reason }))
+            .chain(
+                solvable_orders
+                    .iter()
+                    .map(|order| (order.metadata.uid, OrderEventKind::Solvable)),
+            )
+            .collect()
+    }
+}
+
+fn order_uids(orders: &[Order]) -> HashSet<OrderUid> {
+    orders.iter().map(|order| order.metadata.uid).collect()
+}
+
+/// Replaces the previous order states with the current ones and returns the states that changed.
+/// Orders that are no longer open are forgotten.
+fn changed_order_states(
+    previous: &mut HashMap<OrderUid, OrderEventKind>,
+    current: HashMap<OrderUid, OrderEventKind>,
+) -> Vec<(OrderUid, OrderEventKind)> {
+    let changed = current
+        .iter()
+        .filter(|(uid, state)| previous.get(*uid) != Some(*state))
+        .map(|(uid, state)| (*uid, *state))
+        .collect();
+    *previous = current;
+    changed
+}
+
+/// Filters orders that were explicitly removed from the cache. Removed orders that the database no
+/// longer returns don't need to be filtered anymore and are forgotten.
+fn filter_removed_orders(orders: Vec<Order>, removed: &mut HashSet<OrderUid>) -> Vec<Order> {
+    if removed.is_empty() {
+        return orders;
+    }
+    let uids = order_uids(&orders);
+    removed.retain(|uid| uids.contains(uid));
+    filter_removed_orders_(orders, removed)
+}
+
+fn filter_removed_orders_(mut orders: Vec<Order>, removed: &HashSet<OrderUid>) -> Vec<Order> {
+    orders.retain(|order| !removed.contains(&order.metadata.uid));
+    orders
 }
 
 /// Filters all orders whose owners are in the set of "banned" users.
@@ -208,6 +369,63 @@ fn filter_banned_user_orders(mut orders: Vec<Order>, banned_users: &HashSet<H160
     orders
 }
 
+/// Filters EIP-1271 orders whose signatures are no longer accepted by the owner contract. Smart
+/// contract wallets can revoke signatures at any time so we need to check them on every update.
+/// All signatures are checked with batched calls.
+async fn filter_invalid_signature_orders(
+    mut orders: Vec<Order>,
+    signature_validator: &dyn SignatureValidating,
+) -> Vec<Order> {
+    let checks = orders
+        .iter()
+        .filter_map(|order| match &order.creation.signature {
+            Signature::Eip1271(signature) => {
+                // The first 32 bytes of the order uid are the EIP-712 message hash that was signed.
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(&order.metadata.uid.0[..32]);
+                Some(SignatureCheck {
+                    owner: order.metadata.owner,
+                    hash,
+                    signature: signature.clone(),
+                })
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if checks.is_empty() {
+        return orders;
+    }
+
+    let mut results = signature_validator
+        .validate_signatures(checks)
+        .await
+        .into_iter();
+    orders.retain(|order| {
+        if !matches!(order.creation.signature, Signature::Eip1271(_)) {
+            return true;
+        }
+        match results.next().expect("one result per check") {
+            Ok(()) => true,
+            Err(SignatureValidationError::Invalid) => {
+                tracing::debug!(
+                    order_uid = ?order.metadata.uid,
+                    "filtered order because of invalid eip-1271 signature",
+                );
+                false
+            }
+            Err(SignatureValidationError::Other(err)) => {
+                tracing::warn!(
+                    order_uid = ?order.metadata.uid,
+                    ?err,
+                    "failed to validate eip-1271 signature",
+                );
+                false
+            }
+        }
+    });
+    orders
+}
+
 /// Returns existing balances and Vec of queries that need to be peformed.
 fn new_balances(old_balances: &Balances, orders: &[Order]) -> (HashMap<Query, U256>, Vec<Query>) {
     let mut new_balances = HashMap::new();
@@ -432,8 +650,10 @@ fn to_normalized_price(price: f64) -> Option<U256> {
 mod tests {
     use super::*;
     use crate::{
-        account_balances::MockBalanceFetching, database::orders::MockOrderStoring,
+        account_balances::MockBalanceFetching, database::auctions::MockAuctionStoring,
+        database::order_events::MockOrderEventStoring, database::orders::MockOrderStoring,
         database::orders::SolvableOrders as DbOrders, metrics::NoopMetrics,
+        signature_validator::MockSignatureValidating,
     };
     use chrono::{DateTime, NaiveDateTime, Utc};
     use futures::StreamExt;
@@ -568,15 +788,30 @@ mod tests {
             futures::stream::iter(std::iter::repeat(Ok(1.0)).take(a.len()).enumerate()).boxed()
         });
 
+        let mut order_events = MockOrderEventStoring::new();
+        order_events
+            .expect_store_order_events()
+            .returning(|_| Ok(()));
+
+        let mut auctions = MockAuctionStoring::new();
+        let mut next_id = 0;
+        auctions.expect_next_auction_id().returning(move || {
+            next_id += 1;
+            Ok(next_id)
+        });
+
         let cache = SolvableOrdersCache::new(
             Duration::from_secs(0),
             Arc::new(order_storing),
             Default::default(),
             Arc::new(balance_fetcher),
             Arc::new(bad_token_detector),
+            Arc::new(MockSignatureValidating::new()),
             receiver,
             Arc::new(native),
             Arc::new(NoopMetrics),
+            Arc::new(order_events),
+            Arc::new(auctions),
         );
 
         cache.update(0).await.unwrap();
@@ -590,6 +825,7 @@ mod tests {
         assert_eq!(orders_[0].metadata.available_balance, Some(1.into()));
         let auction = cache.cached_auction().0;
         assert_eq!(auction.orders.len(), 1);
+        assert_eq!(auction.id, 1);
 
         cache.update(0).await.unwrap();
         assert_eq!(
@@ -604,6 +840,7 @@ mod tests {
         assert_eq!(orders_.len(), 2);
         let auction = cache.cached_auction().0;
         assert_eq!(auction.orders.len(), 2);
+        assert_eq!(auction.id, 2);
 
         cache.update(0).await.unwrap();
         assert_eq!(cache.cached_balance(&Query::from_order(&orders[0])), None,);
@@ -864,4 +1101,193 @@ mod tests {
             [H160([1; 20]), H160([1; 20]), H160([2; 20]), H160([3; 20])],
         );
     }
+
+    #[test]
+    fn records_why_orders_were_filtered() {
+        let order = |uid: u32| Order {
+            metadata: OrderMetadata {
+                uid: OrderUid::from_integer(uid),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut filtered_orders = FilteredOrders::new(&[order(0), order(1), order(2)]);
+        filtered_orders.record(&[order(0), order(2)], FilterReason::BannedUser);
+        filtered_orders.record(&[order(0)], FilterReason::InsufficientBalance);
+
+        assert_eq!(
+            filtered_orders.into_states(&[order(0)]),
+            hashmap! {
+                OrderUid::from_integer(0) => OrderEventKind::Solvable,
+                OrderUid::from_integer(1) => OrderEventKind::Filtered {
+                    reason: FilterReason::BannedUser,
+                },
+                OrderUid::from_integer(2) => OrderEventKind::Filtered {
+                    reason: FilterReason::InsufficientBalance,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn only_reports_changed_order_states() {
+        let uid = OrderUid::from_integer;
+        let filtered = OrderEventKind::Filtered {
+            reason: FilterReason::MissingNativePrice,
+        };
+        let mut states = HashMap::new();
+
+        let changed = changed_order_states(
+            &mut states,
+            hashmap! { uid(0) => OrderEventKind::Solvable, uid(1) => filtered },
+        );
+        assert_eq!(
+            changed.into_iter().collect::<HashMap<_, _>>(),
+            hashmap! { uid(0) => OrderEventKind::Solvable, uid(1) => filtered }
+        );
+
+        let changed = changed_order_states(
+            &mut states,
+            hashmap! { uid(0) => OrderEventKind::Solvable, uid(1) => OrderEventKind::Solvable },
+        );
+        assert_eq!(changed, [(uid(1), OrderEventKind::Solvable)]);
+
+        // Order 0 is no longer open so it is forgotten and reported again if it reappears.
+        let changed = changed_order_states(&mut states, hashmap! { uid(1) => filtered });
+        assert_eq!(changed, [(uid(1), filtered)]);
+        let changed =
+            changed_order_states(&mut states, hashmap! { uid(0) => OrderEventKind::Solvable });
+        assert_eq!(changed, [(uid(0), OrderEventKind::Solvable)]);
+    }
+
+    #[test]
+    fn filters_removed_orders() {
+        let order = |uid: u32| Order {
+            metadata: OrderMetadata {
+                uid: OrderUid::from_integer(uid),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut removed = hashset!(OrderUid::from_integer(1), OrderUid::from_integer(3));
+
+        let filtered_orders =
+            filter_removed_orders(vec![order(0), order(1), order(2)], &mut removed);
+        assert_eq!(filtered_orders, [order(0), order(2)]);
+        // Order 3 is not returned by the database anymore so it doesn't need to be remembered.
+        assert_eq!(removed, hashset!(OrderUid::from_integer(1)));
+
+        let filtered_orders = filter_removed_orders(vec![order(0)], &mut removed);
+        assert_eq!(filtered_orders, [order(0)]);
+        assert!(removed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn update_does_not_write_back_orders_removed_during_update() {
+        let order = Order {
+            creation: OrderCreation {
+                sell_token_balance: SellTokenSource::Erc20,
+                ..Default::default()
+            },
+            metadata: OrderMetadata {
+                uid: OrderUid::from_integer(1),
+                ..Default::default()
+            },
+        };
+
+        let mut order_storing = MockOrderStoring::new();
+        order_storing.expect_solvable_orders().return_once({
+            let order = order.clone();
+            move |_| {
+                Ok(DbOrders {
+                    orders: vec![order],
+                    latest_settlement_block: 0,
+                })
+            }
+        });
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(|queries| vec![Ok(U256::MAX); queries.len()]);
+        let mut native = MockNativePriceEstimating::new();
+        native.expect_estimate_native_prices().returning(|a| {
+            futures::stream::iter(std::iter::repeat(Ok(1.0)).take(a.len()).enumerate()).boxed()
+        });
+        let mut order_events = MockOrderEventStoring::new();
+        order_events
+            .expect_store_order_events()
+            .returning(|_| Ok(()));
+
+        // The order gets removed after the update read it from the database but before the update
+        // writes the cache.
+        let cache_slot = Arc::new(Mutex::new(Weak::<SolvableOrdersCache>::new()));
+        let mut auctions = MockAuctionStoring::new();
+        auctions.expect_next_auction_id().returning({
+            let cache_slot = cache_slot.clone();
+            move || {
+                let cache = cache_slot.lock().unwrap().upgrade().unwrap();
+                cache.remove_order(&OrderUid::from_integer(1));
+                Ok(1)
+            }
+        });
+
+        let (_, receiver) = tokio::sync::watch::channel(Default::default());
+        let cache = SolvableOrdersCache::new(
+            Duration::from_secs(0),
+            Arc::new(order_storing),
+            Default::default(),
+            Arc::new(balance_fetcher),
+            Arc::new(shared::bad_token::list_based::ListBasedDetector::deny_list(
+                Vec::new(),
+            )),
+            Arc::new(MockSignatureValidating::new()),
+            receiver,
+            Arc::new(native),
+            Arc::new(NoopMetrics),
+            Arc::new(order_events),
+            Arc::new(auctions),
+        );
+        *cache_slot.lock().unwrap() = Arc::downgrade(&cache);
+
+        cache.update(0).await.unwrap();
+        assert!(cache.cached_solvable_orders().orders.is_empty());
+        assert!(cache.cached_auction().0.orders.is_empty());
+    }
+
+    #[tokio::test]
+    async fn filters_revoked_eip1271_signatures() {
+        let valid_owner = H160([1; 20]);
+        let revoked_owner = H160([2; 20]);
+        let orders = vec![
+            OrderBuilder::default().build(),
+            OrderBuilder::default()
+                .with_eip1271(valid_owner, vec![1])
+                .build(),
+            OrderBuilder::default()
+                .with_eip1271(revoked_owner, vec![2])
+                .build(),
+        ];
+
+        let mut signature_validator = MockSignatureValidating::new();
+        signature_validator
+            .expect_validate_signatures()
+            .times(1)
+            .returning(move |checks| {
+                assert_eq!(checks.len(), 2);
+                checks
+                    .iter()
+                    .map(|check| {
+                        if check.owner == valid_owner {
+                            Ok(())
+                        } else {
+                            Err(SignatureValidationError::Invalid)
+                        }
+                    })
+                    .collect()
+            });
+
+        let filtered_orders =
+            filter_invalid_signature_orders(orders.clone(), &signature_validator).await;
+        assert_eq!(filtered_orders, orders[..2]);
+    }
 }
@@ -1,6 +1,7 @@
 use super::{BadTokenDetecting, TokenQuality};
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use primitive_types::H160;
+use std::sync::RwLock;
 
 /// If a token is neither in the allow nor the deny list treat it this way.
 pub enum UnknownTokenStrategy {
@@ -11,9 +12,26 @@ pub enum UnknownTokenStrategy {
 
 /// Classify tokens with explicit allow and deny lists.
 pub struct ListBasedDetector {
+    lists: RwLock<Lists>,
+    strategy: UnknownTokenStrategy,
+}
+
+struct Lists {
     allow_list: Vec<H160>,
     deny_list: Vec<H160>,
-    strategy: UnknownTokenStrategy,
+}
+
+impl Lists {
+    fn new(allow_list: Vec<H160>, deny_list: Vec<H160>) -> Result<Self> {
+        ensure!(
+            allow_list.iter().all(|token| !deny_list.contains(token)),
+            "token is allowed and denied"
+        );
+        Ok(Self {
+            allow_list,
+            deny_list,
+        })
+    }
 }
 
 impl ListBasedDetector {
@@ -23,37 +41,39 @@ impl ListBasedDetector {
         deny_list: Vec<H160>,
         strategy: UnknownTokenStrategy,
     ) -> Self {
-        assert!(
-            allow_list.iter().all(|token| !deny_list.contains(token)),
-            "token is allowed and denied"
-        );
         Self {
-            allow_list,
-            deny_list,
+            lists: RwLock::new(Lists::new(allow_list, deny_list).unwrap()),
             strategy,
         }
     }
 
     pub fn deny_list(list: Vec<H160>) -> Self {
-        Self {
-            allow_list: Vec::new(),
-            deny_list: list,
-            strategy: UnknownTokenStrategy::Allow,
-        }
+        Self::new(Vec::new(), list, UnknownTokenStrategy::Allow)
+    }
+
+    /// Replaces the allow and deny lists. Fails and keeps the current lists if the same token is
+    /// both allowed and denied.
+    pub fn set_lists(&self, allow_list: Vec<H160>, deny_list: Vec<H160>) -> Result<()> {
+        let lists = Lists::new(allow_list, deny_list)?;
+        *self.lists.write().unwrap() = lists;
+        Ok(())
     }
 }
 
 #[async_trait::async_trait]
 impl BadTokenDetecting for ListBasedDetector {
     async fn detect(&self, token: ethcontract::H160) -> Result<TokenQuality> {
-        if self.allow_list.contains(&token) {
-            return Ok(TokenQuality::Good);
-        }
-
-        if self.deny_list.contains(&token) {
-            return Ok(TokenQuality::Bad {
-                reason: "deny listed".to_string(),
-            });
+        {
+            let lists = self.lists.read().unwrap();
+            if lists.allow_list.contains(&token) {
+                return Ok(TokenQuality::Good);
+            }
+
+            if lists.deny_list.contains(&token) {
+                return Ok(TokenQuality::Bad {
+                    reason: "deny listed".to_string(),
+                });
+            }
         }
 
         match &self.strategy {
@@ -76,11 +96,11 @@ mod tests {
     fn uses_lists() {
         // Would panic if used.
         let inner = MockBadTokenDetecting::new();
-        let detector = ListBasedDetector {
-            allow_list: vec![H160::from_low_u64_le(0)],
-            deny_list: vec![H160::from_low_u64_le(1)],
-            strategy: UnknownTokenStrategy::Forward(Box::new(inner)),
-        };
+        let detector = ListBasedDetector::new(
+            vec![H160::from_low_u64_le(0)],
+            vec![H160::from_low_u64_le(1)],
+            UnknownTokenStrategy::Forward(Box::new(inner)),
+        );
 
         let result = detector
             .detect(H160::from_low_u64_le(0))
@@ -97,22 +117,14 @@ mod tests {
 
     #[test]
     fn not_in_list_default() {
-        let detector = ListBasedDetector {
-            allow_list: Vec::new(),
-            deny_list: Vec::new(),
-            strategy: UnknownTokenStrategy::Allow,
-        };
+        let detector = ListBasedDetector::new(Vec::new(), Vec::new(), UnknownTokenStrategy::Allow);
         let result = detector
             .detect(H160::from_low_u64_le(0))
             .now_or_never()
             .unwrap();
         assert!(result.unwrap().is_good());
 
-        let detector = ListBasedDetector {
-            allow_list: Vec::new(),
-            deny_list: Vec::new(),
-            strategy: UnknownTokenStrategy::Deny,
-        };
+        let detector = ListBasedDetector::new(Vec::new(), Vec::new(), UnknownTokenStrategy::Deny);
         let result = detector
             .detect(H160::from_low_u64_le(0))
             .now_or_never()
@@ -128,11 +140,11 @@ mod tests {
             .times(1)
             .returning(|_| Ok(TokenQuality::Good));
 
-        let detector = ListBasedDetector {
-            allow_list: Vec::new(),
-            deny_list: Vec::new(),
-            strategy: UnknownTokenStrategy::Forward(Box::new(inner)),
-        };
+        let detector = ListBasedDetector::new(
+            Vec::new(),
+            Vec::new(),
+            UnknownTokenStrategy::Forward(Box::new(inner)),
+        );
 
         let result = detector
             .detect(H160::from_low_u64_le(0))
@@ -140,4 +152,26 @@ mod tests {
             .unwrap();
         assert!(result.unwrap().is_good());
     }
+
+    #[test]
+    fn updates_lists() {
+        let token = H160::from_low_u64_le(0);
+        let detector = ListBasedDetector::deny_list(Vec::new());
+        let is_good = |detector: &ListBasedDetector| {
+            detector
+                .detect(token)
+                .now_or_never()
+                .unwrap()
+                .unwrap()
+                .is_good()
+        };
+        assert!(is_good(&detector));
+
+        detector.set_lists(Vec::new(), vec![token]).unwrap();
+        assert!(!is_good(&detector));
+
+        // Conflicting lists are rejected and the previous lists stay in place.
+        assert!(detector.set_lists(vec![token], vec![token]).is_err());
+        assert!(!is_good(&detector));
+    }
 }
@@ -5,6 +5,7 @@ pub mod trace_call;
 
 use anyhow::Result;
 use primitive_types::H160;
+use std::sync::Arc;
 
 /// How well behaved a token is.
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -31,3 +32,10 @@ impl TokenQuality {
 pub trait BadTokenDetecting: Send + Sync {
     async fn detect(&self, token: H160) -> Result<TokenQuality>;
 }
+
+#[async_trait::async_trait]
+impl<T: BadTokenDetecting> BadTokenDetecting for Arc<T> {
+    async fn detect(&self, token: H160) -> Result<TokenQuality> {
+        self.as_ref().detect(token).await
+    }
+}
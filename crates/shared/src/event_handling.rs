@@ -38,7 +38,8 @@ pub trait EventStoring<T>: Send + Sync {
     ///
     /// # Arguments
     /// * `events` the contract events to be replaced by the implementer
-    /// * `range` indicates a particular range of blocks on which to operate.
+    /// * `range` indicates a particular range of blocks on which to operate. A `Latest` end
+    ///   includes all blocks after the start.
     async fn replace_events(
         &mut self,
         events: Vec<EthcontractEvent<T>>,
@@ -165,6 +166,24 @@ where
         Ok(())
     }
 
+    /// The events of a historical block range as they are currently on chain.
+    ///
+    /// Unlike `update_events` this neither stores the events nor touches the last handled block.
+    /// All events of the range are collected at once so callers should keep the range small.
+    pub async fn events_in_block_range(
+        &self,
+        blocks: RangeInclusive<u64>,
+    ) -> Result<Vec<EthcontractEvent<C::Event>>> {
+        let range = BlockNumber::Specific(*blocks.start())..=BlockNumber::Specific(*blocks.end());
+        tracing::debug!("fetching events in block range {:?}", range);
+        self.past_events(&range)
+            .await
+            .context("failed to get past events")?
+            .try_collect::<Vec<_>>()
+            .await
+            .context("failed to get events")
+    }
+
     async fn past_events(
         &self,
         block_range: &RangeInclusive<BlockNumber>,
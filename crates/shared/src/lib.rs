@@ -22,6 +22,7 @@ pub mod request_sharing;
 pub mod solver_utils;
 pub mod sources;
 pub mod subgraph;
+pub mod surplus;
 pub mod time;
 pub mod token_info;
 pub mod token_list;
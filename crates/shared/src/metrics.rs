@@ -54,6 +54,17 @@ pub trait LivenessChecking: Send + Sync {
     async fn is_alive(&self) -> bool;
 }
 
+/// Alive if all of the checks are alive.
+#[async_trait::async_trait]
+impl LivenessChecking for Vec<Arc<dyn LivenessChecking>> {
+    async fn is_alive(&self) -> bool {
+        futures::future::join_all(self.iter().map(|check| check.is_alive()))
+            .await
+            .into_iter()
+            .all(|alive| alive)
+    }
+}
+
 pub fn serve_metrics(liveness: Arc<dyn LivenessChecking>, address: SocketAddr) -> JoinHandle<()> {
     let filter = handle_metrics().or(handle_liveness(liveness));
     tracing::info!(%address, "serving metrics");
@@ -131,6 +131,36 @@ pub trait PriceEstimating: Send + Sync + 'static {
     /// Returns one result for each query in arbitrary order. The usize is the index into the queries slice.
     fn estimates<'a>(&'a self, queries: &'a [Query])
         -> BoxStream<'_, (usize, PriceEstimateResult)>;
+
+    /// Like `estimates` but also returns the name of the estimator that produced each result if
+    /// the implementation knows it.
+    fn estimates_with_estimator<'a>(
+        &'a self,
+        queries: &'a [Query],
+    ) -> BoxStream<'_, (usize, PriceEstimateResult, Option<String>)> {
+        self.estimates(queries)
+            .map(|(index, result)| (index, result, None))
+            .boxed()
+    }
+}
+
+impl<T> PriceEstimating for std::sync::Arc<T>
+where
+    T: PriceEstimating + ?Sized,
+{
+    fn estimates<'a>(
+        &'a self,
+        queries: &'a [Query],
+    ) -> BoxStream<'_, (usize, PriceEstimateResult)> {
+        self.as_ref().estimates(queries)
+    }
+
+    fn estimates_with_estimator<'a>(
+        &'a self,
+        queries: &'a [Query],
+    ) -> BoxStream<'_, (usize, PriceEstimateResult, Option<String>)> {
+        self.as_ref().estimates_with_estimator(queries)
+    }
 }
 
 /// Use a PriceEstimating with a single query.
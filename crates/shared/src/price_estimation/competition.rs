@@ -1,12 +1,18 @@
 use crate::{
     metrics,
     price_estimation::{
-        Estimate, PriceEstimateResult, PriceEstimating, PriceEstimationError, Query,
+        single_estimate, Estimate, PriceEstimateResult, PriceEstimating, PriceEstimationError,
+        Query,
     },
 };
 use futures::stream::StreamExt;
 use model::order::OrderKind;
-use std::{cmp::Ordering, num::NonZeroUsize, sync::Arc};
+use std::{
+    cmp::Ordering,
+    num::NonZeroUsize,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 /// Price estimator that pulls estimates from various sources
 /// and competes on the best price. Returns a price estimation
@@ -30,11 +36,74 @@ impl RacingCompetitionPriceEstimator {
     }
 }
 
+/// The result of a single estimator of a competition.
+#[derive(Debug)]
+pub struct EstimatorResult {
+    pub estimator: String,
+    pub result: PriceEstimateResult,
+    pub elapsed: Duration,
+}
+
+/// The results of all estimators of a competition for one query.
+#[derive(Debug)]
+pub struct CompetitionBreakdown {
+    pub results: Vec<EstimatorResult>,
+    /// Index into `results` of the result that wins the competition.
+    pub winner: usize,
+}
+
+impl CompetitionBreakdown {
+    /// The result that wins the competition.
+    pub fn into_winner(mut self) -> PriceEstimateResult {
+        self.results.swap_remove(self.winner).result
+    }
+}
+
+impl RacingCompetitionPriceEstimator {
+    /// Waits for the results of all estimators instead of returning early and reports each of them
+    /// individually together with the winner. This is a regular competition so the winner counts
+    /// towards the won queries metric.
+    pub async fn breakdown(&self, query: &Query) -> CompetitionBreakdown {
+        let results =
+            futures::future::join_all(self.inner.iter().map(|(estimator, instance)| async move {
+                let start = Instant::now();
+                let result = single_estimate(instance.as_ref(), query).await;
+                EstimatorResult {
+                    estimator: estimator.clone(),
+                    result,
+                    elapsed: start.elapsed(),
+                }
+            }))
+            .await;
+        // Unwrap because there is at least one estimator.
+        let winner = best_result(query, results.iter().map(|result| &result.result)).unwrap();
+        let EstimatorResult {
+            estimator, result, ..
+        } = &results[winner];
+        tracing::debug!(?query, ?result, %estimator, "winning price estimate");
+        metrics()
+            .queries_won
+            .with_label_values(&[estimator.as_str(), query.kind.label()])
+            .inc();
+        CompetitionBreakdown { results, winner }
+    }
+}
+
 impl PriceEstimating for RacingCompetitionPriceEstimator {
     fn estimates<'a>(
         &'a self,
         queries: &'a [Query],
     ) -> futures::stream::BoxStream<'_, (usize, PriceEstimateResult)> {
+        self.estimates_with_estimator(queries)
+            .map(|(index, result, _)| (index, result))
+            .boxed()
+    }
+
+    /// Returns the name of the winning estimator with every result.
+    fn estimates_with_estimator<'a>(
+        &'a self,
+        queries: &'a [Query],
+    ) -> futures::stream::BoxStream<'_, (usize, PriceEstimateResult, Option<String>)> {
         debug_assert!(queries.iter().all(|query| {
             query.buy_token != model::order::BUY_ETH_ADDRESS
                 && query.sell_token != model::order::BUY_ETH_ADDRESS
@@ -78,7 +147,7 @@ impl PriceEstimating for RacingCompetitionPriceEstimator {
                 .with_label_values(&[estimator, query.kind.label()])
                 .inc();
 
-            Some((query_index, result))
+            Some((query_index, result, Some(estimator.to_string())))
         };
 
         combined_stream
@@ -106,6 +175,12 @@ impl CompetitionPriceEstimator {
     }
 }
 
+impl CompetitionPriceEstimator {
+    pub async fn breakdown(&self, query: &Query) -> CompetitionBreakdown {
+        self.inner.breakdown(query).await
+    }
+}
+
 impl PriceEstimating for CompetitionPriceEstimator {
     fn estimates<'a>(
         &'a self,
@@ -113,6 +188,13 @@ impl PriceEstimating for CompetitionPriceEstimator {
     ) -> futures::stream::BoxStream<'_, (usize, PriceEstimateResult)> {
         self.inner.estimates(queries)
     }
+
+    fn estimates_with_estimator<'a>(
+        &'a self,
+        queries: &'a [Query],
+    ) -> futures::stream::BoxStream<'_, (usize, PriceEstimateResult, Option<String>)> {
+        self.inner.estimates_with_estimator(queries)
+    }
 }
 
 fn best_result<'a>(
@@ -408,4 +490,89 @@ mod tests {
         assert_eq!(i, 0);
         assert_eq!(result.as_ref().unwrap(), &estimate(0));
     }
+
+    #[tokio::test]
+    async fn breakdown_reports_every_estimator() {
+        fn estimator(result: PriceEstimateResult) -> Arc<dyn PriceEstimating> {
+            let mut estimator = MockPriceEstimating::new();
+            estimator
+                .expect_estimates()
+                .returning(move |_| futures::stream::iter([(0, result.clone())]).boxed());
+            Arc::new(estimator)
+        }
+        let estimator = CompetitionPriceEstimator::new(vec![
+            (
+                "low".to_owned(),
+                estimator(Ok(Estimate {
+                    out_amount: 1.into(),
+                    gas: 10,
+                })),
+            ),
+            (
+                "high".to_owned(),
+                estimator(Ok(Estimate {
+                    out_amount: 2.into(),
+                    gas: 20,
+                })),
+            ),
+            (
+                "failing".to_owned(),
+                estimator(Err(PriceEstimationError::NoLiquidity)),
+            ),
+        ]);
+        let query = Query {
+            sell_token: H160::from_low_u64_be(1),
+            buy_token: H160::from_low_u64_be(2),
+            in_amount: 1.into(),
+            kind: OrderKind::Sell,
+        };
+
+        let breakdown = estimator.breakdown(&query).await;
+        let estimators: Vec<_> = breakdown
+            .results
+            .iter()
+            .map(|result| result.estimator.as_str())
+            .collect();
+        assert_eq!(estimators, ["low", "high", "failing"]);
+        assert_eq!(breakdown.results[1].result.as_ref().unwrap().gas, 20);
+        assert!(matches!(
+            breakdown.results[2].result,
+            Err(PriceEstimationError::NoLiquidity)
+        ));
+        assert_eq!(breakdown.winner, 1);
+        assert_eq!(breakdown.into_winner().unwrap().out_amount, 2.into());
+    }
+
+    #[tokio::test]
+    async fn reports_winning_estimator() {
+        fn estimator(out_amount: u64) -> Arc<dyn PriceEstimating> {
+            let mut estimator = MockPriceEstimating::new();
+            estimator.expect_estimates().returning(move |_| {
+                let estimate = Estimate {
+                    out_amount: out_amount.into(),
+                    gas: 0,
+                };
+                futures::stream::iter([(0, Ok(estimate))]).boxed()
+            });
+            Arc::new(estimator)
+        }
+        let estimator = CompetitionPriceEstimator::new(vec![
+            ("low".to_owned(), estimator(1)),
+            ("high".to_owned(), estimator(2)),
+        ]);
+        let query = Query {
+            sell_token: H160::from_low_u64_be(1),
+            buy_token: H160::from_low_u64_be(2),
+            in_amount: 1.into(),
+            kind: OrderKind::Sell,
+        };
+
+        let results = estimator
+            .estimates_with_estimator(std::slice::from_ref(&query))
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.as_ref().unwrap().out_amount, 2.into());
+        assert_eq!(results[0].2.as_deref(), Some("high"));
+    }
 }
@@ -17,6 +17,17 @@ pub trait NativePriceEstimating: Send + Sync {
         &'a self,
         tokens: &'a [H160],
     ) -> BoxStream<'_, (usize, NativePriceEstimateResult)>;
+
+    /// Like `estimate_native_prices` but also returns the name of the estimator that produced each
+    /// price if the implementation knows it.
+    fn estimate_native_prices_with_estimator<'a>(
+        &'a self,
+        tokens: &'a [H160],
+    ) -> BoxStream<'_, (usize, NativePriceEstimateResult, Option<String>)> {
+        self.estimate_native_prices(tokens)
+            .map(|(index, result)| (index, result, None))
+            .boxed()
+    }
 }
 
 /// Wrapper around price estimators specialized to estimate a token's price compared to the current
@@ -66,6 +77,21 @@ impl NativePriceEstimating for NativePriceEstimator {
         });
         stream.boxed()
     }
+
+    fn estimate_native_prices_with_estimator<'a>(
+        &'a self,
+        tokens: &'a [H160],
+    ) -> BoxStream<'_, (usize, NativePriceEstimateResult, Option<String>)> {
+        let stream = async_stream::stream!({
+            let queries: Vec<_> = tokens.iter().map(|token| self.query(token)).collect();
+            let mut inner = self.inner.estimates_with_estimator(&queries);
+            while let Some((i, result, estimator)) = inner.next().await {
+                let result = result.map(|estimate| estimate.price_in_buy_token_f64(&queries[i]));
+                yield (i, result, estimator)
+            }
+        });
+        stream.boxed()
+    }
 }
 
 pub async fn native_single_estimate(
@@ -80,6 +106,19 @@ pub async fn native_single_estimate(
         .1
 }
 
+/// Like `native_single_estimate` but also returns the name of the estimator that produced the price.
+pub async fn native_single_estimate_with_estimator(
+    estimator: &dyn NativePriceEstimating,
+    token: &H160,
+) -> (NativePriceEstimateResult, Option<String>) {
+    let (_, result, estimator) = estimator
+        .estimate_native_prices_with_estimator(std::slice::from_ref(token))
+        .next()
+        .await
+        .unwrap();
+    (result, estimator)
+}
+
 pub async fn native_vec_estimates(
     estimator: &dyn NativePriceEstimating,
     queries: &[H160],
@@ -18,9 +18,19 @@ impl Metrics for NoopMetrics {
     fn native_price_cache(&self, _: usize, _: usize) {}
 }
 
+/// A cached native price and how long ago it was estimated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CachedNativePrice {
+    pub price: f64,
+    pub age: Duration,
+    /// The name of the estimator that produced the price if it is known.
+    pub estimator: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 struct CachedPrice {
     price: f64,
+    estimator: Option<String>,
     updated_at: Instant,
     requested_at: Instant,
 }
@@ -40,21 +50,24 @@ impl Inner {
     ) -> impl Stream<Item = (usize, NativePriceEstimateResult)> + 'a {
         debug_assert!(!tokens.is_empty());
         self.estimator
-            .estimate_native_prices(tokens)
-            .inspect(|(i, result)| {
+            .estimate_native_prices_with_estimator(tokens)
+            .map(|(i, result, estimator)| {
                 if let Ok(price) = result {
-                    let token = &tokens[*i];
+                    let token = &tokens[i];
                     let now = Instant::now();
                     let mut cache = self.cache.lock().unwrap();
                     let mut entry = cache.entry(*token).or_insert_with(|| CachedPrice {
-                        price: *price,
+                        price,
+                        estimator: None,
                         updated_at: now,
                         requested_at: now,
                     });
                     entry.updated_at = now;
                     entry.requested_at = now;
-                    entry.price = *price;
+                    entry.price = price;
+                    entry.estimator = estimator;
                 }
+                (i, result)
             })
     }
 
@@ -77,6 +90,14 @@ impl Inner {
                 _ => Either::Right(i),
             })
     }
+
+    fn remove_outdated_prices(&self) {
+        let now = Instant::now();
+        self.cache
+            .lock()
+            .unwrap()
+            .retain(|_, cached| now.saturating_duration_since(cached.updated_at) < self.max_age);
+    }
 }
 
 /// Wrapper around `Box<dyn PriceEstimating>` which caches successful price estimates for some time
@@ -112,6 +133,26 @@ impl CachingNativePriceEstimator {
             PREFETCH_TIME,
         ));
     }
+
+    /// Spawns a background task removing outdated prices once per `interval`.
+    ///
+    /// For caches without a maintenance task, which only keep prices for `max_age`, so that they
+    /// don't grow with every token that was ever requested.
+    pub fn spawn_pruning_task(&self, interval: Duration) {
+        tokio::spawn(remove_outdated_prices(Arc::downgrade(&self.0), interval));
+    }
+
+    /// Returns the cached price of the token if it is not outdated without estimating it.
+    pub fn get_cached_price(&self, token: &H160) -> Option<CachedNativePrice> {
+        let cache = self.0.cache.lock().unwrap();
+        let entry = cache.get(token)?;
+        let age = Instant::now().saturating_duration_since(entry.updated_at);
+        (age < self.0.max_age).then(|| CachedNativePrice {
+            price: entry.price,
+            age,
+            estimator: entry.estimator.clone(),
+        })
+    }
 }
 
 #[async_trait::async_trait]
@@ -184,6 +225,15 @@ async fn update_recently_used_outdated_prices(
     }
 }
 
+async fn remove_outdated_prices(inner: Weak<Inner>, interval: Duration) {
+    while let Some(inner) = inner.upgrade() {
+        inner.remove_outdated_prices();
+        // Don't keep the cache alive while sleeping.
+        drop(inner);
+        tokio::time::sleep(interval).await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -198,12 +248,12 @@ mod tests {
     async fn caches_successful_estimates() {
         let mut inner = MockNativePriceEstimating::new();
         inner
-            .expect_estimate_native_prices()
+            .expect_estimate_native_prices_with_estimator()
             .times(1)
             .returning(move |tokens| {
                 assert_eq!(tokens.len(), 1);
                 assert!(tokens[0] == token(0));
-                futures::stream::iter([(0, Ok(1.0))]).boxed()
+                futures::stream::iter([(0, Ok(1.0), None)]).boxed()
             });
 
         let estimator = CachingNativePriceEstimator::new(
@@ -222,15 +272,76 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn returns_cached_price_until_outdated() {
+        let mut inner = MockNativePriceEstimating::new();
+        inner
+            .expect_estimate_native_prices_with_estimator()
+            .times(1)
+            .returning(|_| {
+                futures::stream::iter([(0, Ok(1.0), Some("estimator".to_string()))]).boxed()
+            });
+
+        let estimator = CachingNativePriceEstimator::new(
+            Box::new(inner),
+            Duration::from_millis(30),
+            Arc::new(NoopMetrics),
+        );
+        assert_eq!(estimator.get_cached_price(&token(0)), None);
+
+        estimator
+            .estimate_native_prices(&[token(0)])
+            .collect::<Vec<_>>()
+            .await;
+        let cached = estimator.get_cached_price(&token(0)).unwrap();
+        assert!(cached.price.to_i64().unwrap() == 1);
+        assert!(cached.age < Duration::from_millis(30));
+        assert_eq!(cached.estimator.as_deref(), Some("estimator"));
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(estimator.get_cached_price(&token(0)), None);
+    }
+
+    #[tokio::test]
+    async fn removes_outdated_prices() {
+        let mut inner = MockNativePriceEstimating::new();
+        inner
+            .expect_estimate_native_prices_with_estimator()
+            .times(2)
+            .returning(|tokens| {
+                futures::stream::iter((0..tokens.len()).map(|i| (i, Ok(1.0), None))).boxed()
+            });
+
+        let estimator = CachingNativePriceEstimator::new(
+            Box::new(inner),
+            Duration::from_millis(30),
+            Arc::new(NoopMetrics),
+        );
+        estimator
+            .estimate_native_prices(&[token(0)])
+            .collect::<Vec<_>>()
+            .await;
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        estimator
+            .estimate_native_prices(&[token(1)])
+            .collect::<Vec<_>>()
+            .await;
+
+        estimator.0.remove_outdated_prices();
+        let cache = estimator.0.cache.lock().unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains_key(&token(1)));
+    }
+
     #[tokio::test]
     async fn does_not_cache_failed_estimates() {
         let mut inner = MockNativePriceEstimating::new();
         inner
-            .expect_estimate_native_prices()
+            .expect_estimate_native_prices_with_estimator()
             .times(10)
             .returning(move |tokens| {
                 assert_eq!(tokens.len(), 1);
-                futures::stream::iter([(0, Err(PriceEstimationError::NoLiquidity))]).boxed()
+                futures::stream::iter([(0, Err(PriceEstimationError::NoLiquidity), None)]).boxed()
             });
 
         let estimator = CachingNativePriceEstimator::new(
@@ -256,39 +367,39 @@ mod tests {
         let mut inner = MockNativePriceEstimating::new();
         // first request from user
         inner
-            .expect_estimate_native_prices()
+            .expect_estimate_native_prices_with_estimator()
             .times(1)
             .returning(move |tokens| {
                 assert_eq!(tokens.len(), 1);
                 assert!(tokens[0] == token(0));
-                futures::stream::iter([(0, Ok(1.0))]).boxed()
+                futures::stream::iter([(0, Ok(1.0), None)]).boxed()
             });
         // second request from user
         inner
-            .expect_estimate_native_prices()
+            .expect_estimate_native_prices_with_estimator()
             .times(1)
             .returning(move |tokens| {
                 assert_eq!(tokens.len(), 1);
                 assert!(tokens[0] == token(1));
-                futures::stream::iter([(0, Ok(2.0))]).boxed()
+                futures::stream::iter([(0, Ok(2.0), None)]).boxed()
             });
         // maintenance task updates n=1 outdated prices
         inner
-            .expect_estimate_native_prices()
+            .expect_estimate_native_prices_with_estimator()
             .times(1)
             .returning(move |tokens| {
                 assert_eq!(tokens.len(), 1);
                 assert!(tokens[0] == token(1));
-                futures::stream::iter([(0, Ok(4.0))]).boxed()
+                futures::stream::iter([(0, Ok(4.0), None)]).boxed()
             });
         // user requested something which has been skipped by the maintenance task
         inner
-            .expect_estimate_native_prices()
+            .expect_estimate_native_prices_with_estimator()
             .times(1)
             .returning(move |tokens| {
                 assert_eq!(tokens.len(), 1);
                 assert!(tokens[0] == token(0));
-                futures::stream::iter([(0, Ok(3.0))]).boxed()
+                futures::stream::iter([(0, Ok(3.0), None)]).boxed()
             });
 
         let estimator = CachingNativePriceEstimator::new(
@@ -337,19 +448,19 @@ mod tests {
     async fn maintenance_can_update_all_old_queries() {
         let mut inner = MockNativePriceEstimating::new();
         inner
-            .expect_estimate_native_prices()
+            .expect_estimate_native_prices_with_estimator()
             .times(1)
             .returning(move |tokens| {
                 assert_eq!(tokens.len(), 10);
-                futures::stream::iter(std::iter::repeat(Ok(1.0)).enumerate().take(10)).boxed()
+                futures::stream::iter((0..10).map(|i| (i, Ok(1.0), None))).boxed()
             });
         // background task updates all outdated prices
         inner
-            .expect_estimate_native_prices()
+            .expect_estimate_native_prices_with_estimator()
             .times(1)
             .returning(move |tokens| {
                 assert_eq!(tokens.len(), 10);
-                futures::stream::iter(std::iter::repeat(Ok(2.0)).enumerate().take(10)).boxed()
+                futures::stream::iter((0..10).map(|i| (i, Ok(2.0), None))).boxed()
             });
 
         let estimator = CachingNativePriceEstimator::new(
@@ -6,7 +6,7 @@ use crate::{
     },
 };
 use anyhow::anyhow;
-use futures::StreamExt;
+use futures::{stream::BoxStream, StreamExt};
 use model::order::BUY_ETH_ADDRESS;
 use primitive_types::H160;
 use std::{
@@ -115,18 +115,19 @@ impl SanitizedPriceEstimator {
 
         results
     }
-}
 
-impl PriceEstimating for SanitizedPriceEstimator {
-    fn estimates<'a>(
+    /// Trivial estimates have no estimator. The names of the estimators of the other estimates are
+    /// only requested from the inner estimator if `with_estimator` is set.
+    fn sanitized_estimates<'a>(
         &'a self,
         queries: &'a [Query],
-    ) -> futures::stream::BoxStream<'_, (usize, super::PriceEstimateResult)> {
+        with_estimator: bool,
+    ) -> BoxStream<'a, (usize, PriceEstimateResult, Option<String>)> {
         let stream = async_stream::stream! {
             // Handle easy estimates first.
             let mut queries: Vec<(usize, Query)> = queries.iter().copied().enumerate().collect();
-            for easy in self.estimate_easy_queries(&mut queries).await {
-                yield easy;
+            for (index, result) in self.estimate_easy_queries(&mut queries).await {
+                yield (index, result, None);
             }
 
             // The remaining queries are difficult and need to be forwarded to the inner estimator. Some
@@ -170,9 +171,16 @@ impl PriceEstimating for SanitizedPriceEstimator {
 
             let inner_queries: Vec<Query> =
                 difficult_queries.iter().map(|query| query.query).collect();
-            let mut stream = self.inner.estimates(&inner_queries);
+            let mut stream = if with_estimator {
+                self.inner.estimates_with_estimator(&inner_queries)
+            } else {
+                self.inner
+                    .estimates(&inner_queries)
+                    .map(|(i, estimate)| (i, estimate, None))
+                    .boxed()
+            };
 
-            while let Some((i, mut estimate)) = stream.next().await {
+            while let Some((i, mut estimate, estimator)) = stream.next().await {
                 let query = &difficult_queries[i];
                 if let Some(Modification::AddGas(gas)) = query.modification {
                     if let Ok(estimate) = &mut estimate {
@@ -182,7 +190,7 @@ impl PriceEstimating for SanitizedPriceEstimator {
                                 let err = PriceEstimationError::Other(anyhow!(
                                     "cost of converting native asset would overflow gas price"
                                 ));
-                                yield (query.original_query_index, Err(err));
+                                yield (query.original_query_index, Err(err), estimator);
                                 continue;
                             }
                         };
@@ -193,13 +201,31 @@ impl PriceEstimating for SanitizedPriceEstimator {
                         );
                     }
                 }
-                yield (query.original_query_index, estimate);
+                yield (query.original_query_index, estimate, estimator);
             }
         };
         stream.boxed()
     }
 }
 
+impl PriceEstimating for SanitizedPriceEstimator {
+    fn estimates<'a>(
+        &'a self,
+        queries: &'a [Query],
+    ) -> BoxStream<'_, (usize, PriceEstimateResult)> {
+        self.sanitized_estimates(queries, false)
+            .map(|(index, result, _)| (index, result))
+            .boxed()
+    }
+
+    fn estimates_with_estimator<'a>(
+        &'a self,
+        queries: &'a [Query],
+    ) -> BoxStream<'_, (usize, PriceEstimateResult, Option<String>)> {
+        self.sanitized_estimates(queries, true)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,153 @@
+use num::{BigRational, Signed as _, Zero as _};
+
+// The difference between what you were willing to sell (executed_amount * limit_price)
+// converted into reference token (multiplied by buy_token_price)
+// and what you had to sell denominated in the reference token (executed_amount * buy_token_price)
+pub fn buy_order_surplus(
+    sell_token_price: &BigRational,
+    buy_token_price: &BigRational,
+    sell_amount_limit: &BigRational,
+    buy_amount_limit: &BigRational,
+    executed_buy_amount: &BigRational,
+) -> Option<BigRational> {
+    if buy_amount_limit.is_zero() {
+        return None;
+    }
+    let limit_sell_amount = executed_buy_amount * sell_amount_limit / buy_amount_limit;
+    let res = (limit_sell_amount * sell_token_price) - (executed_buy_amount * buy_token_price);
+    if res.is_negative() {
+        None
+    } else {
+        Some(res)
+    }
+}
+
+// The difference of your proceeds denominated in the reference token (executed_sell_amount * sell_token_price)
+// and what you were minimally willing to receive in buy tokens (executed_sell_amount * limit_price)
+// converted to amount in reference token at the effective price (multiplied by buy_token_price)
+pub fn sell_order_surplus(
+    sell_token_price: &BigRational,
+    buy_token_price: &BigRational,
+    sell_amount_limit: &BigRational,
+    buy_amount_limit: &BigRational,
+    executed_sell_amount: &BigRational,
+) -> Option<BigRational> {
+    if sell_amount_limit.is_zero() {
+        return None;
+    }
+    let limit_buy_amount = executed_sell_amount * buy_amount_limit / sell_amount_limit;
+    let res = (executed_sell_amount * sell_token_price) - (limit_buy_amount * buy_token_price);
+    if res.is_negative() {
+        None
+    } else {
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num::FromPrimitive as _;
+
+    fn r(u: u128) -> BigRational {
+        BigRational::from_u128(u).unwrap()
+    }
+
+    #[test]
+    #[allow(clippy::just_underscores_and_digits)]
+    fn test_buy_order_surplus() {
+        // Two goods are worth the same (100 each). If we were willing to pay up to 60 to receive 50,
+        // but ended paying the price (1) we have a surplus of 10 sell units, so a total surplus of 1000.
+        assert_eq!(
+            buy_order_surplus(&r(100), &r(100), &r(60), &r(50), &r(50)),
+            Some(r(1000))
+        );
+
+        // If our trade got only half filled, we only get half the surplus
+        assert_eq!(
+            buy_order_surplus(&r(100), &r(100), &r(60), &r(50), &r(25)),
+            Some(r(500))
+        );
+
+        // No surplus if trade is not at all filled
+        assert_eq!(
+            buy_order_surplus(&r(100), &r(100), &r(60), &r(50), &r(0)),
+            Some(r(0))
+        );
+
+        // No surplus if trade is filled at limit
+        assert_eq!(
+            buy_order_surplus(&r(100), &r(100), &r(50), &r(50), &r(50)),
+            Some(r(0))
+        );
+
+        // Arithmetic error when limit price not respected
+        assert_eq!(
+            buy_order_surplus(&r(100), &r(100), &r(40), &r(50), &r(50)),
+            None
+        );
+
+        // Sell Token worth twice as much as buy token. If we were willing to sell at parity, we will
+        // have a surplus of 50% of tokens, worth 200 each.
+        assert_eq!(
+            buy_order_surplus(&r(200), &r(100), &r(50), &r(50), &r(50)),
+            Some(r(5000))
+        );
+
+        // Buy Token worth twice as much as sell token. If we were willing to sell at 3:1, we will
+        // have a surplus of 20 sell tokens, worth 100 each.
+        assert_eq!(
+            buy_order_surplus(&r(100), &r(200), &r(60), &r(20), &r(20)),
+            Some(r(2000))
+        );
+    }
+
+    #[test]
+    #[allow(clippy::just_underscores_and_digits)]
+    fn test_sell_order_surplus() {
+        // Two goods are worth the same (100 each). If we were willing to receive as little as 40,
+        // but ended paying the price (1) we have a surplus of 10 bought units, so a total surplus of 1000.
+        assert_eq!(
+            sell_order_surplus(&r(100), &r(100), &r(50), &r(40), &r(50)),
+            Some(r(1000))
+        );
+
+        // If our trade got only half filled, we only get half the surplus
+        assert_eq!(
+            sell_order_surplus(&r(100), &r(100), &r(50), &r(40), &r(25)),
+            Some(r(500))
+        );
+
+        // No surplus if trade is not at all filled
+        assert_eq!(
+            sell_order_surplus(&r(100), &r(100), &r(50), &r(40), &r(0)),
+            Some(r(0))
+        );
+
+        // No surplus if trade is filled at limit
+        assert_eq!(
+            sell_order_surplus(&r(100), &r(100), &r(50), &r(50), &r(50)),
+            Some(r(0))
+        );
+
+        // Arithmetic error when limit price not respected
+        assert_eq!(
+            sell_order_surplus(&r(100), &r(100), &r(50), &r(60), &r(50)),
+            None
+        );
+
+        // Sell token worth twice as much as buy token. If we were willing to buy at parity, we will
+        // have a surplus of 100% of buy tokens, worth 100 each.
+        assert_eq!(
+            sell_order_surplus(&r(200), &r(100), &r(50), &r(50), &r(50)),
+            Some(r(5000))
+        );
+
+        // Buy Token worth twice as much as sell token. If we were willing to sell at 3:1, we will
+        // have a surplus of 10 buy tokens, worth 200 each.
+        assert_eq!(
+            sell_order_surplus(&r(100), &r(200), &r(60), &r(20), &r(60)),
+            Some(r(2000))
+        );
+    }
+}
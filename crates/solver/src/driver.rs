@@ -19,10 +19,12 @@ use contracts::GPv2Settlement;
 use futures::future::join_all;
 use gas_estimation::{EstimatedGasPrice, GasPriceEstimating};
 use itertools::{Either, Itertools};
+use model::solver_competition::{Objective, SolverCompetition, SolverSettlement};
 use num::{rational::Ratio, BigInt, BigRational, ToPrimitive};
 use primitive_types::{H160, H256};
 use rand::prelude::SliceRandom;
 use shared::{
+    conversions::U256Ext as _,
     current_block::{self, CurrentBlockStream},
     recent_block_cache::Block,
     token_list::TokenList,
@@ -50,7 +52,7 @@ pub struct Driver {
     market_makable_token_list: Option<TokenList>,
     block_stream: CurrentBlockStream,
     solution_submitter: SolutionSubmitter,
-    solve_id: u64,
+    run_id: u64,
     max_settlements_per_solver: usize,
     api: OrderBookApi,
     order_converter: OrderConverter,
@@ -113,7 +115,7 @@ impl Driver {
             market_makable_token_list,
             block_stream,
             solution_submitter,
-            solve_id: 0,
+            run_id: 0,
             max_settlements_per_solver,
             api,
             order_converter,
@@ -408,6 +410,7 @@ impl Driver {
             current_block::block_number(&self.block_stream.borrow())?;
 
         let mut auction = self.api.get_auction().await.context("get_auction")?;
+        let auction_id = auction.id;
         let before_count = auction.orders.len();
         self.in_flight_orders.update_and_filter(&mut auction);
         if before_count != auction.orders.len() {
@@ -462,9 +465,9 @@ impl Driver {
 
         let mut solver_settlements = Vec::new();
 
-        let auction_id = self.next_auction_id();
         let auction = Auction {
             id: auction_id,
+            run_id: self.next_run_id(),
             orders: orders.clone(),
             liquidity,
             gas_price: gas_price.effective_gas_price(),
@@ -593,6 +596,32 @@ impl Driver {
 
         rated_settlements.sort_by(|a, b| a.1.objective_value().cmp(&b.1.objective_value()));
         print_settlements(&rated_settlements, &self.fee_objective_scaling_factor);
+        let solutions = errors
+            .iter()
+            .map(|(solver, settlement, _, error)| SolverSettlement {
+                solver: solver.name().to_string(),
+                objective: None,
+                simulation_error: Some(error.to_string()),
+                ..solver_settlement_without_objective(settlement)
+            })
+            .chain(
+                rated_settlements
+                    .iter()
+                    .map(|(solver, settlement, _)| SolverSettlement {
+                        solver: solver.name().to_string(),
+                        objective: Some(objective(settlement)),
+                        simulation_error: None,
+                        ..solver_settlement_without_objective(&settlement.settlement)
+                    }),
+            )
+            .collect();
+        let mut solver_competition = SolverCompetition {
+            auction_id,
+            gas_price: gas_price.effective_gas_price(),
+            liquidity_collected_block: current_block_during_liquidity_fetch,
+            transaction_hash: None,
+            solutions,
+        };
         if let Some((winning_solver, mut winning_settlement, access_list)) = rated_settlements.pop()
         {
             // If we have enough buffer in the settlement contract to not use on-chain interactions, remove those
@@ -639,6 +668,7 @@ impl Driver {
                 )
                 .await
             {
+                solver_competition.transaction_hash = Some(receipt.transaction_hash);
                 let orders = winning_settlement
                     .settlement
                     .traded_orders()
@@ -672,12 +702,17 @@ impl Driver {
         }
         // Happens after settlement submission so that we do not delay it.
         self.report_simulation_errors(errors, current_block_during_liquidity_fetch, gas_price);
+        if !solver_competition.solutions.is_empty() {
+            if let Err(err) = self.api.send_solver_competition(&solver_competition).await {
+                tracing::warn!(?err, "failed to send solver competition");
+            }
+        }
         Ok(())
     }
 
-    fn next_auction_id(&mut self) -> u64 {
-        let id = self.solve_id;
-        self.solve_id += 1;
+    fn next_run_id(&mut self) -> u64 {
+        let id = self.run_id;
+        self.run_id += 1;
         id
     }
 }
@@ -688,6 +723,36 @@ fn is_only_selling_trusted_tokens(settlement: &Settlement, token_list: &TokenLis
         .any(|order| token_list.get(&order.creation.sell_token).is_none())
 }
 
+/// The objective value of the settlement split into its components.
+fn objective(settlement: &RatedSettlement) -> Objective {
+    let cost = settlement.gas_estimate.to_big_rational() * &settlement.gas_price;
+    Objective {
+        total: settlement.objective_value().to_f64().unwrap_or(f64::NAN),
+        surplus: settlement.surplus.to_f64().unwrap_or(f64::NAN),
+        fees: settlement
+            .scaled_unsubsidized_fee
+            .to_f64()
+            .unwrap_or(f64::NAN),
+        cost: cost.to_f64().unwrap_or(f64::NAN),
+        gas: settlement.gas_estimate.low_u64(),
+    }
+}
+
+fn solver_settlement_without_objective(settlement: &Settlement) -> SolverSettlement {
+    SolverSettlement {
+        clearing_prices: settlement
+            .clearing_prices()
+            .iter()
+            .map(|(token, price)| (*token, *price))
+            .collect(),
+        orders: settlement
+            .traded_orders()
+            .map(|order| order.metadata.uid)
+            .collect(),
+        ..Default::default()
+    }
+}
+
 fn print_settlements(
     rated_settlements: &[(Arc<dyn Solver>, RatedSettlement, Option<AccessList>)],
     fee_objective_scaling_factor: &BigRational,
@@ -52,6 +52,11 @@ struct Arguments {
     #[clap(long, env, default_value = "http://localhost:8080")]
     orderbook_url: Url,
 
+    /// The secret the orderbook expects when reporting the solver competition of each run. The
+    /// competition is not reported if this is not set.
+    #[clap(long, env)]
+    solver_competition_auth: Option<String>,
+
     /// The API endpoint to call the mip solver
     #[clap(long, env, default_value = "http://localhost:8000")]
     mip_solver_url: Url,
@@ -639,7 +644,11 @@ async fn main() {
         transaction_strategies,
         access_list_estimator,
     };
-    let api = OrderBookApi::new(args.orderbook_url, client.clone());
+    let api = OrderBookApi::new(
+        args.orderbook_url,
+        client.clone(),
+        args.solver_competition_auth,
+    );
     let order_converter = OrderConverter {
         native_token: native_token_contract.clone(),
         liquidity_order_owners: args.shared.liquidity_order_owners.into_iter().collect(),
@@ -1,16 +1,22 @@
 use anyhow::Result;
-use model::auction::Auction;
+use model::{auction::Auction, solver_competition::SolverCompetition};
 use reqwest::{Client, Url};
 
 pub struct OrderBookApi {
     base: Url,
     client: Client,
+    competition_auth: Option<String>,
 }
 
 impl OrderBookApi {
     /// base: protocol and host of the url. example: `https://example.com`
-    pub fn new(base: Url, client: Client) -> Self {
-        Self { base, client }
+    /// competition_auth: secret the orderbook expects when reporting solver competitions
+    pub fn new(base: Url, client: Client, competition_auth: Option<String>) -> Self {
+        Self {
+            base,
+            client,
+            competition_auth,
+        }
     }
 
     pub async fn get_auction(&self) -> Result<Auction> {
@@ -18,6 +24,24 @@ impl OrderBookApi {
         let auction = self.client.get(url).send().await?.json().await?;
         Ok(auction)
     }
+
+    /// Does nothing if no authorization is configured because the orderbook would reject the
+    /// competition.
+    pub async fn send_solver_competition(&self, competition: &SolverCompetition) -> Result<()> {
+        let auth = match &self.competition_auth {
+            Some(auth) => auth,
+            None => return Ok(()),
+        };
+        let url = self.base.join("api/v1/solver_competition")?;
+        self.client
+            .post(url)
+            .header("authorization", auth)
+            .json(competition)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -28,7 +52,11 @@ pub mod test_util {
     #[tokio::test]
     #[ignore]
     async fn local_orderbook() {
-        let api = OrderBookApi::new(Url::parse("http://localhost:8080").unwrap(), Client::new());
+        let api = OrderBookApi::new(
+            Url::parse("http://localhost:8080").unwrap(),
+            Client::new(),
+            None,
+        );
         let auction = api.get_auction().await.unwrap();
         println!("{:#?}", auction);
     }
@@ -40,6 +68,7 @@ pub mod test_util {
         let api = OrderBookApi::new(
             Url::parse("https://barn.api.cow.fi/mainnet/").unwrap(),
             Client::new(),
+            None,
         );
         let auction = api.get_auction().await.unwrap();
         println!("{:#?}", auction);
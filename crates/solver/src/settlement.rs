@@ -12,7 +12,10 @@ use itertools::Itertools;
 use model::order::{Order, OrderKind};
 use num::{rational::Ratio, BigInt, BigRational, One, Signed, Zero};
 use primitive_types::{H160, U256};
-use shared::conversions::U256Ext as _;
+use shared::{
+    conversions::U256Ext as _,
+    surplus::{buy_order_surplus, sell_order_surplus},
+};
 use std::{
     collections::{HashMap, HashSet},
     ops::{Mul, Sub},
@@ -148,6 +151,7 @@ impl OrderTrade {
     pub fn encode(&self) -> EncodedTrade {
         encoding::encode_trade(
             &self.trade.order.creation,
+            self.trade.order.metadata.owner,
             self.trade.sell_token_index,
             self.buy_token_index,
             &self.trade.executed_amount,
@@ -162,6 +166,7 @@ impl LiquidityOrderTrade {
         let buy_token_index = clearing_price_vec_length + self.buy_token_offset_index;
         encoding::encode_trade(
             &self.trade.order.creation,
+            self.trade.order.metadata.owner,
             self.trade.sell_token_index,
             buy_token_index,
             &self.trade.executed_amount,
@@ -444,50 +449,6 @@ impl From<Settlement> for EncodedSettlement {
     }
 }
 
-// The difference between what you were willing to sell (executed_amount * limit_price)
-// converted into reference token (multiplied by buy_token_price)
-// and what you had to sell denominated in the reference token (executed_amount * buy_token_price)
-fn buy_order_surplus(
-    sell_token_price: &BigRational,
-    buy_token_price: &BigRational,
-    sell_amount_limit: &BigRational,
-    buy_amount_limit: &BigRational,
-    executed_buy_amount: &BigRational,
-) -> Option<BigRational> {
-    if buy_amount_limit.is_zero() {
-        return None;
-    }
-    let limit_sell_amount = executed_buy_amount * sell_amount_limit / buy_amount_limit;
-    let res = (limit_sell_amount * sell_token_price) - (executed_buy_amount * buy_token_price);
-    if res.is_negative() {
-        None
-    } else {
-        Some(res)
-    }
-}
-
-// The difference of your proceeds denominated in the reference token (executed_sell_amount * sell_token_price)
-// and what you were minimally willing to receive in buy tokens (executed_sell_amount * limit_price)
-// converted to amount in reference token at the effective price (multiplied by buy_token_price)
-fn sell_order_surplus(
-    sell_token_price: &BigRational,
-    buy_token_price: &BigRational,
-    sell_amount_limit: &BigRational,
-    buy_amount_limit: &BigRational,
-    executed_sell_amount: &BigRational,
-) -> Option<BigRational> {
-    if sell_amount_limit.is_zero() {
-        return None;
-    }
-    let limit_buy_amount = executed_sell_amount * buy_amount_limit / sell_amount_limit;
-    let res = (executed_sell_amount * sell_token_price) - (limit_buy_amount * buy_token_price);
-    if res.is_negative() {
-        None
-    } else {
-        Some(res)
-    }
-}
-
 /// Surplus Ratio represents the percentage difference of the executed price with the limit price.
 /// This is calculated for orders with a corresponding trade. This value is always non-negative
 /// since orders are contractually bound to be settled on or beyond their limit price.
@@ -946,104 +907,6 @@ pub mod tests {
         settlement.total_surplus(&external_prices);
     }
 
-    #[test]
-    #[allow(clippy::just_underscores_and_digits)]
-    fn test_buy_order_surplus() {
-        // Two goods are worth the same (100 each). If we were willing to pay up to 60 to receive 50,
-        // but ended paying the price (1) we have a surplus of 10 sell units, so a total surplus of 1000.
-        assert_eq!(
-            buy_order_surplus(&r(100), &r(100), &r(60), &r(50), &r(50)),
-            Some(r(1000))
-        );
-
-        // If our trade got only half filled, we only get half the surplus
-        assert_eq!(
-            buy_order_surplus(&r(100), &r(100), &r(60), &r(50), &r(25)),
-            Some(r(500))
-        );
-
-        // No surplus if trade is not at all filled
-        assert_eq!(
-            buy_order_surplus(&r(100), &r(100), &r(60), &r(50), &r(0)),
-            Some(r(0))
-        );
-
-        // No surplus if trade is filled at limit
-        assert_eq!(
-            buy_order_surplus(&r(100), &r(100), &r(50), &r(50), &r(50)),
-            Some(r(0))
-        );
-
-        // Arithmetic error when limit price not respected
-        assert_eq!(
-            buy_order_surplus(&r(100), &r(100), &r(40), &r(50), &r(50)),
-            None
-        );
-
-        // Sell Token worth twice as much as buy token. If we were willing to sell at parity, we will
-        // have a surplus of 50% of tokens, worth 200 each.
-        assert_eq!(
-            buy_order_surplus(&r(200), &r(100), &r(50), &r(50), &r(50)),
-            Some(r(5000))
-        );
-
-        // Buy Token worth twice as much as sell token. If we were willing to sell at 3:1, we will
-        // have a surplus of 20 sell tokens, worth 100 each.
-        assert_eq!(
-            buy_order_surplus(&r(100), &r(200), &r(60), &r(20), &r(20)),
-            Some(r(2000))
-        );
-    }
-
-    #[test]
-    #[allow(clippy::just_underscores_and_digits)]
-    fn test_sell_order_surplus() {
-        // Two goods are worth the same (100 each). If we were willing to receive as little as 40,
-        // but ended paying the price (1) we have a surplus of 10 bought units, so a total surplus of 1000.
-        assert_eq!(
-            sell_order_surplus(&r(100), &r(100), &r(50), &r(40), &r(50)),
-            Some(r(1000))
-        );
-
-        // If our trade got only half filled, we only get half the surplus
-        assert_eq!(
-            sell_order_surplus(&r(100), &r(100), &r(50), &r(40), &r(25)),
-            Some(r(500))
-        );
-
-        // No surplus if trade is not at all filled
-        assert_eq!(
-            sell_order_surplus(&r(100), &r(100), &r(50), &r(40), &r(0)),
-            Some(r(0))
-        );
-
-        // No surplus if trade is filled at limit
-        assert_eq!(
-            sell_order_surplus(&r(100), &r(100), &r(50), &r(50), &r(50)),
-            Some(r(0))
-        );
-
-        // Arithmetic error when limit price not respected
-        assert_eq!(
-            sell_order_surplus(&r(100), &r(100), &r(50), &r(60), &r(50)),
-            None
-        );
-
-        // Sell token worth twice as much as buy token. If we were willing to buy at parity, we will
-        // have a surplus of 100% of buy tokens, worth 100 each.
-        assert_eq!(
-            sell_order_surplus(&r(200), &r(100), &r(50), &r(50), &r(50)),
-            Some(r(5000))
-        );
-
-        // Buy Token worth twice as much as sell token. If we were willing to sell at 3:1, we will
-        // have a surplus of 10 buy tokens, worth 200 each.
-        assert_eq!(
-            sell_order_surplus(&r(100), &r(200), &r(60), &r(20), &r(60)),
-            Some(r(2000))
-        );
-    }
-
     #[test]
     #[allow(clippy::just_underscores_and_digits)]
     fn test_surplus_ratio() {
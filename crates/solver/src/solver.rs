@@ -67,13 +67,19 @@ pub trait Solver: Send + Sync + 'static {
 /// A batch auction for a solver to produce a settlement for.
 #[derive(Clone, Debug)]
 pub struct Auction {
-    /// An ID that identifies a batch within a `Driver` instance.
+    /// The ID the orderbook assigned to the auction.
     ///
-    /// Note that this ID is not unique across multiple instances of drivers,
-    /// in particular it cannot be used to uniquely identify batches across
-    /// service restarts.
+    /// It is unique across services and restarts but the driver can solve
+    /// the same auction multiple times if the orderbook hasn't created a new
+    /// one in the meantime.
     pub id: u64,
 
+    /// An ID that identifies a single run of a `Driver` instance.
+    ///
+    /// Unlike `id` it changes every time the driver solves an auction, even
+    /// if it solves the same auction again with new liquidity and gas price.
+    pub run_id: u64,
+
     /// The GPv2 orders to match.
     pub orders: Vec<LimitOrder>,
 
@@ -110,6 +116,7 @@ impl Default for Auction {
         let never = Instant::now() + Duration::from_secs(SECONDS_IN_A_YEAR);
         Self {
             id: Default::default(),
+            run_id: Default::default(),
             orders: Default::default(),
             liquidity: Default::default(),
             gas_price: Default::default(),
@@ -41,9 +41,9 @@ pub fn is_transaction_failure(error: &ExecutionError) -> bool {
 
 // TODO: special rounding for the prices we get from the solver?
 
-/// Data shared between multiple instances of the http solver for the same solve id.
+/// Data shared between multiple instances of the http solver for the same driver run.
 pub struct InstanceData {
-    solve_id: u64,
+    run_id: u64,
     model: BatchAuctionModel,
     context: SettlementContext,
 }
@@ -376,6 +376,7 @@ impl Solver for HttpSolver {
         &self,
         Auction {
             id,
+            run_id,
             mut orders,
             liquidity,
             gas_price,
@@ -394,13 +395,13 @@ impl Solver for HttpSolver {
         let (model, context) = {
             let mut guard = self.instance_cache.lock().await;
             match guard.as_mut() {
-                Some(data) if data.solve_id == id => (data.model.clone(), data.context.clone()),
+                Some(data) if data.run_id == run_id => (data.model.clone(), data.context.clone()),
                 _ => {
                     let (model, context) = self
                         .prepare_model(id, orders, liquidity, gas_price, external_prices)
                         .await?;
                     *guard = Some(InstanceData {
-                        solve_id: id,
+                        run_id,
                         model: model.clone(),
                         context: context.clone(),
                     });
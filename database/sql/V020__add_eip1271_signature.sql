@@ -0,0 +1 @@
+ALTER TYPE SigningScheme ADD VALUE 'eip1271';
@@ -0,0 +1,4 @@
+-- Orders are paginated by (creation_timestamp, uid) so the uid needs to be part of the index for
+-- the database to be able to start immediately at the cursor.
+DROP INDEX user_order_creation_timestamp;
+CREATE INDEX user_order_creation_timestamp ON orders USING BTREE (owner, creation_timestamp DESC, uid DESC);
@@ -0,0 +1,21 @@
+-- Quotes are stored so that orders can reference the quote they were created from. This lets us
+-- reuse the quote's fee parameters when validating the order and audit how accurate quotes are.
+CREATE TABLE quotes (
+  id bigserial PRIMARY KEY,
+  owner bytea NOT NULL,
+  sell_token bytea NOT NULL,
+  buy_token bytea NOT NULL,
+  sell_amount numeric(78,0) NOT NULL,
+  buy_amount numeric(78,0) NOT NULL,
+  fee_amount numeric(78,0) NOT NULL,
+  app_data bytea NOT NULL,
+  order_kind OrderKind NOT NULL,
+  gas_amount double precision NOT NULL,
+  gas_price double precision NOT NULL,
+  sell_token_price double precision NOT NULL,
+  expiration_timestamp timestamptz NOT NULL
+);
+
+CREATE INDEX quotes_expiration_timestamp ON quotes USING BTREE (expiration_timestamp);
+
+ALTER TABLE orders ADD COLUMN quote_id bigint;
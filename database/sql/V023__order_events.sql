@@ -0,0 +1,14 @@
+-- A timeline of what happened to an order so that we can tell users why their order did or did
+-- not get filled.
+CREATE TYPE OrderEventLabel AS ENUM ('created', 'solvable', 'filtered', 'traded', 'cancelled', 'expired');
+CREATE TYPE OrderFilterReason AS ENUM ('banned_user', 'unsupported_token', 'invalid_signature', 'insufficient_balance', 'missing_native_price');
+
+CREATE TABLE order_events (
+  order_uid bytea NOT NULL,
+  timestamp timestamptz NOT NULL,
+  label OrderEventLabel NOT NULL,
+  -- Only set for filtered events.
+  filter_reason OrderFilterReason
+);
+
+CREATE INDEX order_events_by_uid ON order_events USING BTREE (order_uid, timestamp);
@@ -0,0 +1,8 @@
+-- The timestamps of the blocks settlements happened in so that trades can be aggregated over time.
+-- Kept by block number instead of in the settlements table because settlements are deleted and
+-- reinserted when the most recent blocks are reindexed. Filled in after the settlement event is
+-- indexed so the most recent settlements might not have a timestamp yet.
+CREATE TABLE settlement_block_timestamps (
+  block_number bigint PRIMARY KEY,
+  block_timestamp timestamptz NOT NULL
+);
@@ -0,0 +1,12 @@
+-- The settlements all solvers proposed for an auction so that the result of the competition can be
+-- audited. Stored as json because it is only ever read back as a whole. The driver can run several
+-- times for the same auction so every run gets its own row.
+CREATE TABLE solver_competitions (
+  id bigserial PRIMARY KEY,
+  auction_id bigint NOT NULL,
+  tx_hash bytea,
+  json jsonb NOT NULL
+);
+
+CREATE INDEX solver_competitions_auction_id ON solver_competitions USING BTREE (auction_id);
+CREATE INDEX solver_competitions_tx_hash ON solver_competitions USING BTREE (tx_hash);
@@ -0,0 +1,3 @@
+-- Every auction the orderbook creates gets an id from this sequence so that auctions can be
+-- correlated across services and restarts.
+CREATE SEQUENCE auction_ids;
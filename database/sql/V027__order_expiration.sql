@@ -0,0 +1,11 @@
+-- The time the expiry sweeper marked the order as expired. This happens shortly after `valid_to`
+-- passes, also for orders that were filled or cancelled before, so that orders which can no longer
+-- become solvable drop out of the index below.
+ALTER TABLE orders ADD COLUMN expiration_timestamp timestamptz;
+
+-- Orders that expired before the sweeper existed are marked without emitting events.
+UPDATE orders SET expiration_timestamp = to_timestamp(valid_to)
+WHERE valid_to < EXTRACT(EPOCH FROM now());
+
+CREATE INDEX unexpired_orders_valid_to ON orders USING BTREE (valid_to)
+WHERE expiration_timestamp IS NULL;
@@ -0,0 +1,15 @@
+-- Orders that can no longer be traded are moved here together with their trades by the order
+-- archiver to keep the live tables small. Columns added to `orders` or `trades` in the future have
+-- to be added to the archive tables as well and the views below have to be recreated.
+CREATE TABLE archived_orders (LIKE orders INCLUDING ALL);
+CREATE TABLE archived_trades (LIKE trades INCLUDING ALL);
+
+CREATE VIEW all_orders AS
+SELECT * FROM orders
+UNION ALL
+SELECT * FROM archived_orders;
+
+CREATE VIEW all_trades AS
+SELECT * FROM trades
+UNION ALL
+SELECT * FROM archived_trades;
@@ -0,0 +1,21 @@
+-- What mined settlements actually cost according to their transaction receipt. Settlements whose
+-- receipt can't be used to compute the cost are recorded without a cost so that they aren't retried.
+CREATE TABLE settlement_costs (
+  tx_hash bytea PRIMARY KEY,
+  gas_used numeric(78,0),
+  effective_gas_price numeric(78,0)
+);
+
+-- The fee we estimated for a traded order compared with its share of the settlement cost. Both fees
+-- are in native token atoms and the settlement cost is split evenly across the settlement's trades,
+-- so only reconciliations of settlements with a single trade tell how much gas a token pair needs.
+CREATE TABLE fee_reconciliations (
+  order_uid bytea NOT NULL,
+  tx_hash bytea NOT NULL,
+  sell_token bytea NOT NULL,
+  buy_token bytea NOT NULL,
+  estimated_fee double precision NOT NULL,
+  realized_fee double precision NOT NULL,
+  settlement_trades bigint NOT NULL,
+  PRIMARY KEY (order_uid, tx_hash)
+);
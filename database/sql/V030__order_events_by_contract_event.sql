@@ -0,0 +1,7 @@
+-- Traded and cancelled order events reference the contract event that caused them. This way
+-- indexing the same event again does not record another order event and reorgs can remove them.
+ALTER TABLE order_events
+  ADD COLUMN block_number bigint,
+  ADD COLUMN log_index bigint;
+
+CREATE UNIQUE INDEX order_events_by_contract_event ON order_events (order_uid, block_number, log_index);
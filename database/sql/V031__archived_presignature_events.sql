@@ -0,0 +1,8 @@
+-- Presignature events of archived orders are archived with them so that `presignature_events`
+-- stays small too. Like `all_trades` this view has to be recreated when columns are added.
+CREATE TABLE archived_presignature_events (LIKE presignature_events INCLUDING ALL);
+
+CREATE VIEW all_presignature_events AS
+SELECT * FROM presignature_events
+UNION ALL
+SELECT * FROM archived_presignature_events;